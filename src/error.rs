@@ -0,0 +1,70 @@
+use crate::images::Format;
+use std::{error::Error, fmt::Display, io};
+
+// Types...
+/// Result padrao das operacoes de leitura e escrita de imagens
+pub type ImageResult<T> = Result<T, ImageError>;
+
+// Enums...
+/// Erro customizado para quando uma imagem e lida ou escrita
+#[derive(Debug)]
+pub enum ImageError {
+    /// Os dados terminaram antes do esperado
+    Truncated,
+    /// A assinatura (magic bytes) nao corresponde ao formato
+    InvalidMagic,
+    /// Profundidade de bits por pixel nao suportada
+    UnsupportedBitDepth(u16),
+    /// Metodo de compressao nao suportado
+    UnsupportedCompression(u32),
+    /// As dimensoes da imagem estouram os limites de memoria/aritmetica
+    DimensionOverflow,
+    /// Formato reconhecido, mas sem codec implementado
+    UnsupportedFormat(Format),
+    /// Recurso do formato que a lib ainda nao suporta
+    Unsupported(&'static str),
+    /// Dados corrompidos ou inconsistentes
+    InvalidData(&'static str),
+    /// Erro de entrada e saida
+    Io(io::Error),
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::Truncated => write!(f, "image data is truncated"),
+            ImageError::InvalidMagic => write!(f, "image signature is invalid"),
+            ImageError::UnsupportedBitDepth(bits) => {
+                write!(f, "unsupported bit depth: {bits}")
+            }
+            ImageError::UnsupportedCompression(method) => {
+                write!(f, "unsupported compression method: {method}")
+            }
+            ImageError::DimensionOverflow => write!(f, "image dimensions are too large"),
+            ImageError::UnsupportedFormat(format) => {
+                write!(f, "unsupported image format: {format:?}")
+            }
+            ImageError::Unsupported(feature) => write!(f, "unsupported feature: {feature}"),
+            ImageError::InvalidData(reason) => write!(f, "invalid image data: {reason}"),
+            ImageError::Io(error) => write!(f, "I/O error: {error}"),
+        }
+    }
+}
+
+impl Error for ImageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ImageError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => ImageError::Truncated,
+            _ => ImageError::Io(error),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::images::RGB;
+use crate::images::pixel::Pixel;
 
 use super::{Filter, FilterError};
 
@@ -17,13 +17,10 @@ impl Filter for BoxBlur {
         let size = widht * height;
         let step = self.0 * 2 + 1;
 
-        let mut buffer = vec![RGB::default(); size];
+        let mut buffer = image.get_pixels().to_vec();
 
-        for index in 0..size {
-            let mut counter = 0;
-            let mut red = 0;
-            let mut green = 0;
-            let mut blue = 0;
+        for (index, color) in buffer.iter_mut().enumerate() {
+            let mut window = Vec::new();
 
             let start_index = (index as isize) - (self.0 * widht) as isize;
 
@@ -37,31 +34,21 @@ impl Filter for BoxBlur {
                 let row_start = (index / widht) * widht;
                 let row_end = row_start + widht;
 
-                let start =
-                    (index.checked_sub(self.0).unwrap_or_default()).clamp(row_start, row_end);
-                let end = (index.checked_add(self.0).unwrap_or(usize::max_value()))
-                    .clamp(row_start, row_end);
+                let start = index.saturating_sub(self.0).clamp(row_start, row_end);
+                let end = index.saturating_add(self.0).clamp(row_start, row_end);
 
                 if end >= size {
                     continue;
                 }
 
-                for color in image.get_slice_pixels(start..end + 1) {
-                    red += color.red() as u32;
-                    green += color.green() as u32;
-                    blue += color.blue() as u32;
-                    counter += 1;
-                }
+                window.extend(image.get_slice_pixels(start..end + 1));
             }
 
-            let color = &mut buffer[index];
-            color.set_red((red.checked_div(counter).unwrap_or_default()) as u8);
-            color.set_green((green.checked_div(counter).unwrap_or_default()) as u8);
-            color.set_blue((blue.checked_div(counter).unwrap_or_default()) as u8);
+            *color = color.mean_color(window);
         }
 
-        for color in image.pixels() {
-            *color = buffer.remove(0);
+        for (color, blurred) in image.pixels().iter_mut().zip(buffer) {
+            *color = blurred;
         }
 
         Ok(())
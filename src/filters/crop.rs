@@ -0,0 +1,41 @@
+use super::{FilterError, Transform};
+use crate::images::{Image, buffer::ImageBuffer};
+
+/// Corta a imagem no retangulo que comeca em (`x`, `y`), nas coordenadas dos pixels guardados
+pub struct Crop {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Crop {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+impl Transform for Crop {
+    fn apply<I: Image>(&self, image: &I) -> Result<ImageBuffer<I::Pixel>, FilterError> {
+        let right = self.x.checked_add(self.width);
+        let bottom = self.y.checked_add(self.height);
+        let inside = right.is_some_and(|right| right <= image.widht())
+            && bottom.is_some_and(|bottom| bottom <= image.height());
+        if self.width == 0 || self.height == 0 || !inside {
+            return Err(FilterError::InvalidDimensions);
+        }
+
+        let mut pixels = Vec::with_capacity(self.width * self.height);
+        for y in self.y..self.y + self.height {
+            let start = y * image.widht() + self.x;
+            pixels.extend_from_slice(image.get_slice_pixels(start..start + self.width));
+        }
+
+        Ok(ImageBuffer::from_pixels(self.width, self.height, pixels)?)
+    }
+}
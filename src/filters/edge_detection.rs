@@ -1,4 +1,5 @@
 use super::{Filter, FilterError};
+use crate::images::pixel::Pixel;
 
 pub struct EdgeDetection {
     gray_scale: bool,
@@ -38,17 +39,18 @@ impl Default for EdgeDetection {
 }
 
 //# # #    //# # #   //# # #
-const KERNEL_TABLE: [i16; 9] = [-1, 0, 1, -2, 0, 2, -1, 0, 1];
+const KERNEL_TABLE: [f32; 9] = [-1.0, 0.0, 1.0, -2.0, 0.0, 2.0, -1.0, 0.0, 1.0];
 
 impl Filter for EdgeDetection {
     fn apply(&self, image: &mut impl crate::images::Image) -> Result<(), FilterError> {
         let size = image.get_pixels().len();
         let widht = image.widht();
 
+        // Cinza na escala das cores de 8 bits, sem limitar: imagens HDR passam de 255
         let pixels = image
             .get_pixels()
             .iter()
-            .map(|c| c.grayscale())
+            .map(|c| c.to_rgba().0[..3].iter().sum::<f32>() * 255.0 / 3.0)
             .collect::<Vec<_>>();
 
         for index in 0..size {
@@ -70,37 +72,33 @@ impl Filter for EdgeDetection {
                 clamp_index_in_row(widht, down_row, index + widht + 1), // 8
             ];
 
-            let gx = (pixels[pxs[0]].red() as i16 * KERNEL_TABLE[0]
-                + pixels[pxs[3]].red() as i16 * KERNEL_TABLE[3]
-                + pixels[pxs[6]].red() as i16 * KERNEL_TABLE[6])
-                + (pixels[pxs[2]].red() as i16 * KERNEL_TABLE[2]
-                    + pixels[pxs[5]].red() as i16 * KERNEL_TABLE[5]
-                    + pixels[pxs[8]].red() as i16 * KERNEL_TABLE[8]);
+            let gx = (pixels[pxs[0]] * KERNEL_TABLE[0]
+                + pixels[pxs[3]] * KERNEL_TABLE[3]
+                + pixels[pxs[6]] * KERNEL_TABLE[6])
+                + (pixels[pxs[2]] * KERNEL_TABLE[2]
+                    + pixels[pxs[5]] * KERNEL_TABLE[5]
+                    + pixels[pxs[8]] * KERNEL_TABLE[8]);
 
-            let gy = (pixels[pxs[0]].red() as i16 * KERNEL_TABLE[0]
-                + pixels[pxs[2]].red() as i16 * KERNEL_TABLE[3]
-                + pixels[pxs[3]].red() as i16 * KERNEL_TABLE[6])
-                + (pixels[pxs[6]].red() as i16 * KERNEL_TABLE[2]
-                    + pixels[pxs[7]].red() as i16 * KERNEL_TABLE[5]
-                    + pixels[pxs[8]].red() as i16 * KERNEL_TABLE[8]);
+            let gy = (pixels[pxs[0]] * KERNEL_TABLE[0]
+                + pixels[pxs[2]] * KERNEL_TABLE[3]
+                + pixels[pxs[3]] * KERNEL_TABLE[6])
+                + (pixels[pxs[6]] * KERNEL_TABLE[2]
+                    + pixels[pxs[7]] * KERNEL_TABLE[5]
+                    + pixels[pxs[8]] * KERNEL_TABLE[8]);
 
-            let magnitude = (gx.abs() + gy.abs()).clamp(0, 255) as u8;
+            let magnitude = gx.abs() + gy.abs();
 
             let color = &mut image.pixels()[index];
 
-            if self.gray_scale {
-                if magnitude > self.limit {
-                    color.set_value(magnitude);
-                } else {
-                    color.set_value(0);
-                }
+            // So o tipo do pixel limita o valor (as cores de 8 e 16 bits param em 1.0)
+            let value = if magnitude <= self.limit as f32 {
+                0.0
+            } else if self.gray_scale {
+                magnitude / 255.0
             } else {
-                if magnitude > self.limit {
-                    color.set_value(255);
-                } else {
-                    color.set_value(0);
-                }
-            }
+                1.0
+            };
+            *color = color.with_color(value, value, value);
         }
 
         Ok(())
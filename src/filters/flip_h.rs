@@ -1,5 +1,4 @@
 use super::{Filter, FilterError};
-use crate::images::Format;
 
 #[derive(Default)]
 pub struct FlipH {
@@ -30,7 +29,7 @@ impl FlipH {
 }
 
 impl Filter for FlipH {
-    fn apply(&self, image: &mut impl crate::images::Image) -> Result<(), super::FilterError> {
+    fn apply(&self, image: &mut impl crate::images::Image) -> Result<(), FilterError> {
         let start = if self.is_full {
             (0, 0)
         } else {
@@ -43,20 +42,14 @@ impl Filter for FlipH {
             (self.end_x, self.end_y)
         };
 
-        match image.format() {
-            Format::BMP => {
-                for i in start.1..end.1 {
-                    let start = start.0 + (i * image.widht());
-                    let end = end.0 + (i * image.widht());
-                    let line = image.slice_pixels(start..end);
+        for i in start.1..end.1 {
+            let start = start.0 + (i * image.widht());
+            let end = end.0 + (i * image.widht());
+            let line = image.slice_pixels(start..end);
 
-                    line.reverse();
-                }
-
-                Ok(())
-            }
-
-            _ => Err(FilterError::InvalidFormat),
+            line.reverse();
         }
+
+        Ok(())
     }
 }
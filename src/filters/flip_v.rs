@@ -1,5 +1,4 @@
 use super::{Filter, FilterError};
-use crate::images::Format;
 
 #[derive(Default)]
 pub struct FlipV {
@@ -45,27 +44,19 @@ impl Filter for FlipV {
             (self.end_x, self.end_y)
         };
 
-        match image.format() {
-            Format::BMP => {
-                for x in start.0..end.0 {
-                    let mut column = Vec::with_capacity(end.1 - start.1);
+        for x in start.0..end.0 {
+            let mut column = Vec::with_capacity(end.1 - start.1);
 
-                    for y in start.1..end.1 {
-                        let index = (y * image.widht()) + x;
-                        column.push(image.get_pixels()[index].clone());
-                    }
-
-                    column.reverse();
-
-                    for y in start.1..end.1 {
-                        let index = (y * image.widht()) + x;
-                        image.pixels()[index] = column.remove(0);
-                    }
-                }
+            for y in start.1..end.1 {
+                let index = (y * image.widht()) + x;
+                column.push(image.get_pixels()[index].clone());
             }
 
-            _ => {
-                return Err(FilterError::InvalidFormat);
+            column.reverse();
+
+            for y in start.1..end.1 {
+                let index = (y * image.widht()) + x;
+                image.pixels()[index] = column.remove(0);
             }
         }
 
@@ -1,12 +1,12 @@
 use super::{Filter, FilterError};
+use crate::images::pixel::Pixel;
 
 pub struct GrayScale;
 
 impl Filter for GrayScale {
     fn apply(&self, image: &mut impl crate::images::Image) -> Result<(), FilterError> {
         for pixel in image.pixels() {
-            let new_color = pixel.grayscale();
-            *pixel = new_color;
+            *pixel = pixel.gray();
         }
 
         Ok(())
@@ -1,12 +1,18 @@
-use crate::images::Image;
+use crate::error::ImageError;
+use crate::images::{Image, buffer::ImageBuffer};
 use std::{error::Error, fmt::Display};
 
 pub mod box_blur;
+pub mod crop;
 pub mod edge_detection;
 pub mod flip_h;
 pub mod flip_v;
 pub mod grayscale;
 pub mod negative;
+pub mod pad;
+pub mod resize;
+pub mod rotate;
+pub mod transpose;
 
 // Traits...
 /// Trait que representa um filtro para ser aplicado em uma imagem, sendo generico para qualquer image
@@ -14,35 +20,47 @@ pub trait Filter {
     fn apply(&self, image: &mut impl Image) -> Result<(), FilterError>;
 }
 
+/// Trait que representa uma operacao que muda as dimensoes da imagem (cortar, girar, etc).
+/// Ela gera uma nova imagem e `Image::transform` troca os pixels e os cabecalhos da original
+pub trait Transform {
+    fn apply<I: Image>(&self, image: &I) -> Result<ImageBuffer<I::Pixel>, FilterError>;
+}
+
 // Enums...
 /// Erro customizado para quando um filtro é aplicado
 #[derive(Debug)]
 pub enum FilterError {
+    /// O filtro nao funciona no formato da imagem
     InvalidFormat,
+    /// Area ou tamanho fora da imagem, ou vazio
+    InvalidDimensions,
+    /// O formato nao aceita o resultado (ex: tamanho acima do limite do formato)
+    Image(ImageError),
 }
 
 impl Error for FilterError {
-    fn cause(&self) -> Option<&dyn Error> {
-        None
-    }
-
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            FilterError::InvalidFormat => "Filter not support for format image",
+            FilterError::Image(error) => Some(error),
+            _ => None,
         }
     }
-
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
 }
 
 impl Display for FilterError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FilterError::InvalidFormat => {
-                write!(f, "FilterError")
+                write!(f, "filter is not supported for this image format")
             }
+            FilterError::InvalidDimensions => write!(f, "filter dimensions are invalid"),
+            FilterError::Image(error) => write!(f, "{error}"),
         }
     }
 }
+
+impl From<ImageError> for FilterError {
+    fn from(error: ImageError) -> Self {
+        FilterError::Image(error)
+    }
+}
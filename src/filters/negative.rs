@@ -1,13 +1,13 @@
 use super::Filter;
+use crate::images::pixel::Pixel;
 
 pub struct Negative;
 
 impl Filter for Negative {
     fn apply(&self, image: &mut impl crate::images::Image) -> Result<(), super::FilterError> {
         for pixel in image.pixels() {
-            pixel.set_red(255 - pixel.red());
-            pixel.set_green(255 - pixel.green());
-            pixel.set_blue(255 - pixel.blue());
+            let [red, green, blue, _] = pixel.to_rgba().0;
+            *pixel = pixel.with_color(1.0 - red, 1.0 - green, 1.0 - blue);
         }
 
         Ok(())
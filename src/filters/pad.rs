@@ -0,0 +1,53 @@
+use super::{FilterError, Transform};
+use crate::images::{Image, RGB, buffer::ImageBuffer, pixel::Pixel};
+
+/// Adiciona bordas de `color` em volta da imagem, com a largura de cada lado em pixels
+pub struct Pad {
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub color: RGB,
+}
+
+impl Pad {
+    pub fn new(top: usize, right: usize, bottom: usize, left: usize, color: RGB) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+            color,
+        }
+    }
+
+    /// Mesma borda nos quatro lados
+    pub fn uniform(size: usize, color: RGB) -> Self {
+        Self::new(size, size, size, size, color)
+    }
+}
+
+impl Transform for Pad {
+    fn apply<I: Image>(&self, image: &I) -> Result<ImageBuffer<I::Pixel>, FilterError> {
+        let (widht, height) = (image.widht(), image.height());
+        let new_width = [self.left, self.right]
+            .into_iter()
+            .try_fold(widht, usize::checked_add);
+        let new_height = [self.top, self.bottom]
+            .into_iter()
+            .try_fold(height, usize::checked_add);
+        let (Some(new_width), Some(new_height)) = (new_width, new_height) else {
+            return Err(FilterError::InvalidDimensions);
+        };
+
+        let mut output = ImageBuffer::new(new_width, new_height, self.color.convert())?;
+        for y in 0..height {
+            let start = (self.top + y) * new_width + self.left;
+            output
+                .slice_pixels(start..start + widht)
+                .clone_from_slice(image.get_slice_pixels(y * widht..(y + 1) * widht));
+        }
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,354 @@
+use super::{FilterError, Transform};
+use crate::error::{ImageError, ImageResult};
+use crate::images::{
+    Image,
+    buffer::ImageBuffer,
+    pixel::{Pixel, Rgba},
+};
+use std::f32::consts::PI;
+
+// Consts...
+/// Soma de pesos abaixo disso e tratada como zero (usa o pixel mais proximo)
+const MIN_WEIGHT_SUM: f32 = 1e-6;
+
+// Enums...
+/// Enum que representa o filtro usado para amostrar a imagem de origem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Pixel mais proximo, sem misturar cores
+    Nearest,
+    /// Linear (triangulo), com raio de 1 pixel
+    #[default]
+    Bilinear,
+    /// Cubica de Catmull-Rom (a = -0.5), com raio de 2 pixels
+    Bicubic,
+    /// Lanczos com 3 lobulos, o mais nitido e o mais lento
+    Lanczos3,
+}
+
+impl Interpolation {
+    /// Raio do filtro em pixels da origem, antes de aumentar para reduzir a imagem
+    pub(crate) fn support(self) -> f32 {
+        match self {
+            Self::Nearest => 0.5,
+            Self::Bilinear => 1.0,
+            Self::Bicubic => 2.0,
+            Self::Lanczos3 => 3.0,
+        }
+    }
+
+    /// Peso de um pixel a `distance` pixels do ponto amostrado
+    pub(crate) fn weight(self, distance: f32) -> f32 {
+        let x = distance.abs();
+
+        match self {
+            Self::Nearest => match x < 0.5 {
+                true => 1.0,
+                false => 0.0,
+            },
+            Self::Bilinear => (1.0 - x).max(0.0),
+            Self::Bicubic => {
+                const A: f32 = -0.5;
+                if x < 1.0 {
+                    ((A + 2.0) * x - (A + 3.0)) * x * x + 1.0
+                } else if x < 2.0 {
+                    ((A * x - 5.0 * A) * x + 8.0 * A) * x - 4.0 * A
+                } else {
+                    0.0
+                }
+            }
+            Self::Lanczos3 => match x < 3.0 {
+                true => sinc(x) * sinc(x / 3.0),
+                false => 0.0,
+            },
+        }
+    }
+}
+
+// Structs...
+/// Struct que representa um redimensionamento. Diferente dos filtros, muda o tamanho, entao
+/// gera uma nova imagem em vez de alterar a original.
+///
+/// As medidas sao as da imagem exibida: com uma orientacao que troca largura e altura, a
+/// imagem guardada e redimensionada com elas trocadas e a orientacao e mantida
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resize {
+    pub width: usize,
+    pub height: usize,
+    pub filter: Interpolation,
+}
+
+/// Area da origem amostrada, em pixels (pode comecar no meio de um pixel)
+#[derive(Clone, Copy)]
+struct Window {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// Pesos de um pixel de saida: os pixels da origem a partir de `start`
+struct Weights {
+    start: usize,
+    values: Vec<f32>,
+}
+
+impl Resize {
+    pub fn new(width: usize, height: usize, filter: Interpolation) -> Self {
+        Self {
+            width,
+            height,
+            filter,
+        }
+    }
+
+    /// Redimensiona para exatamente `width` x `height`, sem manter a proporcao
+    pub fn apply<I: Image>(&self, image: &I) -> ImageResult<ImageBuffer<I::Pixel>> {
+        let (width, height) = self.stored_size(image);
+
+        self.resample(image, width, height, full_window(image))
+    }
+
+    /// Maior tamanho que cabe em `width` x `height` mantendo a proporcao
+    pub fn fit<I: Image>(&self, image: &I) -> ImageResult<ImageBuffer<I::Pixel>> {
+        let (width, height) = self.fit_size(image);
+
+        self.resample(image, width, height, full_window(image))
+    }
+
+    /// Cobre `width` x `height` mantendo a proporcao e corta o que sobra, centralizado
+    pub fn fill<I: Image>(&self, image: &I) -> ImageResult<ImageBuffer<I::Pixel>> {
+        let (width, height) = self.stored_size(image);
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("resize dimensions are zero"));
+        }
+
+        let (source_width, source_height) = (image.widht() as f64, image.height() as f64);
+        let scale = (width as f64 / source_width).max(height as f64 / source_height);
+        let (span_x, span_y) = (
+            (width as f64 / scale).min(source_width),
+            (height as f64 / scale).min(source_height),
+        );
+        let window = Window {
+            x: (source_width - span_x) / 2.0,
+            y: (source_height - span_y) / 2.0,
+            width: span_x,
+            height: span_y,
+        };
+
+        self.resample(image, width, height, window)
+    }
+
+    /// Igual a `fit`, mas nunca aumenta a imagem. Imagens que ja cabem so sao copiadas
+    pub fn thumbnail<I: Image>(&self, image: &I) -> ImageResult<ImageBuffer<I::Pixel>> {
+        let (width, height) = self.stored_size(image);
+        if image.widht() <= width && image.height() <= height {
+            return self.resample(image, image.widht(), image.height(), full_window(image));
+        }
+
+        self.fit(image)
+    }
+
+    /// Tamanho pedido nos eixos da imagem guardada
+    fn stored_size(&self, image: &impl Image) -> (usize, usize) {
+        match image.orientation().swaps_dimensions() {
+            true => (self.height, self.width),
+            false => (self.width, self.height),
+        }
+    }
+
+    fn fit_size(&self, image: &impl Image) -> (usize, usize) {
+        let (width, height) = self.stored_size(image);
+        let (source_width, source_height) = (image.widht() as f64, image.height() as f64);
+        let scale = (width as f64 / source_width).min(height as f64 / source_height);
+
+        // Nunca menos de 1 pixel, para imagens muito finas
+        let fitted = |size: f64, limit: usize| ((size * scale).round() as usize).clamp(1, limit);
+        match width == 0 || height == 0 {
+            true => (0, 0),
+            false => (fitted(source_width, width), fitted(source_height, height)),
+        }
+    }
+
+    /// Reamostra a janela da origem em `width` x `height`, primeiro nas linhas e depois nas
+    /// colunas. As cores sao multiplicadas pelo alpha para pixels transparentes nao mancharem
+    /// os vizinhos
+    fn resample<I: Image>(
+        &self,
+        image: &I,
+        width: usize,
+        height: usize,
+        window: Window,
+    ) -> ImageResult<ImageBuffer<I::Pixel>> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("resize dimensions are zero"));
+        }
+        let size = width
+            .checked_mul(height)
+            .ok_or(ImageError::DimensionOverflow)?;
+
+        let (source_width, source_height) = (image.widht(), image.height());
+        if source_width == 0 || source_height == 0 {
+            return Err(ImageError::InvalidData("image is empty"));
+        }
+
+        let full = window.x == 0.0
+            && window.y == 0.0
+            && window.width == source_width as f64
+            && window.height == source_height as f64;
+        if full && width == source_width && height == source_height {
+            return Ok(
+                ImageBuffer::from_raw(width, height, image.get_pixels().to_vec())
+                    .with_orientation(image.orientation()),
+            );
+        }
+
+        let columns = weights(self.filter, source_width, width, window.x, window.width);
+        let rows = weights(self.filter, source_height, height, window.y, window.height);
+
+        // So as linhas usadas por alguma linha de saida passam pela primeira etapa
+        let first = rows.iter().map(|row| row.start).min().unwrap_or(0);
+        let last = rows
+            .iter()
+            .map(|row| row.start + row.values.len())
+            .max()
+            .unwrap_or(0);
+
+        let pixels = image.get_pixels();
+        let opaque = pixels.iter().all(|pixel| pixel.to_rgba().0[3] >= 1.0);
+
+        let mut horizontal = Vec::with_capacity((last - first) * width);
+        let mut line = Vec::with_capacity(source_width);
+        for y in first..last {
+            line.clear();
+            line.extend(
+                pixels[y * source_width..(y + 1) * source_width]
+                    .iter()
+                    .map(premultiply),
+            );
+            horizontal.extend(columns.iter().map(|column| convolve(&line, column)));
+        }
+
+        let mut output = Vec::with_capacity(size);
+        let mut row = vec![[0.0; 4]; width];
+        for weights in &rows {
+            row.fill([0.0; 4]);
+            for (offset, weight) in weights.values.iter().enumerate() {
+                let start = (weights.start + offset - first) * width;
+                for (sum, color) in row.iter_mut().zip(&horizontal[start..start + width]) {
+                    for (channel, value) in sum.iter_mut().zip(color) {
+                        *channel += value * weight;
+                    }
+                }
+            }
+
+            output.extend(row.iter().map(|color| unpremultiply(color, opaque)));
+        }
+
+        Ok(ImageBuffer::from_raw(width, height, output).with_orientation(image.orientation()))
+    }
+}
+
+/// Como operacao de `Image::transform`, redimensiona para exatamente `width` x `height`
+impl Transform for Resize {
+    fn apply<I: Image>(&self, image: &I) -> Result<ImageBuffer<I::Pixel>, FilterError> {
+        Ok(Resize::apply(self, image)?)
+    }
+}
+
+// Utils Functions
+fn full_window(image: &impl Image) -> Window {
+    Window {
+        x: 0.0,
+        y: 0.0,
+        width: image.widht() as f64,
+        height: image.height() as f64,
+    }
+}
+
+/// Pesos de cada pixel de saida num eixo. Ao reduzir, o raio do filtro cresce na mesma
+/// proporcao, para todos os pixels da origem contribuirem
+fn weights(
+    filter: Interpolation,
+    size: usize,
+    output: usize,
+    offset: f64,
+    span: f64,
+) -> Vec<Weights> {
+    let scale = span / output as f64;
+    let filter_scale = scale.max(1.0);
+    let support = filter.support() as f64 * filter_scale;
+
+    (0..output)
+        .map(|index| {
+            let center = offset + (index as f64 + 0.5) * scale;
+            let nearest = Weights {
+                start: (center.floor().max(0.0) as usize).min(size - 1),
+                values: vec![1.0],
+            };
+            if filter == Interpolation::Nearest {
+                return nearest;
+            }
+
+            let start = ((center - support).floor().max(0.0) as usize).min(size - 1);
+            let end = ((center + support).ceil() as usize).clamp(start + 1, size);
+            let mut values = (start..end)
+                .map(|source| {
+                    let distance = (source as f64 + 0.5 - center) / filter_scale;
+                    filter.weight(distance as f32)
+                })
+                .collect::<Vec<_>>();
+
+            let total = values.iter().sum::<f32>();
+            if total.abs() < MIN_WEIGHT_SUM {
+                return nearest;
+            }
+            values.iter_mut().for_each(|value| *value /= total);
+
+            Weights { start, values }
+        })
+        .collect()
+}
+
+fn convolve(line: &[[f32; 4]], weights: &Weights) -> [f32; 4] {
+    let mut sum = [0.0; 4];
+
+    for (offset, weight) in weights.values.iter().enumerate() {
+        let color = line[weights.start + offset];
+        for (channel, value) in sum.iter_mut().zip(color) {
+            *channel += value * weight;
+        }
+    }
+
+    sum
+}
+
+pub(super) fn premultiply(pixel: &impl Pixel) -> [f32; 4] {
+    let [red, green, blue, alpha] = pixel.to_rgba().0;
+    [red * alpha, green * alpha, blue * alpha, alpha]
+}
+
+/// Volta as cores para sem alpha multiplicado. So o alpha e limitado; o limite das cores fica
+/// com o tipo de pixel (f32 mantem valores fora de 0.0..=1.0)
+pub(super) fn unpremultiply<P: Pixel>(color: &[f32; 4], opaque: bool) -> P {
+    let [red, green, blue, alpha] = *color;
+    if opaque {
+        return P::from_rgba(Rgba([red, green, blue, 1.0]));
+    }
+
+    let alpha = alpha.clamp(0.0, 1.0);
+    if alpha == 0.0 {
+        return P::from_rgba(Rgba([0.0; 4]));
+    }
+
+    P::from_rgba(Rgba([red / alpha, green / alpha, blue / alpha, alpha]))
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        return 1.0;
+    }
+
+    let x = x * PI;
+    x.sin() / x
+}
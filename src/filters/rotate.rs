@@ -0,0 +1,190 @@
+use super::{
+    FilterError, Transform,
+    resize::{Interpolation, premultiply, unpremultiply},
+};
+use crate::images::{Image, Orientation, RGB, buffer::ImageBuffer};
+
+// Consts...
+/// Folga no calculo do tamanho expandido, para 90 graus nao ganhar um pixel por arredondamento
+const SIZE_EPSILON: f64 = 1e-6;
+
+/// Gira a imagem 90 graus no sentido horario
+pub struct Rotate90;
+
+/// Gira a imagem 180 graus
+pub struct Rotate180;
+
+/// Gira a imagem 270 graus no sentido horario (90 no anti-horario)
+pub struct Rotate270;
+
+/// Gira a imagem `degrees` graus no sentido horario (negativo gira no anti-horario) em volta do
+/// centro. Com `expand` a imagem cresce para caber inteira; sem ele mantem o tamanho e corta os
+/// cantos. As areas descobertas sao preenchidas com `background`
+pub struct Rotate {
+    pub degrees: f32,
+    pub interpolation: Interpolation,
+    pub expand: bool,
+    pub background: RGB,
+}
+
+impl Rotate {
+    pub fn new(degrees: f32, interpolation: Interpolation, expand: bool, background: RGB) -> Self {
+        Self {
+            degrees,
+            interpolation,
+            expand,
+            background,
+        }
+    }
+}
+
+impl Transform for Rotate90 {
+    fn apply<I: Image>(&self, image: &I) -> Result<ImageBuffer<I::Pixel>, FilterError> {
+        rotate(image, Orientation::Rotate90)
+    }
+}
+
+impl Transform for Rotate180 {
+    fn apply<I: Image>(&self, image: &I) -> Result<ImageBuffer<I::Pixel>, FilterError> {
+        rotate(image, Orientation::Rotate180)
+    }
+}
+
+impl Transform for Rotate270 {
+    fn apply<I: Image>(&self, image: &I) -> Result<ImageBuffer<I::Pixel>, FilterError> {
+        rotate(image, Orientation::Rotate270)
+    }
+}
+
+impl Transform for Rotate {
+    /// Cada pixel de saida e amostrado na origem pela rotacao inversa. Vizinhos fora da imagem
+    /// contam como `background`, o que suaviza as bordas
+    fn apply<I: Image>(&self, image: &I) -> Result<ImageBuffer<I::Pixel>, FilterError> {
+        if !self.degrees.is_finite() {
+            return Err(FilterError::InvalidDimensions);
+        }
+
+        let (widht, height) = (image.widht(), image.height());
+        let radians = (self.degrees as f64).to_radians();
+        let (sin, cos) = radians.sin_cos();
+
+        let (new_width, new_height) = match self.expand {
+            true => (
+                expanded(widht as f64 * cos.abs() + height as f64 * sin.abs()),
+                expanded(widht as f64 * sin.abs() + height as f64 * cos.abs()),
+            ),
+            false => (widht, height),
+        };
+        let size = new_width
+            .checked_mul(new_height)
+            .ok_or(FilterError::InvalidDimensions)?;
+
+        let source = image
+            .get_pixels()
+            .iter()
+            .map(premultiply)
+            .collect::<Vec<_>>();
+        let background = premultiply(&self.background);
+        let opaque = background[3] >= 1.0 && source.iter().all(|color| color[3] >= 1.0);
+
+        let (center_x, center_y) = (widht as f64 / 2.0, height as f64 / 2.0);
+        let (new_center_x, new_center_y) = (new_width as f64 / 2.0, new_height as f64 / 2.0);
+        let sampler = Sampler {
+            source: &source,
+            widht,
+            height,
+            background,
+            interpolation: self.interpolation,
+        };
+
+        let mut pixels = Vec::with_capacity(size);
+        for y in 0..new_height {
+            let dy = y as f64 + 0.5 - new_center_y;
+            for x in 0..new_width {
+                let dx = x as f64 + 0.5 - new_center_x;
+                let source_x = center_x + dx * cos + dy * sin;
+                let source_y = center_y - dx * sin + dy * cos;
+
+                let color = sampler.sample(source_x, source_y);
+                pixels.push(unpremultiply(&color, opaque));
+            }
+        }
+
+        Ok(ImageBuffer::from_pixels(new_width, new_height, pixels)?)
+    }
+}
+
+/// Amostra a origem (cores com alpha multiplicado) num ponto qualquer
+struct Sampler<'a> {
+    source: &'a [[f32; 4]],
+    widht: usize,
+    height: usize,
+    background: [f32; 4],
+    interpolation: Interpolation,
+}
+
+impl Sampler<'_> {
+    /// `x` e `y` sao coordenadas continuas: o centro do pixel (i, j) fica em (i + 0.5, j + 0.5)
+    fn sample(&self, x: f64, y: f64) -> [f32; 4] {
+        if self.interpolation == Interpolation::Nearest {
+            return self.get(x.floor(), y.floor());
+        }
+
+        let support = self.interpolation.support() as f64;
+        let taps = |center: f64| {
+            let first = (center - 0.5 - support).floor() + 1.0;
+            let last = (center - 0.5 + support).floor();
+            (first as i64..=last as i64).map(move |index| {
+                let distance = index as f64 + 0.5 - center;
+                (index as f64, self.interpolation.weight(distance as f32))
+            })
+        };
+
+        let mut sum = [0.0; 4];
+        let mut total = 0.0;
+        for (row, weight_y) in taps(y) {
+            for (column, weight_x) in taps(x) {
+                let weight = weight_x * weight_y;
+                if weight == 0.0 {
+                    continue;
+                }
+
+                let color = self.get(column, row);
+                for (channel, value) in sum.iter_mut().zip(color) {
+                    *channel += value * weight;
+                }
+                total += weight;
+            }
+        }
+
+        match total == 0.0 {
+            true => self.get(x.floor(), y.floor()),
+            false => sum.map(|channel| channel / total),
+        }
+    }
+
+    fn get(&self, x: f64, y: f64) -> [f32; 4] {
+        if x < 0.0 || y < 0.0 || x >= self.widht as f64 || y >= self.height as f64 {
+            return self.background;
+        }
+
+        self.source[y as usize * self.widht + x as usize]
+    }
+}
+
+// Utils Functions
+/// Os giros retos sao as mesmas operacoes usadas para exibir a orientacao do EXIF
+fn rotate<I: Image>(
+    image: &I,
+    orientation: Orientation,
+) -> Result<ImageBuffer<I::Pixel>, FilterError> {
+    let (pixels, width, height) =
+        orientation.apply(image.get_pixels(), image.widht(), image.height());
+
+    Ok(ImageBuffer::from_pixels(width, height, pixels)?)
+}
+
+/// Tamanho inteiro que cabe a medida girada, com pelo menos 1 pixel
+fn expanded(size: f64) -> usize {
+    ((size - SIZE_EPSILON).ceil() as usize).max(1)
+}
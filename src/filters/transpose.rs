@@ -0,0 +1,14 @@
+use super::{FilterError, Transform};
+use crate::images::{Image, Orientation, buffer::ImageBuffer};
+
+/// Espelha a imagem na diagonal principal, trocando x e y (e a largura pela altura)
+pub struct Transpose;
+
+impl Transform for Transpose {
+    fn apply<I: Image>(&self, image: &I) -> Result<ImageBuffer<I::Pixel>, FilterError> {
+        let (pixels, width, height) =
+            Orientation::Transpose.apply(image.get_pixels(), image.widht(), image.height());
+
+        Ok(ImageBuffer::from_pixels(width, height, pixels)?)
+    }
+}
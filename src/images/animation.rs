@@ -0,0 +1,187 @@
+use super::{
+    Format, Image, RGB,
+    buffer::ImageBuffer,
+    frames::{Disposal, Frame, Frames},
+    gif::Gif,
+};
+use crate::error::{ImageError, ImageResult};
+use crate::filters::{Filter, FilterError};
+
+// Structs...
+/// Struct que representa uma imagem animada: uma sequencia de quadros posicionados numa tela,
+/// cada um com seu tempo e descarte. Diferente de `Image`, que e sempre uma figura so
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    frames: Frames,
+}
+
+impl Animation {
+    /// Cria uma animacao vazia com o tamanho da tela
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            frames: Frames::new(width, height),
+        }
+    }
+
+    pub fn from_frames(frames: Frames) -> Self {
+        Self { frames }
+    }
+
+    /// Abre uma animacao de qualquer formato suportado. Formatos sem animacao viram um quadro so
+    pub fn open(path: impl Into<String>) -> ImageResult<Self> {
+        let path = path.into();
+
+        match Format::from_path(&path)? {
+            Format::GIF => Ok(Gif::open(path)?.into()),
+            _ => Ok(ImageBuffer::from(super::open(path)?).into()),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.frames.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.frames.height()
+    }
+
+    /// Quantidade de vezes que a animacao toca, 0 para sempre
+    pub fn loop_count(&self) -> u16 {
+        self.frames.loop_count()
+    }
+
+    pub fn set_loop_count(&mut self, loop_count: u16) {
+        self.frames.set_loop_count(loop_count);
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn frames(&self) -> &Frames {
+        &self.frames
+    }
+
+    pub fn frames_mut(&mut self) -> &mut Frames {
+        &mut self.frames
+    }
+
+    pub fn into_frames(self) -> Frames {
+        self.frames
+    }
+
+    /// Aplica o filtro em cada quadro, do jeito que ele esta guardado. Quadros menores que a tela
+    /// sao filtrados sozinhos; use `filter_composited` para filtros que dependem da tela inteira
+    /// (espelhar, borrar perto das bordas do quadro, etc)
+    pub fn filter(&mut self, filter: impl Filter) -> Result<(), FilterError> {
+        for frame in self.frames.iter_mut() {
+            filter.apply(frame.image_mut())?;
+        }
+
+        Ok(())
+    }
+
+    /// Junta os quadros em telas inteiras (`coalesce`) e depois aplica o filtro em cada uma
+    pub fn filter_composited(&mut self, filter: impl Filter) -> Result<(), FilterError> {
+        self.coalesce()?;
+        self.filter(filter)
+    }
+
+    /// Desenha a animacao e devolve a tela inteira como ela fica ao mostrar cada quadro. Falha
+    /// com `DimensionOverflow` se a posicao mais o tamanho de um quadro nao cabe em `usize`
+    pub fn composite(&self) -> ImageResult<Vec<ImageBuffer>> {
+        let (width, height) = (self.width(), self.height());
+        let background = RGB::new(0, 0, 0, Some(0));
+        let mut canvas = vec![background.clone(); width * height];
+        let mut output = Vec::with_capacity(self.len());
+
+        for frame in &self.frames {
+            let previous = (frame.disposal() == Disposal::Previous).then(|| canvas.clone());
+            let image = frame.image();
+            let right = frame.left().checked_add(image.widht());
+            let bottom = frame.top().checked_add(image.height());
+            let (Some(right), Some(bottom)) = (right, bottom) else {
+                return Err(ImageError::DimensionOverflow);
+            };
+            let columns = frame.left().min(width)..right.min(width);
+            let rows = frame.top().min(height)..bottom.min(height);
+
+            for y in rows.clone() {
+                for x in columns.clone() {
+                    let source =
+                        &image.get_pixels()[(y - frame.top()) * image.widht() + x - frame.left()];
+                    let target = &mut canvas[y * width + x];
+                    *target = blend(target, source);
+                }
+            }
+
+            output.push(ImageBuffer::from_raw(width, height, canvas.clone()));
+
+            match (frame.disposal(), previous) {
+                (Disposal::Background, _) => {
+                    for y in rows {
+                        canvas[y * width + columns.start..y * width + columns.end]
+                            .fill(background.clone());
+                    }
+                }
+                (Disposal::Previous, Some(previous)) => canvas = previous,
+                _ => {}
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Troca cada quadro pela tela inteira desenhada (`composite`), mantendo os tempos. Depois
+    /// disso todos os quadros ficam em (0, 0) e limpam a tela ao sair, ja que cada um e completo
+    pub fn coalesce(&mut self) -> ImageResult<()> {
+        let canvases = self.composite()?;
+
+        for (frame, canvas) in self.frames.iter_mut().zip(canvases) {
+            *frame.image_mut() = canvas;
+            frame.set_offset(0, 0);
+            frame.set_disposal(Disposal::Background);
+        }
+
+        Ok(())
+    }
+}
+
+impl From<ImageBuffer> for Animation {
+    fn from(image: ImageBuffer) -> Self {
+        let mut frames = Frames::new(image.widht(), image.height());
+        frames.push(Frame::new(image));
+
+        Self { frames }
+    }
+}
+
+// Utils Functions
+/// Desenha `source` sobre `target` usando o alpha (sem alpha conta como opaco)
+fn blend(target: &RGB, source: &RGB) -> RGB {
+    let alpha = source.alpha().unwrap_or(u8::MAX) as u32;
+    match alpha {
+        0 => return target.clone(),
+        255 => return source.clone(),
+        _ => {}
+    }
+
+    let below = target.alpha().unwrap_or(u8::MAX) as u32 * (255 - alpha) / 255;
+    let total = alpha + below;
+    let channel = |s: u8, t: u8| ((s as u32 * alpha + t as u32 * below + total / 2) / total) as u8;
+
+    RGB::new(
+        channel(source.red(), target.red()),
+        channel(source.green(), target.green()),
+        channel(source.blue(), target.blue()),
+        Some(total as u8),
+    )
+}
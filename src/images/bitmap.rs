@@ -1,47 +1,420 @@
-use super::{Image, RGB, i32_from_le_bytes, u32_from_le_bytes};
-use std::fs::File;
-use std::io::{Read, Result as IOResult, Write};
-use std::usize;
+use super::{Image, Orientation, RGB, buffer::ImageBuffer, i32_from_le_bytes, u32_from_le_bytes};
+use crate::error::{ImageError, ImageResult};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+// Consts...
+const FILE_HEADER_SIZE: u32 = 14;
+const INFO_HEADER_SIZE: u32 = 40;
+/// Resolucao padrao das imagens criadas (72 DPI em pixels por metro)
+const DEFAULT_RESOLUTION: i32 = 2835;
+const V2_HEADER_SIZE: u32 = 52;
+const V3_HEADER_SIZE: u32 = 56;
+const V4_HEADER_SIZE: u32 = 108;
+const V5_HEADER_SIZE: u32 = 124;
+
+// Enums...
+/// Enum que representa os metodos de compressao do campo `biCompression`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Rgb,
+    Rle8,
+    Rle4,
+    Bitfields,
+    AlphaBitfields,
+}
+
+impl Compression {
+    fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Rgb),
+            1 => Some(Self::Rle8),
+            2 => Some(Self::Rle4),
+            3 => Some(Self::Bitfields),
+            6 => Some(Self::AlphaBitfields),
+            _ => None,
+        }
+    }
+
+    fn to_u32(self) -> u32 {
+        match self {
+            Self::Rgb => 0,
+            Self::Rle8 => 1,
+            Self::Rle4 => 2,
+            Self::Bitfields => 3,
+            Self::AlphaBitfields => 6,
+        }
+    }
+
+    fn is_rle(&self) -> bool {
+        matches!(self, Self::Rle8 | Self::Rle4)
+    }
+
+    fn is_bitfields(&self) -> bool {
+        matches!(self, Self::Bitfields | Self::AlphaBitfields)
+    }
+}
+
+/// Enum que representa o campo `bV4CSType` dos cabecalhos BITMAPV4/V5
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    CalibratedRgb,
+    Srgb,
+    Windows,
+    Linked,
+    Embedded,
+    Other(u32),
+}
+
+impl ColorSpace {
+    fn from_u32(value: u32) -> Self {
+        match value {
+            0 => Self::CalibratedRgb,
+            0x7352_4742 => Self::Srgb,
+            0x5769_6E20 => Self::Windows,
+            0x4C49_4E4B => Self::Linked,
+            0x4D42_4544 => Self::Embedded,
+            other => Self::Other(other),
+        }
+    }
+}
 
 /// Struct para representa um Bitmap Image, nao sendo obragorio o uso podendo implementar sua propria estrutura
 pub struct Bitmap {
     file_header: FileHeader,
     dib_header: DIBHeader,
     surface: Surface,
+    orientation: Orientation,
 }
 
 impl Bitmap {
-    fn size_in_bytes(&self) -> u32 {
+    /// Cria um bitmap de 24 ou 32 bits preenchido com uma unica cor
+    pub fn new(width: usize, height: usize, fill: RGB, bits_per_pixel: u16) -> ImageResult<Self> {
+        let size = width
+            .checked_mul(height)
+            .ok_or(ImageError::DimensionOverflow)?;
+
+        Self::from_pixels(width, height, vec![fill; size], bits_per_pixel)
+    }
+
+    /// Cria um bitmap de 24 ou 32 bits a partir de pixels em ordem de linhas, com a origem no
+    /// canto superior esquerdo. Os cabecalhos e o alinhamento das linhas sao calculados aqui
+    pub fn from_pixels(
+        width: usize,
+        height: usize,
+        pixels: Vec<RGB>,
+        bits_per_pixel: u16,
+    ) -> ImageResult<Self> {
+        if !matches!(bits_per_pixel, 24 | 32) {
+            return Err(ImageError::UnsupportedBitDepth(bits_per_pixel));
+        }
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("bmp dimensions are invalid"));
+        }
+        if width > i32::MAX as usize || height > i32::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(ImageError::InvalidData(
+                "pixel count does not match the dimensions",
+            ));
+        }
+
+        let size_image = row_stride(width, bits_per_pixel)
+            .and_then(|stride| stride.checked_mul(height))
+            .and_then(|size| u32::try_from(size).ok())
+            .ok_or(ImageError::DimensionOverflow)?;
+
+        let dib_header =
+            DIBHeader::from_dimensions(width as i32, height as i32, bits_per_pixel, size_image);
+        let pixel_start_of = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+        let file_header = FileHeader {
+            identify: String::from("BM"),
+            size_file: pixel_start_of
+                .checked_add(size_image)
+                .ok_or(ImageError::DimensionOverflow)?,
+            pixel_start_of,
+        };
+        let surface = Surface {
+            row_size: width as u32,
+            column_size: height as u32,
+            pixels,
+        };
+
+        Ok(Self {
+            file_header,
+            dib_header,
+            surface,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    pub fn size_in_bytes(&self) -> u32 {
         self.file_header.size_file
     }
 
     pub fn identify(&self) -> &str {
         &self.file_header.identify
     }
+
+    /// Tabela de cores das imagens indexadas (1, 4 e 8 bits), vazia nas demais
+    pub fn palette(&self) -> &[RGB] {
+        &self.dib_header.palette
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.dib_header.compression
+    }
+
+    /// Mascaras dos canais vermelho, verde, azul e alpha das imagens de 16 e 32 bits
+    pub fn channel_masks(&self) -> [u32; 4] {
+        self.dib_header.channel_masks()
+    }
+
+    /// Espaco de cor declarado nos cabecalhos V4/V5, `None` nos cabecalhos menores
+    pub fn color_space(&self) -> Option<ColorSpace> {
+        (self.dib_header.size_header >= V4_HEADER_SIZE)
+            .then(|| ColorSpace::from_u32(u32_from_le_bytes(&self.dib_header.bytes[56..60])))
+    }
+
+    /// Perfil ICC embutido (ou o nome do perfil ligado) de um cabecalho V5
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        (!self.dib_header.profile.is_empty()).then_some(self.dib_header.profile.as_slice())
+    }
+
+    /// Define a compressao usada ao salvar. `Rle8`/`Rle4` so se aplicam quando a imagem cabe em
+    /// uma paleta de 256/16 cores; caso contrario a imagem e salva sem compressao.
+    /// `Bitfields`/`AlphaBitfields` so existem em 16 e 32 bits (nas outras profundidades o pedido
+    /// e ignorado) e comecam com as mascaras padroes da profundidade
+    pub fn set_compression(&mut self, compression: Compression) {
+        if compression.is_bitfields() {
+            if !matches!(self.dib_header.pixels, 16 | 32) {
+                return;
+            }
+            if !self.dib_header.compression.is_bitfields() {
+                self.dib_header.masks = self.dib_header.channel_masks();
+            }
+        }
+
+        self.dib_header.compression = compression;
+    }
+
+    /// Le o DIB de um icone (ICO/CUR): sem o cabecalho de arquivo e com a altura contando a
+    /// mascara AND de 1 bit gravada depois dos pixels. A mascara vira alpha quando a imagem nao
+    /// tem um alpha proprio
+    pub(crate) fn decode_icon_dib(data: &[u8]) -> ImageResult<Self> {
+        if data.len() < INFO_HEADER_SIZE as usize {
+            return Err(ImageError::Truncated);
+        }
+
+        let size_header = u32_from_le_bytes(&data[0..4]);
+        let bits_per_pixel = u16::from_le_bytes([data[14], data[15]]);
+        let method = u32_from_le_bytes(&data[16..20]);
+        let colors_used = u32_from_le_bytes(&data[32..36]);
+
+        // Os icones nao tem o offset dos pixels, entao ele e calculado como o de um BMP
+        let palette_size = match bits_per_pixel {
+            1 | 4 | 8 if colors_used == 0 => 4 << bits_per_pixel,
+            1 | 4 | 8 => colors_used.min(256) * 4,
+            _ => 0,
+        };
+        let masks_size = match (size_header, Compression::from_u32(method)) {
+            (INFO_HEADER_SIZE, Some(Compression::Bitfields)) => 12,
+            (INFO_HEADER_SIZE, Some(Compression::AlphaBitfields)) => 16,
+            _ => 0,
+        };
+        let file_header = FileHeader {
+            identify: String::from("BM"),
+            size_file: 0,
+            pixel_start_of: (FILE_HEADER_SIZE + palette_size + masks_size)
+                .saturating_add(size_header),
+        };
+
+        let mut reader = std::io::Cursor::new(data);
+        let mut dib_header = DIBHeader::new(&mut reader, &file_header)?;
+        if dib_header.compression.is_rle() {
+            return Err(ImageError::UnsupportedCompression(method));
+        }
+        dib_header.height /= 2;
+        if dib_header.height <= 0 {
+            return Err(ImageError::InvalidData("ico dimensions are invalid"));
+        }
+
+        let mut surface = Surface::new(&mut reader, &dib_header)?;
+        let width = surface.row_size as usize;
+        let stride = row_stride(width, 1).ok_or(ImageError::DimensionOverflow)?;
+
+        // Arquivos antigos podem nao gravar a mascara; sem ela tudo e opaco
+        let mut mask = Vec::new();
+        reader
+            .take((stride * surface.column_size as usize) as u64)
+            .read_to_end(&mut mask)?;
+        let mut rows = mask.chunks_exact(stride).collect::<Vec<_>>();
+        rows.reverse();
+
+        let own_alpha = dib_header.pixels == 32
+            && surface
+                .pixels
+                .iter()
+                .any(|pixel| pixel.alpha.is_some_and(|alpha| alpha > 0));
+        if !own_alpha {
+            let transparent =
+                |x: usize, y: usize| rows.get(y).is_some_and(|row| read_index(row, x, 1) == 1);
+            let masked = (0..surface.pixels.len()).any(|i| transparent(i % width, i / width));
+
+            for (i, pixel) in surface.pixels.iter_mut().enumerate() {
+                pixel.alpha = match masked {
+                    true if transparent(i % width, i / width) => Some(0),
+                    true => Some(u8::MAX),
+                    false => None,
+                };
+            }
+        }
+
+        dib_header.size_image = 0;
+        Ok(Self {
+            file_header,
+            dib_header,
+            surface,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    /// Grava o bitmap como o DIB de um icone: sem o cabecalho de arquivo, com a altura dobrada e
+    /// a mascara AND (ligada nos pixels totalmente transparentes) depois dos pixels
+    pub(crate) fn encode_icon_dib(&self) -> Vec<u8> {
+        let mut dib_header = self.dib_header.clone();
+        dib_header.height = dib_header.height.saturating_abs();
+
+        let pixels = self.surface.to_bytes(&dib_header);
+        let stride = row_stride(self.widht(), 1).unwrap_or_default();
+        let mut mask = Vec::with_capacity(stride * self.height());
+        for row in self.surface.file_rows(&dib_header) {
+            let mut line = vec![0_u8; stride];
+            for (x, pixel) in row.iter().enumerate() {
+                if pixel.alpha == Some(0) {
+                    write_index(&mut line, x, 1, 1);
+                }
+            }
+            mask.append(&mut line);
+        }
+
+        dib_header.size_image = (pixels.len() + mask.len()) as u32;
+        dib_header.height *= 2;
+
+        let mut bytes = dib_header.to_bytes();
+        bytes.extend_from_slice(&dib_header.mask_bytes());
+        bytes.extend_from_slice(&dib_header.palette_bytes());
+        bytes.extend_from_slice(&pixels);
+        bytes.extend_from_slice(&mask);
+
+        bytes
+    }
+
+    /// Cabecalhos e pixels como sao gravados. A profundidade e a compressao podem mudar se os
+    /// pixels nao couberem mais na paleta
+    fn layout(&self) -> (FileHeader, DIBHeader, Vec<u8>) {
+        let mut file_header = self.file_header.clone();
+        let mut dib_header = self.dib_header.clone();
+
+        // Mantem a profundidade indexada apenas se todos os pixels ainda existem na paleta
+        if !dib_header.palette.is_empty() && !self.surface.fits_palette(&dib_header.palette) {
+            dib_header.colors_used = 0;
+            dib_header.palette.clear();
+        }
+
+        // RLE so existe para imagens indexadas, entao monta uma paleta se as cores couberem
+        if dib_header.compression.is_rle()
+            && dib_header.palette.is_empty()
+            && let Some(palette) = self.surface.build_palette(256)
+        {
+            dib_header.colors_used = palette.len() as u32;
+            dib_header.palette = palette;
+        }
+
+        // RLE so pode ser gravado de baixo para cima
+        if dib_header.compression.is_rle() {
+            dib_header.height = dib_header.height.saturating_abs();
+        }
+
+        if dib_header.palette.is_empty() {
+            if dib_header.pixels <= 8 {
+                dib_header.pixels = if self.surface.has_alpha() { 32 } else { 24 };
+            }
+            if dib_header.compression.is_rle() {
+                dib_header.compression = Compression::Rgb;
+            }
+        } else if dib_header.compression.is_rle() {
+            if dib_header.compression == Compression::Rle4 && dib_header.palette.len() <= 16 {
+                dib_header.pixels = 4;
+            } else {
+                dib_header.compression = Compression::Rle8;
+                dib_header.pixels = 8;
+            }
+        }
+
+        let pixels = if dib_header.compression.is_rle() {
+            self.surface.to_rle_bytes(&dib_header)
+        } else {
+            self.surface.to_bytes(&dib_header)
+        };
+        let masks = dib_header.mask_bytes().len();
+        let palette = dib_header.palette_bytes().len();
+
+        dib_header.size_image = pixels.len() as u32;
+        file_header.pixel_start_of = FILE_HEADER_SIZE
+            + dib_header.size_header
+            + (masks + palette + dib_header.extra.len()) as u32;
+        dib_header.profile_offset =
+            file_header.pixel_start_of - FILE_HEADER_SIZE + pixels.len() as u32;
+        file_header.size_file =
+            file_header.pixel_start_of + pixels.len() as u32 + dib_header.profile.len() as u32;
+
+        (file_header, dib_header, pixels)
+    }
 }
 
 impl Image for Bitmap {
-    fn open(path: impl Into<String>) -> IOResult<Bitmap> {
-        let path = path.into();
-        let mut image = File::open(path)?;
+    type Pixel = RGB;
+
+    fn decode(mut image: impl Read + Seek) -> ImageResult<Bitmap> {
+        let start = image.stream_position()?;
 
-        let file_header = FileHeader::new(&mut image);
-        let dib_header = DIBHeader::new(&mut image);
-        let surface = Surface::new(&mut image, &dib_header);
+        let file_header = FileHeader::new(&mut image)?;
+        let mut dib_header = DIBHeader::new(&mut image, &file_header)?;
+        let surface = Surface::new(&mut image, &dib_header)?;
+
+        // O perfil ICC fica em qualquer posicao, com offset relativo ao inicio do cabecalho DIB
+        if let Some((offset, size)) = dib_header.profile_location() {
+            image.seek(SeekFrom::Start(
+                start + FILE_HEADER_SIZE as u64 + offset as u64,
+            ))?;
+            image
+                .take(size as u64)
+                .read_to_end(&mut dib_header.profile)?;
+            if dib_header.profile.len() < size as usize {
+                return Err(ImageError::Truncated);
+            }
+        }
 
         Ok(Self {
             file_header,
             dib_header,
             surface,
+            orientation: Orientation::Normal,
         })
     }
 
-    fn save(&mut self, path: impl Into<String>) -> IOResult<()> {
-        let mut file = File::create_new(path.into())?;
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        let (file_header, dib_header, pixels) = self.layout();
 
-        file.write_all(&mut self.file_header.to_bytes())?;
-        file.write_all(&mut self.dib_header.to_bytes())?;
-        file.write_all(&mut self.surface.to_bytes())?;
+        writer.write_all(&file_header.to_bytes())?;
+        writer.write_all(&dib_header.to_bytes())?;
+        writer.write_all(&dib_header.mask_bytes())?;
+        writer.write_all(&dib_header.palette_bytes())?;
+        writer.write_all(&dib_header.extra)?;
+        writer.write_all(&pixels)?;
+        writer.write_all(&dib_header.profile)?;
 
         Ok(())
     }
@@ -53,12 +426,34 @@ impl Image for Bitmap {
         filter.apply(self)
     }
 
+    /// Atualiza as dimensoes do DIB (mantendo o sentido das linhas) e os tamanhos dos pixels e do
+    /// arquivo. O alinhamento das linhas segue a nova largura
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        let image = Self::from_pixels(width, height, pixels, 24)?;
+
+        self.dib_header.width = width as i32;
+        self.dib_header.height = if self.dib_header.height < 0 {
+            -(height as i32)
+        } else {
+            height as i32
+        };
+        self.surface = image.surface;
+
+        let (file_header, dib_header, _) = self.layout();
+        self.file_header.size_file = file_header.size_file;
+        self.file_header.pixel_start_of = file_header.pixel_start_of;
+        self.dib_header.size_image = dib_header.size_image;
+        self.dib_header.profile_offset = dib_header.profile_offset;
+
+        Ok(())
+    }
+
     fn widht(&self) -> usize {
-        self.dib_header.width.abs() as usize
+        self.dib_header.width.unsigned_abs() as usize
     }
 
     fn height(&self) -> usize {
-        self.dib_header.height.abs() as usize
+        self.dib_header.height.unsigned_abs() as usize
     }
 
     fn format(&self) -> super::Format {
@@ -78,27 +473,23 @@ impl Image for Bitmap {
     }
 
     fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
-        let width = self.dib_header.width.abs() as usize;
-        let height = self.dib_header.height.abs() as usize;
+        let width = self.widht();
 
-        if x >= width && y >= height {
+        if x >= width || y >= self.height() {
             return None;
         }
 
-        let index = (self.surface.pixels.len() - 1) - ((y * width + x) as usize);
-        self.surface.pixels.get_mut(index)
+        self.surface.pixels.get_mut(y * width + x)
     }
 
     fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
-        let width = self.dib_header.width.abs() as usize;
-        let height = self.dib_header.height.abs() as usize;
+        let width = self.widht();
 
-        if x >= width && y >= height {
+        if x >= width || y >= self.height() {
             return None;
         }
 
-        let index = (self.surface.pixels.len() - 1) - ((y * width + x) as usize);
-        self.surface.pixels.get(index)
+        self.surface.pixels.get(y * width + x)
     }
 
     fn slice_pixels(&mut self, range: std::ops::Range<usize>) -> &mut [RGB] {
@@ -108,19 +499,78 @@ impl Image for Bitmap {
     fn get_slice_pixels(&self, range: std::ops::Range<usize>) -> &[RGB] {
         &self.surface.pixels[range]
     }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (pixels, width, height) =
+            self.orientation
+                .apply(&self.surface.pixels, self.widht(), self.height());
+
+        // Mantem o sentido das linhas do arquivo original (negativo = de cima para baixo)
+        self.dib_header.width = width as i32;
+        self.dib_header.height = if self.dib_header.height < 0 {
+            -(height as i32)
+        } else {
+            height as i32
+        };
+        self.surface.row_size = width as u32;
+        self.surface.column_size = height as u32;
+        self.surface.pixels = pixels;
+        self.orientation = Orientation::Normal;
+    }
 }
 
-#[derive(Debug)]
+impl From<Bitmap> for ImageBuffer {
+    fn from(image: Bitmap) -> Self {
+        let (width, height) = (image.widht(), image.height());
+
+        ImageBuffer::from_raw(width, height, image.surface.pixels)
+            .with_orientation(image.orientation)
+    }
+}
+
+impl TryFrom<ImageBuffer> for Bitmap {
+    type Error = ImageError;
+
+    /// Gera um bitmap de 24 bits, ou de 32 bits se algum pixel tiver alpha
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let has_alpha = buffer
+            .get_pixels()
+            .iter()
+            .any(|pixel| pixel.alpha().is_some());
+        let bits_per_pixel = if has_alpha { 32 } else { 24 };
+        let orientation = buffer.orientation();
+
+        let mut image = Bitmap::from_pixels(width, height, buffer.into_pixels(), bits_per_pixel)?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+#[derive(Debug, Clone)]
 struct FileHeader {
     pub identify: String,
     pub size_file: u32,
     pub pixel_start_of: u32,
 }
 impl FileHeader {
-    pub fn new(image: &mut File) -> Self {
-        let mut extract = [0_u8; 14];
+    pub fn new(image: &mut impl Read) -> ImageResult<Self> {
+        let mut extract = [0_u8; FILE_HEADER_SIZE as usize];
+
+        image.read_exact(&mut extract)?;
 
-        image.read_exact(&mut extract).unwrap();
+        if &extract[0..2] != b"BM" {
+            return Err(ImageError::InvalidMagic);
+        }
 
         let mut identify = String::new();
         identify.push(extract[0] as char);
@@ -129,11 +579,11 @@ impl FileHeader {
         let size_file = u32_from_le_bytes(&extract[2..6]);
         let pixel_start_of = u32_from_le_bytes(&extract[10..]);
 
-        Self {
+        Ok(Self {
             identify,
             size_file,
             pixel_start_of,
-        }
+        })
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -158,40 +608,212 @@ impl FileHeader {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct DIBHeader {
     bytes: Vec<u8>,
     pub size_header: u32,
-    pub width: i32,  // 0..3
-    pub height: i32, // 4..7
-    pub pixels: u16, // 10..11
-                     //pub raw_size: u32, // 16..19
+    pub width: i32,               // 0..3
+    pub height: i32,              // 4..7
+    pub pixels: u16,              // 10..11
+    pub compression: Compression, // 12..15
+    pub size_image: u32,          // 16..19
+    pub colors_used: u32,         // 28..31
+    /// Mascaras R, G, B e A (36..51 nos cabecalhos V2+, ou logo apos o cabecalho de 40 bytes)
+    pub masks: [u32; 4],
+    pub profile_offset: u32, // 108..111
+    /// Perfil ICC dos cabecalhos V5, lido do offset indicado pelo cabecalho
+    pub profile: Vec<u8>,
+    /// Tabela de cores das imagens indexadas, ja convertida para RGB
+    pub palette: Vec<RGB>,
+    /// Bytes entre o cabecalho DIB (ou a paleta) e o inicio dos pixels
+    pub extra: Vec<u8>,
 }
 impl DIBHeader {
-    pub fn new(image: &mut File) -> Self {
-        let mut bytes: Vec<u8> = vec![0_u8; (4) as usize];
-        image.read_exact(&mut bytes).unwrap();
+    pub fn new(image: &mut impl Read, file_header: &FileHeader) -> ImageResult<Self> {
+        let mut bytes: Vec<u8> = vec![0_u8; 4];
+        image.read_exact(&mut bytes)?;
 
         let size_header = u32_from_le_bytes(&bytes);
+        if size_header == 12 {
+            return Err(ImageError::Unsupported("bmp OS/2 core header"));
+        }
+        if !(INFO_HEADER_SIZE..=V5_HEADER_SIZE).contains(&size_header) {
+            return Err(ImageError::InvalidData("bmp DIB header size is invalid"));
+        }
 
         let mut extract = vec![0_u8; (size_header - 4) as usize];
-        image.read_exact(&mut extract).unwrap();
+        image.read_exact(&mut extract)?;
 
         let width = i32_from_le_bytes(&extract[0..=3]);
         let height = i32_from_le_bytes(&extract[4..=7]);
 
         let pixels = u16::from_le_bytes([extract[10], extract[11]]);
+        let method = u32_from_le_bytes(&extract[12..=15]);
+        let size_image = u32_from_le_bytes(&extract[16..=19]);
+        let colors_used = u32_from_le_bytes(&extract[28..=31]);
+
+        if width <= 0 || height == 0 || height == i32::MIN {
+            return Err(ImageError::InvalidData("bmp dimensions are invalid"));
+        }
+
+        let compression = match (Compression::from_u32(method), pixels) {
+            (Some(Compression::Rgb), _) => Compression::Rgb,
+            (Some(Compression::Rle8), 8) => Compression::Rle8,
+            (Some(Compression::Rle4), 4) => Compression::Rle4,
+            (Some(bitfields @ (Compression::Bitfields | Compression::AlphaBitfields)), 16 | 32) => {
+                bitfields
+            }
+            _ => return Err(ImageError::UnsupportedCompression(method)),
+        };
+
+        let extra_size = file_header
+            .pixel_start_of
+            .checked_sub(FILE_HEADER_SIZE + size_header)
+            .ok_or(ImageError::InvalidData(
+                "bmp pixel offset overlaps the headers",
+            ))?;
+
+        let mut extra = Vec::new();
+        image.take(extra_size as u64).read_to_end(&mut extra)?;
+        if extra.len() < extra_size as usize {
+            return Err(ImageError::Truncated);
+        }
+
+        let palette = if pixels <= 8 {
+            read_palette(&mut extra, pixels, colors_used)?
+        } else {
+            Vec::new()
+        };
+
+        let mut masks = [0_u32; 4];
+        if compression.is_bitfields() {
+            if size_header >= V2_HEADER_SIZE {
+                let count = if size_header >= V3_HEADER_SIZE { 4 } else { 3 };
+                for (i, mask) in masks.iter_mut().enumerate().take(count) {
+                    *mask = u32_from_le_bytes(&extract[36 + i * 4..40 + i * 4]);
+                }
+            } else {
+                // No cabecalho de 40 bytes as mascaras vem logo depois dele
+                let count = if compression == Compression::AlphaBitfields {
+                    4
+                } else {
+                    3
+                };
+                if extra.len() < count * 4 {
+                    return Err(ImageError::Truncated);
+                }
+                for (mask, value) in masks
+                    .iter_mut()
+                    .zip(extra.drain(..count * 4).as_slice().chunks(4))
+                {
+                    *mask = u32_from_le_bytes(value);
+                }
+            }
+        }
+
+        let profile_offset = if size_header >= V5_HEADER_SIZE {
+            u32_from_le_bytes(&extract[108..112])
+        } else {
+            0
+        };
 
         bytes.append(&mut extract);
-        DIBHeader {
-            bytes: bytes,
+        Ok(DIBHeader {
+            bytes,
             size_header,
             width,
             height,
             pixels,
+            compression,
+            size_image,
+            colors_used,
+            masks,
+            profile_offset,
+            profile: Vec::new(),
+            palette,
+            extra,
+        })
+    }
+
+    /// Cabecalho BITMAPINFOHEADER (40 bytes) de uma imagem sem compressao, de baixo para cima
+    pub fn from_dimensions(width: i32, height: i32, pixels: u16, size_image: u32) -> Self {
+        let mut bytes = vec![0_u8; INFO_HEADER_SIZE as usize];
+        bytes[12..14].copy_from_slice(&1_u16.to_le_bytes());
+        bytes[24..28].copy_from_slice(&DEFAULT_RESOLUTION.to_le_bytes());
+        bytes[28..32].copy_from_slice(&DEFAULT_RESOLUTION.to_le_bytes());
+
+        Self {
+            bytes,
+            size_header: INFO_HEADER_SIZE,
+            width,
+            height,
+            pixels,
+            compression: Compression::Rgb,
+            size_image,
+            colors_used: 0,
+            masks: [0; 4],
+            profile_offset: 0,
+            profile: Vec::new(),
+            palette: Vec::new(),
+            extra: Vec::new(),
         }
     }
 
+    /// Mascaras efetivas: as do arquivo com BI_BITFIELDS, ou as padroes da profundidade
+    pub fn channel_masks(&self) -> [u32; 4] {
+        if self.compression.is_bitfields() {
+            return self.masks;
+        }
+
+        match self.pixels {
+            16 => [0x7C00, 0x03E0, 0x001F, 0],
+            32 => [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000],
+            _ => [0; 4],
+        }
+    }
+
+    /// Mascaras gravadas apos o cabecalho de 40 bytes, vazio quando ficam no proprio cabecalho
+    pub fn mask_bytes(&self) -> Vec<u8> {
+        if !self.compression.is_bitfields() || self.size_header >= V2_HEADER_SIZE {
+            return Vec::new();
+        }
+
+        let count = if self.compression == Compression::AlphaBitfields {
+            4
+        } else {
+            3
+        };
+        self.masks[..count]
+            .iter()
+            .flat_map(|mask| mask.to_le_bytes())
+            .collect()
+    }
+
+    /// Offset e tamanho do perfil ICC quando o espaco de cor e embutido ou ligado
+    pub fn profile_location(&self) -> Option<(u32, u32)> {
+        if self.size_header < V5_HEADER_SIZE {
+            return None;
+        }
+
+        let color_space = ColorSpace::from_u32(u32_from_le_bytes(&self.bytes[56..60]));
+        let size = u32_from_le_bytes(&self.bytes[116..120]);
+
+        match color_space {
+            ColorSpace::Embedded | ColorSpace::Linked if size > 0 => {
+                Some((self.profile_offset, size))
+            }
+            _ => None,
+        }
+    }
+
+    /// Tabela de cores no formato do arquivo (BGR0)
+    pub fn palette_bytes(&self) -> Vec<u8> {
+        self.palette
+            .iter()
+            .flat_map(|c| [c.blue, c.green, c.red, 0])
+            .collect()
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = self.bytes.clone();
 
@@ -217,86 +839,490 @@ impl DIBHeader {
         bytes[14] = pixels_bytes[0];
         bytes[15] = pixels_bytes[1];
 
+        bytes[16..20].copy_from_slice(&self.compression.to_u32().to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.size_image.to_le_bytes());
+        bytes[32..36].copy_from_slice(&self.colors_used.to_le_bytes());
+
+        if self.compression.is_bitfields() && self.size_header >= V2_HEADER_SIZE {
+            let count = if self.size_header >= V3_HEADER_SIZE {
+                4
+            } else {
+                3
+            };
+            for (i, mask) in self.masks.iter().enumerate().take(count) {
+                bytes[40 + i * 4..44 + i * 4].copy_from_slice(&mask.to_le_bytes());
+            }
+        }
+
+        if self.profile_location().is_some() {
+            bytes[112..116].copy_from_slice(&self.profile_offset.to_le_bytes());
+        }
+
         bytes
     }
 }
 
 #[derive(Debug)]
 struct Surface {
-    pub padding: usize,
     pub row_size: u32,
     pub column_size: u32,
-    pub alpha_channel: bool,
     pub pixels: Vec<RGB>,
 }
 impl Surface {
-    pub fn new(image: &mut File, dib: &DIBHeader) -> Self {
-        let padding = {
-            let i = (dib.width * (dib.pixels as i32)) % 4;
-            if i == 0 { 0 } else { (4 - i) as usize }
-        };
+    pub fn new(image: &mut impl Read, dib: &DIBHeader) -> ImageResult<Self> {
+        match dib.pixels {
+            1 | 4 | 8 | 16 | 24 | 32 => {}
+            bits => return Err(ImageError::UnsupportedBitDepth(bits)),
+        }
 
-        let total_pixels = i32::abs(dib.width * dib.height);
-        let bytes_per_pixels = dib.pixels / 8;
+        let row_size = dib.width.unsigned_abs() as usize;
+        let column_size = dib.height.unsigned_abs() as usize;
 
-        let mut bytes = vec![0_u8; (total_pixels * (bytes_per_pixels) as i32) as usize];
-        let mut pixels = vec![RGB::default(); total_pixels as usize];
+        if dib.compression.is_rle() {
+            return Self::new_rle(image, dib, row_size, column_size);
+        }
 
-        image.read_exact(&mut bytes).unwrap();
+        let stride = row_stride(row_size, dib.pixels).ok_or(ImageError::DimensionOverflow)?;
+        let total_bytes = stride
+            .checked_mul(column_size)
+            .ok_or(ImageError::DimensionOverflow)?;
 
-        let mut index = bytes.len() - 1;
+        // Le de forma incremental para que um cabecalho mentiroso nao aloque memoria demais
+        let mut bytes = Vec::new();
+        image.take(total_bytes as u64).read_to_end(&mut bytes)?;
+        if bytes.len() < total_bytes {
+            return Err(ImageError::Truncated);
+        }
 
-        for _ in 0..i32::abs(dib.height) {
-            index -= padding;
-            for _ in 0..i32::abs(dib.width) {
-                let pixel = pixels.get_mut(index / bytes_per_pixels as usize).unwrap();
-                for i in 0..bytes_per_pixels {
-                    match i {
-                        0 => pixel.red = bytes[index],
-                        1 => pixel.green = bytes[index],
-                        2 => pixel.blue = bytes[index],
-                        _ => pixel.alpha = Some(bytes[index]),
+        let masks = dib.channel_masks();
+
+        let mut pixels = Vec::with_capacity(row_size * column_size);
+        for row in bytes.chunks_exact(stride) {
+            match dib.pixels {
+                24 => {
+                    for color in row[..row_size * 3].chunks_exact(3) {
+                        pixels.push(RGB::new(color[2], color[1], color[0], None));
                     }
+                }
+                16 | 32 => {
+                    let bytes_per_pixels = dib.pixels as usize / 8;
+                    for color in row[..row_size * bytes_per_pixels].chunks_exact(bytes_per_pixels) {
+                        let mut value = [0_u8; 4];
+                        value[..bytes_per_pixels].copy_from_slice(color);
+                        let value = u32::from_le_bytes(value);
 
-                    index = index.checked_sub(1).unwrap_or(0);
+                        pixels.push(RGB::new(
+                            read_channel(value, masks[0]).unwrap_or_default(),
+                            read_channel(value, masks[1]).unwrap_or_default(),
+                            read_channel(value, masks[2]).unwrap_or_default(),
+                            read_channel(value, masks[3]),
+                        ));
+                    }
+                }
+                bits => {
+                    for x in 0..row_size {
+                        let index = read_index(row, x, bits);
+                        pixels.push(dib.palette.get(index).cloned().unwrap_or_default());
+                    }
                 }
             }
         }
 
-        Self {
-            alpha_channel: if bytes_per_pixels == 32 { true } else { false },
-            padding,
+        if dib.height > 0 {
+            flip_rows(&mut pixels, row_size);
+        }
+
+        Ok(Self {
             pixels,
-            row_size: dib.width.abs() as u32,
-            column_size: dib.height.abs() as u32,
+            row_size: row_size as u32,
+            column_size: column_size as u32,
+        })
+    }
+
+    fn new_rle(
+        image: &mut impl Read,
+        dib: &DIBHeader,
+        row_size: usize,
+        column_size: usize,
+    ) -> ImageResult<Self> {
+        let total_pixels = row_size
+            .checked_mul(column_size)
+            .ok_or(ImageError::DimensionOverflow)?;
+
+        let mut bytes = Vec::new();
+        if dib.size_image > 0 {
+            image.take(dib.size_image as u64).read_to_end(&mut bytes)?;
+        } else {
+            image.read_to_end(&mut bytes)?;
+        }
+
+        // Cada par de bytes do fluxo grava no maximo 255 pixels. Os escapes de fim de linha e
+        // delta pulam mais do que isso, mas depender deles para cobrir um cabecalho enorme e
+        // tratado como truncado, para que o tamanho declarado nao chegue a ser alocado
+        let reachable = bytes.len().div_ceil(2).saturating_mul(255);
+        if total_pixels > reachable {
+            return Err(ImageError::Truncated);
+        }
+
+        let indices = decode_rle(&bytes, row_size, total_pixels, dib.pixels)?;
+        let mut pixels: Vec<RGB> = indices
+            .iter()
+            .map(|index| {
+                dib.palette
+                    .get(*index as usize)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        if dib.height > 0 {
+            flip_rows(&mut pixels, row_size);
         }
+
+        Ok(Self {
+            pixels,
+            row_size: row_size as u32,
+            column_size: column_size as u32,
+        })
     }
 
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let total_pixels = (self.row_size * self.column_size) as usize;
-        let bytes_per_pixels = if self.alpha_channel { 4 } else { 3 };
-
-        let mut bytes = vec![0_u8; total_pixels * bytes_per_pixels];
-
-        let mut index = bytes.len() - 1;
-
-        for x in 0..self.row_size {
-            index -= self.padding;
-            for y in 0..self.column_size {
-                let pixel: &RGB = self.pixels.get(index / bytes_per_pixels).unwrap();
-                for i in 0..bytes_per_pixels {
-                    match i {
-                        0 => bytes[index] = pixel.red,
-                        1 => bytes[index] = pixel.green,
-                        2 => bytes[index] = pixel.blue,
-                        _ => bytes[index] = pixel.alpha.unwrap_or(0),
-                    }
+    pub fn to_bytes(&self, dib: &DIBHeader) -> Vec<u8> {
+        let row_size = self.row_size as usize;
+        let stride = row_stride(row_size, dib.pixels).unwrap_or_default();
+        let indices = palette_indices(&dib.palette);
+        let masks = dib.channel_masks();
+
+        let mut bytes = Vec::with_capacity(stride * self.column_size as usize);
+
+        for row in self.file_rows(dib) {
+            let mut line = vec![0_u8; stride];
+
+            for (x, pixel) in row.iter().enumerate() {
+                match dib.pixels {
+                    24 => line[x * 3..x * 3 + 3].copy_from_slice(&[
+                        pixel.blue,
+                        pixel.green,
+                        pixel.red,
+                    ]),
+                    16 | 32 => {
+                        let bytes_per_pixels = dib.pixels as usize / 8;
+                        let value = write_channel(pixel.red, masks[0])
+                            | write_channel(pixel.green, masks[1])
+                            | write_channel(pixel.blue, masks[2])
+                            | write_channel(pixel.alpha.unwrap_or(255), masks[3]);
 
-                    index = index.checked_sub(1).unwrap_or(0);
+                        line[x * bytes_per_pixels..(x + 1) * bytes_per_pixels]
+                            .copy_from_slice(&value.to_le_bytes()[..bytes_per_pixels]);
+                    }
+                    bits => {
+                        let index = indices.get(&rgb_key(pixel)).copied().unwrap_or_default();
+                        write_index(&mut line, x, bits, index);
+                    }
                 }
             }
+
+            bytes.append(&mut line);
+        }
+
+        bytes
+    }
+
+    /// Codifica os pixels com RLE8 ou RLE4, terminando cada linha com EOL e o bitmap com EOF
+    pub fn to_rle_bytes(&self, dib: &DIBHeader) -> Vec<u8> {
+        let indices = palette_indices(&dib.palette);
+        let rows = self.file_rows(dib);
+        let mut bytes = Vec::new();
+
+        for (y, row) in rows.iter().enumerate() {
+            let row = row
+                .iter()
+                .map(|pixel| indices.get(&rgb_key(pixel)).copied().unwrap_or_default())
+                .collect::<Vec<_>>();
+
+            encode_rle_row(&mut bytes, &row, dib.pixels);
+
+            if y + 1 == rows.len() {
+                bytes.extend_from_slice(&[0, 1]);
+            } else {
+                bytes.extend_from_slice(&[0, 0]);
+            }
         }
 
         bytes
     }
+
+    /// Monta uma paleta com as cores usadas, ou `None` se houver mais de `max_colors` cores
+    pub fn build_palette(&self, max_colors: usize) -> Option<Vec<RGB>> {
+        let mut palette = Vec::new();
+        let mut seen = HashMap::new();
+
+        for pixel in &self.pixels {
+            if seen.insert(rgb_key(pixel), ()).is_none() {
+                if palette.len() == max_colors {
+                    return None;
+                }
+                palette.push(RGB::new(pixel.red, pixel.green, pixel.blue, None));
+            }
+        }
+
+        Some(palette)
+    }
+
+    /// Verifica se todos os pixels podem ser representados pela paleta
+    pub fn fits_palette(&self, palette: &[RGB]) -> bool {
+        let indices = palette_indices(palette);
+        self.pixels
+            .iter()
+            .all(|pixel| indices.contains_key(&rgb_key(pixel)))
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.pixels.iter().any(|pixel| pixel.alpha.is_some())
+    }
+
+    /// Linhas na ordem em que sao gravadas: de baixo para cima quando a altura e positiva
+    fn file_rows(&self, dib: &DIBHeader) -> Vec<&[RGB]> {
+        let mut rows = self
+            .pixels
+            .chunks(self.row_size as usize)
+            .collect::<Vec<_>>();
+        if dib.height > 0 {
+            rows.reverse();
+        }
+
+        rows
+    }
+}
+
+/// Inverte a ordem das linhas, convertendo entre a ordem do arquivo e a origem superior esquerda
+fn flip_rows(pixels: &mut [RGB], width: usize) {
+    if width == 0 {
+        return;
+    }
+
+    let height = pixels.len() / width;
+    for y in 0..height / 2 {
+        let (top, bottom) = pixels.split_at_mut((height - 1 - y) * width);
+        top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+    }
+}
+
+/// Tamanho em bytes de uma linha de pixels, alinhada em 4 bytes
+fn row_stride(width: usize, bits_per_pixel: u16) -> Option<usize> {
+    width
+        .checked_mul(bits_per_pixel as usize)?
+        .checked_add(31)
+        .map(|bits| bits / 32 * 4)
+}
+
+/// Extrai a tabela de cores (BGR0) do inicio dos bytes apos o cabecalho DIB
+fn read_palette(
+    extra: &mut Vec<u8>,
+    bits_per_pixel: u16,
+    colors_used: u32,
+) -> ImageResult<Vec<RGB>> {
+    let max_colors = 1_usize << bits_per_pixel;
+    let colors = match colors_used as usize {
+        0 => max_colors,
+        n if n <= max_colors => n,
+        _ => return Err(ImageError::InvalidData("bmp palette has too many colors")),
+    };
+
+    // Alguns arquivos declaram mais cores do que realmente gravam
+    let colors = colors.min(extra.len() / 4);
+    if colors == 0 {
+        return Err(ImageError::InvalidData("bmp indexed image has no palette"));
+    }
+
+    let palette = extra
+        .drain(..colors * 4)
+        .collect::<Vec<_>>()
+        .chunks_exact(4)
+        .map(|c| RGB::new(c[2], c[1], c[0], None))
+        .collect();
+
+    Ok(palette)
+}
+
+/// Extrai um canal pela mascara e o escala para 8 bits, `None` se a mascara for vazia
+fn read_channel(value: u32, mask: u32) -> Option<u8> {
+    if mask == 0 {
+        return None;
+    }
+
+    let shift = mask.trailing_zeros();
+    let max = (mask >> shift) as u64;
+    let channel = ((value & mask) >> shift) as u64;
+
+    Some(((channel * 255 + max / 2) / max) as u8)
+}
+
+/// Escala um canal de 8 bits para o tamanho da mascara e o posiciona nela
+fn write_channel(value: u8, mask: u32) -> u32 {
+    if mask == 0 {
+        return 0;
+    }
+
+    let shift = mask.trailing_zeros();
+    let max = (mask >> shift) as u64;
+    let channel = (value as u64 * max + 127) / 255;
+
+    ((channel as u32) << shift) & mask
+}
+
+fn read_index(row: &[u8], x: usize, bits_per_pixel: u16) -> usize {
+    let bits = bits_per_pixel as usize;
+    let bit = x * bits;
+    let shift = 8 - bits - (bit % 8);
+
+    ((row[bit / 8] >> shift) as usize) & ((1 << bits) - 1)
+}
+
+fn write_index(row: &mut [u8], x: usize, bits_per_pixel: u16, index: u8) {
+    let bits = bits_per_pixel as usize;
+    let bit = x * bits;
+    let shift = 8 - bits - (bit % 8);
+
+    row[bit / 8] |= index << shift;
+}
+
+/// Decodifica um fluxo RLE8/RLE4 em indices da paleta, tratando os escapes de fim de linha,
+/// fim do bitmap e deslocamento (delta)
+fn decode_rle(bytes: &[u8], width: usize, total: usize, bits: u16) -> ImageResult<Vec<u8>> {
+    let mut indices = vec![0_u8; total];
+    let (mut x, mut y) = (0_usize, 0_usize);
+    let mut offset = 0;
+
+    let mut put = |x: &mut usize, y: usize, value: u8| {
+        if *x < width && y * width + *x < total {
+            indices[y * width + *x] = value;
+        }
+        *x += 1;
+    };
+
+    loop {
+        let pair = bytes.get(offset..offset + 2).ok_or(ImageError::Truncated)?;
+        let (count, value) = (pair[0] as usize, pair[1]);
+        offset += 2;
+
+        if count > 0 {
+            for i in 0..count {
+                let index = match bits {
+                    4 if i % 2 == 0 => value >> 4,
+                    4 => value & 0x0F,
+                    _ => value,
+                };
+                put(&mut x, y, index);
+            }
+            continue;
+        }
+
+        match value {
+            0 => {
+                x = 0;
+                y += 1;
+            }
+            1 => break,
+            2 => {
+                let delta = bytes.get(offset..offset + 2).ok_or(ImageError::Truncated)?;
+                x += delta[0] as usize;
+                y += delta[1] as usize;
+                offset += 2;
+            }
+            count => {
+                let count = count as usize;
+                let size = if bits == 4 { count.div_ceil(2) } else { count };
+                let run = bytes
+                    .get(offset..offset + size)
+                    .ok_or(ImageError::Truncated)?;
+
+                for i in 0..count {
+                    let index = match bits {
+                        4 if i % 2 == 0 => run[i / 2] >> 4,
+                        4 => run[i / 2] & 0x0F,
+                        _ => run[i],
+                    };
+                    put(&mut x, y, index);
+                }
+
+                // Os trechos absolutos sao alinhados em 2 bytes
+                offset += size + size % 2;
+            }
+        }
+
+        if y * width >= total {
+            break;
+        }
+    }
+
+    Ok(indices)
+}
+
+/// Codifica uma linha de indices: repeticoes viram pares (quantidade, valor) e trechos sem
+/// repeticao viram blocos absolutos
+fn encode_rle_row(bytes: &mut Vec<u8>, row: &[u8], bits: u16) {
+    let run_length = |start: usize| -> usize {
+        row[start..]
+            .iter()
+            .take(255)
+            .take_while(|v| **v == row[start])
+            .count()
+    };
+
+    let mut x = 0;
+    while x < row.len() {
+        let run = run_length(x);
+        if run >= 2 {
+            let value = if bits == 4 {
+                row[x] << 4 | row[x]
+            } else {
+                row[x]
+            };
+            bytes.extend_from_slice(&[run as u8, value]);
+            x += run;
+            continue;
+        }
+
+        let mut end = x + 1;
+        while end < row.len() && end - x < 255 && run_length(end) < 2 {
+            end += 1;
+        }
+
+        let literal = &row[x..end];
+        if literal.len() < 3 {
+            for value in literal {
+                let value = if bits == 4 { *value << 4 } else { *value };
+                bytes.extend_from_slice(&[1, value]);
+            }
+        } else {
+            bytes.extend_from_slice(&[0, literal.len() as u8]);
+            let start = bytes.len();
+            if bits == 4 {
+                for pair in literal.chunks(2) {
+                    bytes.push(pair[0] << 4 | pair.get(1).copied().unwrap_or(0));
+                }
+            } else {
+                bytes.extend_from_slice(literal);
+            }
+            if (bytes.len() - start) % 2 == 1 {
+                bytes.push(0);
+            }
+        }
+
+        x = end;
+    }
+}
+
+fn rgb_key(color: &RGB) -> (u8, u8, u8) {
+    (color.red, color.green, color.blue)
+}
+
+/// Mapa de cor para o primeiro indice da paleta com aquela cor
+fn palette_indices(palette: &[RGB]) -> HashMap<(u8, u8, u8), u8> {
+    let mut indices = HashMap::new();
+    for (index, color) in palette.iter().enumerate() {
+        indices.entry(rgb_key(color)).or_insert(index as u8);
+    }
+
+    indices
 }
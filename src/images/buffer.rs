@@ -0,0 +1,216 @@
+use super::{
+    Format, Image, Orientation, RGB,
+    dynamic::DynamicImage,
+    hdr::Hdr,
+    pixel::{Pixel, Rgb, Rgba},
+    png::Png,
+};
+use crate::error::{ImageError, ImageResult};
+use crate::filters::{Filter, FilterError};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+// Structs...
+/// Struct que representa uma imagem apenas em memoria, sem nenhum cabecalho de formato.
+/// E a representacao de trabalho: qualquer codec converte de e para ela sem perdas
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageBuffer<P: Pixel = RGB> {
+    width: usize,
+    height: usize,
+    pixels: Vec<P>,
+    orientation: Orientation,
+}
+
+impl<P: Pixel> ImageBuffer<P> {
+    /// Cria uma imagem preenchida com uma unica cor
+    pub fn new(width: usize, height: usize, fill: P) -> ImageResult<Self> {
+        let size = width
+            .checked_mul(height)
+            .ok_or(ImageError::DimensionOverflow)?;
+
+        Ok(Self::from_raw(width, height, vec![fill; size]))
+    }
+
+    /// Cria uma imagem a partir de pixels em ordem de linhas, com origem no canto superior esquerdo
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<P>) -> ImageResult<Self> {
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(ImageError::InvalidData(
+                "pixel count does not match the dimensions",
+            ));
+        }
+
+        Ok(Self::from_raw(width, height, pixels))
+    }
+
+    /// Devolve os pixels, consumindo a imagem
+    pub fn into_pixels(self) -> Vec<P> {
+        self.pixels
+    }
+
+    /// Construtor usado pelos codecs, que ja garantem `pixels.len() == width * height`
+    pub(crate) fn from_raw(width: usize, height: usize, pixels: Vec<P>) -> Self {
+        Self {
+            width,
+            height,
+            pixels,
+            orientation: Orientation::Normal,
+        }
+    }
+
+    pub(crate) fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Converte todos os pixels para outro tipo, mantendo dimensoes e orientacao
+    pub fn convert<Q: Pixel>(&self) -> ImageBuffer<Q> {
+        let pixels = self.pixels.iter().map(Pixel::convert).collect();
+
+        ImageBuffer::from_raw(self.width, self.height, pixels).with_orientation(self.orientation)
+    }
+}
+
+impl From<DynamicImage> for ImageBuffer {
+    fn from(image: DynamicImage) -> Self {
+        match image {
+            DynamicImage::Bitmap(image) => image.into(),
+            DynamicImage::Png(image) => image.into(),
+            DynamicImage::Pnm(image) => image.into(),
+            DynamicImage::Qoi(image) => image.into(),
+            DynamicImage::Jpeg(image) => image.into(),
+            DynamicImage::Gif(image) => image.into(),
+            DynamicImage::Tga(image) => image.into(),
+            DynamicImage::Tiff(image) => image.into(),
+            DynamicImage::Ico(image) => image.into(),
+            DynamicImage::WebP(image) => image.into(),
+            DynamicImage::Hdr(image) => image,
+        }
+    }
+}
+
+/// PNG, TIFF e PNM mantem as amostras de 16 bits do arquivo; os demais formatos sao de 8 bits
+impl From<DynamicImage> for ImageBuffer<Rgba<u16>> {
+    fn from(image: DynamicImage) -> Self {
+        match image {
+            DynamicImage::Png(image) => image.into(),
+            DynamicImage::Tiff(image) => image.into(),
+            DynamicImage::Pnm(image) => image.into(),
+            other => ImageBuffer::<RGB>::from(other).convert(),
+        }
+    }
+}
+
+impl<P: Pixel> Image for ImageBuffer<P> {
+    type Pixel = P;
+
+    /// Le uma imagem de qualquer formato suportado, detectado pelo conteudo. O HDR e lido em
+    /// `f32`, sem passar pelos 8 bits do `DynamicImage`, e pixels com mais de 8 bits por canal
+    /// recebem as amostras de 16 bits do PNG, TIFF e PNM
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        if Format::from_reader(&mut reader)? == Some(Format::HDR) {
+            let buffer: ImageBuffer<Rgb<f32>> = Hdr::decode(reader)?.into();
+            return Ok(buffer.convert());
+        }
+
+        let image = DynamicImage::decode(reader)?;
+        if size_of::<P::Subpixel>() > 1 {
+            let buffer: ImageBuffer<Rgba<u16>> = image.into();
+            return Ok(buffer.convert());
+        }
+
+        let buffer: ImageBuffer = image.into();
+        Ok(buffer.convert())
+    }
+
+    /// Escreve a imagem como PNG: de 16 bits quando o pixel tem mais de 8 bits por canal, ou de
+    /// 8 bits, que guarda qualquer imagem `RGB` sem perdas
+    fn encode(&self, writer: impl Write) -> ImageResult<()> {
+        if size_of::<P::Subpixel>() > 1 {
+            return Png::try_from(self.convert::<Rgba<u16>>())?.encode(writer);
+        }
+
+        DynamicImage::from_buffer(self.convert(), Format::PNG)?.encode(writer)
+    }
+
+    fn filter(&mut self, filter: impl Filter) -> Result<(), FilterError> {
+        filter.apply(self)
+    }
+
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<P>) -> ImageResult<()> {
+        let image = Self::from_pixels(width, height, pixels)?;
+
+        self.width = image.width;
+        self.height = image.height;
+        self.pixels = image.pixels;
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Formato usado por `encode`
+    fn format(&self) -> Format {
+        Format::PNG
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        (P::CHANNELS * size_of::<P::Subpixel>() * 8) as u16
+    }
+
+    fn pixels(&mut self) -> &mut [P] {
+        &mut self.pixels
+    }
+
+    fn get_pixels(&self) -> &[P] {
+        &self.pixels
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut P> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get_mut(y * self.width + x)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&P> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get(y * self.width + x)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [P] {
+        &mut self.pixels[range]
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[P] {
+        &self.pixels[range]
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (pixels, width, height) = self
+            .orientation
+            .apply(&self.pixels, self.width, self.height);
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+        self.orientation = Orientation::Normal;
+    }
+}
@@ -0,0 +1,232 @@
+use super::{
+    Format, Image, Orientation, RGB, bitmap::Bitmap, buffer::ImageBuffer, gif::Gif, hdr::Hdr,
+    ico::Ico, jpeg::Jpeg, pixel::Rgb, png::Png, pnm::Pnm, qoi::Qoi, tga::Tga, tiff::Tiff,
+    webp::WebP,
+};
+use crate::error::{ImageError, ImageResult};
+use crate::filters::{Filter, FilterError};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+/// Repassa a chamada para a imagem concreta de cada variante
+macro_rules! dispatch {
+    ($value:expr, $image:ident => $body:expr) => {
+        match $value {
+            DynamicImage::Bitmap($image) => $body,
+            DynamicImage::Png($image) => $body,
+            DynamicImage::Pnm($image) => $body,
+            DynamicImage::Qoi($image) => $body,
+            DynamicImage::Jpeg($image) => $body,
+            DynamicImage::Gif($image) => $body,
+            DynamicImage::Tga($image) => $body,
+            DynamicImage::Tiff($image) => $body,
+            DynamicImage::Ico($image) => $body,
+            DynamicImage::WebP($image) => $body,
+            DynamicImage::Hdr($image) => $body,
+        }
+    };
+}
+
+// Enums...
+/// Enum que guarda uma imagem de qualquer formato, detectado na leitura, com pixels de 8 bits
+pub enum DynamicImage {
+    Bitmap(Bitmap),
+    Png(Png),
+    Pnm(Pnm),
+    Qoi(Qoi),
+    Jpeg(Jpeg),
+    Gif(Gif),
+    Tga(Tga),
+    Tiff(Tiff),
+    Ico(Ico),
+    WebP(WebP),
+    /// HDR reduzido para 8 bits, com os valores acima de 1.0 limitados. Para manter os `f32`
+    /// use `Hdr` ou `ImageBuffer<Rgb<f32>>`
+    Hdr(ImageBuffer),
+}
+
+impl DynamicImage {
+    /// Le a imagem assumindo o formato informado, sem olhar os magic bytes
+    pub fn decode_format(reader: impl Read + Seek, format: Format) -> ImageResult<Self> {
+        match format {
+            Format::BMP => Ok(Self::Bitmap(Bitmap::decode(reader)?)),
+            Format::PNG => Ok(Self::Png(Png::decode(reader)?)),
+            Format::PNM => Ok(Self::Pnm(Pnm::decode(reader)?)),
+            Format::QOI => Ok(Self::Qoi(Qoi::decode(reader)?)),
+            Format::JPEG => Ok(Self::Jpeg(Jpeg::decode(reader)?)),
+            Format::GIF => Ok(Self::Gif(Gif::decode(reader)?)),
+            Format::TGA => Ok(Self::Tga(Tga::decode(reader)?)),
+            Format::TIFF => Ok(Self::Tiff(Tiff::decode(reader)?)),
+            Format::ICO => Ok(Self::Ico(Ico::decode(reader)?)),
+            Format::WEBP => Ok(Self::WebP(WebP::decode(reader)?)),
+            Format::HDR => {
+                let buffer: ImageBuffer<Rgb<f32>> = Hdr::decode(reader)?.into();
+                Ok(Self::Hdr(buffer.convert()))
+            }
+        }
+    }
+
+    /// Converte a imagem em memoria para o codec do formato informado, pronta para ser salva
+    pub fn from_buffer(buffer: ImageBuffer, format: Format) -> ImageResult<Self> {
+        match format {
+            Format::BMP => Ok(Self::Bitmap(buffer.try_into()?)),
+            Format::PNG => Ok(Self::Png(buffer.try_into()?)),
+            Format::PNM => Ok(Self::Pnm(buffer.try_into()?)),
+            Format::QOI => Ok(Self::Qoi(buffer.try_into()?)),
+            Format::JPEG => Ok(Self::Jpeg(buffer.try_into()?)),
+            Format::GIF => Ok(Self::Gif(buffer.try_into()?)),
+            Format::TGA => Ok(Self::Tga(buffer.try_into()?)),
+            Format::TIFF => Ok(Self::Tiff(buffer.try_into()?)),
+            Format::ICO => Ok(Self::Ico(buffer.try_into()?)),
+            Format::WEBP => Ok(Self::WebP(buffer.try_into()?)),
+            Format::HDR => {
+                Hdr::try_from(buffer.convert::<Rgb<f32>>())?;
+                Ok(Self::Hdr(buffer))
+            }
+        }
+    }
+}
+
+impl From<Bitmap> for DynamicImage {
+    fn from(image: Bitmap) -> Self {
+        Self::Bitmap(image)
+    }
+}
+
+impl From<Png> for DynamicImage {
+    fn from(image: Png) -> Self {
+        Self::Png(image)
+    }
+}
+
+impl From<Pnm> for DynamicImage {
+    fn from(image: Pnm) -> Self {
+        Self::Pnm(image)
+    }
+}
+
+impl From<Qoi> for DynamicImage {
+    fn from(image: Qoi) -> Self {
+        Self::Qoi(image)
+    }
+}
+
+impl From<Jpeg> for DynamicImage {
+    fn from(image: Jpeg) -> Self {
+        Self::Jpeg(image)
+    }
+}
+
+impl From<Gif> for DynamicImage {
+    fn from(image: Gif) -> Self {
+        Self::Gif(image)
+    }
+}
+
+impl From<Tga> for DynamicImage {
+    fn from(image: Tga) -> Self {
+        Self::Tga(image)
+    }
+}
+
+impl From<Tiff> for DynamicImage {
+    fn from(image: Tiff) -> Self {
+        Self::Tiff(image)
+    }
+}
+
+impl From<Ico> for DynamicImage {
+    fn from(image: Ico) -> Self {
+        Self::Ico(image)
+    }
+}
+
+impl From<WebP> for DynamicImage {
+    fn from(image: WebP) -> Self {
+        Self::WebP(image)
+    }
+}
+
+impl Image for DynamicImage {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let format = Format::from_reader(&mut reader)?.ok_or(ImageError::InvalidMagic)?;
+        Self::decode_format(reader, format)
+    }
+
+    /// O HDR volta para `f32` ao salvar, sem alpha
+    fn encode(&self, writer: impl Write) -> ImageResult<()> {
+        if let Self::Hdr(buffer) = self {
+            return Hdr::try_from(buffer.convert::<Rgb<f32>>())?.encode(writer);
+        }
+
+        dispatch!(self, image => image.encode(writer))
+    }
+
+    fn filter(&mut self, filter: impl Filter) -> Result<(), FilterError> {
+        filter.apply(self)
+    }
+
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        dispatch!(self, image => image.set_pixels(width, height, pixels))
+    }
+
+    fn widht(&self) -> usize {
+        dispatch!(self, image => image.widht())
+    }
+
+    fn height(&self) -> usize {
+        dispatch!(self, image => image.height())
+    }
+
+    fn format(&self) -> Format {
+        match self {
+            Self::Hdr(_) => Format::HDR,
+            _ => dispatch!(self, image => image.format()),
+        }
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        match self {
+            Self::Hdr(_) => 32,
+            _ => dispatch!(self, image => image.bytes_per_pixels()),
+        }
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        dispatch!(self, image => image.pixels())
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        dispatch!(self, image => image.get_pixels())
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        dispatch!(self, image => image.pixel(x, y))
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        dispatch!(self, image => image.get_pixel(x, y))
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        dispatch!(self, image => image.slice_pixels(range))
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        dispatch!(self, image => image.get_slice_pixels(range))
+    }
+
+    fn orientation(&self) -> Orientation {
+        dispatch!(self, image => image.orientation())
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        dispatch!(self, image => image.set_orientation(orientation))
+    }
+
+    fn normalize_orientation(&mut self) {
+        dispatch!(self, image => image.normalize_orientation())
+    }
+}
@@ -0,0 +1,180 @@
+use super::buffer::ImageBuffer;
+use std::time::Duration;
+
+// Enums...
+/// Enum que representa o que fazer com a area do quadro antes de desenhar o proximo
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Disposal {
+    /// Deixa o quadro na tela
+    #[default]
+    None,
+    /// Limpa a area do quadro para o fundo (transparente)
+    Background,
+    /// Restaura a area para o que havia antes do quadro
+    Previous,
+}
+
+// Structs...
+/// Struct que representa um quadro de uma imagem animada, posicionado dentro da tela
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    image: ImageBuffer,
+    left: usize,
+    top: usize,
+    delay: Duration,
+    disposal: Disposal,
+}
+
+impl Frame {
+    /// Cria um quadro no canto superior esquerdo, sem espera e sem descarte
+    pub fn new(image: ImageBuffer) -> Self {
+        Self {
+            image,
+            left: 0,
+            top: 0,
+            delay: Duration::ZERO,
+            disposal: Disposal::None,
+        }
+    }
+
+    pub fn image(&self) -> &ImageBuffer {
+        &self.image
+    }
+
+    pub fn image_mut(&mut self) -> &mut ImageBuffer {
+        &mut self.image
+    }
+
+    pub fn into_image(self) -> ImageBuffer {
+        self.image
+    }
+
+    pub fn left(&self) -> usize {
+        self.left
+    }
+
+    pub fn top(&self) -> usize {
+        self.top
+    }
+
+    /// Posicao do canto superior esquerdo do quadro na tela
+    pub fn set_offset(&mut self, left: usize, top: usize) {
+        self.left = left;
+        self.top = top;
+    }
+
+    /// Tempo que o quadro fica na tela antes do proximo
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    pub fn disposal(&self) -> Disposal {
+        self.disposal
+    }
+
+    pub fn set_disposal(&mut self, disposal: Disposal) {
+        self.disposal = disposal;
+    }
+}
+
+/// Struct que representa a sequencia de quadros de uma imagem animada e o tamanho da tela
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frames {
+    width: usize,
+    height: usize,
+    /// Quantidade de vezes que a animacao toca, 0 para sempre
+    loop_count: u16,
+    frames: Vec<Frame>,
+}
+
+impl Frames {
+    /// Cria uma sequencia vazia com o tamanho da tela
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            loop_count: 0,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub(crate) fn set_size(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Quantidade de vezes que a animacao toca, 0 para sempre
+    pub fn loop_count(&self) -> u16 {
+        self.loop_count
+    }
+
+    pub fn set_loop_count(&mut self, loop_count: u16) {
+        self.loop_count = loop_count;
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Frame> {
+        self.frames.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Frame> {
+        self.frames.get_mut(index)
+    }
+
+    pub fn as_slice(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [Frame] {
+        &mut self.frames
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Frame> {
+        self.frames.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Frame> {
+        self.frames.iter_mut()
+    }
+}
+
+impl IntoIterator for Frames {
+    type Item = Frame;
+    type IntoIter = std::vec::IntoIter<Frame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Frames {
+    type Item = &'a Frame;
+    type IntoIter = std::slice::Iter<'a, Frame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter()
+    }
+}
@@ -0,0 +1,947 @@
+use super::{
+    Format, Image, Orientation, RGB,
+    animation::Animation,
+    buffer::ImageBuffer,
+    frames::{Disposal, Frame, Frames},
+};
+use crate::error::{ImageError, ImageResult};
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+use std::time::Duration;
+
+// Consts...
+const MAGIC_87: [u8; 6] = *b"GIF87a";
+const MAGIC_89: [u8; 6] = *b"GIF89a";
+const SCREEN_DESCRIPTOR_SIZE: usize = 13;
+const IMAGE_DESCRIPTOR_SIZE: usize = 9;
+
+const BLOCK_EXTENSION: u8 = 0x21;
+const BLOCK_IMAGE: u8 = 0x2C;
+const BLOCK_TRAILER: u8 = 0x3B;
+const LABEL_GRAPHIC_CONTROL: u8 = 0xF9;
+const LABEL_APPLICATION: u8 = 0xFF;
+
+/// Maior tamanho de codigo LZW e a quantidade de codigos que ele permite
+const MAX_CODE_SIZE: u32 = 12;
+const MAX_CODES: usize = 1 << MAX_CODE_SIZE;
+const MAX_COLORS: usize = 256;
+
+/// Passes do entrelacamento: primeira linha e intervalo entre as linhas
+const INTERLACE_PASSES: [(usize, usize); 4] = [(0, 8), (4, 8), (2, 4), (1, 2)];
+
+/// Alpha abaixo deste valor vira o indice transparente ao salvar
+const ALPHA_THRESHOLD: u8 = 128;
+
+// Structs...
+/// Struct para representa uma imagem GIF, com todos os quadros. Como `Image` ela expoe o primeiro
+/// quadro, que sempre cobre a tela inteira
+pub struct Gif {
+    frames: Frames,
+    interlaced: bool,
+    orientation: Orientation,
+}
+
+/// Dados da Graphic Control Extension, que valem para a proxima imagem
+#[derive(Default, Clone, Copy)]
+struct Control {
+    delay: u16,
+    disposal: Disposal,
+    transparent: Option<u8>,
+}
+
+/// Junta os codigos LZW em bytes, comecando pelo bit menos significativo
+#[derive(Default)]
+struct CodeWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl Gif {
+    /// Cria um GIF de um quadro so a partir de pixels em ordem de linhas
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<Self> {
+        let mut frames = Frames::new(width, height);
+        frames.push(Frame::new(ImageBuffer::from_pixels(width, height, pixels)?));
+
+        Self::from_frames(frames)
+    }
+
+    /// Cria um GIF animado. O primeiro quadro e ampliado para cobrir a tela, com o resto
+    /// transparente
+    pub fn from_frames(mut frames: Frames) -> ImageResult<Self> {
+        if frames.is_empty() {
+            return Err(ImageError::InvalidData("gif has no frames"));
+        }
+        if frames.width() == 0 || frames.height() == 0 {
+            return Err(ImageError::InvalidData("gif dimensions are zero"));
+        }
+        if frames.width() > u16::MAX as usize || frames.height() > u16::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+
+        cover_screen(&mut frames);
+
+        Ok(Self {
+            frames,
+            interlaced: false,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    pub fn frames(&self) -> &Frames {
+        &self.frames
+    }
+
+    /// Os quadros podem ser editados mas nao removidos, ja que o GIF sempre tem pelo menos um
+    pub fn frames_mut(&mut self) -> &mut [Frame] {
+        self.frames.as_mut_slice()
+    }
+
+    /// Adiciona um quadro no fim da animacao
+    pub fn push_frame(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    /// Quantidade de vezes que a animacao toca, 0 para sempre
+    pub fn set_loop_count(&mut self, loop_count: u16) {
+        self.frames.set_loop_count(loop_count);
+    }
+
+    pub fn into_frames(self) -> Frames {
+        self.frames
+    }
+
+    pub fn interlaced(&self) -> bool {
+        self.interlaced
+    }
+
+    /// Define se os quadros sao gravados entrelacados
+    pub fn set_interlaced(&mut self, value: bool) {
+        self.interlaced = value;
+    }
+
+    /// `from_frames` e `read_gif` nunca criam um GIF sem quadros, e eles nao podem ser removidos
+    fn first(&self) -> &ImageBuffer {
+        self.frames.as_slice()[0].image()
+    }
+
+    fn first_mut(&mut self) -> &mut ImageBuffer {
+        self.frames.as_mut_slice()[0].image_mut()
+    }
+
+    fn read_gif(data: &[u8]) -> ImageResult<Self> {
+        if data.len() < SCREEN_DESCRIPTOR_SIZE {
+            return Err(ImageError::Truncated);
+        }
+        if data[0..6] != MAGIC_87 && data[0..6] != MAGIC_89 {
+            return Err(ImageError::InvalidMagic);
+        }
+
+        let mut width = u16::from_le_bytes([data[6], data[7]]) as usize;
+        let mut height = u16::from_le_bytes([data[8], data[9]]) as usize;
+        let packed = data[10];
+
+        let mut pos = SCREEN_DESCRIPTOR_SIZE;
+        let global = if packed & 0x80 != 0 {
+            Some(read_palette(data, &mut pos, packed)?)
+        } else {
+            None
+        };
+
+        let mut frames = Vec::new();
+        let mut control = Control::default();
+        // Sem a extensao NETSCAPE a animacao toca uma vez
+        let mut loop_count = 1;
+        let mut interlaced = false;
+
+        loop {
+            let Some(block) = data.get(pos) else {
+                if frames.is_empty() {
+                    return Err(ImageError::Truncated);
+                }
+                break;
+            };
+            pos += 1;
+
+            match *block {
+                BLOCK_EXTENSION => {
+                    let label = *data.get(pos).ok_or(ImageError::Truncated)?;
+                    pos += 1;
+                    let blocks = read_sub_blocks(data, &mut pos);
+
+                    match label {
+                        LABEL_GRAPHIC_CONTROL => {
+                            if let Some([packed, low, high, index, ..]) =
+                                blocks.first().map(Vec::as_slice)
+                            {
+                                control = Control {
+                                    delay: u16::from_le_bytes([*low, *high]),
+                                    disposal: match (packed >> 2) & 0x07 {
+                                        2 => Disposal::Background,
+                                        3 => Disposal::Previous,
+                                        _ => Disposal::None,
+                                    },
+                                    transparent: (packed & 0x01 != 0).then_some(*index),
+                                };
+                            }
+                        }
+                        LABEL_APPLICATION => {
+                            let looping = blocks.first().is_some_and(|id| {
+                                id.as_slice() == b"NETSCAPE2.0" || id.as_slice() == b"ANIMEXTS1.0"
+                            });
+                            if let (true, Some([1, low, high, ..])) =
+                                (looping, blocks.get(1).map(Vec::as_slice))
+                            {
+                                let count = u16::from_le_bytes([*low, *high]);
+                                loop_count = if count == 0 {
+                                    0
+                                } else {
+                                    count.saturating_add(1)
+                                };
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                BLOCK_IMAGE => {
+                    let frame = read_frame(data, &mut pos, global.as_deref(), control)?;
+                    interlaced |= frame.1;
+                    let (right, bottom) = frame_end(&frame.0)?;
+                    width = width.max(right);
+                    height = height.max(bottom);
+                    frames.push(frame.0);
+                    control = Control::default();
+                }
+                BLOCK_TRAILER => break,
+                _ if !frames.is_empty() => break,
+                _ => return Err(ImageError::InvalidData("gif block is unknown")),
+            }
+        }
+
+        if frames.is_empty() {
+            return Err(ImageError::InvalidData("gif has no frames"));
+        }
+
+        // A tela so e alocada inteira para o primeiro quadro, e os dados precisam conseguir
+        // cobrir ela (cada codigo LZW de pelo menos 2 bits gera no maximo MAX_CODES pixels)
+        let screen = width
+            .checked_mul(height)
+            .ok_or(ImageError::DimensionOverflow)?;
+        if screen / MAX_CODES > data.len() * 4 {
+            return Err(ImageError::Truncated);
+        }
+
+        let mut sequence = Frames::new(width, height);
+        sequence.set_loop_count(loop_count);
+        for frame in frames {
+            sequence.push(frame);
+        }
+        cover_screen(&mut sequence);
+
+        Ok(Self {
+            frames: sequence,
+            interlaced,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    fn write_gif(&self) -> ImageResult<Vec<u8>> {
+        let mut width = self.frames.width();
+        let mut height = self.frames.height();
+        for frame in &self.frames {
+            let (right, bottom) = frame_end(frame)?;
+            width = width.max(right);
+            height = height.max(bottom);
+        }
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+
+        let mut bytes = Vec::new();
+        let mut global = Vec::new();
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            let image = frame.image();
+            let (palette, transparent, indices) = quantize(image.get_pixels());
+            let bits = palette_bits(palette.len());
+
+            let local = if index == 0 {
+                bytes.extend_from_slice(&MAGIC_89);
+                bytes.extend_from_slice(&(width as u16).to_le_bytes());
+                bytes.extend_from_slice(&(height as u16).to_le_bytes());
+                bytes.extend_from_slice(&[0xF0 | (bits - 1), 0, 0]);
+                write_palette(&mut bytes, &palette, bits);
+                global = palette.clone();
+
+                if self.frames.len() > 1 && self.frames.loop_count() != 1 {
+                    let count = self.frames.loop_count().saturating_sub(1);
+                    bytes.extend_from_slice(&[BLOCK_EXTENSION, LABEL_APPLICATION, 11]);
+                    bytes.extend_from_slice(b"NETSCAPE2.0");
+                    bytes.extend_from_slice(&[3, 1]);
+                    bytes.extend_from_slice(&count.to_le_bytes());
+                    bytes.push(0);
+                }
+                false
+            } else {
+                palette != global
+            };
+
+            let disposal = match frame.disposal() {
+                Disposal::None => 1,
+                Disposal::Background => 2,
+                Disposal::Previous => 3,
+            };
+            let delay = (frame.delay().as_millis().div_ceil(10)).min(u16::MAX as u128) as u16;
+            bytes.extend_from_slice(&[BLOCK_EXTENSION, LABEL_GRAPHIC_CONTROL, 4]);
+            bytes.push((disposal << 2) | transparent.is_some() as u8);
+            bytes.extend_from_slice(&delay.to_le_bytes());
+            bytes.extend_from_slice(&[transparent.unwrap_or(0), 0]);
+
+            let mut packed = 0;
+            if local {
+                packed |= 0x80 | (bits - 1);
+            }
+            if self.interlaced {
+                packed |= 0x40;
+            }
+            bytes.push(BLOCK_IMAGE);
+            for value in [frame.left(), frame.top(), image.widht(), image.height()] {
+                bytes.extend_from_slice(&(value as u16).to_le_bytes());
+            }
+            bytes.push(packed);
+            if local {
+                write_palette(&mut bytes, &palette, bits);
+            }
+
+            let indices = if self.interlaced {
+                interlace(&indices, image.widht(), image.height())
+            } else {
+                indices
+            };
+
+            let min_code_size = bits.max(2);
+            bytes.push(min_code_size);
+            for block in lzw_encode(&indices, min_code_size as u32).chunks(255) {
+                bytes.push(block.len() as u8);
+                bytes.extend_from_slice(block);
+            }
+            bytes.push(0);
+        }
+
+        bytes.push(BLOCK_TRAILER);
+        Ok(bytes)
+    }
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+impl From<Gif> for ImageBuffer {
+    fn from(image: Gif) -> Self {
+        let orientation = image.orientation;
+        let mut frames = image.frames.into_iter();
+
+        match frames.next() {
+            Some(frame) => frame.into_image().with_orientation(orientation),
+            None => ImageBuffer::from_raw(0, 0, Vec::new()).with_orientation(orientation),
+        }
+    }
+}
+
+impl TryFrom<ImageBuffer> for Gif {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Gif::from_pixels(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+impl From<Gif> for Animation {
+    fn from(mut image: Gif) -> Self {
+        image.normalize_orientation();
+        Animation::from_frames(image.frames)
+    }
+}
+
+impl TryFrom<Animation> for Gif {
+    type Error = ImageError;
+
+    fn try_from(animation: Animation) -> ImageResult<Self> {
+        Gif::from_frames(animation.into_frames())
+    }
+}
+
+impl Image for Gif {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_gif(&data)
+    }
+
+    /// Grava todos os quadros, reduzindo cada um para ate 256 cores
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        writer.write_all(&self.write_gif()?)?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    /// So funciona com um quadro: numa animacao os outros quadros ficariam fora do lugar (use
+    /// `Animation` para transformar todos)
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        if self.frames.len() > 1 {
+            return Err(ImageError::Unsupported(
+                "gif geometry changes with several frames",
+            ));
+        }
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("gif dimensions are zero"));
+        }
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+
+        self.first_mut().set_pixels(width, height, pixels)?;
+        self.frames.set_size(width, height);
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.first().widht()
+    }
+
+    fn height(&self) -> usize {
+        self.first().height()
+    }
+
+    fn format(&self) -> Format {
+        Format::GIF
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        8
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        self.first_mut().pixels()
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        self.first().get_pixels()
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        self.first_mut().pixel(x, y)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        self.first().get_pixel(x, y)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        self.first_mut().slice_pixels(range)
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        self.first().get_slice_pixels(range)
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    /// Aplica a orientacao em todos os quadros, movendo tambem a posicao de cada um na tela
+    fn normalize_orientation(&mut self) {
+        let orientation = self.orientation;
+        let (width, height) = (self.frames.width(), self.frames.height());
+
+        for frame in self.frames.iter_mut() {
+            let (w, h) = (frame.image().widht(), frame.image().height());
+            let (l, t) = (frame.left(), frame.top());
+            let (right, bottom) = (
+                width.saturating_sub(l.saturating_add(w)),
+                height.saturating_sub(t.saturating_add(h)),
+            );
+
+            let (left, top) = match orientation {
+                Orientation::Normal => (l, t),
+                Orientation::FlipHorizontal => (right, t),
+                Orientation::Rotate180 => (right, bottom),
+                Orientation::FlipVertical => (l, bottom),
+                Orientation::Transpose => (t, l),
+                Orientation::Rotate90 => (bottom, l),
+                Orientation::Transverse => (bottom, right),
+                Orientation::Rotate270 => (t, right),
+            };
+
+            let (pixels, w, h) = orientation.apply(frame.image().get_pixels(), w, h);
+            *frame.image_mut() = ImageBuffer::from_raw(w, h, pixels);
+            frame.set_offset(left, top);
+        }
+
+        if orientation.swaps_dimensions() {
+            self.frames.set_size(height, width);
+        }
+        self.orientation = Orientation::Normal;
+    }
+}
+
+// Utils Functions
+/// Coluna e linha logo depois do quadro na tela
+fn frame_end(frame: &Frame) -> ImageResult<(usize, usize)> {
+    let right = frame.left().checked_add(frame.image().widht());
+    let bottom = frame.top().checked_add(frame.image().height());
+
+    right.zip(bottom).ok_or(ImageError::DimensionOverflow)
+}
+
+/// Amplia o primeiro quadro para a tela inteira, deixando transparente o que ele nao cobre
+fn cover_screen(frames: &mut Frames) {
+    let (width, height) = (frames.width(), frames.height());
+    let Some(frame) = frames.get_mut(0) else {
+        return;
+    };
+
+    let image = frame.image();
+    let (w, h) = (image.widht(), image.height());
+    let (left, top) = (frame.left(), frame.top());
+    if (left, top, w, h) == (0, 0, width, height) {
+        return;
+    }
+
+    let mut canvas = vec![RGB::new(0, 0, 0, Some(0)); width * height];
+    for y in 0..h.min(height.saturating_sub(top)) {
+        for x in 0..w.min(width.saturating_sub(left)) {
+            canvas[(top + y) * width + left + x] = image.get_pixels()[y * w + x].clone();
+        }
+    }
+
+    *frame.image_mut() = ImageBuffer::from_raw(width, height, canvas);
+    frame.set_offset(0, 0);
+}
+
+fn read_palette(data: &[u8], pos: &mut usize, packed: u8) -> ImageResult<Vec<RGB>> {
+    let size = 3 << ((packed & 0x07) + 1);
+    let bytes = data.get(*pos..*pos + size).ok_or(ImageError::Truncated)?;
+    *pos += size;
+
+    Ok(bytes
+        .chunks_exact(3)
+        .map(|c| RGB::new(c[0], c[1], c[2], None))
+        .collect())
+}
+
+/// Le os sub-blocos ate o terminador. Um arquivo cortado devolve o que deu para ler
+fn read_sub_blocks(data: &[u8], pos: &mut usize) -> Vec<Vec<u8>> {
+    let mut blocks = Vec::new();
+    while let Some(size) = data.get(*pos).map(|s| *s as usize) {
+        *pos += 1;
+        if size == 0 {
+            break;
+        }
+
+        let end = (*pos + size).min(data.len());
+        blocks.push(data[*pos..end].to_vec());
+        *pos = end;
+    }
+    blocks
+}
+
+/// Le um descritor de imagem e seus dados, retornando o quadro e se ele era entrelacado
+fn read_frame(
+    data: &[u8],
+    pos: &mut usize,
+    global: Option<&[RGB]>,
+    control: Control,
+) -> ImageResult<(Frame, bool)> {
+    let descriptor = data
+        .get(*pos..*pos + IMAGE_DESCRIPTOR_SIZE)
+        .ok_or(ImageError::Truncated)?;
+    *pos += IMAGE_DESCRIPTOR_SIZE;
+
+    let field = |i: usize| u16::from_le_bytes([descriptor[i], descriptor[i + 1]]) as usize;
+    let (left, top, width, height) = (field(0), field(2), field(4), field(6));
+    let packed = descriptor[8];
+    let interlaced = packed & 0x40 != 0;
+
+    let local = if packed & 0x80 != 0 {
+        Some(read_palette(data, pos, packed)?)
+    } else {
+        None
+    };
+    let palette = local
+        .as_deref()
+        .or(global)
+        .ok_or(ImageError::InvalidData("gif has no palette"))?;
+
+    let min_code_size = *data.get(*pos).ok_or(ImageError::Truncated)? as u32;
+    *pos += 1;
+    if !(1..MAX_CODE_SIZE).contains(&min_code_size) {
+        return Err(ImageError::InvalidData("gif lzw code size is invalid"));
+    }
+
+    let compressed = read_sub_blocks(data, pos).concat();
+    let total = width
+        .checked_mul(height)
+        .ok_or(ImageError::DimensionOverflow)?;
+    if total / MAX_CODES > compressed.len() * 4 {
+        return Err(ImageError::Truncated);
+    }
+
+    let mut indices = lzw_decode(&compressed, min_code_size, total)?;
+    // Dados curtos: o resto do quadro fica transparente
+    let missing = total - indices.len();
+    if interlaced {
+        indices = deinterlace(&indices, width, height);
+    }
+
+    let mut pixels = indices
+        .iter()
+        .map(|index| match palette.get(*index as usize) {
+            Some(color) if control.transparent == Some(*index) => {
+                RGB::new(color.red(), color.green(), color.blue(), Some(0))
+            }
+            Some(color) => color.clone(),
+            None => RGB::new(0, 0, 0, None),
+        })
+        .collect::<Vec<_>>();
+    if !interlaced {
+        pixels.extend(std::iter::repeat_n(RGB::new(0, 0, 0, Some(0)), missing));
+    }
+
+    let mut frame = Frame::new(ImageBuffer::from_raw(width, height, pixels));
+    frame.set_offset(left, top);
+    frame.set_delay(Duration::from_millis(control.delay as u64 * 10));
+    frame.set_disposal(control.disposal);
+
+    Ok((frame, interlaced))
+}
+
+/// Descompacta os indices, parando em `limit` pixels
+fn lzw_decode(data: &[u8], min_code_size: u32, limit: usize) -> ImageResult<Vec<u8>> {
+    let clear = 1_usize << min_code_size;
+    let end = clear + 1;
+
+    let mut prefixes = vec![0_u16; MAX_CODES];
+    let mut suffixes = vec![0_u8; MAX_CODES];
+    let mut firsts = vec![0_u8; MAX_CODES];
+    let mut lengths = vec![0_usize; MAX_CODES];
+    for code in 0..clear {
+        suffixes[code] = code as u8;
+        firsts[code] = code as u8;
+        lengths[code] = 1;
+    }
+
+    let mut output = Vec::with_capacity(limit);
+    let mut size = min_code_size + 1;
+    let mut next = end + 1;
+    let mut previous: Option<usize> = None;
+
+    let mut buffer = 0_u32;
+    let mut count = 0_u32;
+    let mut bytes = data.iter();
+
+    while output.len() < limit {
+        while count < size {
+            let Some(byte) = bytes.next() else {
+                return Ok(output);
+            };
+            buffer |= (*byte as u32) << count;
+            count += 8;
+        }
+        let code = (buffer & ((1 << size) - 1)) as usize;
+        buffer >>= size;
+        count -= size;
+
+        if code == clear {
+            size = min_code_size + 1;
+            next = end + 1;
+            previous = None;
+            continue;
+        }
+        if code == end {
+            break;
+        }
+
+        let Some(last) = previous else {
+            if code >= clear {
+                return Err(ImageError::InvalidData("gif lzw code is invalid"));
+            }
+            output.push(code as u8);
+            previous = Some(code);
+            continue;
+        };
+
+        let first = if code < next {
+            firsts[code]
+        } else if code == next {
+            firsts[last]
+        } else {
+            return Err(ImageError::InvalidData("gif lzw code is invalid"));
+        };
+
+        if next < MAX_CODES {
+            prefixes[next] = last as u16;
+            suffixes[next] = first;
+            firsts[next] = firsts[last];
+            lengths[next] = lengths[last] + 1;
+            next += 1;
+            if next == 1 << size && size < MAX_CODE_SIZE {
+                size += 1;
+            }
+        }
+
+        // Escreve a sequencia de tras para frente, seguindo os prefixos
+        let start = output.len();
+        let length = lengths[code];
+        output.resize(start + length, 0);
+        let mut current = code;
+        for slot in output[start..].iter_mut().rev() {
+            *slot = suffixes[current];
+            current = prefixes[current] as usize;
+        }
+        previous = Some(code);
+    }
+
+    output.truncate(limit);
+    Ok(output)
+}
+
+fn lzw_encode(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear = 1_u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut writer = CodeWriter::default();
+    let mut table = HashMap::new();
+    let mut size = min_code_size + 1;
+    let mut next = end + 1;
+    writer.write(clear, size);
+
+    let Some((first, rest)) = indices.split_first() else {
+        writer.write(end, size);
+        return writer.finish();
+    };
+
+    let mut prefix = *first as u16;
+    for index in rest {
+        if let Some(code) = table.get(&(prefix, *index)) {
+            prefix = *code;
+            continue;
+        }
+
+        writer.write(prefix, size);
+        table.insert((prefix, *index), next);
+        next += 1;
+        if next as usize > 1 << size && size < MAX_CODE_SIZE {
+            size += 1;
+        }
+
+        // Tabela cheia: recomeca do zero
+        if next as usize == MAX_CODES {
+            writer.write(clear, size);
+            table.clear();
+            size = min_code_size + 1;
+            next = end + 1;
+        }
+        prefix = *index as u16;
+    }
+
+    writer.write(prefix, size);
+    // O leitor cria mais um codigo ao ler o ultimo, o que pode aumentar o tamanho do fim
+    if next as usize + 1 > 1 << size && size < MAX_CODE_SIZE {
+        size += 1;
+    }
+    writer.write(end, size);
+
+    writer.finish()
+}
+
+/// Ordem das linhas no arquivo entrelacado
+fn interlaced_rows(height: usize) -> impl Iterator<Item = usize> {
+    INTERLACE_PASSES
+        .into_iter()
+        .flat_map(move |(start, step)| (start..height).step_by(step))
+}
+
+fn interlace(indices: &[u8], width: usize, height: usize) -> Vec<u8> {
+    interlaced_rows(height)
+        .flat_map(|row| &indices[row * width..(row + 1) * width])
+        .copied()
+        .collect()
+}
+
+/// Recoloca as linhas na ordem normal. Linhas que faltam ficam com o indice 0
+fn deinterlace(indices: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut output = vec![0_u8; width * height];
+    for (source, row) in interlaced_rows(height).enumerate() {
+        let Some(line) = indices.get(source * width..(source + 1) * width) else {
+            break;
+        };
+        output[row * width..(row + 1) * width].copy_from_slice(line);
+    }
+    output
+}
+
+/// Bits do campo de tamanho da paleta (a paleta gravada tem `1 << bits` cores)
+fn palette_bits(len: usize) -> u8 {
+    (usize::BITS - len.max(2).saturating_sub(1).leading_zeros()) as u8
+}
+
+fn write_palette(bytes: &mut Vec<u8>, palette: &[[u8; 3]], bits: u8) {
+    for color in palette {
+        bytes.extend_from_slice(color);
+    }
+    let padding = (1 << bits) - palette.len();
+    bytes.extend(std::iter::repeat_n(0, padding * 3));
+}
+
+/// Reduz os pixels para uma paleta de ate 256 cores (uma delas transparente, se preciso),
+/// usando median cut quando ha cores demais. Retorna a paleta, o indice transparente e os
+/// indices dos pixels
+fn quantize(pixels: &[RGB]) -> (Vec<[u8; 3]>, Option<u8>, Vec<u8>) {
+    let is_transparent = |p: &RGB| p.alpha().is_some_and(|a| a < ALPHA_THRESHOLD);
+    let has_transparent = pixels.iter().any(is_transparent);
+
+    let mut counts = HashMap::new();
+    for pixel in pixels.iter().filter(|p| !is_transparent(p)) {
+        *counts
+            .entry([pixel.red(), pixel.green(), pixel.blue()])
+            .or_insert(0_u64) += 1;
+    }
+    let mut colors = counts.into_iter().collect::<Vec<_>>();
+    colors.sort_unstable();
+
+    let max = MAX_COLORS - has_transparent as usize;
+    let mut palette = if colors.len() <= max {
+        colors.iter().map(|(color, _)| *color).collect()
+    } else {
+        median_cut(colors, max)
+    };
+
+    let transparent = has_transparent.then(|| {
+        palette.push([0, 0, 0]);
+        (palette.len() - 1) as u8
+    });
+
+    let mut cache = HashMap::new();
+    let indices = pixels
+        .iter()
+        .map(|pixel| {
+            if let (true, Some(index)) = (is_transparent(pixel), transparent) {
+                return index;
+            }
+            let color = [pixel.red(), pixel.green(), pixel.blue()];
+            *cache.entry(color).or_insert_with(|| {
+                nearest(&palette[..palette.len() - has_transparent as usize], color)
+            })
+        })
+        .collect();
+
+    (palette, transparent, indices)
+}
+
+/// Divide as cores em `max` caixas, sempre cortando na mediana do canal com maior variacao
+fn median_cut(colors: Vec<([u8; 3], u64)>, max: usize) -> Vec<[u8; 3]> {
+    let range = |colors: &[([u8; 3], u64)], channel: usize| {
+        let values = colors.iter().map(|(c, _)| c[channel]);
+        values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+    };
+
+    let mut boxes = vec![colors];
+    while boxes.len() < max {
+        let Some((index, channel, width)) = boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, b)| (0..3).map(move |c| (i, c, range(b, c))))
+            .max_by_key(|(i, c, width)| (*width, usize::MAX - i, usize::MAX - c))
+        else {
+            break;
+        };
+        if width == 0 {
+            break;
+        }
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|(color, _)| color[channel]);
+
+        let total = colors.iter().map(|(_, count)| count).sum::<u64>();
+        let mut accumulated = 0;
+        let mut split = colors.len() / 2;
+        for (i, (_, count)) in colors.iter().enumerate() {
+            accumulated += count;
+            if accumulated * 2 >= total {
+                split = i + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, colors.len() - 1);
+
+        let second = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(second);
+    }
+
+    boxes
+        .iter()
+        .map(|colors| {
+            let total = colors.iter().map(|(_, count)| count).sum::<u64>().max(1);
+            let channel = |c: usize| {
+                let sum = colors
+                    .iter()
+                    .map(|(color, count)| color[c] as u64 * count)
+                    .sum::<u64>();
+                ((sum + total / 2) / total) as u8
+            };
+            [channel(0), channel(1), channel(2)]
+        })
+        .collect()
+}
+
+fn nearest(palette: &[[u8; 3]], color: [u8; 3]) -> u8 {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| {
+            (0..3)
+                .map(|c| (entry[c] as i32 - color[c] as i32).pow(2))
+                .sum::<i32>()
+        })
+        .map_or(0, |(index, _)| index as u8)
+}
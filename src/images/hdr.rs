@@ -0,0 +1,519 @@
+use super::{Format, Image, Orientation, buffer::ImageBuffer, pixel::Rgb};
+use crate::error::{ImageError, ImageResult};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+// Consts...
+const MAGIC_RADIANCE: &[u8] = b"#?RADIANCE";
+const MAGIC_RGBE: &[u8] = b"#?RGBE";
+const FORMAT_RGBE: &str = "32-bit_rle_rgbe";
+const FORMAT_XYZE: &str = "32-bit_rle_xyze";
+
+/// Scanlines com RLE por canal precisam ter de 8 a 32767 pixels
+const MIN_RLE_LENGTH: usize = 8;
+const MAX_RLE_LENGTH: usize = 0x7FFF;
+/// Menor sequencia gravada como repeticao; as menores ficam nos trechos literais
+const MIN_RUN: usize = 4;
+const MAX_RUN: usize = 127;
+const MAX_LITERAL: usize = 128;
+
+/// Valores menores sao gravados como preto (e o limite usado pelo `rgbe.c` do Radiance)
+const MIN_VALUE: f64 = 1e-32;
+
+// Structs...
+/// Struct para representa uma imagem Radiance HDR (RGBE). Os pixels sao `Rgb<f32>` sem limite
+/// superior: 1.0 e apenas a referencia de branco
+pub struct Hdr {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb<f32>>,
+    orientation: Orientation,
+}
+
+impl Hdr {
+    /// Cria uma imagem HDR a partir de pixels em ordem de linhas. Valores negativos ou NaN so
+    /// viram 0 ao salvar
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Rgb<f32>>) -> ImageResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("hdr dimensions are zero"));
+        }
+        if width > i32::MAX as usize || height > i32::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(ImageError::InvalidData(
+                "pixel count does not match the dimensions",
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    fn read_hdr(data: &[u8]) -> ImageResult<Self> {
+        if !data.starts_with(MAGIC_RADIANCE) && !data.starts_with(MAGIC_RGBE) {
+            return Err(ImageError::InvalidMagic);
+        }
+
+        // Cabecalho: uma variavel por linha ate a linha vazia
+        let mut offset = 0;
+        let mut next_line = || -> ImageResult<&[u8]> {
+            let rest = &data[offset..];
+            let end = rest
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .ok_or(ImageError::Truncated)?;
+            offset += end + 1;
+            Ok(&rest[..end])
+        };
+
+        next_line()?;
+        loop {
+            let line = next_line()?;
+            if line.is_empty() {
+                break;
+            }
+
+            let line = String::from_utf8_lossy(line);
+            match line.trim().strip_prefix("FORMAT=") {
+                Some(FORMAT_RGBE) | None => {}
+                Some(FORMAT_XYZE) => return Err(ImageError::Unsupported("hdr XYZE color space")),
+                Some(_) => return Err(ImageError::InvalidData("hdr format is unknown")),
+            }
+        }
+
+        let resolution = Resolution::parse(next_line()?)?;
+        let (width, height) = resolution.dimensions();
+        let total = width
+            .checked_mul(height)
+            .ok_or(ImageError::DimensionOverflow)?;
+
+        // Uma repeticao de 2 bytes gera ate 127 bytes de um canal
+        let body = &data[offset..];
+        if total.div_ceil(MAX_RUN / 2) > body.len() {
+            return Err(ImageError::Truncated);
+        }
+
+        let mut reader = ScanlineReader {
+            data: body,
+            offset: 0,
+        };
+        let mut pixels = vec![Rgb([0.0; 3]); total];
+        let mut scanline = Vec::with_capacity(resolution.scan_length);
+        for scan in 0..resolution.scanlines {
+            reader.read(resolution.scan_length, &mut scanline)?;
+            for (position, rgbe) in scanline.iter().enumerate() {
+                let (x, y) = resolution.coordinates(scan, position);
+                pixels[y * width + x] = rgbe_to_rgb(*rgbe);
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    fn write_hdr(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64 + self.pixels.len() * 4);
+        bytes.extend_from_slice(MAGIC_RADIANCE);
+        bytes.extend_from_slice(format!("\nFORMAT={FORMAT_RGBE}\n\n").as_bytes());
+        bytes.extend_from_slice(format!("-Y {} +X {}\n", self.height, self.width).as_bytes());
+
+        let rle = (MIN_RLE_LENGTH..=MAX_RLE_LENGTH).contains(&self.width);
+        let mut channel = Vec::with_capacity(self.width);
+        for row in self.pixels.chunks(self.width) {
+            let rgbe = row.iter().map(rgb_to_rgbe).collect::<Vec<_>>();
+
+            if !rle {
+                bytes.extend(rgbe.iter().flatten());
+                continue;
+            }
+
+            bytes.extend_from_slice(&[2, 2, (self.width >> 8) as u8, self.width as u8]);
+            for index in 0..4 {
+                channel.clear();
+                channel.extend(rgbe.iter().map(|pixel| pixel[index]));
+                write_rle(&mut bytes, &channel);
+            }
+        }
+
+        bytes
+    }
+}
+
+/// Linha de resolucao (ex: `-Y 480 +X 640`): o primeiro eixo e o das scanlines e o segundo o
+/// dos pixels dentro de cada uma
+struct Resolution {
+    scanlines: usize,
+    scan_length: usize,
+    /// As scanlines sao colunas (`X` primeiro)
+    transposed: bool,
+    /// As scanlines vao de baixo para cima (ou da direita para a esquerda, se transpostas)
+    reverse_scan: bool,
+    /// Os pixels da scanline vao na direcao contraria a normal
+    reverse_position: bool,
+}
+
+impl Resolution {
+    fn parse(line: &[u8]) -> ImageResult<Self> {
+        let invalid = || ImageError::InvalidData("hdr resolution line is invalid");
+        let line = std::str::from_utf8(line).map_err(|_| invalid())?;
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let [first_axis, first_size, second_axis, second_size] = tokens[..] else {
+            return Err(invalid());
+        };
+
+        let scanlines = first_size.parse::<usize>().map_err(|_| invalid())?;
+        let scan_length = second_size.parse::<usize>().map_err(|_| invalid())?;
+        if scanlines == 0 || scan_length == 0 {
+            return Err(ImageError::InvalidData("hdr dimensions are zero"));
+        }
+
+        // Y cresce para cima, entao -Y e de cima para baixo; X cresce para a direita
+        let (transposed, reverse_scan, reverse_position) = match (first_axis, second_axis) {
+            ("-Y", "+X") => (false, false, false),
+            ("-Y", "-X") => (false, false, true),
+            ("+Y", "+X") => (false, true, false),
+            ("+Y", "-X") => (false, true, true),
+            ("+X", "-Y") => (true, false, false),
+            ("+X", "+Y") => (true, false, true),
+            ("-X", "-Y") => (true, true, false),
+            ("-X", "+Y") => (true, true, true),
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            scanlines,
+            scan_length,
+            transposed,
+            reverse_scan,
+            reverse_position,
+        })
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        match self.transposed {
+            true => (self.scanlines, self.scan_length),
+            false => (self.scan_length, self.scanlines),
+        }
+    }
+
+    /// Posicao (x, y) com origem no canto superior esquerdo de um pixel da scanline
+    fn coordinates(&self, scan: usize, position: usize) -> (usize, usize) {
+        let scan = match self.reverse_scan {
+            true => self.scanlines - 1 - scan,
+            false => scan,
+        };
+        let position = match self.reverse_position {
+            true => self.scan_length - 1 - position,
+            false => position,
+        };
+
+        match self.transposed {
+            true => (scan, position),
+            false => (position, scan),
+        }
+    }
+}
+
+/// Le as scanlines em sequencia: RLE por canal, pixels sem compressao ou o RLE antigo
+struct ScanlineReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl ScanlineReader<'_> {
+    fn byte(&mut self) -> ImageResult<u8> {
+        let byte = *self.data.get(self.offset).ok_or(ImageError::Truncated)?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> ImageResult<&[u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + len)
+            .ok_or(ImageError::Truncated)?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn read(&mut self, len: usize, output: &mut Vec<[u8; 4]>) -> ImageResult<()> {
+        output.clear();
+
+        let start = self.data.get(self.offset..self.offset + 4);
+        let rle = match start {
+            Some([2, 2, high, _]) => high & 0x80 == 0,
+            _ => false,
+        };
+        if !rle || !(MIN_RLE_LENGTH..=MAX_RLE_LENGTH).contains(&len) {
+            return self.read_flat(len, output);
+        }
+
+        let header = self.bytes(4)?;
+        if ((header[2] as usize) << 8 | header[3] as usize) != len {
+            return Err(ImageError::InvalidData("hdr scanline length mismatch"));
+        }
+
+        output.resize(len, [0; 4]);
+        for channel in 0..4 {
+            let mut position = 0;
+            while position < len {
+                let count = self.byte()? as usize;
+                if count > 128 {
+                    let run = count - 128;
+                    let value = self.byte()?;
+                    if position + run > len {
+                        return Err(ImageError::InvalidData("hdr run overflows the scanline"));
+                    }
+                    for pixel in &mut output[position..position + run] {
+                        pixel[channel] = value;
+                    }
+                    position += run;
+                } else {
+                    if count == 0 || position + count > len {
+                        return Err(ImageError::InvalidData("hdr run overflows the scanline"));
+                    }
+                    let values = self.bytes(count)?;
+                    for (pixel, value) in output[position..position + count].iter_mut().zip(values)
+                    {
+                        pixel[channel] = *value;
+                    }
+                    position += count;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pixels de 4 bytes; `(1, 1, 1, n)` repete o pixel anterior, com `n` deslocado 8 bits a
+    /// mais a cada repeticao seguida
+    fn read_flat(&mut self, len: usize, output: &mut Vec<[u8; 4]>) -> ImageResult<()> {
+        let mut shift = 0;
+
+        while output.len() < len {
+            let bytes = self.bytes(4)?;
+            let pixel = [bytes[0], bytes[1], bytes[2], bytes[3]];
+
+            if pixel[..3] != [1, 1, 1] {
+                output.push(pixel);
+                shift = 0;
+                continue;
+            }
+
+            let previous = *output
+                .last()
+                .ok_or(ImageError::InvalidData("hdr repeat has no previous pixel"))?;
+            let count = (pixel[3] as usize)
+                .checked_shl(shift)
+                .filter(|count| output.len() + count <= len)
+                .ok_or(ImageError::InvalidData("hdr run overflows the scanline"))?;
+            output.extend(std::iter::repeat_n(previous, count));
+            shift += 8;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Hdr> for ImageBuffer<Rgb<f32>> {
+    fn from(image: Hdr) -> Self {
+        ImageBuffer::from_raw(image.width, image.height, image.pixels)
+            .with_orientation(image.orientation)
+    }
+}
+
+impl TryFrom<ImageBuffer<Rgb<f32>>> for Hdr {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer<Rgb<f32>>) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Hdr::from_pixels(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+impl Image for Hdr {
+    type Pixel = Rgb<f32>;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_hdr(&data)
+    }
+
+    /// Grava sempre de cima para baixo (`-Y H +X W`), com RLE quando a largura permite
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        writer.write_all(&self.write_hdr())?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    fn set_pixels(
+        &mut self,
+        width: usize,
+        height: usize,
+        pixels: Vec<Rgb<f32>>,
+    ) -> ImageResult<()> {
+        let image = Self::from_pixels(width, height, pixels)?;
+
+        self.width = image.width;
+        self.height = image.height;
+        self.pixels = image.pixels;
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        Format::HDR
+    }
+
+    /// Cada pixel ocupa 4 bytes no arquivo (mantissas RGB e expoente)
+    fn bytes_per_pixels(&self) -> u16 {
+        32
+    }
+
+    fn pixels(&mut self) -> &mut [Rgb<f32>] {
+        &mut self.pixels
+    }
+
+    fn get_pixels(&self) -> &[Rgb<f32>] {
+        &self.pixels
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut Rgb<f32>> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get_mut(y * self.width + x)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&Rgb<f32>> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get(y * self.width + x)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [Rgb<f32>] {
+        &mut self.pixels[range]
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[Rgb<f32>] {
+        &self.pixels[range]
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (pixels, width, height) = self
+            .orientation
+            .apply(&self.pixels, self.width, self.height);
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+        self.orientation = Orientation::Normal;
+    }
+}
+
+// Utils Functions
+/// Mantissas de 8 bits com um expoente comum: valor = mantissa * 2^(expoente - 136)
+fn rgbe_to_rgb(rgbe: [u8; 4]) -> Rgb<f32> {
+    if rgbe[3] == 0 {
+        return Rgb([0.0; 3]);
+    }
+
+    let scale = 2_f64.powi(rgbe[3] as i32 - 136);
+    Rgb([rgbe[0], rgbe[1], rgbe[2]].map(|mantissa| (mantissa as f64 * scale) as f32))
+}
+
+/// Conversao inversa, truncando as mantissas como o `rgbe.c`. Negativos e NaN viram 0 e valores
+/// grandes demais ficam no maior expoente
+fn rgb_to_rgbe(color: &Rgb<f32>) -> [u8; 4] {
+    let [red, green, blue] = color.0.map(|value| match value.is_nan() {
+        true => 0.0,
+        false => (value as f64).max(0.0),
+    });
+    let max = red.max(green).max(blue);
+    if max < MIN_VALUE {
+        return [0; 4];
+    }
+
+    // max = mantissa * 2^exponent, com a mantissa em 0.5..1
+    let exponent = (max.log2().floor() as i32 + 1).min(127);
+    let scale = 256.0 / 2_f64.powi(exponent);
+    let [red, green, blue] = [red, green, blue].map(|value| (value * scale).min(255.0) as u8);
+
+    [red, green, blue, (exponent + 128) as u8]
+}
+
+/// RLE de um canal: repeticoes de `MIN_RUN` ou mais bytes viram (128 + n, valor) e o resto vai
+/// em trechos literais (n, bytes)
+fn write_rle(output: &mut Vec<u8>, data: &[u8]) {
+    let mut position = 0;
+    let mut literal_start = 0;
+
+    let flush = |output: &mut Vec<u8>, literal: &[u8]| {
+        for chunk in literal.chunks(MAX_LITERAL) {
+            output.push(chunk.len() as u8);
+            output.extend_from_slice(chunk);
+        }
+    };
+
+    while position < data.len() {
+        let run = data[position..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|byte| **byte == data[position])
+            .count();
+
+        if run >= MIN_RUN {
+            flush(output, &data[literal_start..position]);
+            output.push((128 + run) as u8);
+            output.push(data[position]);
+            position += run;
+            literal_start = position;
+        } else {
+            position += run;
+        }
+    }
+
+    flush(output, &data[literal_start..]);
+}
@@ -0,0 +1,422 @@
+use super::{Format, Image, Orientation, RGB, bitmap::Bitmap, buffer::ImageBuffer, png::Png};
+use crate::error::{ImageError, ImageResult};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+// Consts...
+const HEADER_SIZE: usize = 6;
+const ENTRY_SIZE: usize = 16;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// Maior lado de uma imagem no diretorio (0 no arquivo significa 256)
+const MAX_SIZE: usize = 256;
+
+// Enums...
+/// Enum que representa o tipo do arquivo, no campo `idType` do cabecalho
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IconKind {
+    #[default]
+    Icon,
+    /// Cursor (CUR): cada imagem guarda o ponto de clique (hotspot)
+    Cursor,
+}
+
+// Structs...
+/// Struct que representa uma das imagens de um icone
+#[derive(Debug, Clone, PartialEq)]
+pub struct IconEntry {
+    image: ImageBuffer,
+    hotspot: (u16, u16),
+    bits_per_pixel: u16,
+    png: bool,
+}
+
+impl IconEntry {
+    /// Cria uma entrada de ate 256x256 pixels. Imagens de 256 sao gravadas como PNG, como o
+    /// Windows faz; as menores como DIB
+    pub fn new(image: ImageBuffer) -> ImageResult<Self> {
+        validate_dimensions(image.widht(), image.height())?;
+        let png = image.widht() == MAX_SIZE || image.height() == MAX_SIZE;
+
+        Ok(Self {
+            image,
+            hotspot: (0, 0),
+            bits_per_pixel: 32,
+            png,
+        })
+    }
+
+    pub fn image(&self) -> &ImageBuffer {
+        &self.image
+    }
+
+    pub fn image_mut(&mut self) -> &mut ImageBuffer {
+        &mut self.image
+    }
+
+    pub fn into_image(self) -> ImageBuffer {
+        self.image
+    }
+
+    /// Ponto de clique (x, y) a partir do canto superior esquerdo, usado so em cursores
+    pub fn hotspot(&self) -> (u16, u16) {
+        self.hotspot
+    }
+
+    pub fn set_hotspot(&mut self, x: u16, y: u16) {
+        self.hotspot = (x, y);
+    }
+
+    /// Bits por pixel da imagem lida (32 para PNG). A escrita usa 32 bits se houver alpha e 24
+    /// caso contrario
+    pub fn bits_per_pixel(&self) -> u16 {
+        self.bits_per_pixel
+    }
+
+    pub fn png(&self) -> bool {
+        self.png
+    }
+
+    /// Define se a imagem e gravada como PNG em vez de DIB
+    pub fn set_png(&mut self, value: bool) {
+        self.png = value;
+    }
+}
+
+/// Struct para representa um arquivo ICO ou CUR com todas as imagens. Como `Image` ela expoe a
+/// maior imagem
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ico {
+    kind: IconKind,
+    entries: Vec<IconEntry>,
+}
+
+impl Ico {
+    /// Cria um icone com uma imagem a partir de pixels em ordem de linhas
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<Self> {
+        Self::from_images(vec![ImageBuffer::from_pixels(width, height, pixels)?])
+    }
+
+    /// Cria um icone com varias imagens, por exemplo 16, 32, 48 e 256 pixels
+    pub fn from_images(images: Vec<ImageBuffer>) -> ImageResult<Self> {
+        let entries = images
+            .into_iter()
+            .map(IconEntry::new)
+            .collect::<ImageResult<Vec<_>>>()?;
+
+        Self::from_entries(IconKind::Icon, entries)
+    }
+
+    pub fn from_entries(kind: IconKind, entries: Vec<IconEntry>) -> ImageResult<Self> {
+        if entries.is_empty() {
+            return Err(ImageError::InvalidData("ico has no images"));
+        }
+        if entries.len() > u16::MAX as usize {
+            return Err(ImageError::InvalidData("ico has too many images"));
+        }
+
+        Ok(Self { kind, entries })
+    }
+
+    pub fn kind(&self) -> IconKind {
+        self.kind
+    }
+
+    pub fn set_kind(&mut self, kind: IconKind) {
+        self.kind = kind;
+    }
+
+    pub fn entries(&self) -> &[IconEntry] {
+        &self.entries
+    }
+
+    /// As imagens podem ser editadas mas nao removidas, ja que o icone sempre tem pelo menos uma
+    pub fn entries_mut(&mut self) -> &mut [IconEntry] {
+        &mut self.entries
+    }
+
+    pub fn into_entries(self) -> Vec<IconEntry> {
+        self.entries
+    }
+
+    pub fn push(&mut self, entry: IconEntry) -> ImageResult<()> {
+        if self.entries.len() == u16::MAX as usize {
+            return Err(ImageError::InvalidData("ico has too many images"));
+        }
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Indice da maior imagem (em area e depois em bits por pixel)
+    fn largest(&self) -> usize {
+        self.entries
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, entry)| {
+                let image = &entry.image;
+                (
+                    image.widht() * image.height(),
+                    entry.bits_per_pixel,
+                    std::cmp::Reverse(*i),
+                )
+            })
+            .map(|(i, _)| i)
+            .unwrap_or_default()
+    }
+
+    /// `from_entries` nunca cria um icone sem imagens, e elas nao podem ser removidas
+    fn main_image(&self) -> &ImageBuffer {
+        &self.entries[self.largest()].image
+    }
+
+    fn main_image_mut(&mut self) -> &mut ImageBuffer {
+        let index = self.largest();
+        &mut self.entries[index].image
+    }
+
+    fn read_ico(data: &[u8]) -> ImageResult<Self> {
+        if data.len() < HEADER_SIZE {
+            return Err(ImageError::Truncated);
+        }
+
+        let kind = match data[0..4] {
+            [0, 0, 1, 0] => IconKind::Icon,
+            [0, 0, 2, 0] => IconKind::Cursor,
+            _ => return Err(ImageError::InvalidMagic),
+        };
+        let count = u16::from_le_bytes([data[4], data[5]]) as usize;
+
+        let mut entries = Vec::with_capacity(count);
+        for index in 0..count {
+            let pos = HEADER_SIZE + index * ENTRY_SIZE;
+            let entry = data
+                .get(pos..pos + ENTRY_SIZE)
+                .ok_or(ImageError::Truncated)?;
+            let hotspot = (
+                u16::from_le_bytes([entry[4], entry[5]]),
+                u16::from_le_bytes([entry[6], entry[7]]),
+            );
+            let size = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]) as usize;
+            let offset = u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]) as usize;
+
+            let bytes = offset
+                .checked_add(size)
+                .and_then(|end| data.get(offset..end))
+                .ok_or(ImageError::Truncated)?;
+
+            // As dimensoes do diretorio podem estar erradas, entao valem as da propria imagem. Alguns
+            // programas gravam PNGs maiores que 256, que sao lidos mas nao podem ser gravados
+            let (image, bits_per_pixel, png) = if bytes.starts_with(&PNG_SIGNATURE) {
+                (ImageBuffer::from(Png::from_bytes(bytes)?), 32, true)
+            } else {
+                let bitmap = Bitmap::decode_icon_dib(bytes)?;
+                let bits = bitmap.bytes_per_pixels();
+                (ImageBuffer::from(bitmap), bits, false)
+            };
+
+            entries.push(IconEntry {
+                image,
+                hotspot: match kind {
+                    IconKind::Cursor => hotspot,
+                    IconKind::Icon => (0, 0),
+                },
+                bits_per_pixel,
+                png,
+            });
+        }
+
+        Self::from_entries(kind, entries)
+    }
+
+    fn write_ico(&self) -> ImageResult<Vec<u8>> {
+        let mut images = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let (width, height) = (entry.image.widht(), entry.image.height());
+            validate_dimensions(width, height)?;
+            let pixels = entry.image.get_pixels().to_vec();
+
+            // O PNG dentro de um icone precisa ser RGBA de 8 bits
+            let (bytes, bits) = if entry.png {
+                let pixels = pixels
+                    .into_iter()
+                    .map(|mut pixel| {
+                        pixel.set_alpha(Some(pixel.alpha().unwrap_or(u8::MAX)));
+                        pixel
+                    })
+                    .collect();
+                (Png::from_pixels(width, height, pixels)?.to_bytes()?, 32)
+            } else {
+                let bits = match pixels.iter().any(|pixel| pixel.alpha().is_some()) {
+                    true => 32,
+                    false => 24,
+                };
+                let bitmap = Bitmap::from_pixels(width, height, pixels, bits)?;
+                (bitmap.encode_icon_dib(), bits)
+            };
+            images.push((bytes, bits));
+        }
+
+        let mut output = Vec::new();
+        output.extend_from_slice(&[0, 0]);
+        output.extend_from_slice(
+            &match self.kind {
+                IconKind::Icon => 1_u16,
+                IconKind::Cursor => 2,
+            }
+            .to_le_bytes(),
+        );
+        output.extend_from_slice(&(self.entries.len() as u16).to_le_bytes());
+
+        let mut offset = HEADER_SIZE + self.entries.len() * ENTRY_SIZE;
+        for (entry, (bytes, bits)) in self.entries.iter().zip(&images) {
+            let (planes, bits) = match self.kind {
+                IconKind::Icon => (1, *bits),
+                IconKind::Cursor => entry.hotspot,
+            };
+
+            // 256 nao cabe em um byte e e gravado como 0
+            output.push(entry.image.widht() as u8);
+            output.push(entry.image.height() as u8);
+            output.extend_from_slice(&[0, 0]);
+            output.extend_from_slice(&planes.to_le_bytes());
+            output.extend_from_slice(&bits.to_le_bytes());
+            output.extend_from_slice(&to_u32(bytes.len())?.to_le_bytes());
+            output.extend_from_slice(&to_u32(offset)?.to_le_bytes());
+            offset += bytes.len();
+        }
+
+        for (bytes, _) in images {
+            output.extend_from_slice(&bytes);
+        }
+
+        Ok(output)
+    }
+}
+
+impl From<Ico> for ImageBuffer {
+    fn from(image: Ico) -> Self {
+        let index = image.largest();
+
+        match image.entries.into_iter().nth(index) {
+            Some(entry) => entry.image,
+            None => ImageBuffer::from_raw(0, 0, Vec::new()),
+        }
+    }
+}
+
+impl TryFrom<ImageBuffer> for Ico {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        Ico::from_images(vec![buffer])
+    }
+}
+
+impl Image for Ico {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_ico(&data)
+    }
+
+    /// Grava todas as imagens; as de 256 pixels (ou marcadas com `set_png`) como PNG e as demais
+    /// como DIB de 24 ou 32 bits com a mascara AND
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        writer.write_all(&self.write_ico()?)?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    /// Troca a maior imagem. O hotspot e mantido dentro da nova area
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        validate_dimensions(width, height)?;
+        let image = ImageBuffer::from_pixels(width, height, pixels)?;
+
+        let index = self.largest();
+        let entry = &mut self.entries[index];
+        let (x, y) = entry.hotspot;
+        entry.image = image;
+        entry.png = width == MAX_SIZE || height == MAX_SIZE;
+        entry.hotspot = (x.min(width as u16 - 1), y.min(height as u16 - 1));
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.main_image().widht()
+    }
+
+    fn height(&self) -> usize {
+        self.main_image().height()
+    }
+
+    fn format(&self) -> Format {
+        Format::ICO
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        self.entries[self.largest()].bits_per_pixel
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        self.main_image_mut().pixels()
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        self.main_image().get_pixels()
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        self.main_image_mut().pixel(x, y)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        self.main_image().get_pixel(x, y)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        self.main_image_mut().slice_pixels(range)
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        self.main_image().get_slice_pixels(range)
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.main_image().orientation()
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.main_image_mut().set_orientation(orientation);
+    }
+
+    fn normalize_orientation(&mut self) {
+        self.main_image_mut().normalize_orientation();
+    }
+}
+
+// Utils Functions
+fn validate_dimensions(width: usize, height: usize) -> ImageResult<()> {
+    if width == 0 || height == 0 {
+        return Err(ImageError::InvalidData("ico dimensions are zero"));
+    }
+    if width > MAX_SIZE || height > MAX_SIZE {
+        return Err(ImageError::Unsupported("ico images larger than 256x256"));
+    }
+
+    Ok(())
+}
+
+fn to_u32(value: usize) -> ImageResult<u32> {
+    u32::try_from(value).map_err(|_| ImageError::DimensionOverflow)
+}
@@ -0,0 +1,1033 @@
+use super::{JpegColorSpace, ZIGZAG, dct_table};
+use crate::error::{ImageError, ImageResult};
+use crate::images::{Orientation, RGB};
+
+// Consts...
+/// Bits da tabela de busca rapida dos codigos de Huffman
+const LOOKUP_BITS: u32 = 9;
+
+// Structs...
+/// Resultado da leitura de um arquivo JPEG
+pub(super) struct Decoded {
+    pub width: usize,
+    pub height: usize,
+    pub color_space: JpegColorSpace,
+    pub progressive: bool,
+    pub orientation: Orientation,
+    pub pixels: Vec<RGB>,
+}
+
+/// Le o JPEG inteiro ja na memoria: marcadores, scans, IDCT, upsampling e conversao de cor
+pub(super) fn decode(data: &[u8]) -> ImageResult<Decoded> {
+    if data.len() < 2 || data[0..2] != [0xFF, 0xD8] {
+        return Err(ImageError::InvalidMagic);
+    }
+
+    let mut decoder = Decoder {
+        data,
+        quant: [None; 4],
+        dc_tables: [const { None }; 4],
+        ac_tables: [const { None }; 4],
+        frame: None,
+        restart_interval: 0,
+        adobe_transform: None,
+        jfif: false,
+        orientation: Orientation::Normal,
+        scans: 0,
+    };
+
+    let mut pos = 2;
+    loop {
+        let Some((marker, next)) = find_marker(data, pos) else {
+            // Arquivo cortado: usa o que ja foi lido, se algum scan chegou a ser decodificado
+            if decoder.scans > 0 {
+                break;
+            }
+            return Err(ImageError::Truncated);
+        };
+        pos = next;
+
+        match marker {
+            0xD9 => break,
+            0xD8 | 0x01 | 0xD0..=0xD7 => continue,
+            _ => {}
+        }
+
+        let length = data
+            .get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+            .ok_or(ImageError::Truncated)?;
+        if length < 2 {
+            return Err(ImageError::InvalidData("jpeg segment length is invalid"));
+        }
+        let segment = data
+            .get(pos + 2..pos + length)
+            .ok_or(ImageError::Truncated)?;
+        pos += length;
+
+        match marker {
+            0xC0..=0xC2 => decoder.read_frame(segment, marker == 0xC2)?,
+            0xC3 | 0xC5..=0xC7 => return Err(ImageError::Unsupported("lossless jpeg")),
+            0xC8..=0xCF if marker != 0xCC => {
+                return Err(ImageError::Unsupported("arithmetic coded jpeg"));
+            }
+            0xC4 => decoder.read_huffman_tables(segment)?,
+            0xDB => decoder.read_quant_tables(segment)?,
+            0xDD => {
+                if segment.len() < 2 {
+                    return Err(ImageError::InvalidData("jpeg DRI length is invalid"));
+                }
+                decoder.restart_interval = u16::from_be_bytes([segment[0], segment[1]]) as usize;
+            }
+            0xDA => pos = decoder.read_scan(segment, pos)?,
+            0xE0 if segment.starts_with(b"JFIF\0") => decoder.jfif = true,
+            0xE1 if segment.starts_with(b"Exif\0\0") => {
+                if let Some(orientation) = exif_orientation(&segment[6..]) {
+                    decoder.orientation = orientation;
+                }
+            }
+            0xEE if segment.starts_with(b"Adobe") && segment.len() >= 12 => {
+                decoder.adobe_transform = Some(segment[11]);
+            }
+            _ => {}
+        }
+    }
+
+    decoder.finish()
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    quant: [Option<[u16; 64]>; 4],
+    dc_tables: [Option<HuffmanTable>; 4],
+    ac_tables: [Option<HuffmanTable>; 4],
+    frame: Option<Frame>,
+    restart_interval: usize,
+    adobe_transform: Option<u8>,
+    jfif: bool,
+    orientation: Orientation,
+    scans: usize,
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    progressive: bool,
+    max_h: usize,
+    max_v: usize,
+    mcus_x: usize,
+    mcus_y: usize,
+    components: Vec<Component>,
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quant_index: usize,
+    /// Tabela de quantizacao copiada no primeiro scan do componente
+    quant: Option<[u16; 64]>,
+    /// Blocos por linha e por coluna, completando o ultimo MCU
+    blocks_w: usize,
+    blocks_h: usize,
+    /// Tamanho real do componente em amostras
+    width: usize,
+    height: usize,
+    coefficients: Vec<i16>,
+    dc_pred: i32,
+}
+
+struct Scan {
+    components: Vec<(usize, usize, usize)>,
+    start: usize,
+    end: usize,
+    high: u8,
+    low: u8,
+}
+
+impl<'a> Decoder<'a> {
+    fn read_frame(&mut self, segment: &[u8], progressive: bool) -> ImageResult<()> {
+        if self.frame.is_some() {
+            return Err(ImageError::Unsupported("jpeg with multiple frames"));
+        }
+        if segment.len() < 6 {
+            return Err(ImageError::Truncated);
+        }
+
+        let precision = segment[0];
+        if precision != 8 {
+            return Err(ImageError::UnsupportedBitDepth(precision as u16));
+        }
+
+        let height = u16::from_be_bytes([segment[1], segment[2]]) as usize;
+        let width = u16::from_be_bytes([segment[3], segment[4]]) as usize;
+        let count = segment[5] as usize;
+
+        if height == 0 {
+            return Err(ImageError::Unsupported("jpeg DNL marker"));
+        }
+        if width == 0 {
+            return Err(ImageError::InvalidData("jpeg dimensions are zero"));
+        }
+        if !matches!(count, 1 | 3 | 4) {
+            return Err(ImageError::Unsupported("jpeg component count"));
+        }
+        if segment.len() < 6 + count * 3 {
+            return Err(ImageError::Truncated);
+        }
+
+        let mut components = Vec::with_capacity(count);
+        for info in segment[6..6 + count * 3].chunks_exact(3) {
+            let h = (info[1] >> 4) as usize;
+            let v = (info[1] & 0x0F) as usize;
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) || info[2] > 3 {
+                return Err(ImageError::InvalidData("jpeg component is invalid"));
+            }
+
+            components.push(Component {
+                id: info[0],
+                h,
+                v,
+                quant_index: info[2] as usize,
+                quant: None,
+                blocks_w: 0,
+                blocks_h: 0,
+                width: 0,
+                height: 0,
+                coefficients: Vec::new(),
+                dc_pred: 0,
+            });
+        }
+
+        let max_h = components.iter().map(|c| c.h).max().unwrap_or(1);
+        let max_v = components.iter().map(|c| c.v).max().unwrap_or(1);
+        let mcus_x = width.div_ceil(8 * max_h);
+        let mcus_y = height.div_ceil(8 * max_v);
+
+        let total_blocks = components
+            .iter()
+            .map(|c| mcus_x * c.h * mcus_y * c.v)
+            .sum::<usize>();
+
+        // Todo bloco gasta pelo menos 1 bit no DC, entao um cabecalho mentiroso para aqui
+        if total_blocks / 8 > self.data.len() {
+            return Err(ImageError::Truncated);
+        }
+
+        for component in &mut components {
+            component.blocks_w = mcus_x * component.h;
+            component.blocks_h = mcus_y * component.v;
+            component.width = (width * component.h).div_ceil(max_h);
+            component.height = (height * component.v).div_ceil(max_v);
+            component.coefficients = vec![0; component.blocks_w * component.blocks_h * 64];
+        }
+
+        self.frame = Some(Frame {
+            width,
+            height,
+            progressive,
+            max_h,
+            max_v,
+            mcus_x,
+            mcus_y,
+            components,
+        });
+
+        Ok(())
+    }
+
+    fn read_huffman_tables(&mut self, mut segment: &[u8]) -> ImageResult<()> {
+        while !segment.is_empty() {
+            if segment.len() < 17 {
+                return Err(ImageError::Truncated);
+            }
+
+            let class = segment[0] >> 4;
+            let index = (segment[0] & 0x0F) as usize;
+            if class > 1 || index > 3 {
+                return Err(ImageError::InvalidData("jpeg huffman table is invalid"));
+            }
+
+            let mut counts = [0_u8; 16];
+            counts.copy_from_slice(&segment[1..17]);
+            let total = counts.iter().map(|c| *c as usize).sum::<usize>();
+            let values = segment
+                .get(17..17 + total)
+                .ok_or(ImageError::Truncated)?
+                .to_vec();
+
+            let table = HuffmanTable::new(&counts, values)?;
+            if class == 0 {
+                self.dc_tables[index] = Some(table);
+            } else {
+                self.ac_tables[index] = Some(table);
+            }
+
+            segment = &segment[17 + total..];
+        }
+
+        Ok(())
+    }
+
+    fn read_quant_tables(&mut self, mut segment: &[u8]) -> ImageResult<()> {
+        while !segment.is_empty() {
+            let precision = segment[0] >> 4;
+            let index = (segment[0] & 0x0F) as usize;
+            if precision > 1 || index > 3 {
+                return Err(ImageError::InvalidData(
+                    "jpeg quantization table is invalid",
+                ));
+            }
+
+            let size = if precision == 0 { 64 } else { 128 };
+            let values = segment.get(1..1 + size).ok_or(ImageError::Truncated)?;
+
+            let mut table = [0_u16; 64];
+            // As tabelas vem em ziguezague
+            for (i, natural) in ZIGZAG.iter().enumerate() {
+                table[*natural] = if precision == 0 {
+                    values[i] as u16
+                } else {
+                    u16::from_be_bytes([values[i * 2], values[i * 2 + 1]])
+                };
+            }
+
+            self.quant[index] = Some(table);
+            segment = &segment[1 + size..];
+        }
+
+        Ok(())
+    }
+
+    /// Le o cabecalho SOS e os dados comprimidos que o seguem, retornando a posicao apos eles
+    fn read_scan(&mut self, segment: &[u8], pos: usize) -> ImageResult<usize> {
+        let frame = self
+            .frame
+            .as_mut()
+            .ok_or(ImageError::InvalidData("jpeg scan before the frame header"))?;
+
+        let count = *segment.first().ok_or(ImageError::Truncated)? as usize;
+        if count == 0 || count > 4 || segment.len() < 1 + count * 2 + 3 {
+            return Err(ImageError::InvalidData("jpeg scan header is invalid"));
+        }
+
+        let mut components = Vec::with_capacity(count);
+        for info in segment[1..1 + count * 2].chunks_exact(2) {
+            let index = frame
+                .components
+                .iter()
+                .position(|c| c.id == info[0])
+                .ok_or(ImageError::InvalidData("jpeg scan component is unknown"))?;
+            components.push((index, (info[1] >> 4) as usize, (info[1] & 0x0F) as usize));
+        }
+
+        let params = &segment[1 + count * 2..];
+        let scan = Scan {
+            components,
+            start: params[0] as usize,
+            end: params[1] as usize,
+            high: params[2] >> 4,
+            low: params[2] & 0x0F,
+        };
+
+        if frame.progressive {
+            let valid = if scan.start == 0 {
+                scan.end == 0
+            } else {
+                scan.start <= scan.end && scan.end <= 63 && scan.components.len() == 1
+            };
+            if !valid || scan.low > 13 {
+                return Err(ImageError::InvalidData("jpeg progressive scan is invalid"));
+            }
+        }
+
+        for (index, dc, ac) in &scan.components {
+            let component = &mut frame.components[*index];
+            if component.quant.is_none() {
+                component.quant = self.quant[component.quant_index];
+            }
+
+            let needs_dc = !frame.progressive || (scan.start == 0 && scan.high == 0);
+            let needs_ac = !frame.progressive || scan.start > 0;
+            if *dc > 3 || *ac > 3 {
+                return Err(ImageError::InvalidData("jpeg huffman table is invalid"));
+            }
+            if needs_dc && self.dc_tables[*dc].is_none() {
+                return Err(ImageError::InvalidData("jpeg huffman table is missing"));
+            }
+            if needs_ac && self.ac_tables[*ac].is_none() {
+                return Err(ImageError::InvalidData("jpeg huffman table is missing"));
+            }
+            component.dc_pred = 0;
+        }
+
+        let mut reader = BitReader::new(self.data, pos);
+        let mut state = ScanState {
+            scan: &scan,
+            dc_tables: &self.dc_tables,
+            ac_tables: &self.ac_tables,
+            eobrun: 0,
+            progressive: frame.progressive,
+        };
+
+        let single = scan.components.len() == 1;
+        let (units_x, units_y) = if single {
+            let component = &frame.components[scan.components[0].0];
+            (component.width.div_ceil(8), component.height.div_ceil(8))
+        } else {
+            (frame.mcus_x, frame.mcus_y)
+        };
+
+        let mut left = self.restart_interval;
+        for unit_y in 0..units_y {
+            for unit_x in 0..units_x {
+                if self.restart_interval > 0 {
+                    if left == 0 {
+                        reader.restart();
+                        state.eobrun = 0;
+                        for (index, _, _) in &scan.components {
+                            frame.components[*index].dc_pred = 0;
+                        }
+                        left = self.restart_interval;
+                    }
+                    left -= 1;
+                }
+
+                if single {
+                    let (index, dc, ac) = scan.components[0];
+                    let component = &mut frame.components[index];
+                    let block = (unit_y * component.blocks_w + unit_x) * 64;
+                    state.decode_block(&mut reader, component, block, dc, ac)?;
+                    continue;
+                }
+
+                for (index, dc, ac) in &scan.components {
+                    let component = &mut frame.components[*index];
+                    for v in 0..component.v {
+                        for h in 0..component.h {
+                            let y = unit_y * component.v + v;
+                            let x = unit_x * component.h + h;
+                            let block = (y * component.blocks_w + x) * 64;
+                            state.decode_block(&mut reader, component, block, *dc, *ac)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.scans += 1;
+        Ok(reader.position())
+    }
+
+    fn finish(self) -> ImageResult<Decoded> {
+        let frame = self
+            .frame
+            .ok_or(ImageError::InvalidData("jpeg is missing the frame header"))?;
+        if self.scans == 0 {
+            return Err(ImageError::InvalidData("jpeg has no scans"));
+        }
+
+        let planes = frame
+            .components
+            .iter()
+            .map(|component| {
+                let plane = component.samples();
+                upsample(
+                    &plane,
+                    component.blocks_w * 8,
+                    component,
+                    frame.width,
+                    frame.height,
+                    frame.max_h,
+                    frame.max_v,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let ids = frame.components.iter().map(|c| c.id).collect::<Vec<_>>();
+        let color_space = match (planes.len(), self.adobe_transform) {
+            (1, _) => JpegColorSpace::Grayscale,
+            (3, Some(0)) => JpegColorSpace::Rgb,
+            (3, None) if !self.jfif && ids == [b'R', b'G', b'B'] => JpegColorSpace::Rgb,
+            (3, _) => JpegColorSpace::YCbCr,
+            (_, Some(2)) => JpegColorSpace::Ycck,
+            _ => JpegColorSpace::Cmyk,
+        };
+        // O Photoshop grava CMYK/YCCK invertido, e ele e quem escreve o marcador Adobe
+        let inverted = self.adobe_transform.is_some();
+
+        let size = frame.width * frame.height;
+        let pixels = (0..size)
+            .map(|i| match color_space {
+                JpegColorSpace::Grayscale => {
+                    let value = planes[0][i];
+                    RGB::new(value, value, value, None)
+                }
+                JpegColorSpace::Rgb => RGB::new(planes[0][i], planes[1][i], planes[2][i], None),
+                JpegColorSpace::YCbCr => ycbcr_to_rgb(planes[0][i], planes[1][i], planes[2][i]),
+                JpegColorSpace::Cmyk | JpegColorSpace::Ycck => {
+                    let (c, m, y) = if color_space == JpegColorSpace::Ycck {
+                        let rgb = ycbcr_to_rgb(planes[0][i], planes[1][i], planes[2][i]);
+                        (255 - rgb.red(), 255 - rgb.green(), 255 - rgb.blue())
+                    } else {
+                        (planes[0][i], planes[1][i], planes[2][i])
+                    };
+                    cmyk_to_rgb(c, m, y, planes[3][i], inverted)
+                }
+            })
+            .collect();
+
+        Ok(Decoded {
+            width: frame.width,
+            height: frame.height,
+            color_space,
+            progressive: frame.progressive,
+            orientation: self.orientation,
+            pixels,
+        })
+    }
+}
+
+impl Component {
+    /// Dequantiza e aplica a IDCT em todos os blocos, gerando o plano de amostras do componente
+    fn samples(&self) -> Vec<u8> {
+        let stride = self.blocks_w * 8;
+        let mut plane = vec![0_u8; stride * self.blocks_h * 8];
+        let quant = self.quant.unwrap_or([1; 64]);
+        let cosines = dct_table();
+
+        for (index, block) in self.coefficients.chunks_exact(64).enumerate() {
+            let mut input = [0_f32; 64];
+            for (i, value) in input.iter_mut().enumerate() {
+                *value = block[i] as f32 * quant[i] as f32;
+            }
+
+            let output = idct(&input, &cosines);
+            let x0 = (index % self.blocks_w) * 8;
+            let y0 = (index / self.blocks_w) * 8;
+            for y in 0..8 {
+                for x in 0..8 {
+                    let value = (output[y * 8 + x] + 128.0).round().clamp(0.0, 255.0);
+                    plane[(y0 + y) * stride + x0 + x] = value as u8;
+                }
+            }
+        }
+
+        plane
+    }
+}
+
+struct ScanState<'s> {
+    scan: &'s Scan,
+    dc_tables: &'s [Option<HuffmanTable>; 4],
+    ac_tables: &'s [Option<HuffmanTable>; 4],
+    eobrun: u32,
+    progressive: bool,
+}
+
+impl ScanState<'_> {
+    fn decode_block(
+        &mut self,
+        reader: &mut BitReader,
+        component: &mut Component,
+        block: usize,
+        dc: usize,
+        ac: usize,
+    ) -> ImageResult<()> {
+        let Some(coefficients) = component.coefficients.get_mut(block..block + 64) else {
+            return Ok(());
+        };
+
+        // As tabelas usadas pelo scan ja foram conferidas em `read_scan`
+        let dc_table = || required(&self.dc_tables[dc]);
+        let ac_table = || required(&self.ac_tables[ac]);
+
+        if !self.progressive {
+            let (dc_table, ac_table) = (dc_table()?, ac_table()?);
+
+            let size = dc_table.decode(reader)?;
+            component.dc_pred += reader.receive_extend(size);
+            coefficients[0] = component.dc_pred as i16;
+
+            let mut k = 1;
+            while k < 64 {
+                let symbol = ac_table.decode(reader)?;
+                let run = (symbol >> 4) as usize;
+                let size = symbol & 0x0F;
+
+                if size == 0 {
+                    if run != 15 {
+                        break;
+                    }
+                    k += 16;
+                    continue;
+                }
+
+                k += run;
+                if k > 63 {
+                    return Err(ImageError::InvalidData(
+                        "jpeg block has too many coefficients",
+                    ));
+                }
+                coefficients[ZIGZAG[k]] = reader.receive_extend(size) as i16;
+                k += 1;
+            }
+
+            return Ok(());
+        }
+
+        let scan = self.scan;
+        let low = scan.low as u32;
+
+        if scan.start == 0 {
+            if scan.high == 0 {
+                let size = dc_table()?.decode(reader)?;
+                component.dc_pred += reader.receive_extend(size);
+                coefficients[0] = (component.dc_pred << low) as i16;
+            } else if reader.bit() {
+                coefficients[0] |= 1 << low;
+            }
+
+            return Ok(());
+        }
+
+        let ac_table = ac_table()?;
+
+        if scan.high == 0 {
+            if self.eobrun > 0 {
+                self.eobrun -= 1;
+                return Ok(());
+            }
+
+            let mut k = scan.start;
+            while k <= scan.end {
+                let symbol = ac_table.decode(reader)?;
+                let run = (symbol >> 4) as u32;
+                let size = symbol & 0x0F;
+
+                if size == 0 {
+                    if run < 15 {
+                        self.eobrun = (1 << run) - 1 + reader.bits(run);
+                        break;
+                    }
+                    k += 16;
+                    continue;
+                }
+
+                k += run as usize;
+                if k > 63 {
+                    return Err(ImageError::InvalidData(
+                        "jpeg block has too many coefficients",
+                    ));
+                }
+                coefficients[ZIGZAG[k]] = (reader.receive_extend(size) << low) as i16;
+                k += 1;
+            }
+
+            return Ok(());
+        }
+
+        // Refinamento dos AC, seguindo o algoritmo da especificacao (G.1.2.3)
+        let positive = 1_i16 << low;
+        let negative = -1_i16 << low;
+        let mut k = scan.start;
+
+        let refine = |reader: &mut BitReader, coefficient: &mut i16| {
+            if reader.bit() && *coefficient & positive == 0 {
+                *coefficient += if *coefficient >= 0 {
+                    positive
+                } else {
+                    negative
+                };
+            }
+        };
+
+        if self.eobrun == 0 {
+            while k <= scan.end {
+                let symbol = ac_table.decode(reader)?;
+                let mut run = (symbol >> 4) as i32;
+                let size = symbol & 0x0F;
+                let mut value = 0;
+
+                if size != 0 {
+                    value = if reader.bit() { positive } else { negative };
+                } else if run != 15 {
+                    self.eobrun = (1 << run) + reader.bits(run as u32);
+                    break;
+                }
+
+                while k <= scan.end {
+                    let coefficient = &mut coefficients[ZIGZAG[k]];
+                    if *coefficient != 0 {
+                        refine(reader, coefficient);
+                    } else {
+                        if run == 0 {
+                            break;
+                        }
+                        run -= 1;
+                    }
+                    k += 1;
+                }
+
+                if value != 0 && k <= scan.end {
+                    coefficients[ZIGZAG[k]] = value;
+                }
+                k += 1;
+            }
+        }
+
+        if self.eobrun > 0 {
+            while k <= scan.end {
+                let coefficient = &mut coefficients[ZIGZAG[k]];
+                if *coefficient != 0 {
+                    refine(reader, coefficient);
+                }
+                k += 1;
+            }
+            self.eobrun -= 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Tabela de Huffman com busca rapida pelos primeiros `LOOKUP_BITS` bits
+struct HuffmanTable {
+    /// Simbolo e tamanho do codigo (`tamanho << 8 | simbolo`), 0 quando o codigo e maior
+    lookup: Vec<u16>,
+    max_code: [i32; 18],
+    offsets: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl HuffmanTable {
+    fn new(counts: &[u8; 16], values: Vec<u8>) -> ImageResult<Self> {
+        let mut lookup = vec![0_u16; 1 << LOOKUP_BITS];
+        let mut max_code = [-1_i32; 18];
+        let mut offsets = [0_i32; 17];
+
+        let mut code = 0_i32;
+        let mut index = 0_usize;
+        for length in 1..=16 {
+            let count = counts[length - 1] as i32;
+            offsets[length] = index as i32 - code;
+            if code + count > 1 << length {
+                return Err(ImageError::InvalidData("jpeg huffman table is invalid"));
+            }
+
+            for _ in 0..count {
+                if length as u32 <= LOOKUP_BITS {
+                    let shift = LOOKUP_BITS - length as u32;
+                    let start = (code as usize) << shift;
+                    for entry in &mut lookup[start..start + (1 << shift)] {
+                        *entry = ((length as u16) << 8) | values[index] as u16;
+                    }
+                }
+                code += 1;
+                index += 1;
+            }
+
+            if count > 0 {
+                max_code[length] = code - 1;
+            }
+            code <<= 1;
+        }
+        max_code[17] = i32::MAX;
+
+        Ok(Self {
+            lookup,
+            max_code,
+            offsets,
+            values,
+        })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> ImageResult<u8> {
+        let peek = reader.peek(LOOKUP_BITS);
+        let entry = self.lookup[peek as usize];
+        if entry != 0 {
+            reader.consume((entry >> 8) as u32);
+            return Ok(entry as u8);
+        }
+
+        let mut code = 0_i32;
+        for length in 1..=16 {
+            code = (code << 1) | reader.bit() as i32;
+            if code <= self.max_code[length] {
+                let index = (self.offsets[length] + code) as usize;
+                return self
+                    .values
+                    .get(index)
+                    .copied()
+                    .ok_or(ImageError::InvalidData("jpeg huffman code is invalid"));
+            }
+        }
+
+        Err(ImageError::InvalidData("jpeg huffman code is invalid"))
+    }
+}
+
+/// Leitor de bits dos dados comprimidos, que remove o byte 0x00 apos cada 0xFF e para nos
+/// marcadores (devolvendo zeros depois deles)
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u64,
+    count: u32,
+    at_marker: bool,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self {
+            data,
+            pos,
+            buffer: 0,
+            count: 0,
+            at_marker: false,
+        }
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 56 {
+            let mut byte = 0;
+            if !self.at_marker {
+                match self.data.get(self.pos) {
+                    Some(0xFF) => match self.data.get(self.pos + 1) {
+                        Some(0x00) => {
+                            byte = 0xFF;
+                            self.pos += 2;
+                        }
+                        _ => self.at_marker = true,
+                    },
+                    Some(value) => {
+                        byte = *value;
+                        self.pos += 1;
+                    }
+                    None => self.at_marker = true,
+                }
+            }
+
+            self.buffer |= (byte as u64) << (56 - self.count);
+            self.count += 8;
+        }
+    }
+
+    fn peek(&mut self, bits: u32) -> u32 {
+        if self.count < bits {
+            self.fill();
+        }
+        (self.buffer >> (64 - bits)) as u32
+    }
+
+    fn consume(&mut self, bits: u32) {
+        self.buffer <<= bits;
+        self.count -= bits;
+    }
+
+    fn bits(&mut self, bits: u32) -> u32 {
+        if bits == 0 {
+            return 0;
+        }
+        let value = self.peek(bits);
+        self.consume(bits);
+        value
+    }
+
+    fn bit(&mut self) -> bool {
+        self.bits(1) == 1
+    }
+
+    /// Le `size` bits e os converte para o valor com sinal (F.2.2.1)
+    fn receive_extend(&mut self, size: u8) -> i32 {
+        if size == 0 {
+            return 0;
+        }
+        let size = size.min(16) as u32;
+        let value = self.bits(size) as i32;
+        if value < 1 << (size - 1) {
+            value - (1 << size) + 1
+        } else {
+            value
+        }
+    }
+
+    /// Descarta os bits restantes e pula o marcador RSTn seguinte
+    fn restart(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+        self.at_marker = false;
+
+        if let Some((marker, next)) = find_marker(self.data, self.pos)
+            && (0xD0..=0xD7).contains(&marker)
+        {
+            self.pos = next;
+        }
+    }
+
+    /// Posicao do primeiro byte ainda nao usado
+    fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+// Utils Functions
+fn required(table: &Option<HuffmanTable>) -> ImageResult<&HuffmanTable> {
+    table
+        .as_ref()
+        .ok_or(ImageError::InvalidData("jpeg huffman table is missing"))
+}
+
+/// Procura o proximo marcador a partir de `pos`, retornando ele e a posicao logo depois
+fn find_marker(data: &[u8], mut pos: usize) -> Option<(u8, usize)> {
+    while pos + 1 < data.len() {
+        if data[pos] == 0xFF && data[pos + 1] != 0x00 && data[pos + 1] != 0xFF {
+            return Some((data[pos + 1], pos + 2));
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// IDCT 2D separavel de um bloco 8x8 em ordem natural
+fn idct(input: &[f32; 64], cosines: &[f32; 64]) -> [f32; 64] {
+    let mut rows = [0_f32; 64];
+    for y in 0..8 {
+        for x in 0..8 {
+            rows[y * 8 + x] = (0..8).map(|u| input[y * 8 + u] * cosines[u * 8 + x]).sum();
+        }
+    }
+
+    let mut output = [0_f32; 64];
+    for x in 0..8 {
+        for y in 0..8 {
+            output[y * 8 + x] = (0..8).map(|v| rows[v * 8 + x] * cosines[v * 8 + y]).sum();
+        }
+    }
+    output
+}
+
+/// Amplia o plano do componente para o tamanho da imagem com interpolacao linear, centrando as
+/// amostras como no upsampling "fancy" da libjpeg
+fn upsample(
+    plane: &[u8],
+    stride: usize,
+    component: &Component,
+    width: usize,
+    height: usize,
+    max_h: usize,
+    max_v: usize,
+) -> Vec<u8> {
+    let (source_w, source_h) = (component.width.max(1), component.height.max(1));
+
+    if component.h == max_h && component.v == max_v {
+        let mut output = Vec::with_capacity(width * height);
+        for y in 0..height {
+            output.extend_from_slice(&plane[y * stride..y * stride + width]);
+        }
+        return output;
+    }
+
+    let position = |target: usize, factor: f32, size: usize| -> (usize, usize, f32) {
+        let source = ((target as f32 + 0.5) / factor - 0.5).max(0.0);
+        let first = (source.floor() as usize).min(size - 1);
+        let second = (first + 1).min(size - 1);
+        (first, second, source - first as f32)
+    };
+
+    let factor_x = max_h as f32 / component.h as f32;
+    let factor_y = max_v as f32 / component.v as f32;
+
+    let mut horizontal = vec![0_f32; width * source_h];
+    for y in 0..source_h {
+        for x in 0..width {
+            let (first, second, weight) = position(x, factor_x, source_w);
+            let a = plane[y * stride + first] as f32;
+            let b = plane[y * stride + second] as f32;
+            horizontal[y * width + x] = a + (b - a) * weight;
+        }
+    }
+
+    let mut output = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let (first, second, weight) = position(y, factor_y, source_h);
+        for x in 0..width {
+            let a = horizontal[first * width + x];
+            let b = horizontal[second * width + x];
+            output.push((a + (b - a) * weight).round().clamp(0.0, 255.0) as u8);
+        }
+    }
+    output
+}
+
+fn ycbcr_to_rgb(y: u8, cb: u8, cr: u8) -> RGB {
+    let y = y as f32;
+    let cb = cb as f32 - 128.0;
+    let cr = cr as f32 - 128.0;
+
+    let clamp = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+    RGB::new(
+        clamp(y + 1.402 * cr),
+        clamp(y - 0.344_136 * cb - 0.714_136 * cr),
+        clamp(y + 1.772 * cb),
+        None,
+    )
+}
+
+fn cmyk_to_rgb(c: u8, m: u8, y: u8, k: u8, inverted: bool) -> RGB {
+    let (c, m, y, k) = if inverted {
+        (c as u32, m as u32, y as u32, k as u32)
+    } else {
+        (
+            255 - c as u32,
+            255 - m as u32,
+            255 - y as u32,
+            255 - k as u32,
+        )
+    };
+
+    let channel = |value: u32| ((value * k + 127) / 255) as u8;
+    RGB::new(channel(c), channel(m), channel(y), None)
+}
+
+/// Le a tag de orientacao (0x0112) do IFD0 de um bloco EXIF (cabecalho TIFF)
+fn exif_orientation(tiff: &[u8]) -> Option<Orientation> {
+    let big_endian = match tiff.get(0..2)? {
+        b"II" => false,
+        b"MM" => true,
+        _ => return None,
+    };
+
+    let u16_at = |pos: usize| -> Option<u16> {
+        let bytes = [*tiff.get(pos)?, *tiff.get(pos + 1)?];
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |pos: usize| -> Option<u32> {
+        let bytes = [
+            *tiff.get(pos)?,
+            *tiff.get(pos + 1)?,
+            *tiff.get(pos + 2)?,
+            *tiff.get(pos + 3)?,
+        ];
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    let ifd = u32_at(4)? as usize;
+    let entries = u16_at(ifd)? as usize;
+    for entry in 0..entries {
+        let pos = ifd + 2 + entry * 12;
+        if u16_at(pos)? == 0x0112 {
+            return Orientation::from_exif(u16_at(pos + 8)?);
+        }
+    }
+
+    None
+}
@@ -0,0 +1,650 @@
+use super::{JpegOptions, JpegSubsampling, ZIGZAG, dct_table};
+use crate::images::zlib::{canonical_codes, code_lengths};
+use crate::images::{Orientation, RGB};
+
+// Consts...
+/// Tabela de quantizacao de luminancia do anexo K.1 (qualidade 50), em ordem natural
+const LUMA_QUANT: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// Tabela de quantizacao de crominancia do anexo K.1 (qualidade 50), em ordem natural
+const CHROMA_QUANT: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+// Tabelas de Huffman padrao do anexo K.3: quantidade de codigos por tamanho e os simbolos
+const DC_LUMA_COUNTS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const DC_LUMA_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+const DC_CHROMA_COUNTS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const DC_CHROMA_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+const AC_LUMA_COUNTS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 125];
+const AC_LUMA_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xA1, 0x08, 0x23, 0x42, 0xB1, 0xC1, 0x15, 0x52, 0xD1, 0xF0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0A, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2A, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7,
+    0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3, 0xC4, 0xC5,
+    0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xE1, 0xE2,
+    0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+    0xF9, 0xFA,
+];
+const AC_CHROMA_COUNTS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 119];
+const AC_CHROMA_VALUES: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xA1, 0xB1, 0xC1, 0x09, 0x23, 0x33, 0x52, 0xF0,
+    0x15, 0x62, 0x72, 0xD1, 0x0A, 0x16, 0x24, 0x34, 0xE1, 0x25, 0xF1, 0x17, 0x18, 0x19, 0x1A, 0x26,
+    0x27, 0x28, 0x29, 0x2A, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5,
+    0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3,
+    0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA,
+    0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+    0xF9, 0xFA,
+];
+
+/// Faixas de coeficientes AC gravadas em scans separados no modo progressivo
+const PROGRESSIVE_BANDS: [(usize, usize); 2] = [(1, 5), (6, 63)];
+
+/// Maior sequencia de blocos vazios guardada num unico simbolo EOBn
+const MAX_EOBRUN: u32 = 0x7FFF;
+
+// Structs...
+/// Componente ja transformado e quantizado, com os blocos completando o ultimo MCU
+struct Plane {
+    id: u8,
+    h: usize,
+    v: usize,
+    /// 0 para luminancia e 1 para crominancia (quantizacao e Huffman)
+    table: usize,
+    blocks_w: usize,
+    /// Blocos com amostras reais, usados nos scans de um unico componente
+    used_w: usize,
+    used_h: usize,
+    coefficients: Vec<i16>,
+}
+
+/// Codigos de Huffman de uma tabela, junto com a forma gravada no segmento DHT
+struct HuffmanCode {
+    counts: [u8; 16],
+    values: Vec<u8>,
+    codes: [u16; 256],
+    lengths: [u8; 256],
+}
+
+/// Escreve a imagem como JPEG: baseline com um unico scan, ou progressivo com scans separados
+/// para o DC e para as faixas de AC de cada componente
+pub(super) fn encode(
+    width: usize,
+    height: usize,
+    pixels: &[RGB],
+    options: &JpegOptions,
+    orientation: Orientation,
+) -> Vec<u8> {
+    let gray = pixels
+        .iter()
+        .all(|p| p.red() == p.green() && p.green() == p.blue());
+
+    let quality = options.quality.clamp(1, 100) as u32;
+    let quant = [
+        scale_quant(&LUMA_QUANT, quality),
+        scale_quant(&CHROMA_QUANT, quality),
+    ];
+
+    let planes = build_planes(width, height, pixels, gray, options.subsampling, &quant);
+    let max_h = planes.iter().map(|p| p.h).max().unwrap_or(1);
+    let max_v = planes.iter().map(|p| p.v).max().unwrap_or(1);
+    let mcus = (width.div_ceil(8 * max_h), height.div_ceil(8 * max_v));
+
+    let mut bytes = vec![0xFF, 0xD8];
+    write_segment(
+        &mut bytes,
+        0xE0,
+        &[b'J', b'F', b'I', b'F', 0, 1, 1, 0, 0, 1, 0, 1, 0, 0],
+    );
+
+    if orientation != Orientation::Normal {
+        let mut exif = b"Exif\0\0MM\0\x2A\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+        exif.extend_from_slice(&orientation.to_exif().to_be_bytes());
+        exif.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        write_segment(&mut bytes, 0xE1, &exif);
+    }
+
+    let mut dqt = Vec::new();
+    for (index, table) in quant.iter().enumerate().take(if gray { 1 } else { 2 }) {
+        dqt.push(index as u8);
+        dqt.extend(ZIGZAG.iter().map(|natural| table[*natural] as u8));
+    }
+    write_segment(&mut bytes, 0xDB, &dqt);
+
+    let mut sof = vec![8];
+    sof.extend_from_slice(&(height as u16).to_be_bytes());
+    sof.extend_from_slice(&(width as u16).to_be_bytes());
+    sof.push(planes.len() as u8);
+    for plane in &planes {
+        sof.extend_from_slice(&[
+            plane.id,
+            ((plane.h << 4) | plane.v) as u8,
+            plane.table as u8,
+        ]);
+    }
+    write_segment(
+        &mut bytes,
+        if options.progressive { 0xC2 } else { 0xC0 },
+        &sof,
+    );
+
+    let all = (0..planes.len()).collect::<Vec<_>>();
+    if options.progressive {
+        write_scan(&mut bytes, &planes, &all, ScanKind::Dc, mcus, true);
+        for index in 0..planes.len() {
+            for (start, end) in PROGRESSIVE_BANDS {
+                write_scan(
+                    &mut bytes,
+                    &planes,
+                    &[index],
+                    ScanKind::Ac(start, end),
+                    mcus,
+                    true,
+                );
+            }
+        }
+    } else {
+        let optimize = options.optimize_huffman;
+        write_scan(
+            &mut bytes,
+            &planes,
+            &all,
+            ScanKind::Baseline,
+            mcus,
+            optimize,
+        );
+    }
+
+    bytes.extend_from_slice(&[0xFF, 0xD9]);
+    bytes
+}
+
+/// Grava um scan completo: as tabelas de Huffman que ele usa (DHT), o cabecalho (SOS) e os dados
+fn write_scan(
+    bytes: &mut Vec<u8>,
+    planes: &[Plane],
+    scan: &[usize],
+    kind: ScanKind,
+    mcus: (usize, usize),
+    optimize: bool,
+) {
+    // Posicoes das tabelas: 0 e 1 sao os DC (luminancia e crominancia), 2 e 3 os AC
+    let mut used = [false; 4];
+    for index in scan {
+        let table = planes[*index].table;
+        match kind {
+            ScanKind::Baseline => {
+                used[table] = true;
+                used[2 + table] = true;
+            }
+            ScanKind::Dc => used[table] = true,
+            ScanKind::Ac(..) => used[2 + table] = true,
+        }
+    }
+
+    let tables: [HuffmanCode; 4] = if optimize {
+        let mut counter = Counter {
+            frequencies: [[0; 256]; 4],
+        };
+        encode_scan(&mut counter, planes, scan, kind, mcus);
+        counter.frequencies.map(|f| HuffmanCode::optimized(&f))
+    } else {
+        [
+            HuffmanCode::new(&DC_LUMA_COUNTS, &DC_LUMA_VALUES),
+            HuffmanCode::new(&DC_CHROMA_COUNTS, &DC_CHROMA_VALUES),
+            HuffmanCode::new(&AC_LUMA_COUNTS, &AC_LUMA_VALUES),
+            HuffmanCode::new(&AC_CHROMA_COUNTS, &AC_CHROMA_VALUES),
+        ]
+    };
+
+    let mut dht = Vec::new();
+    for (slot, table) in tables.iter().enumerate().filter(|(slot, _)| used[*slot]) {
+        dht.push((((slot / 2) << 4) | (slot % 2)) as u8);
+        dht.extend_from_slice(&table.counts);
+        dht.extend_from_slice(&table.values);
+    }
+    write_segment(bytes, 0xC4, &dht);
+
+    let (start, end) = match kind {
+        ScanKind::Baseline => (0, 63),
+        ScanKind::Dc => (0, 0),
+        ScanKind::Ac(start, end) => (start, end),
+    };
+
+    let mut sos = vec![scan.len() as u8];
+    for index in scan {
+        let plane = &planes[*index];
+        sos.extend_from_slice(&[plane.id, ((plane.table << 4) | plane.table) as u8]);
+    }
+    sos.extend_from_slice(&[start as u8, end as u8, 0]);
+    write_segment(bytes, 0xDA, &sos);
+
+    let mut writer = BitWriter {
+        bytes,
+        buffer: 0,
+        count: 0,
+        tables: &tables,
+    };
+    encode_scan(&mut writer, planes, scan, kind, mcus);
+    writer.flush();
+}
+
+/// Percorre os blocos do scan na ordem do arquivo, mandando os simbolos para o `Emitter`
+fn encode_scan(
+    emitter: &mut impl Emitter,
+    planes: &[Plane],
+    scan: &[usize],
+    kind: ScanKind,
+    mcus: (usize, usize),
+) {
+    let mut predictions = vec![0_i32; scan.len()];
+    let mut eobrun = 0_u32;
+
+    let mut block = |emitter: &mut dyn Emitter, position: usize, plane: &Plane, offset: usize| {
+        let coefficients = &plane.coefficients[offset * 64..offset * 64 + 64];
+        let (dc, ac) = (plane.table, 2 + plane.table);
+
+        if !matches!(kind, ScanKind::Ac(..)) {
+            let diff = coefficients[0] as i32 - predictions[position];
+            predictions[position] = coefficients[0] as i32;
+            let size = bit_size(diff);
+            emitter.symbol(dc, size);
+            emitter.bits(extend_bits(diff, size), size);
+        }
+
+        match kind {
+            ScanKind::Dc => {}
+            ScanKind::Baseline => {
+                let mut run = 0;
+                for natural in &ZIGZAG[1..] {
+                    let value = coefficients[*natural] as i32;
+                    if value == 0 {
+                        run += 1;
+                        continue;
+                    }
+                    while run > 15 {
+                        emitter.symbol(ac, 0xF0);
+                        run -= 16;
+                    }
+                    let size = bit_size(value);
+                    emitter.symbol(ac, (run << 4) as u8 | size);
+                    emitter.bits(extend_bits(value, size), size);
+                    run = 0;
+                }
+                if run > 0 {
+                    emitter.symbol(ac, 0x00);
+                }
+            }
+            ScanKind::Ac(start, end) => {
+                let mut run = 0;
+                for natural in &ZIGZAG[start..=end] {
+                    let value = coefficients[*natural] as i32;
+                    if value == 0 {
+                        run += 1;
+                        continue;
+                    }
+                    emit_eobrun(emitter, ac, &mut eobrun);
+                    while run > 15 {
+                        emitter.symbol(ac, 0xF0);
+                        run -= 16;
+                    }
+                    let size = bit_size(value);
+                    emitter.symbol(ac, (run << 4) as u8 | size);
+                    emitter.bits(extend_bits(value, size), size);
+                    run = 0;
+                }
+                if run > 0 {
+                    eobrun += 1;
+                    if eobrun == MAX_EOBRUN {
+                        emit_eobrun(emitter, ac, &mut eobrun);
+                    }
+                }
+            }
+        }
+    };
+
+    if let [index] = scan {
+        // Scan de um componente so: percorre apenas os blocos com amostras reais
+        let plane = &planes[*index];
+        for y in 0..plane.used_h {
+            for x in 0..plane.used_w {
+                block(emitter, 0, plane, y * plane.blocks_w + x);
+            }
+        }
+        if let ScanKind::Ac(..) = kind {
+            emit_eobrun(emitter, 2 + plane.table, &mut eobrun);
+        }
+        return;
+    }
+
+    for mcu_y in 0..mcus.1 {
+        for mcu_x in 0..mcus.0 {
+            for (position, index) in scan.iter().enumerate() {
+                let plane = &planes[*index];
+                for v in 0..plane.v {
+                    for h in 0..plane.h {
+                        let offset = (mcu_y * plane.v + v) * plane.blocks_w + mcu_x * plane.h + h;
+                        block(emitter, position, plane, offset);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Converte os pixels para YCbCr (ou so Y), subamostra a crominancia e aplica DCT e quantizacao
+fn build_planes(
+    width: usize,
+    height: usize,
+    pixels: &[RGB],
+    gray: bool,
+    subsampling: JpegSubsampling,
+    quant: &[[u16; 64]; 2],
+) -> Vec<Plane> {
+    let (max_h, max_v) = match (gray, subsampling) {
+        (false, JpegSubsampling::Chroma420) => (2, 2),
+        _ => (1, 1),
+    };
+    let stride = width.div_ceil(8 * max_h) * 8 * max_h;
+    let rows = height.div_ceil(8 * max_v) * 8 * max_v;
+
+    // Completa o ultimo MCU repetindo a ultima coluna e a ultima linha
+    let mut samples = vec![Vec::with_capacity(stride * rows); if gray { 1 } else { 3 }];
+    for y in 0..rows {
+        for x in 0..stride {
+            let pixel = &pixels[y.min(height - 1) * width + x.min(width - 1)];
+            let (r, g, b) = (
+                pixel.red() as f32,
+                pixel.green() as f32,
+                pixel.blue() as f32,
+            );
+
+            samples[0].push(0.299 * r + 0.587 * g + 0.114 * b);
+            if !gray {
+                samples[1].push(-0.168_736 * r - 0.331_264 * g + 0.5 * b + 128.0);
+                samples[2].push(0.5 * r - 0.418_688 * g - 0.081_312 * b + 128.0);
+            }
+        }
+    }
+
+    let cosines = dct_table();
+    samples
+        .into_iter()
+        .enumerate()
+        .map(|(index, full)| {
+            let (h, v) = if index == 0 { (max_h, max_v) } else { (1, 1) };
+            let (factor_x, factor_y) = (max_h / h, max_v / v);
+            let plane_w = stride / factor_x;
+            let plane_h = rows / factor_y;
+
+            let plane = if factor_x == 1 && factor_y == 1 {
+                full
+            } else {
+                downsample(&full, stride, plane_w, plane_h, factor_x, factor_y)
+            };
+
+            let table = index.min(1);
+            let blocks_w = plane_w / 8;
+            let blocks_h = plane_h / 8;
+            let mut coefficients = Vec::with_capacity(blocks_w * blocks_h * 64);
+            for block_y in 0..blocks_h {
+                for block_x in 0..blocks_w {
+                    let mut input = [0_f32; 64];
+                    for y in 0..8 {
+                        for x in 0..8 {
+                            let sample = plane[(block_y * 8 + y) * plane_w + block_x * 8 + x];
+                            input[y * 8 + x] = sample - 128.0;
+                        }
+                    }
+
+                    let output = fdct(&input, &cosines);
+                    coefficients.extend(
+                        output
+                            .iter()
+                            .zip(&quant[table])
+                            .map(|(value, q)| (value / *q as f32).round() as i16),
+                    );
+                }
+            }
+
+            Plane {
+                id: index as u8 + 1,
+                h,
+                v,
+                table,
+                blocks_w,
+                used_w: (width * h).div_ceil(max_h).div_ceil(8),
+                used_h: (height * v).div_ceil(max_v).div_ceil(8),
+                coefficients,
+            }
+        })
+        .collect()
+}
+
+// Enums...
+#[derive(Clone, Copy)]
+enum ScanKind {
+    /// Todos os coeficientes de todos os componentes num unico scan
+    Baseline,
+    /// Apenas os DC, de todos os componentes
+    Dc,
+    /// Uma faixa de AC de um unico componente
+    Ac(usize, usize),
+}
+
+// Traits...
+/// Destino dos simbolos de um scan: a contagem de frequencias ou a gravacao dos bits
+trait Emitter {
+    fn symbol(&mut self, table: usize, symbol: u8);
+
+    fn bits(&mut self, value: u32, count: u8);
+}
+
+struct Counter {
+    frequencies: [[u32; 256]; 4],
+}
+
+impl Emitter for Counter {
+    fn symbol(&mut self, table: usize, symbol: u8) {
+        self.frequencies[table][symbol as usize] += 1;
+    }
+
+    fn bits(&mut self, _value: u32, _count: u8) {}
+}
+
+struct BitWriter<'a> {
+    bytes: &'a mut Vec<u8>,
+    buffer: u32,
+    count: u8,
+    tables: &'a [HuffmanCode; 4],
+}
+
+impl BitWriter<'_> {
+    /// Completa o ultimo byte com bits 1, como pede a especificacao
+    fn flush(&mut self) {
+        if self.count > 0 {
+            let padding = 8 - self.count;
+            self.bits((1 << padding) - 1, padding);
+        }
+    }
+}
+
+impl Emitter for BitWriter<'_> {
+    fn symbol(&mut self, table: usize, symbol: u8) {
+        let table = &self.tables[table];
+        let (code, length) = (table.codes[symbol as usize], table.lengths[symbol as usize]);
+        self.bits(code as u32, length);
+    }
+
+    fn bits(&mut self, value: u32, count: u8) {
+        for bit in (0..count).rev() {
+            self.buffer = (self.buffer << 1) | ((value >> bit) & 1);
+            self.count += 1;
+
+            if self.count == 8 {
+                let byte = self.buffer as u8;
+                self.bytes.push(byte);
+                // 0xFF nos dados precisa de um 0x00 para nao ser lido como marcador
+                if byte == 0xFF {
+                    self.bytes.push(0x00);
+                }
+                self.buffer = 0;
+                self.count = 0;
+            }
+        }
+    }
+}
+
+impl HuffmanCode {
+    fn new(counts: &[u8; 16], values: &[u8]) -> Self {
+        let mut codes = [0_u16; 256];
+        let mut lengths = [0_u8; 256];
+
+        let mut code = 0_u16;
+        let mut symbols = values.iter();
+        for (length, count) in counts.iter().enumerate() {
+            for symbol in symbols.by_ref().take(*count as usize) {
+                codes[*symbol as usize] = code;
+                lengths[*symbol as usize] = length as u8 + 1;
+                code += 1;
+            }
+            code <<= 1;
+        }
+
+        Self {
+            counts: *counts,
+            values: values.to_vec(),
+            codes,
+            lengths,
+        }
+    }
+
+    /// Monta a tabela ideal para as frequencias do scan. Um simbolo extra com frequencia 1 fica
+    /// com o codigo de 16 bits so com 1, que o JPEG nao permite usar
+    fn optimized(frequencies: &[u32; 256]) -> Self {
+        let mut frequencies = frequencies.to_vec();
+        frequencies.push(1);
+
+        let lengths = code_lengths(&frequencies, 16);
+        // O simbolo extra e o ultimo codigo do maior tamanho, entao fica de fora sem mudar os outros
+        let codes = canonical_codes(&lengths[..256]);
+
+        let mut symbols = (0..256).filter(|s| lengths[*s] > 0).collect::<Vec<_>>();
+        symbols.sort_by_key(|s| (lengths[*s], *s));
+
+        let mut table = Self {
+            counts: [0; 16],
+            values: symbols.iter().map(|s| *s as u8).collect(),
+            codes: [0; 256],
+            lengths: [0; 256],
+        };
+        for symbol in symbols {
+            table.counts[lengths[symbol] as usize - 1] += 1;
+            table.codes[symbol] = codes[symbol];
+            table.lengths[symbol] = lengths[symbol];
+        }
+
+        table
+    }
+}
+
+// Utils Functions
+/// Escala a tabela do anexo K pela qualidade, com a mesma formula da libjpeg
+fn scale_quant(table: &[u16; 64], quality: u32) -> [u16; 64] {
+    let scale = if quality < 50 {
+        5000 / quality
+    } else {
+        200 - quality * 2
+    };
+
+    table.map(|value| ((value as u32 * scale + 50) / 100).clamp(1, 255) as u16)
+}
+
+/// Media de cada bloco `factor_x` x `factor_y` de amostras
+fn downsample(
+    full: &[f32],
+    stride: usize,
+    width: usize,
+    height: usize,
+    factor_x: usize,
+    factor_y: usize,
+) -> Vec<f32> {
+    let area = (factor_x * factor_y) as f32;
+    let mut output = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = 0.0;
+            for dy in 0..factor_y {
+                for dx in 0..factor_x {
+                    sum += full[(y * factor_y + dy) * stride + x * factor_x + dx];
+                }
+            }
+            output.push(sum / area);
+        }
+    }
+    output
+}
+
+/// DCT 2D separavel de um bloco 8x8 em ordem natural
+fn fdct(input: &[f32; 64], cosines: &[f32; 64]) -> [f32; 64] {
+    let mut rows = [0_f32; 64];
+    for y in 0..8 {
+        for u in 0..8 {
+            rows[y * 8 + u] = (0..8).map(|x| input[y * 8 + x] * cosines[u * 8 + x]).sum();
+        }
+    }
+
+    let mut output = [0_f32; 64];
+    for u in 0..8 {
+        for v in 0..8 {
+            output[v * 8 + u] = (0..8).map(|y| rows[y * 8 + u] * cosines[v * 8 + y]).sum();
+        }
+    }
+    output
+}
+
+/// Quantidade de bits do valor absoluto (a "categoria" do coeficiente)
+fn bit_size(value: i32) -> u8 {
+    (32 - value.unsigned_abs().leading_zeros()) as u8
+}
+
+/// Bits gravados depois do simbolo: o proprio valor, ou seu complemento quando negativo
+fn extend_bits(value: i32, size: u8) -> u32 {
+    if value < 0 {
+        (value - 1) as u32 & ((1 << size) - 1)
+    } else {
+        value as u32
+    }
+}
+
+/// Grava a sequencia de blocos vazios pendente como um simbolo EOBn
+fn emit_eobrun(emitter: &mut dyn Emitter, table: usize, eobrun: &mut u32) {
+    if *eobrun == 0 {
+        return;
+    }
+
+    let size = (31 - eobrun.leading_zeros()) as u8;
+    emitter.symbol(table, size << 4);
+    emitter.bits(*eobrun & ((1 << size) - 1), size);
+    *eobrun = 0;
+}
+
+fn write_segment(bytes: &mut Vec<u8>, marker: u8, data: &[u8]) {
+    bytes.extend_from_slice(&[0xFF, marker]);
+    bytes.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+    bytes.extend_from_slice(data);
+}
@@ -0,0 +1,290 @@
+use super::{Format, Image, Orientation, RGB, buffer::ImageBuffer};
+use crate::error::{ImageError, ImageResult};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+mod decoder;
+mod encoder;
+
+// Consts...
+/// Posicao natural (linha * 8 + coluna) de cada coeficiente na ordem em ziguezague
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+// Enums...
+/// Enum que representa o espaco de cor dos componentes gravados no arquivo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JpegColorSpace {
+    Grayscale,
+    YCbCr,
+    /// RGB sem transformacao (marcador Adobe com transform 0)
+    Rgb,
+    Cmyk,
+    /// CMYK com o CMY guardado como YCbCr (marcador Adobe com transform 2)
+    Ycck,
+}
+
+/// Enum que representa a resolucao das cores (Cb e Cr) em relacao ao brilho na gravacao
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum JpegSubsampling {
+    /// Cores na resolucao completa
+    Chroma444,
+    /// Cores com metade da largura e metade da altura
+    #[default]
+    Chroma420,
+}
+
+// Structs...
+/// Opcoes usadas ao salvar um JPEG
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JpegOptions {
+    /// Qualidade de 1 a 100 (valores fora da faixa sao limitados), como na libjpeg
+    pub quality: u8,
+    /// Subamostragem das cores; imagens cinzas sao gravadas so com o brilho
+    pub subsampling: JpegSubsampling,
+    /// Gera tabelas de Huffman para a imagem em vez de usar as do anexo K (arquivo menor)
+    pub optimize_huffman: bool,
+    /// Grava scans progressivos, que sempre usam tabelas de Huffman otimizadas
+    pub progressive: bool,
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self {
+            quality: 75,
+            subsampling: JpegSubsampling::Chroma420,
+            optimize_huffman: true,
+            progressive: false,
+        }
+    }
+}
+
+/// Struct para representa uma imagem JPEG, ja decodificada para RGB
+pub struct Jpeg {
+    width: usize,
+    height: usize,
+    color_space: JpegColorSpace,
+    progressive: bool,
+    options: JpegOptions,
+    pixels: Vec<RGB>,
+    orientation: Orientation,
+}
+
+impl Jpeg {
+    /// Cria uma imagem JPEG a partir de pixels em ordem de linhas. O alpha e descartado ao
+    /// salvar, ja que o formato nao tem transparencia
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("jpeg dimensions are zero"));
+        }
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(ImageError::InvalidData(
+                "pixel count does not match the dimensions",
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            color_space: JpegColorSpace::YCbCr,
+            progressive: false,
+            options: JpegOptions::default(),
+            pixels,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    /// Opcoes usadas por `encode` e `save`
+    pub fn options(&self) -> JpegOptions {
+        self.options
+    }
+
+    pub fn set_options(&mut self, options: JpegOptions) {
+        self.options = options;
+    }
+
+    /// Espaco de cor em que o arquivo lido estava gravado
+    pub fn color_space(&self) -> JpegColorSpace {
+        self.color_space
+    }
+
+    /// Indica se o arquivo lido era progressivo
+    pub fn progressive(&self) -> bool {
+        self.progressive
+    }
+
+    fn read_jpeg(data: &[u8]) -> ImageResult<Self> {
+        let decoded = decoder::decode(data)?;
+
+        Ok(Self {
+            width: decoded.width,
+            height: decoded.height,
+            color_space: decoded.color_space,
+            progressive: decoded.progressive,
+            options: JpegOptions {
+                progressive: decoded.progressive,
+                ..JpegOptions::default()
+            },
+            pixels: decoded.pixels,
+            orientation: decoded.orientation,
+        })
+    }
+}
+
+impl From<Jpeg> for ImageBuffer {
+    fn from(image: Jpeg) -> Self {
+        ImageBuffer::from_raw(image.width, image.height, image.pixels)
+            .with_orientation(image.orientation)
+    }
+}
+
+impl TryFrom<ImageBuffer> for Jpeg {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Jpeg::from_pixels(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+impl Image for Jpeg {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_jpeg(&data)
+    }
+
+    /// Grava com as opcoes de `options`; a orientacao vai num bloco EXIF quando nao e a normal
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        let bytes = encoder::encode(
+            self.width,
+            self.height,
+            &self.pixels,
+            &self.options,
+            self.orientation,
+        );
+        writer.write_all(&bytes)?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        let image = Self::from_pixels(width, height, pixels)?;
+
+        self.width = image.width;
+        self.height = image.height;
+        self.pixels = image.pixels;
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        Format::JPEG
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        match self.color_space {
+            JpegColorSpace::Grayscale => 8,
+            JpegColorSpace::YCbCr | JpegColorSpace::Rgb => 24,
+            JpegColorSpace::Cmyk | JpegColorSpace::Ycck => 32,
+        }
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        &mut self.pixels
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        &self.pixels
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get_mut(y * self.width + x)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get(y * self.width + x)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        &mut self.pixels[range]
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        &self.pixels[range]
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (pixels, width, height) = self
+            .orientation
+            .apply(&self.pixels, self.width, self.height);
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+        self.orientation = Orientation::Normal;
+    }
+}
+
+// Utils Functions
+/// Cossenos da DCT ja multiplicados pelos fatores de normalizacao: `[u * 8 + x]`
+fn dct_table() -> [f32; 64] {
+    let mut table = [0_f32; 64];
+    for u in 0..8 {
+        let scale = if u == 0 {
+            std::f32::consts::FRAC_1_SQRT_2
+        } else {
+            1.0
+        };
+        for x in 0..8 {
+            let angle = ((2 * x + 1) * u) as f32 * std::f32::consts::PI / 16.0;
+            table[u * 8 + x] = scale * angle.cos() / 2.0;
+        }
+    }
+    table
+}
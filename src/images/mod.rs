@@ -1,13 +1,236 @@
-use std::{io::Result as IOResult, ops::Range};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
 
-use crate::filters::{Filter, FilterError};
+use crate::error::{ImageError, ImageResult};
+use crate::filters::{Filter, FilterError, Transform};
 
+pub mod animation;
 pub mod bitmap;
+pub mod buffer;
+pub mod dynamic;
+pub mod frames;
+pub mod gif;
+pub mod hdr;
+pub mod ico;
+pub mod jpeg;
+pub mod pixel;
+pub mod png;
+pub mod pnm;
+pub mod qoi;
+pub mod tga;
+pub mod tiff;
+pub mod webp;
+pub mod zlib;
+
+use dynamic::DynamicImage;
+use pixel::Pixel;
+
+// Consts...
+/// Quantidade de bytes do inicio do arquivo usada para detectar o formato
+pub const MAGIC_SIZE: usize = 16;
 
 // Enums...
 /// Enums que representa os possivel formatos de imagens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Format {
     BMP,
+    PNG,
+    PNM,
+    QOI,
+    GIF,
+    JPEG,
+    TIFF,
+    WEBP,
+    ICO,
+    TGA,
+    HDR,
+}
+
+impl Format {
+    /// Detecta o formato pelos primeiros bytes do arquivo (magic bytes)
+    pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [b'B', b'M', ..] => Some(Format::BMP),
+            [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n', ..] => Some(Format::PNG),
+            [b'P', b'1'..=b'7', ..] => Some(Format::PNM),
+            [b'q', b'o', b'i', b'f', ..] => Some(Format::QOI),
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(Format::GIF),
+            [0xFF, 0xD8, 0xFF, ..] => Some(Format::JPEG),
+            [b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => Some(Format::TIFF),
+            [
+                b'R',
+                b'I',
+                b'F',
+                b'F',
+                _,
+                _,
+                _,
+                _,
+                b'W',
+                b'E',
+                b'B',
+                b'P',
+                ..,
+            ] => Some(Format::WEBP),
+            [0x00, 0x00, 0x01 | 0x02, 0x00, ..] if is_icon_directory(bytes) => Some(Format::ICO),
+            [
+                b'#',
+                b'?',
+                b'R',
+                b'A',
+                b'D',
+                b'I',
+                b'A',
+                b'N',
+                b'C',
+                b'E',
+                ..,
+            ]
+            | [b'#', b'?', b'R', b'G', b'B', b'E', ..] => Some(Format::HDR),
+            _ => None,
+        }
+    }
+
+    /// Detecta o formato pelo conteudo: primeiro os magic bytes e depois a assinatura no fim do
+    /// arquivo (o TGA so tem assinatura no rodape). Volta o leitor para a posicao inicial
+    pub fn from_reader(reader: &mut (impl Read + Seek)) -> ImageResult<Option<Self>> {
+        let start = reader.stream_position()?;
+
+        let mut magic = Vec::with_capacity(MAGIC_SIZE);
+        (&mut *reader)
+            .take(MAGIC_SIZE as u64)
+            .read_to_end(&mut magic)?;
+
+        let mut format = Format::from_magic(&magic);
+        let end = reader.seek(SeekFrom::End(0))?;
+        let signature = tga::SIGNATURE.len() as u64;
+        if format.is_none() && end >= start + signature {
+            let mut footer = [0; tga::SIGNATURE.len()];
+            reader.seek(SeekFrom::End(-(signature as i64)))?;
+            reader.read_exact(&mut footer)?;
+            format = (footer == tga::SIGNATURE).then_some(Format::TGA);
+        }
+
+        reader.seek(SeekFrom::Start(start))?;
+        Ok(format)
+    }
+
+    /// Detecta o formato pela extensao do arquivo, sem diferenciar maiusculas
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+
+        match extension.as_str() {
+            "bmp" | "dib" => Some(Format::BMP),
+            "png" => Some(Format::PNG),
+            "pbm" | "pgm" | "ppm" | "pnm" | "pam" => Some(Format::PNM),
+            "qoi" => Some(Format::QOI),
+            "gif" => Some(Format::GIF),
+            "jpg" | "jpeg" | "jpe" | "jfif" => Some(Format::JPEG),
+            "tif" | "tiff" => Some(Format::TIFF),
+            "webp" => Some(Format::WEBP),
+            "ico" | "cur" => Some(Format::ICO),
+            "tga" | "icb" | "vda" | "vst" => Some(Format::TGA),
+            "hdr" | "rgbe" => Some(Format::HDR),
+            _ => None,
+        }
+    }
+
+    /// Detecta o formato de um caminho, primeiro pelo conteudo e depois pela extensao
+    pub fn from_path(path: impl Into<String>) -> ImageResult<Self> {
+        let path = path.into();
+        let mut file = File::open(&path)?;
+
+        Format::from_reader(&mut file)?
+            .or_else(|| {
+                Path::new(&path)
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .and_then(Format::from_extension)
+            })
+            .ok_or(ImageError::InvalidMagic)
+    }
+}
+
+/// Enum que representa a orientacao de exibicao da imagem, com os valores da tag EXIF 0x0112
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    #[default]
+    Normal = 1,
+    FlipHorizontal = 2,
+    Rotate180 = 3,
+    FlipVertical = 4,
+    /// Espelha na diagonal principal (troca x e y)
+    Transpose = 5,
+    /// Precisa girar 90 graus no sentido horario para exibir
+    Rotate90 = 6,
+    /// Espelha na diagonal secundaria
+    Transverse = 7,
+    /// Precisa girar 270 graus no sentido horario para exibir
+    Rotate270 = 8,
+}
+
+impl Orientation {
+    pub fn from_exif(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(Self::Normal),
+            2 => Some(Self::FlipHorizontal),
+            3 => Some(Self::Rotate180),
+            4 => Some(Self::FlipVertical),
+            5 => Some(Self::Transpose),
+            6 => Some(Self::Rotate90),
+            7 => Some(Self::Transverse),
+            8 => Some(Self::Rotate270),
+            _ => None,
+        }
+    }
+
+    pub fn to_exif(self) -> u16 {
+        self as u16
+    }
+
+    /// Indica se aplicar a orientacao troca a largura pela altura
+    pub fn swaps_dimensions(self) -> bool {
+        matches!(
+            self,
+            Self::Transpose | Self::Rotate90 | Self::Transverse | Self::Rotate270
+        )
+    }
+
+    /// Aplica a orientacao em pixels com origem no canto superior esquerdo, retornando os novos
+    /// pixels, largura e altura
+    pub fn apply<P: Clone>(
+        self,
+        pixels: &[P],
+        width: usize,
+        height: usize,
+    ) -> (Vec<P>, usize, usize) {
+        let (new_width, new_height) = if self.swaps_dimensions() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+
+        let mut output = Vec::with_capacity(pixels.len());
+        for y in 0..new_height {
+            for x in 0..new_width {
+                let (src_x, src_y) = match self {
+                    Self::Normal => (x, y),
+                    Self::FlipHorizontal => (width - 1 - x, y),
+                    Self::Rotate180 => (width - 1 - x, height - 1 - y),
+                    Self::FlipVertical => (x, height - 1 - y),
+                    Self::Transpose => (y, x),
+                    Self::Rotate90 => (y, height - 1 - x),
+                    Self::Transverse => (width - 1 - y, height - 1 - x),
+                    Self::Rotate270 => (width - 1 - y, x),
+                };
+                output.push(pixels[src_y * width + src_x].clone());
+            }
+        }
+
+        (output, new_width, new_height)
+    }
 }
 
 // Structs...
@@ -43,7 +266,7 @@ impl RGB {
     }
 
     pub fn alpha(&self) -> Option<u8> {
-        self.alpha.clone()
+        self.alpha
     }
 
     pub fn set_red(&mut self, value: u8) {
@@ -63,7 +286,7 @@ impl RGB {
     }
 
     pub fn add_red(&mut self, red: u8) {
-        self.red = self.red.checked_add(red).unwrap_or(255);
+        self.red = self.red.saturating_add(red);
     }
 
     pub fn overflowing_add_red(&mut self, value: u8) {
@@ -71,7 +294,7 @@ impl RGB {
     }
 
     pub fn add_green(&mut self, green: u8) {
-        self.green = self.green.checked_add(green).unwrap_or(255);
+        self.green = self.green.saturating_add(green);
     }
 
     pub fn overflowing_add_green(&mut self, value: u8) {
@@ -79,7 +302,7 @@ impl RGB {
     }
 
     pub fn add_blue(&mut self, blue: u8) {
-        self.blue = self.blue.checked_add(blue).unwrap_or(255);
+        self.blue = self.blue.saturating_add(blue);
     }
 
     pub fn overflowing_add_blue(&mut self, value: u8) {
@@ -88,7 +311,7 @@ impl RGB {
 
     pub fn add_aplha(&mut self, alpha: u8) {
         if let Some(value) = &mut self.alpha {
-            *value = value.checked_add(alpha).unwrap_or(255);
+            *value = value.saturating_add(alpha);
         }
     }
 
@@ -99,20 +322,20 @@ impl RGB {
     }
 
     pub fn sub_red(&mut self, red: u8) {
-        self.red = self.red.checked_sub(red).unwrap_or(0);
+        self.red = self.red.saturating_sub(red);
     }
 
     pub fn sub_green(&mut self, green: u8) {
-        self.green = self.green.checked_sub(green).unwrap_or(0);
+        self.green = self.green.saturating_sub(green);
     }
 
     pub fn sub_blue(&mut self, blue: u8) {
-        self.blue = self.blue.checked_sub(blue).unwrap_or(0);
+        self.blue = self.blue.saturating_sub(blue);
     }
 
     pub fn sub_aplha(&mut self, alpha: u8) {
         if let Some(value) = &mut self.alpha {
-            *value = value.checked_sub(alpha).unwrap_or(0);
+            *value = value.saturating_sub(alpha);
         }
     }
 
@@ -140,7 +363,7 @@ impl RGB {
             red: m,
             green: m,
             blue: m,
-            alpha: self.alpha.clone(),
+            alpha: self.alpha,
         }
     }
 
@@ -152,16 +375,78 @@ impl RGB {
 }
 
 // Traits...
-/// Trait que representa uma image generica, com os metodos que todo imagem deve ter
+/// Trait que representa uma image generica, com os metodos que todo imagem deve ter.
+///
+/// Os pixels ficam sempre em ordem de linhas com a origem no canto superior esquerdo:
+/// o pixel `(x, y)` esta no indice `y * widht() + x`, independente da ordem usada pelo arquivo
 pub trait Image {
-    fn open(path: impl Into<String>) -> IOResult<Self>
+    /// Tipo de pixel guardado pela imagem (`RGB` nos codecs de 8 bits)
+    type Pixel: Pixel;
+
+    /// Le a imagem de qualquer fonte de bytes posicionavel (arquivo, `Cursor`, etc)
+    fn decode(reader: impl Read + Seek) -> ImageResult<Self>
     where
         Self: Sized;
 
-    fn save(&mut self, path: impl Into<String>) -> IOResult<()>;
+    /// Escreve a imagem codificada em qualquer destino de bytes
+    fn encode(&self, writer: impl Write) -> ImageResult<()>;
+
+    fn open(path: impl Into<String>) -> ImageResult<Self>
+    where
+        Self: Sized,
+    {
+        let file = File::open(path.into())?;
+        Self::decode(BufReader::new(file))
+    }
+
+    fn save(&mut self, path: impl Into<String>) -> ImageResult<()> {
+        let file = File::create_new(path.into())?;
+        let mut writer = BufWriter::new(file);
+
+        self.encode(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn from_bytes(bytes: &[u8]) -> ImageResult<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode(Cursor::new(bytes))
+    }
+
+    fn to_bytes(&self) -> ImageResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.encode(&mut bytes)?;
+
+        Ok(bytes)
+    }
 
     fn filter(&mut self, filter: impl Filter) -> Result<(), FilterError>;
 
+    /// Aplica uma operacao que muda as dimensoes (cortar, girar, etc). Os pixels sao trocados
+    /// com `set_pixels`, entao a imagem continua no mesmo formato
+    fn transform(&mut self, transform: impl Transform) -> Result<(), FilterError>
+    where
+        Self: Sized,
+    {
+        let buffer = transform.apply(self)?;
+        let (width, height) = (buffer.widht(), buffer.height());
+        self.set_pixels(width, height, buffer.into_pixels())?;
+
+        Ok(())
+    }
+
+    /// Troca todos os pixels, com novas dimensoes, atualizando os cabecalhos do formato. Falha
+    /// se o formato nao aceitar o tamanho
+    fn set_pixels(
+        &mut self,
+        width: usize,
+        height: usize,
+        pixels: Vec<Self::Pixel>,
+    ) -> ImageResult<()>;
+
     fn widht(&self) -> usize;
 
     fn height(&self) -> usize;
@@ -170,20 +455,80 @@ pub trait Image {
 
     fn bytes_per_pixels(&self) -> u16;
 
-    fn pixels(&mut self) -> &mut [RGB];
+    fn pixels(&mut self) -> &mut [Self::Pixel];
+
+    fn get_pixels(&self) -> &[Self::Pixel];
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut Self::Pixel>;
 
-    fn get_pixels(&self) -> &[RGB];
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&Self::Pixel>;
 
-    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB>;
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [Self::Pixel];
 
-    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB>;
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[Self::Pixel];
 
-    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB];
+    /// Orientacao de exibicao guardada junto da imagem (ex: EXIF). Ela nao altera os pixels e e
+    /// ignorada pelos formatos que nao conseguem grava-la
+    fn orientation(&self) -> Orientation;
 
-    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB];
+    fn set_orientation(&mut self, orientation: Orientation);
+
+    /// Aplica a orientacao nos pixels (e dimensoes) e volta ela para `Orientation::Normal`
+    fn normalize_orientation(&mut self);
+}
+
+// Functions...
+/// Abre uma imagem de qualquer formato suportado, detectando o formato automaticamente. O HDR
+/// e reduzido para 8 bits; `Hdr::open` mantem os pixels `f32`
+pub fn open(path: impl Into<String>) -> ImageResult<DynamicImage> {
+    let path = path.into();
+    let format = Format::from_path(&path)?;
+    let file = File::open(path)?;
+
+    DynamicImage::decode_format(BufReader::new(file), format)
 }
 
 // Utils Functions
+/// O ICO/CUR nao tem assinatura propria e comeca como um TGA true-color (`00 00 02 00`), entao
+/// so e aceito se a primeira entrada do diretorio fizer sentido
+fn is_icon_directory(bytes: &[u8]) -> bool {
+    let [
+        _,
+        _,
+        kind,
+        _,
+        count_low,
+        count_high,
+        width,
+        height,
+        _,
+        reserved,
+        x_low,
+        x_high,
+        y_low,
+        y_high,
+        ..,
+    ] = *bytes
+    else {
+        return false;
+    };
+
+    let count = u16::from_le_bytes([count_low, count_high]);
+    let planes = u16::from_le_bytes([x_low, x_high]);
+    let bit_count = u16::from_le_bytes([y_low, y_high]);
+    // Largura e altura 0 valem 256
+    let size = |value: u8| if value == 0 { 256 } else { value as u16 };
+
+    let entry = match kind {
+        // ICO: planos (0 ou 1) e bits por pixel
+        1 => planes <= 1 && matches!(bit_count, 0 | 1 | 4 | 8 | 16 | 24 | 32),
+        // CUR: os mesmos campos guardam o hotspot, que precisa estar dentro da imagem
+        _ => planes < size(width) && bit_count < size(height),
+    };
+
+    count > 0 && reserved == 0 && entry
+}
+
 pub fn u32_from_le_bytes(bytes: &[u8]) -> u32 {
     if bytes.len() < 4 {
         return 0;
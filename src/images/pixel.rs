@@ -0,0 +1,316 @@
+use super::{Orientation, RGB};
+use std::fmt::Debug;
+
+// Traits...
+/// Trait que representa o tipo de um canal (u8, u16 ou f32), sempre convertido para f32 na
+/// faixa 0.0..=1.0 nas conversoes entre tipos de pixel
+pub trait Primitive: Copy + Debug + Default + PartialEq + PartialOrd + 'static {
+    /// Valor maximo do canal (1.0 para f32)
+    const MAX: Self;
+
+    fn to_f32(self) -> f32;
+
+    /// Converte de volta, arredondando e limitando os inteiros. f32 nao e limitado (HDR)
+    fn from_f32(value: f32) -> Self;
+}
+
+/// Trait que representa um pixel generico, usado por `Image` e pelos filtros
+pub trait Pixel: Clone + Debug + Default + PartialEq {
+    type Subpixel: Primitive;
+
+    /// Quantidade de canais guardados, contando o alpha
+    const CHANNELS: usize;
+
+    /// Cor em RGBa de f32 na faixa 0.0..=1.0; pixels sem alpha sao opacos (1.0)
+    fn to_rgba(&self) -> Rgba<f32>;
+
+    /// Cria o pixel a partir de uma cor RGBa de f32. Pixels de cinza usam a media dos canais
+    fn from_rgba(color: Rgba<f32>) -> Self;
+
+    /// Troca os canais de cor mantendo o alpha do pixel como esta
+    fn with_color(&self, red: f32, green: f32, blue: f32) -> Self {
+        let alpha = self.to_rgba().0[3];
+        Self::from_rgba(Rgba([red, green, blue, alpha]))
+    }
+
+    /// Converte para outro tipo de pixel. Sem perdas quando o destino tem os mesmos canais (ou
+    /// mais) e a mesma precisao (ou maior)
+    fn convert<P: Pixel>(&self) -> P {
+        P::from_rgba(self.to_rgba())
+    }
+
+    /// Cinza com a media dos canais de cor, mantendo o alpha
+    fn gray(&self) -> Self {
+        let [red, green, blue, _] = self.to_rgba().0;
+        let value = (red + green + blue) / 3.0;
+        self.with_color(value, value, value)
+    }
+
+    /// Media das cores de `pixels`, mantendo o alpha deste pixel. Sem pixels, devolve o proprio
+    fn mean_color<'a>(&self, pixels: impl IntoIterator<Item = &'a Self>) -> Self
+    where
+        Self: 'a,
+    {
+        let mut sum = [0.0; 3];
+        let mut count = 0.0;
+        for pixel in pixels {
+            let [red, green, blue, _] = pixel.to_rgba().0;
+            sum = [sum[0] + red, sum[1] + green, sum[2] + blue];
+            count += 1.0;
+        }
+
+        match count > 0.0 {
+            true => self.with_color(sum[0] / count, sum[1] / count, sum[2] / count),
+            false => self.clone(),
+        }
+    }
+}
+
+// Structs...
+/// Pixel de cinza
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Luma<T: Primitive>(pub [T; 1]);
+
+/// Pixel de cinza com alpha
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LumaA<T: Primitive>(pub [T; 2]);
+
+/// Pixel RGB sem alpha
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rgb<T: Primitive>(pub [T; 3]);
+
+/// Pixel RGB com alpha
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rgba<T: Primitive>(pub [T; 4]);
+
+/// Copia em 16 bits por canal dos pixels de um codec que guarda 16 bits (PNG, TIFF e PNM). A
+/// interface `Image` e os filtros alteram so os pixels `RGB`, entao o codec limpa a copia em todo
+/// acesso mutavel a eles
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Samples16(Vec<Rgba<u16>>);
+
+impl Primitive for u8 {
+    const MAX: Self = u8::MAX;
+
+    fn to_f32(self) -> f32 {
+        self as f32 / u8::MAX as f32
+    }
+
+    fn from_f32(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8
+    }
+}
+
+impl Primitive for u16 {
+    const MAX: Self = u16::MAX;
+
+    fn to_f32(self) -> f32 {
+        self as f32 / u16::MAX as f32
+    }
+
+    fn from_f32(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+    }
+}
+
+impl Primitive for f32 {
+    const MAX: Self = 1.0;
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+impl<T: Primitive> Pixel for Luma<T> {
+    type Subpixel = T;
+    const CHANNELS: usize = 1;
+
+    fn to_rgba(&self) -> Rgba<f32> {
+        let value = self.0[0].to_f32();
+        Rgba([value, value, value, 1.0])
+    }
+
+    fn from_rgba(color: Rgba<f32>) -> Self {
+        Luma([T::from_f32(average(&color))])
+    }
+}
+
+impl<T: Primitive> Pixel for LumaA<T> {
+    type Subpixel = T;
+    const CHANNELS: usize = 2;
+
+    fn to_rgba(&self) -> Rgba<f32> {
+        let value = self.0[0].to_f32();
+        Rgba([value, value, value, self.0[1].to_f32()])
+    }
+
+    fn from_rgba(color: Rgba<f32>) -> Self {
+        LumaA([T::from_f32(average(&color)), T::from_f32(color.0[3])])
+    }
+}
+
+impl<T: Primitive> Pixel for Rgb<T> {
+    type Subpixel = T;
+    const CHANNELS: usize = 3;
+
+    fn to_rgba(&self) -> Rgba<f32> {
+        let [red, green, blue] = self.0.map(T::to_f32);
+        Rgba([red, green, blue, 1.0])
+    }
+
+    fn from_rgba(color: Rgba<f32>) -> Self {
+        let [red, green, blue, _] = color.0;
+        Rgb([red, green, blue].map(T::from_f32))
+    }
+}
+
+impl<T: Primitive> Pixel for Rgba<T> {
+    type Subpixel = T;
+    const CHANNELS: usize = 4;
+
+    fn to_rgba(&self) -> Rgba<f32> {
+        Rgba(self.0.map(T::to_f32))
+    }
+
+    fn from_rgba(color: Rgba<f32>) -> Self {
+        Rgba(color.0.map(T::from_f32))
+    }
+}
+
+/// `RGB` e o pixel de 8 bits dos codecs; o alpha `None` equivale a opaco
+impl Pixel for RGB {
+    type Subpixel = u8;
+    const CHANNELS: usize = 4;
+
+    fn to_rgba(&self) -> Rgba<f32> {
+        Rgba([
+            self.red().to_f32(),
+            self.green().to_f32(),
+            self.blue().to_f32(),
+            self.alpha().map_or(1.0, u8::to_f32),
+        ])
+    }
+
+    /// Alpha totalmente opaco vira `None`, para nao criar um canal alpha sem necessidade
+    fn from_rgba(color: Rgba<f32>) -> Self {
+        let [red, green, blue, alpha] = color.0;
+        RGB::new(
+            u8::from_f32(red),
+            u8::from_f32(green),
+            u8::from_f32(blue),
+            (alpha < 1.0).then(|| u8::from_f32(alpha)),
+        )
+    }
+
+    fn with_color(&self, red: f32, green: f32, blue: f32) -> Self {
+        RGB::new(
+            u8::from_f32(red),
+            u8::from_f32(green),
+            u8::from_f32(blue),
+            self.alpha(),
+        )
+    }
+
+    /// Media inteira e truncada dos canais, o resultado do `GrayScale` em 8 bits
+    fn gray(&self) -> Self {
+        self.grayscale()
+    }
+
+    /// Media inteira e truncada das cores, o resultado do `BoxBlur` em 8 bits
+    fn mean_color<'a>(&self, pixels: impl IntoIterator<Item = &'a Self>) -> Self {
+        let mut sum = [0_u32; 3];
+        let mut count = 0;
+        for pixel in pixels {
+            sum[0] += pixel.red() as u32;
+            sum[1] += pixel.green() as u32;
+            sum[2] += pixel.blue() as u32;
+            count += 1;
+        }
+
+        match count {
+            0 => self.clone(),
+            _ => RGB::new(
+                (sum[0] / count) as u8,
+                (sum[1] / count) as u8,
+                (sum[2] / count) as u8,
+                self.alpha(),
+            ),
+        }
+    }
+}
+
+impl Samples16 {
+    pub(crate) fn new(samples: Vec<Rgba<u16>>) -> Self {
+        Self(samples)
+    }
+
+    /// Pixels de 8 bits da copia. Com `alpha` falso o alpha fica `None`
+    pub(crate) fn narrow(&self, alpha: bool) -> Vec<RGB> {
+        self.0
+            .iter()
+            .map(|color| {
+                let [red, green, blue, value] = color.0.map(narrow);
+                RGB::new(red, green, blue, alpha.then_some(value))
+            })
+            .collect()
+    }
+
+    /// A copia, se ela existe. Os codecs a limpam em todo acesso mutavel aos pixels, entao
+    /// uma copia com o mesmo tamanho ainda corresponde a eles
+    pub(crate) fn get(&self, pixels: &[RGB]) -> Option<&[Rgba<u16>]> {
+        let current = !self.0.is_empty() && self.0.len() == pixels.len();
+
+        current.then_some(self.0.as_slice())
+    }
+
+    /// Pixels em 16 bits: a copia quando ainda vale, ou os pixels de 8 bits expandidos
+    pub(crate) fn widen(&self, pixels: &[RGB]) -> Vec<Rgba<u16>> {
+        match self.get(pixels) {
+            Some(samples) => samples.to_vec(),
+            None => pixels.iter().map(Pixel::convert).collect(),
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Aplica na copia a orientacao aplicada nos pixels
+    pub(crate) fn orient(&mut self, orientation: Orientation, width: usize, height: usize) {
+        if !self.0.is_empty() {
+            self.0 = orientation.apply(&self.0, width, height).0;
+        }
+    }
+}
+
+impl From<RGB> for Rgba<u8> {
+    fn from(color: RGB) -> Self {
+        Rgba([
+            color.red(),
+            color.green(),
+            color.blue(),
+            color.alpha().unwrap_or(u8::MAX),
+        ])
+    }
+}
+
+impl From<Rgba<u8>> for RGB {
+    fn from(color: Rgba<u8>) -> Self {
+        let [red, green, blue, alpha] = color.0;
+        RGB::new(red, green, blue, Some(alpha))
+    }
+}
+
+// Utils Functions
+/// Reduz um canal de 16 bits para 8, arredondando
+pub(crate) fn narrow(value: u16) -> u8 {
+    ((value as u32 * 255 + 32767) / 65535) as u8
+}
+
+fn average(color: &Rgba<f32>) -> f32 {
+    (color.0[0] + color.0[1] + color.0[2]) / 3.0
+}
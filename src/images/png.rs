@@ -0,0 +1,1012 @@
+use super::{
+    Format, Image, Orientation, RGB,
+    buffer::ImageBuffer,
+    pixel::{Rgba, Samples16},
+    zlib,
+};
+use crate::error::{ImageError, ImageResult};
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+// Consts...
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+const IDAT_CHUNK_SIZE: usize = 1 << 16;
+
+/// Passos do entrelacamento Adam7: (x inicial, y inicial, passo x, passo y)
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+// Enums...
+/// Enum que representa os tipos de cor definidos no cabecalho IHDR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorType {
+    Grayscale = 0,
+    Rgb = 2,
+    Indexed = 3,
+    GrayscaleAlpha = 4,
+    Rgba = 6,
+}
+
+impl ColorType {
+    fn from_byte(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Grayscale),
+            2 => Some(Self::Rgb),
+            3 => Some(Self::Indexed),
+            4 => Some(Self::GrayscaleAlpha),
+            6 => Some(Self::Rgba),
+            _ => None,
+        }
+    }
+
+    /// Numero de amostras por pixel
+    pub fn channels(&self) -> usize {
+        match self {
+            Self::Grayscale | Self::Indexed => 1,
+            Self::GrayscaleAlpha => 2,
+            Self::Rgb => 3,
+            Self::Rgba => 4,
+        }
+    }
+
+    fn allows_depth(&self, depth: u8) -> bool {
+        match self {
+            Self::Grayscale => matches!(depth, 1 | 2 | 4 | 8 | 16),
+            Self::Indexed => matches!(depth, 1 | 2 | 4 | 8),
+            _ => matches!(depth, 8 | 16),
+        }
+    }
+}
+
+// Structs...
+/// Struct para representa uma imagem PNG, guardando o tipo de cor original para a escrita
+pub struct Png {
+    width: usize,
+    height: usize,
+    color_type: ColorType,
+    bit_depth: u8,
+    interlaced: bool,
+    palette: Vec<RGB>,
+    pixels: Vec<RGB>,
+    samples16: Samples16,
+    orientation: Orientation,
+}
+
+impl Png {
+    /// Cria um PNG RGB de 8 bits a partir de pixels em ordem de linhas. O canal alpha e
+    /// adicionado na escrita se algum pixel tiver alpha
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("png dimensions are zero"));
+        }
+        if width > i32::MAX as usize || height > i32::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(ImageError::InvalidData(
+                "pixel count does not match the dimensions",
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            color_type: ColorType::Rgb,
+            bit_depth: 8,
+            interlaced: false,
+            palette: Vec::new(),
+            pixels,
+            samples16: Samples16::default(),
+            orientation: Orientation::Normal,
+        })
+    }
+
+    /// Cria um PNG de 16 bits por canal. O alpha so e gravado se algum pixel nao for opaco
+    pub fn from_rgba16(width: usize, height: usize, pixels: Vec<Rgba<u16>>) -> ImageResult<Self> {
+        let alpha = pixels.iter().any(|color| color.0[3] != u16::MAX);
+        let samples16 = Samples16::new(pixels);
+
+        let mut image = Self::from_pixels(width, height, samples16.narrow(alpha))?;
+        image.bit_depth = 16;
+        image.samples16 = samples16;
+
+        Ok(image)
+    }
+
+    /// Pixels com 16 bits por canal. Num PNG de 16 bits sao as amostras do arquivo, ate o
+    /// primeiro acesso mutavel aos pixels de 8 bits (`pixels`, `pixel`, `slice_pixels`,
+    /// `set_pixels` e os filtros); nos demais, os pixels de 8 bits expandidos. Para editar sem
+    /// perder os 16 bits, aplique os filtros neste buffer e volte com `Png::try_from`
+    pub fn to_rgba16(&self) -> ImageBuffer<Rgba<u16>> {
+        ImageBuffer::from_raw(self.width, self.height, self.samples16.widen(&self.pixels))
+            .with_orientation(self.orientation)
+    }
+
+    pub fn color_type(&self) -> ColorType {
+        self.color_type
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    pub fn interlaced(&self) -> bool {
+        self.interlaced
+    }
+
+    pub fn set_interlaced(&mut self, value: bool) {
+        self.interlaced = value;
+    }
+
+    fn read_png(data: &[u8]) -> ImageResult<Self> {
+        if data.len() < 8 || data[0..8] != SIGNATURE {
+            return Err(ImageError::InvalidMagic);
+        }
+
+        let mut header: Option<Header> = None;
+        let mut palette: Vec<RGB> = Vec::new();
+        let mut transparency: Option<Vec<u8>> = None;
+        let mut compressed = Vec::new();
+        let mut offset = 8;
+
+        loop {
+            let chunk = Chunk::read(data, &mut offset)?;
+
+            match &chunk.kind {
+                b"IHDR" => header = Some(Header::new(chunk.data)?),
+                b"PLTE" => {
+                    if chunk.data.len() % 3 != 0 || chunk.data.len() > 256 * 3 {
+                        return Err(ImageError::InvalidData("png palette length is invalid"));
+                    }
+                    palette = chunk
+                        .data
+                        .chunks(3)
+                        .map(|c| RGB::new(c[0], c[1], c[2], None))
+                        .collect();
+                }
+                b"tRNS" => transparency = Some(chunk.data.to_vec()),
+                b"IDAT" => compressed.extend_from_slice(chunk.data),
+                b"IEND" => break,
+                kind if kind[0].is_ascii_uppercase() => {
+                    return Err(ImageError::InvalidData("png has an unknown critical chunk"));
+                }
+                _ => {}
+            }
+        }
+
+        let header = header.ok_or(ImageError::InvalidData("png is missing IHDR"))?;
+        if header.color_type == ColorType::Indexed && palette.is_empty() {
+            return Err(ImageError::InvalidData("png indexed image is missing PLTE"));
+        }
+
+        let raw = zlib::decompress_limited(&compressed, header.filtered_size()?)?;
+        let samples = unfilter_image(&raw, &header)?;
+
+        let channels = header.color_type.channels();
+        let mut samples16 = Samples16::default();
+        let pixels = if header.bit_depth == 16 {
+            samples16 = Samples16::new(
+                samples
+                    .chunks(channels)
+                    .map(|sample| header.to_rgba16(sample, transparency.as_deref()))
+                    .collect(),
+            );
+            let alpha = matches!(
+                header.color_type,
+                ColorType::GrayscaleAlpha | ColorType::Rgba
+            ) || transparency.is_some();
+            samples16.narrow(alpha)
+        } else {
+            samples
+                .chunks(channels)
+                .map(|sample| header.to_rgb(sample, &palette, transparency.as_deref()))
+                .collect()
+        };
+
+        if let (ColorType::Indexed, Some(alphas)) = (header.color_type, &transparency) {
+            for (index, color) in palette.iter_mut().enumerate() {
+                color.set_alpha(Some(alphas.get(index).copied().unwrap_or(255)));
+            }
+        }
+
+        Ok(Self {
+            width: header.width,
+            height: header.height,
+            color_type: header.color_type,
+            bit_depth: header.bit_depth,
+            interlaced: header.interlaced,
+            palette,
+            pixels,
+            samples16,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    fn write_png(&self) -> Vec<u8> {
+        let (header, palette) = self.output_header();
+        let samples = self.samples(&header, &palette);
+        let raw = filter_image(&samples, &header);
+        let compressed = zlib::compress(&raw);
+
+        let mut bytes = SIGNATURE.to_vec();
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(header.width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(header.height as u32).to_be_bytes());
+        ihdr.push(header.bit_depth);
+        ihdr.push(header.color_type as u8);
+        ihdr.extend_from_slice(&[0, 0, header.interlaced as u8]);
+        write_chunk(&mut bytes, b"IHDR", &ihdr);
+
+        if header.color_type == ColorType::Indexed {
+            let plte = palette
+                .iter()
+                .flat_map(|c| [c.red(), c.green(), c.blue()])
+                .collect::<Vec<_>>();
+            write_chunk(&mut bytes, b"PLTE", &plte);
+
+            if let Some(last) = palette
+                .iter()
+                .rposition(|c| c.alpha().unwrap_or(255) != 255)
+            {
+                let trns = palette[..=last]
+                    .iter()
+                    .map(|c| c.alpha().unwrap_or(255))
+                    .collect::<Vec<_>>();
+                write_chunk(&mut bytes, b"tRNS", &trns);
+            }
+        }
+
+        for chunk in compressed.chunks(IDAT_CHUNK_SIZE) {
+            write_chunk(&mut bytes, b"IDAT", chunk);
+        }
+        write_chunk(&mut bytes, b"IEND", &[]);
+
+        bytes
+    }
+
+    /// Escolhe o formato de saida mais proximo do original que ainda represente os pixels sem perdas
+    fn output_header(&self) -> (Header, Vec<RGB>) {
+        let has_alpha = self.pixels.iter().any(|c| c.alpha().is_some());
+        // Em 16 bits, cores diferentes podem ficar iguais quando reduzidas para 8
+        let is_gray = match self.samples16.get(&self.pixels) {
+            Some(samples) if self.bit_depth == 16 => {
+                samples.iter().all(|c| c.0[0] == c.0[1] && c.0[1] == c.0[2])
+            }
+            _ => self
+                .pixels
+                .iter()
+                .all(|c| c.red() == c.green() && c.green() == c.blue()),
+        };
+
+        let mut color_type = self.color_type;
+        let mut bit_depth = self.bit_depth;
+        let mut palette = Vec::new();
+
+        if color_type == ColorType::Indexed {
+            match build_palette(&self.palette, &self.pixels) {
+                Some(colors) => {
+                    let needed = match colors.len() {
+                        0..=2 => 1,
+                        3..=4 => 2,
+                        5..=16 => 4,
+                        _ => 8,
+                    };
+                    bit_depth = bit_depth.max(needed);
+                    palette = colors;
+                }
+                None => {
+                    color_type = ColorType::Rgb;
+                    bit_depth = 8;
+                }
+            }
+        }
+
+        if matches!(color_type, ColorType::Grayscale | ColorType::GrayscaleAlpha) && !is_gray {
+            color_type = ColorType::Rgb;
+            bit_depth = bit_depth.max(8);
+        }
+
+        if color_type == ColorType::Grayscale && bit_depth < 8 {
+            let max = (1_u16 << bit_depth) - 1;
+            let representable = self
+                .pixels
+                .iter()
+                .all(|c| (c.red() as u16 * max).is_multiple_of(255));
+
+            if !representable || has_alpha {
+                bit_depth = 8;
+            }
+        }
+
+        if has_alpha {
+            color_type = match color_type {
+                ColorType::Grayscale => ColorType::GrayscaleAlpha,
+                ColorType::Rgb => ColorType::Rgba,
+                other => other,
+            };
+        }
+
+        let header = Header {
+            width: self.width,
+            height: self.height,
+            bit_depth,
+            color_type,
+            interlaced: self.interlaced,
+        };
+
+        (header, palette)
+    }
+
+    /// Converte os pixels em amostras inteiras no tipo de cor e profundidade do cabecalho
+    fn samples(&self, header: &Header, palette: &[RGB]) -> Vec<u16> {
+        let channels = header.color_type.channels();
+        if header.bit_depth == 16 {
+            let mut samples = Vec::with_capacity(self.pixels.len() * channels);
+            for color in self.samples16.widen(&self.pixels) {
+                let [red, green, blue, alpha] = color.0;
+                match header.color_type {
+                    ColorType::Grayscale => samples.push(red),
+                    ColorType::GrayscaleAlpha => samples.extend([red, alpha]),
+                    ColorType::Rgb => samples.extend([red, green, blue]),
+                    _ => samples.extend([red, green, blue, alpha]),
+                }
+            }
+            return samples;
+        }
+
+        let scale = |value: u8| -> u16 {
+            match header.bit_depth {
+                16 => value as u16 * 257,
+                8 => value as u16,
+                depth => value as u16 * ((1 << depth) - 1) / 255,
+            }
+        };
+
+        let indices = palette
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (palette_key(c), i as u16))
+            .collect::<HashMap<_, _>>();
+
+        let mut samples = Vec::with_capacity(self.pixels.len() * channels);
+        for pixel in &self.pixels {
+            let alpha = pixel.alpha().unwrap_or(255);
+            match header.color_type {
+                ColorType::Grayscale => samples.push(scale(pixel.red())),
+                ColorType::GrayscaleAlpha => {
+                    samples.extend([scale(pixel.red()), scale(alpha)]);
+                }
+                ColorType::Rgb => {
+                    samples.extend([
+                        scale(pixel.red()),
+                        scale(pixel.green()),
+                        scale(pixel.blue()),
+                    ]);
+                }
+                ColorType::Rgba => samples.extend([
+                    scale(pixel.red()),
+                    scale(pixel.green()),
+                    scale(pixel.blue()),
+                    scale(alpha),
+                ]),
+                ColorType::Indexed => samples.push(indices[&palette_key(pixel)]),
+            }
+        }
+
+        samples
+    }
+}
+
+impl Image for Png {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_png(&data)
+    }
+
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        writer.write_all(&self.write_png())?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    /// Descarta as amostras de 16 bits, que nao correspondem mais aos pixels
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        let image = Self::from_pixels(width, height, pixels)?;
+
+        self.width = image.width;
+        self.height = image.height;
+        self.pixels = image.pixels;
+        self.samples16.clear();
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        Format::PNG
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        (self.color_type.channels() * self.bit_depth as usize) as u16
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        self.samples16.clear();
+        &mut self.pixels
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        &self.pixels
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.samples16.clear();
+        self.pixels.get_mut(y * self.width + x)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get(y * self.width + x)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        self.samples16.clear();
+        &mut self.pixels[range]
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        &self.pixels[range]
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (pixels, width, height) = self
+            .orientation
+            .apply(&self.pixels, self.width, self.height);
+        self.samples16
+            .orient(self.orientation, self.width, self.height);
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+        self.orientation = Orientation::Normal;
+    }
+}
+
+impl From<Png> for ImageBuffer {
+    fn from(image: Png) -> Self {
+        ImageBuffer::from_raw(image.width, image.height, image.pixels)
+            .with_orientation(image.orientation)
+    }
+}
+
+impl TryFrom<ImageBuffer> for Png {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Png::from_pixels(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+impl From<Png> for ImageBuffer<Rgba<u16>> {
+    fn from(image: Png) -> Self {
+        image.to_rgba16()
+    }
+}
+
+impl TryFrom<ImageBuffer<Rgba<u16>>> for Png {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer<Rgba<u16>>) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Png::from_rgba16(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+#[derive(Debug)]
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: ColorType,
+    interlaced: bool,
+}
+
+impl Header {
+    fn new(data: &[u8]) -> ImageResult<Self> {
+        if data.len() != 13 {
+            return Err(ImageError::InvalidData("png IHDR length is invalid"));
+        }
+
+        let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let bit_depth = data[8];
+        let color_type = ColorType::from_byte(data[9])
+            .ok_or(ImageError::InvalidData("png color type is invalid"))?;
+
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("png dimensions are zero"));
+        }
+        if width > i32::MAX as usize || height > i32::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+        if !color_type.allows_depth(bit_depth) {
+            return Err(ImageError::UnsupportedBitDepth(bit_depth as u16));
+        }
+        if data[10] != 0 {
+            return Err(ImageError::UnsupportedCompression(data[10] as u32));
+        }
+        if data[11] != 0 || data[12] > 1 {
+            return Err(ImageError::InvalidData(
+                "png filter or interlace method is invalid",
+            ));
+        }
+
+        // Garante que o buffer de amostras nao estoure a memoria enderecavel
+        width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(color_type.channels() * 2))
+            .ok_or(ImageError::DimensionOverflow)?;
+
+        Ok(Self {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            interlaced: data[12] == 1,
+        })
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.color_type.channels() * self.bit_depth as usize
+    }
+
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    /// Distancia em bytes usada pelos filtros para o pixel anterior
+    fn filter_stride(&self) -> usize {
+        self.bits_per_pixel().div_ceil(8)
+    }
+
+    /// Tamanho dos dados filtrados: em cada passe, um byte de filtro mais as amostras de cada linha
+    fn filtered_size(&self) -> ImageResult<usize> {
+        self.passes()
+            .into_iter()
+            .try_fold(0_usize, |total, (x0, y0, dx, dy)| {
+                let pass_width = (self.width + dx - 1 - x0) / dx;
+                let pass_height = (self.height + dy - 1 - y0) / dy;
+                if pass_width == 0 {
+                    return Some(total);
+                }
+
+                pass_width
+                    .checked_mul(self.bits_per_pixel())
+                    .map(|bits| bits.div_ceil(8) + 1)
+                    .and_then(|row| row.checked_mul(pass_height))
+                    .and_then(|size| total.checked_add(size))
+            })
+            .ok_or(ImageError::DimensionOverflow)
+    }
+
+    fn passes(&self) -> Vec<(usize, usize, usize, usize)> {
+        if self.interlaced {
+            ADAM7.to_vec()
+        } else {
+            vec![(0, 0, 1, 1)]
+        }
+    }
+
+    fn to_rgb(&self, sample: &[u16], palette: &[RGB], transparency: Option<&[u8]>) -> RGB {
+        let scale = |value: u16| -> u8 {
+            match self.bit_depth {
+                16 => ((value as u32 * 255 + 32767) / 65535) as u8,
+                8 => value as u8,
+                depth => (value as u32 * 255 / ((1 << depth) - 1)) as u8,
+            }
+        };
+
+        let key = |index: usize| transparent_key(transparency, index);
+
+        match self.color_type {
+            ColorType::Grayscale => {
+                let value = scale(sample[0]);
+                let alpha = transparency.map(|_| if sample[0] == key(0) { 0 } else { 255 });
+                RGB::new(value, value, value, alpha)
+            }
+            ColorType::GrayscaleAlpha => {
+                let value = scale(sample[0]);
+                RGB::new(value, value, value, Some(scale(sample[1])))
+            }
+            ColorType::Rgb => {
+                let alpha = transparency.map(|_| {
+                    let transparent =
+                        sample[0] == key(0) && sample[1] == key(1) && sample[2] == key(2);
+                    if transparent { 0 } else { 255 }
+                });
+                RGB::new(scale(sample[0]), scale(sample[1]), scale(sample[2]), alpha)
+            }
+            ColorType::Rgba => RGB::new(
+                scale(sample[0]),
+                scale(sample[1]),
+                scale(sample[2]),
+                Some(scale(sample[3])),
+            ),
+            ColorType::Indexed => {
+                let index = sample[0] as usize;
+                let mut color = palette.get(index).cloned().unwrap_or_default();
+                if let Some(alphas) = transparency {
+                    color.set_alpha(Some(alphas.get(index).copied().unwrap_or(255)));
+                }
+                color
+            }
+        }
+    }
+    /// Cor de 16 bits de um pixel (tipos de cor sem paleta). O tRNS vira alpha 0 ou maximo
+    fn to_rgba16(&self, sample: &[u16], transparency: Option<&[u8]>) -> Rgba<u16> {
+        let key = |index: usize| transparent_key(transparency, index);
+        let keyed = |transparent: bool| match (transparency, transparent) {
+            (Some(_), true) => 0,
+            _ => u16::MAX,
+        };
+
+        match self.color_type {
+            ColorType::Grayscale => {
+                let value = sample[0];
+                Rgba([value, value, value, keyed(value == key(0))])
+            }
+            ColorType::GrayscaleAlpha => Rgba([sample[0], sample[0], sample[0], sample[1]]),
+            ColorType::Rgb => {
+                let transparent = sample[0] == key(0) && sample[1] == key(1) && sample[2] == key(2);
+                Rgba([sample[0], sample[1], sample[2], keyed(transparent)])
+            }
+            _ => Rgba([sample[0], sample[1], sample[2], sample[3]]),
+        }
+    }
+}
+
+struct Chunk<'a> {
+    kind: [u8; 4],
+    data: &'a [u8],
+}
+
+impl<'a> Chunk<'a> {
+    fn read(bytes: &'a [u8], offset: &mut usize) -> ImageResult<Self> {
+        let start = *offset;
+        let head = bytes.get(start..start + 8).ok_or(ImageError::Truncated)?;
+        let len = u32::from_be_bytes([head[0], head[1], head[2], head[3]]) as usize;
+        let kind = [head[4], head[5], head[6], head[7]];
+
+        let end = start
+            .checked_add(8 + len + 4)
+            .ok_or(ImageError::Truncated)?;
+        let body = bytes.get(start + 4..end).ok_or(ImageError::Truncated)?;
+
+        let (checked, crc) = body.split_at(body.len() - 4);
+        if crc32(checked) != u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+            return Err(ImageError::InvalidData("png chunk crc mismatch"));
+        }
+
+        *offset = end;
+        Ok(Self {
+            kind,
+            data: &checked[4..],
+        })
+    }
+}
+
+// Utils Functions
+fn write_chunk(bytes: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = bytes.len();
+    bytes.extend_from_slice(kind);
+    bytes.extend_from_slice(data);
+
+    let crc = crc32(&bytes[start..]);
+    bytes.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFF_u32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+/// Amostra `index` da cor transparente do tRNS (cinza ou RGB), ou um valor que nunca aparece
+fn transparent_key(transparency: Option<&[u8]>, index: usize) -> u16 {
+    transparency
+        .and_then(|t| t.get(index * 2..index * 2 + 2))
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .unwrap_or(u16::MAX)
+}
+
+fn palette_key(color: &RGB) -> u32 {
+    u32::from_be_bytes([
+        color.red(),
+        color.green(),
+        color.blue(),
+        color.alpha().unwrap_or(255),
+    ])
+}
+
+/// Monta a paleta de saida mantendo a ordem original, ou `None` se passar de 256 cores
+fn build_palette(original: &[RGB], pixels: &[RGB]) -> Option<Vec<RGB>> {
+    let mut palette = Vec::new();
+    let mut seen = HashMap::new();
+
+    for color in original.iter().chain(pixels) {
+        let key = palette_key(color);
+        if seen.contains_key(&key) {
+            continue;
+        }
+        if palette.len() == 256 {
+            return None;
+        }
+
+        seen.insert(key, palette.len());
+        let mut color = color.clone();
+        color.set_alpha(Some(color.alpha().unwrap_or(255)));
+        palette.push(color);
+    }
+
+    // Remove do final as cores originais que nao sao mais usadas
+    let used = pixels
+        .iter()
+        .map(palette_key)
+        .collect::<std::collections::HashSet<_>>();
+    while palette.len() > 1 && !used.contains(&palette_key(palette.last().unwrap())) {
+        palette.pop();
+    }
+
+    Some(palette)
+}
+
+/// Desfaz os filtros de cada linha e devolve as amostras de todos os pixels em ordem de varredura
+fn unfilter_image(raw: &[u8], header: &Header) -> ImageResult<Vec<u16>> {
+    // Confere os dados antes de alocar as amostras com o tamanho vindo do IHDR
+    if raw.len() < header.filtered_size()? {
+        return Err(ImageError::Truncated);
+    }
+
+    let channels = header.color_type.channels();
+    let stride = header.filter_stride();
+    let mut samples = vec![0_u16; header.width * header.height * channels];
+    let mut offset = 0;
+
+    for (x0, y0, dx, dy) in header.passes() {
+        let pass_width = (header.width + dx - 1 - x0) / dx;
+        let pass_height = (header.height + dy - 1 - y0) / dy;
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+
+        let row_bytes = header.row_bytes(pass_width);
+        let mut previous = vec![0_u8; row_bytes];
+
+        for row in 0..pass_height {
+            let kind = *raw.get(offset).ok_or(ImageError::Truncated)?;
+            let mut current = raw
+                .get(offset + 1..offset + 1 + row_bytes)
+                .ok_or(ImageError::Truncated)?
+                .to_vec();
+            offset += 1 + row_bytes;
+
+            unfilter_row(kind, &mut current, &previous, stride)?;
+
+            let y = y0 + row * dy;
+            for column in 0..pass_width {
+                let x = x0 + column * dx;
+                let target = (y * header.width + x) * channels;
+                for channel in 0..channels {
+                    let index = column * channels + channel;
+                    samples[target + channel] = read_sample(&current, index, header.bit_depth);
+                }
+            }
+
+            previous = current;
+        }
+    }
+
+    Ok(samples)
+}
+
+fn unfilter_row(kind: u8, current: &mut [u8], previous: &[u8], stride: usize) -> ImageResult<()> {
+    for i in 0..current.len() {
+        let left = if i >= stride { current[i - stride] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= stride { previous[i - stride] } else { 0 };
+
+        let predictor = match kind {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return Err(ImageError::InvalidData("png filter type is invalid")),
+        };
+
+        current[i] = current[i].wrapping_add(predictor);
+    }
+
+    Ok(())
+}
+
+/// Aplica em cada linha o filtro com menor soma absoluta, a heuristica sugerida pela especificacao
+fn filter_image(samples: &[u16], header: &Header) -> Vec<u8> {
+    let channels = header.color_type.channels();
+    let stride = header.filter_stride();
+    let mut raw = Vec::new();
+
+    for (x0, y0, dx, dy) in header.passes() {
+        let pass_width = (header.width + dx - 1 - x0) / dx;
+        let pass_height = (header.height + dy - 1 - y0) / dy;
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+
+        let row_bytes = header.row_bytes(pass_width);
+        let mut previous = vec![0_u8; row_bytes];
+
+        for row in 0..pass_height {
+            let y = y0 + row * dy;
+            let mut current = vec![0_u8; row_bytes];
+
+            for column in 0..pass_width {
+                let x = x0 + column * dx;
+                let source = (y * header.width + x) * channels;
+                for channel in 0..channels {
+                    let index = column * channels + channel;
+                    write_sample(
+                        &mut current,
+                        index,
+                        header.bit_depth,
+                        samples[source + channel],
+                    );
+                }
+            }
+
+            let mut best: Option<(u64, u8, Vec<u8>)> = None;
+            for kind in 0..5_u8 {
+                let filtered = filter_row(kind, &current, &previous, stride);
+                let cost = filtered
+                    .iter()
+                    .map(|b| (*b as i8).unsigned_abs() as u64)
+                    .sum();
+
+                if best.as_ref().is_none_or(|(c, _, _)| cost < *c) {
+                    best = Some((cost, kind, filtered));
+                }
+            }
+
+            let (_, kind, filtered) = best.unwrap();
+            raw.push(kind);
+            raw.extend_from_slice(&filtered);
+            previous = current;
+        }
+    }
+
+    raw
+}
+
+fn filter_row(kind: u8, current: &[u8], previous: &[u8], stride: usize) -> Vec<u8> {
+    (0..current.len())
+        .map(|i| {
+            let left = if i >= stride { current[i - stride] } else { 0 };
+            let up = previous[i];
+            let up_left = if i >= stride { previous[i - stride] } else { 0 };
+
+            let predictor = match kind {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                _ => paeth(left, up, up_left),
+            };
+
+            current[i].wrapping_sub(predictor)
+        })
+        .collect()
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn read_sample(row: &[u8], index: usize, depth: u8) -> u16 {
+    match depth {
+        16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+        8 => row[index] as u16,
+        depth => {
+            let depth = depth as usize;
+            let bit = index * depth;
+            let shift = 8 - depth - (bit % 8);
+            ((row[bit / 8] >> shift) & ((1 << depth) - 1) as u8) as u16
+        }
+    }
+}
+
+fn write_sample(row: &mut [u8], index: usize, depth: u8, value: u16) {
+    match depth {
+        16 => row[index * 2..index * 2 + 2].copy_from_slice(&value.to_be_bytes()),
+        8 => row[index] = value as u8,
+        depth => {
+            let depth = depth as usize;
+            let bit = index * depth;
+            let shift = 8 - depth - (bit % 8);
+            row[bit / 8] |= (value as u8) << shift;
+        }
+    }
+}
@@ -0,0 +1,885 @@
+use super::{
+    Format, Image, Orientation, RGB,
+    buffer::ImageBuffer,
+    pixel::{Rgba, Samples16},
+};
+use crate::error::{ImageError, ImageResult};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+// Consts...
+/// Tamanho maximo das linhas dos formatos ASCII, como recomendado pela especificacao
+const ASCII_LINE_LIMIT: usize = 70;
+
+// Enums...
+/// Enum que representa o tipo de mapa Netpbm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PnmKind {
+    /// PBM (P1/P4): 1 bit por pixel, 1 e preto
+    Bitmap,
+    /// PGM (P2/P5): cinza
+    Graymap,
+    /// PPM (P3/P6): RGB
+    Pixmap,
+    /// PAM (P7): qualquer quantidade de canais, sempre binario
+    ArbitraryMap,
+}
+
+/// Enum que representa a codificacao das amostras
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PnmEncoding {
+    Ascii,
+    Binary,
+}
+
+// Structs...
+/// Struct para representa uma imagem Netpbm (PBM, PGM, PPM ou PAM), guardando o tipo original
+/// para a escrita
+pub struct Pnm {
+    width: usize,
+    height: usize,
+    kind: PnmKind,
+    encoding: PnmEncoding,
+    depth: usize,
+    maxval: u16,
+    tuple_type: String,
+    pixels: Vec<RGB>,
+    samples16: Samples16,
+    orientation: Orientation,
+}
+
+impl Pnm {
+    /// Cria um PPM binario com maxval 255 a partir de pixels em ordem de linhas. Imagens com
+    /// alpha sao salvas como PAM
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("pnm dimensions are zero"));
+        }
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(ImageError::InvalidData(
+                "pixel count does not match the dimensions",
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            kind: PnmKind::Pixmap,
+            encoding: PnmEncoding::Binary,
+            depth: 3,
+            maxval: u8::MAX as u16,
+            tuple_type: String::from("RGB"),
+            pixels,
+            samples16: Samples16::default(),
+            orientation: Orientation::Normal,
+        })
+    }
+
+    /// Cria um PPM binario com maxval 65535. Imagens com algum pixel nao opaco sao salvas como PAM
+    pub fn from_rgba16(width: usize, height: usize, pixels: Vec<Rgba<u16>>) -> ImageResult<Self> {
+        let alpha = pixels.iter().any(|color| color.0[3] != u16::MAX);
+        let samples16 = Samples16::new(pixels);
+
+        let mut image = Self::from_pixels(width, height, samples16.narrow(alpha))?;
+        image.maxval = u16::MAX;
+        image.samples16 = samples16;
+
+        Ok(image)
+    }
+
+    /// Pixels com 16 bits por canal. Com maxval maior que 255 sao as amostras do arquivo (levadas
+    /// para 0..=65535), ate o primeiro acesso mutavel aos pixels de 8 bits; nos demais, os pixels
+    /// de 8 bits expandidos. Filtros aplicados neste buffer mantem os 16 bits ao voltar com
+    /// `Pnm::try_from`
+    pub fn to_rgba16(&self) -> ImageBuffer<Rgba<u16>> {
+        ImageBuffer::from_raw(self.width, self.height, self.samples16.widen(&self.pixels))
+            .with_orientation(self.orientation)
+    }
+
+    pub fn kind(&self) -> PnmKind {
+        self.kind
+    }
+
+    /// Define o tipo usado ao salvar. Se os pixels nao couberem nele (cores num PGM, alpha fora
+    /// do PAM), a escrita usa o tipo mais proximo que os representa sem perdas
+    pub fn set_kind(&mut self, kind: PnmKind) {
+        self.kind = kind;
+    }
+
+    pub fn encoding(&self) -> PnmEncoding {
+        self.encoding
+    }
+
+    /// Define a codificacao usada ao salvar. O PAM e sempre binario
+    pub fn set_encoding(&mut self, encoding: PnmEncoding) {
+        self.encoding = encoding;
+    }
+
+    pub fn maxval(&self) -> u16 {
+        self.maxval
+    }
+
+    /// Define o valor maximo das amostras. Zero e tratado como 1
+    pub fn set_maxval(&mut self, maxval: u16) {
+        self.maxval = maxval.max(1);
+    }
+
+    /// Tipo das tuplas do PAM (`TUPLTYPE`), vazio nos outros formatos
+    pub fn tuple_type(&self) -> &str {
+        &self.tuple_type
+    }
+
+    fn read_pnm(data: &[u8]) -> ImageResult<Self> {
+        let (kind, encoding) = match data {
+            [b'P', b'1', ..] => (PnmKind::Bitmap, PnmEncoding::Ascii),
+            [b'P', b'2', ..] => (PnmKind::Graymap, PnmEncoding::Ascii),
+            [b'P', b'3', ..] => (PnmKind::Pixmap, PnmEncoding::Ascii),
+            [b'P', b'4', ..] => (PnmKind::Bitmap, PnmEncoding::Binary),
+            [b'P', b'5', ..] => (PnmKind::Graymap, PnmEncoding::Binary),
+            [b'P', b'6', ..] => (PnmKind::Pixmap, PnmEncoding::Binary),
+            [b'P', b'7', ..] => (PnmKind::ArbitraryMap, PnmEncoding::Binary),
+            _ => return Err(ImageError::InvalidMagic),
+        };
+
+        let mut tokens = Tokens { data, offset: 2 };
+        let header = if kind == PnmKind::ArbitraryMap {
+            Header::read_pam(&mut tokens)?
+        } else {
+            Header::read(&mut tokens, kind)?
+        };
+
+        let total = header
+            .width
+            .checked_mul(header.height)
+            .and_then(|size| size.checked_mul(header.depth))
+            .ok_or(ImageError::DimensionOverflow)?;
+
+        let samples = match (kind, encoding) {
+            (PnmKind::Bitmap, PnmEncoding::Ascii) => tokens.bits(total)?,
+            (PnmKind::Bitmap, PnmEncoding::Binary) => {
+                let raster = tokens.raster();
+                let stride = header.width.div_ceil(8);
+                if raster.len() / stride < header.height {
+                    return Err(ImageError::Truncated);
+                }
+
+                raster
+                    .chunks(stride)
+                    .take(header.height)
+                    .flat_map(|row| {
+                        (0..header.width).map(move |x| ((row[x / 8] >> (7 - x % 8)) & 1) as u16)
+                    })
+                    .collect()
+            }
+            (_, PnmEncoding::Ascii) => tokens.samples(total, header.maxval)?,
+            (_, PnmEncoding::Binary) => {
+                let raster = tokens.raster();
+                let size = if header.maxval > u8::MAX as u16 { 2 } else { 1 };
+                if raster.len() / size < total {
+                    return Err(ImageError::Truncated);
+                }
+
+                raster
+                    .chunks_exact(size)
+                    .take(total)
+                    .map(|sample| match sample {
+                        [high, low] => u16::from_be_bytes([*high, *low]),
+                        [value] => *value as u16,
+                        _ => 0,
+                    })
+                    .map(|sample| sample.min(header.maxval))
+                    .collect()
+            }
+        };
+
+        if header.maxval > u8::MAX as u16 {
+            let wide = |sample: u16| scale_to_u16(sample, header.maxval);
+            let samples16 = Samples16::new(
+                samples
+                    .chunks_exact(header.depth)
+                    .map(|tuple| match *tuple {
+                        [gray] => Rgba([wide(gray), wide(gray), wide(gray), u16::MAX]),
+                        [gray, alpha] => Rgba([wide(gray), wide(gray), wide(gray), wide(alpha)]),
+                        [red, green, blue] => Rgba([wide(red), wide(green), wide(blue), u16::MAX]),
+                        [red, green, blue, alpha, ..] => {
+                            Rgba([wide(red), wide(green), wide(blue), wide(alpha)])
+                        }
+                        _ => Rgba::default(),
+                    })
+                    .collect(),
+            );
+
+            return Ok(Self {
+                width: header.width,
+                height: header.height,
+                kind,
+                encoding,
+                depth: header.depth,
+                maxval: header.maxval,
+                tuple_type: header.tuple_type,
+                pixels: samples16.narrow(header.depth == 2 || header.depth >= 4),
+                samples16,
+                orientation: Orientation::Normal,
+            });
+        }
+
+        let scale = |sample: u16| scale_to_u8(sample, header.maxval);
+        let pixels = samples
+            .chunks_exact(header.depth)
+            .map(|tuple| match (kind, tuple) {
+                // No PBM o bit 1 e preto
+                (PnmKind::Bitmap, [bit]) => {
+                    let value = if *bit == 0 { u8::MAX } else { 0 };
+                    RGB::new(value, value, value, None)
+                }
+                (_, [gray]) => RGB::new(scale(*gray), scale(*gray), scale(*gray), None),
+                (_, [gray, alpha]) => RGB::new(
+                    scale(*gray),
+                    scale(*gray),
+                    scale(*gray),
+                    Some(scale(*alpha)),
+                ),
+                (_, [red, green, blue]) => RGB::new(scale(*red), scale(*green), scale(*blue), None),
+                (_, [red, green, blue, alpha, ..]) => RGB::new(
+                    scale(*red),
+                    scale(*green),
+                    scale(*blue),
+                    Some(scale(*alpha)),
+                ),
+                _ => RGB::default(),
+            })
+            .collect();
+
+        Ok(Self {
+            width: header.width,
+            height: header.height,
+            kind,
+            encoding,
+            depth: header.depth,
+            maxval: header.maxval,
+            tuple_type: header.tuple_type,
+            pixels,
+            samples16: Samples16::default(),
+            orientation: Orientation::Normal,
+        })
+    }
+
+    fn write_pnm(&self) -> Vec<u8> {
+        let header = self.output_header();
+        let encoding = if header.kind == PnmKind::ArbitraryMap {
+            PnmEncoding::Binary
+        } else {
+            self.encoding
+        };
+
+        let magic = match (header.kind, encoding) {
+            (PnmKind::Bitmap, PnmEncoding::Ascii) => "P1",
+            (PnmKind::Graymap, PnmEncoding::Ascii) => "P2",
+            (PnmKind::Pixmap, PnmEncoding::Ascii) => "P3",
+            (PnmKind::Bitmap, PnmEncoding::Binary) => "P4",
+            (PnmKind::Graymap, PnmEncoding::Binary) => "P5",
+            (PnmKind::Pixmap, PnmEncoding::Binary) => "P6",
+            (PnmKind::ArbitraryMap, _) => "P7",
+        };
+
+        let mut bytes = match header.kind {
+            PnmKind::ArbitraryMap => format!(
+                "{magic}\nWIDTH {}\nHEIGHT {}\nDEPTH {}\nMAXVAL {}\nTUPLTYPE {}\nENDHDR\n",
+                header.width, header.height, header.depth, header.maxval, header.tuple_type
+            ),
+            PnmKind::Bitmap => format!("{magic}\n{} {}\n", header.width, header.height),
+            _ => format!(
+                "{magic}\n{} {}\n{}\n",
+                header.width, header.height, header.maxval
+            ),
+        }
+        .into_bytes();
+
+        let samples = self.samples(&header);
+
+        match (header.kind, encoding) {
+            (PnmKind::Bitmap, PnmEncoding::Binary) => {
+                for row in samples.chunks(header.width) {
+                    let mut line = vec![0_u8; header.width.div_ceil(8)];
+                    for (x, bit) in row.iter().enumerate() {
+                        line[x / 8] |= (*bit as u8) << (7 - x % 8);
+                    }
+                    bytes.append(&mut line);
+                }
+            }
+            (_, PnmEncoding::Binary) => {
+                for sample in samples {
+                    if header.maxval > u8::MAX as u16 {
+                        bytes.extend_from_slice(&sample.to_be_bytes());
+                    } else {
+                        bytes.push(sample as u8);
+                    }
+                }
+            }
+            (_, PnmEncoding::Ascii) => {
+                let separator = if header.kind == PnmKind::Bitmap {
+                    ""
+                } else {
+                    " "
+                };
+
+                for row in samples.chunks(header.width * header.depth) {
+                    let mut line = String::new();
+                    for sample in row {
+                        let value = sample.to_string();
+                        if !line.is_empty() {
+                            if line.len() + separator.len() + value.len() > ASCII_LINE_LIMIT {
+                                bytes.extend_from_slice(line.as_bytes());
+                                bytes.push(b'\n');
+                                line.clear();
+                            } else {
+                                line.push_str(separator);
+                            }
+                        }
+                        line.push_str(&value);
+                    }
+                    bytes.extend_from_slice(line.as_bytes());
+                    bytes.push(b'\n');
+                }
+            }
+        }
+
+        bytes
+    }
+
+    /// Escolhe o tipo de saida mais proximo do original que ainda represente os pixels sem perdas
+    fn output_header(&self) -> Header {
+        let has_alpha = self.pixels.iter().any(|c| c.alpha().is_some());
+        // Acima de 8 bits, cores diferentes podem ficar iguais quando reduzidas para 8
+        let is_gray = match self.samples16.get(&self.pixels) {
+            Some(samples) if self.maxval > u8::MAX as u16 => {
+                samples.iter().all(|c| c.0[0] == c.0[1] && c.0[1] == c.0[2])
+            }
+            _ => self
+                .pixels
+                .iter()
+                .all(|c| c.red() == c.green() && c.green() == c.blue()),
+        };
+
+        let mut kind = self.kind;
+        let mut maxval = if kind == PnmKind::Bitmap {
+            1
+        } else {
+            self.maxval.max(1)
+        };
+
+        let representable = self.pixels.iter().all(|c| {
+            [c.red(), c.green(), c.blue(), c.alpha().unwrap_or(u8::MAX)]
+                .iter()
+                .all(|value| scale_to_u8(scale_from_u8(*value, maxval), maxval) == *value)
+        });
+        if !representable {
+            maxval = maxval.max(u8::MAX as u16);
+        }
+
+        if kind == PnmKind::Bitmap && (!representable || !is_gray || has_alpha) {
+            kind = PnmKind::Graymap;
+        }
+        if kind == PnmKind::Graymap && !is_gray {
+            kind = PnmKind::Pixmap;
+        }
+
+        let original_depth = self.depth();
+        if has_alpha || kind == PnmKind::ArbitraryMap {
+            let color = if !is_gray || matches!(kind, PnmKind::Pixmap) || original_depth >= 3 {
+                3
+            } else {
+                1
+            };
+            let alpha =
+                has_alpha || (kind == PnmKind::ArbitraryMap && original_depth.is_multiple_of(2));
+            let depth = color + alpha as usize;
+
+            let tuple_type = if kind == PnmKind::ArbitraryMap
+                && depth == original_depth
+                && !is_standard_tuple(&self.tuple_type)
+            {
+                self.tuple_type.clone()
+            } else {
+                standard_tuple(depth, maxval).to_string()
+            };
+
+            return Header {
+                width: self.width,
+                height: self.height,
+                kind: PnmKind::ArbitraryMap,
+                depth,
+                maxval,
+                tuple_type,
+            };
+        }
+
+        let depth = if kind == PnmKind::Pixmap { 3 } else { 1 };
+        Header {
+            width: self.width,
+            height: self.height,
+            kind,
+            depth,
+            maxval,
+            tuple_type: String::new(),
+        }
+    }
+
+    /// Converte os pixels em amostras inteiras no tipo e maxval do cabecalho
+    fn samples(&self, header: &Header) -> Vec<u16> {
+        if header.maxval > u8::MAX as u16 && header.kind != PnmKind::Bitmap {
+            let scale = |value: u16| scale_from_u16(value, header.maxval);
+            let mut samples = Vec::with_capacity(self.pixels.len() * header.depth);
+            for color in self.samples16.widen(&self.pixels) {
+                let [red, green, blue, alpha] = color.0.map(scale);
+                match header.depth {
+                    1 => samples.push(red),
+                    2 => samples.extend([red, alpha]),
+                    3 => samples.extend([red, green, blue]),
+                    _ => samples.extend([red, green, blue, alpha]),
+                }
+            }
+            return samples;
+        }
+
+        let scale = |value: u8| scale_from_u8(value, header.maxval);
+
+        let mut samples = Vec::with_capacity(self.pixels.len() * header.depth);
+        for pixel in &self.pixels {
+            let alpha = pixel.alpha().unwrap_or(u8::MAX);
+            match (header.kind, header.depth) {
+                (PnmKind::Bitmap, _) => samples.push((pixel.red() < 128) as u16),
+                (_, 1) => samples.push(scale(pixel.red())),
+                (_, 2) => samples.extend([scale(pixel.red()), scale(alpha)]),
+                (_, 3) => samples.extend([
+                    scale(pixel.red()),
+                    scale(pixel.green()),
+                    scale(pixel.blue()),
+                ]),
+                _ => samples.extend([
+                    scale(pixel.red()),
+                    scale(pixel.green()),
+                    scale(pixel.blue()),
+                    scale(alpha),
+                ]),
+            }
+        }
+
+        samples
+    }
+
+    /// Quantidade de canais do tipo original
+    fn depth(&self) -> usize {
+        match self.kind {
+            PnmKind::Bitmap | PnmKind::Graymap => 1,
+            PnmKind::Pixmap => 3,
+            PnmKind::ArbitraryMap => self.depth,
+        }
+    }
+}
+
+impl From<Pnm> for ImageBuffer {
+    fn from(image: Pnm) -> Self {
+        ImageBuffer::from_raw(image.width, image.height, image.pixels)
+            .with_orientation(image.orientation)
+    }
+}
+
+impl From<Pnm> for ImageBuffer<Rgba<u16>> {
+    fn from(image: Pnm) -> Self {
+        image.to_rgba16()
+    }
+}
+
+impl TryFrom<ImageBuffer<Rgba<u16>>> for Pnm {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer<Rgba<u16>>) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Pnm::from_rgba16(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+impl TryFrom<ImageBuffer> for Pnm {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Pnm::from_pixels(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+impl Image for Pnm {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_pnm(&data)
+    }
+
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        writer.write_all(&self.write_pnm())?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    /// Descarta as amostras de 16 bits, que nao correspondem mais aos pixels
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        let image = Self::from_pixels(width, height, pixels)?;
+
+        self.width = image.width;
+        self.height = image.height;
+        self.pixels = image.pixels;
+        self.samples16.clear();
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        Format::PNM
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        if self.kind == PnmKind::Bitmap {
+            return 1;
+        }
+
+        let sample_bits = if self.maxval > u8::MAX as u16 { 16 } else { 8 };
+        (self.depth() * sample_bits) as u16
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        self.samples16.clear();
+        &mut self.pixels
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        &self.pixels
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.samples16.clear();
+        self.pixels.get_mut(y * self.width + x)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get(y * self.width + x)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        self.samples16.clear();
+        &mut self.pixels[range]
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        &self.pixels[range]
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (pixels, width, height) = self
+            .orientation
+            .apply(&self.pixels, self.width, self.height);
+        self.samples16
+            .orient(self.orientation, self.width, self.height);
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+        self.orientation = Orientation::Normal;
+    }
+}
+
+#[derive(Debug)]
+struct Header {
+    width: usize,
+    height: usize,
+    kind: PnmKind,
+    depth: usize,
+    maxval: u16,
+    tuple_type: String,
+}
+
+impl Header {
+    /// Le o cabecalho dos formatos P1 a P6: largura, altura e maxval (exceto no PBM)
+    fn read(tokens: &mut Tokens, kind: PnmKind) -> ImageResult<Self> {
+        let width = tokens.number()?;
+        let height = tokens.number()?;
+        let maxval = if kind == PnmKind::Bitmap {
+            1
+        } else {
+            tokens.number()?
+        };
+
+        // Um unico espaco em branco separa o cabecalho dos dados
+        tokens.skip_separator()?;
+
+        Self::validate(width, height, maxval)?;
+        Ok(Self {
+            width,
+            height,
+            kind,
+            depth: if kind == PnmKind::Pixmap { 3 } else { 1 },
+            maxval: maxval as u16,
+            tuple_type: String::new(),
+        })
+    }
+
+    /// Le o cabecalho do PAM, com uma chave e valor por linha ate `ENDHDR`
+    fn read_pam(tokens: &mut Tokens) -> ImageResult<Self> {
+        let mut width = None;
+        let mut height = None;
+        let mut depth = None;
+        let mut maxval = None;
+        let mut tuple_type = String::new();
+
+        loop {
+            let key = tokens.token().ok_or(ImageError::Truncated)?;
+            match key {
+                b"WIDTH" => width = Some(tokens.number()?),
+                b"HEIGHT" => height = Some(tokens.number()?),
+                b"DEPTH" => depth = Some(tokens.number()?),
+                b"MAXVAL" => maxval = Some(tokens.number()?),
+                b"TUPLTYPE" => {
+                    // Varias linhas TUPLTYPE sao concatenadas com espaco
+                    let value = tokens.rest_of_line();
+                    if !tuple_type.is_empty() {
+                        tuple_type.push(' ');
+                    }
+                    tuple_type.push_str(&String::from_utf8_lossy(value));
+                }
+                b"ENDHDR" => {
+                    tokens.rest_of_line();
+                    break;
+                }
+                _ => return Err(ImageError::InvalidData("pam header has an unknown key")),
+            }
+        }
+
+        let missing = ImageError::InvalidData("pam header is missing a field");
+        let (width, height, maxval) = match (width, height, maxval) {
+            (Some(width), Some(height), Some(maxval)) => (width, height, maxval),
+            _ => return Err(missing),
+        };
+        let depth = depth.ok_or(missing)?;
+
+        if depth == 0 {
+            return Err(ImageError::InvalidData("pam depth is zero"));
+        }
+        if depth > 4 {
+            return Err(ImageError::Unsupported("pam depth greater than 4"));
+        }
+
+        Self::validate(width, height, maxval)?;
+        Ok(Self {
+            width,
+            height,
+            kind: PnmKind::ArbitraryMap,
+            depth,
+            maxval: maxval as u16,
+            tuple_type,
+        })
+    }
+
+    fn validate(width: usize, height: usize, maxval: usize) -> ImageResult<()> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("pnm dimensions are zero"));
+        }
+        if maxval == 0 || maxval > u16::MAX as usize {
+            return Err(ImageError::InvalidData("pnm maxval is invalid"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Leitor dos tokens do cabecalho e das amostras ASCII, ignorando comentarios (`#` ate o fim
+/// da linha)
+struct Tokens<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.data.get(self.offset) {
+            match byte {
+                b'#' => {
+                    while self.data.get(self.offset).is_some_and(|b| *b != b'\n') {
+                        self.offset += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => self.offset += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn token(&mut self) -> Option<&'a [u8]> {
+        self.skip_whitespace();
+
+        let start = self.offset;
+        while self
+            .data
+            .get(self.offset)
+            .is_some_and(|b| !b.is_ascii_whitespace() && *b != b'#')
+        {
+            self.offset += 1;
+        }
+
+        (self.offset > start).then(|| &self.data[start..self.offset])
+    }
+
+    fn number(&mut self) -> ImageResult<usize> {
+        let token = self.token().ok_or(ImageError::Truncated)?;
+
+        token.iter().try_fold(0_usize, |value, digit| {
+            if !digit.is_ascii_digit() {
+                return Err(ImageError::InvalidData("pnm header number is invalid"));
+            }
+            value
+                .checked_mul(10)
+                .and_then(|value| value.checked_add((digit - b'0') as usize))
+                .ok_or(ImageError::DimensionOverflow)
+        })
+    }
+
+    fn rest_of_line(&mut self) -> &'a [u8] {
+        let start = self.offset;
+        while self.data.get(self.offset).is_some_and(|b| *b != b'\n') {
+            self.offset += 1;
+        }
+
+        let line = &self.data[start..self.offset];
+        self.offset += 1;
+
+        line.trim_ascii()
+    }
+
+    fn skip_separator(&mut self) -> ImageResult<()> {
+        match self.data.get(self.offset) {
+            Some(byte) if byte.is_ascii_whitespace() => {
+                self.offset += 1;
+                Ok(())
+            }
+            Some(_) => Err(ImageError::InvalidData("pnm header is not terminated")),
+            None => Ok(()),
+        }
+    }
+
+    fn raster(&self) -> &'a [u8] {
+        self.data.get(self.offset..).unwrap_or_default()
+    }
+
+    /// Amostras do P1, onde os bits podem vir sem espaco entre eles
+    fn bits(&mut self, total: usize) -> ImageResult<Vec<u16>> {
+        let mut bits = Vec::new();
+        while bits.len() < total {
+            self.skip_whitespace();
+            match self.data.get(self.offset) {
+                Some(b'0') => bits.push(0),
+                Some(b'1') => bits.push(1),
+                Some(_) => return Err(ImageError::InvalidData("pbm sample is invalid")),
+                None => return Err(ImageError::Truncated),
+            }
+            self.offset += 1;
+        }
+
+        Ok(bits)
+    }
+
+    fn samples(&mut self, total: usize, maxval: u16) -> ImageResult<Vec<u16>> {
+        let mut samples = Vec::new();
+        while samples.len() < total {
+            let sample = self.number()?;
+            samples.push(sample.min(maxval as usize) as u16);
+        }
+
+        Ok(samples)
+    }
+}
+
+// Utils Functions
+fn scale_to_u8(sample: u16, maxval: u16) -> u8 {
+    let maxval = maxval.max(1) as u32;
+    ((sample as u32 * 255 + maxval / 2) / maxval) as u8
+}
+
+fn scale_from_u8(value: u8, maxval: u16) -> u16 {
+    ((value as u32 * maxval as u32 + 127) / 255) as u16
+}
+
+/// Leva uma amostra de 0..=maxval para 0..=65535. Sem perdas: `scale_from_u16` volta ao valor
+fn scale_to_u16(sample: u16, maxval: u16) -> u16 {
+    let maxval = maxval.max(1) as u32;
+    ((sample as u32 * 65535 + maxval / 2) / maxval) as u16
+}
+
+fn scale_from_u16(value: u16, maxval: u16) -> u16 {
+    ((value as u32 * maxval as u32 + 32767) / 65535) as u16
+}
+
+fn is_standard_tuple(tuple_type: &str) -> bool {
+    matches!(
+        tuple_type,
+        "BLACKANDWHITE"
+            | "GRAYSCALE"
+            | "RGB"
+            | "BLACKANDWHITE_ALPHA"
+            | "GRAYSCALE_ALPHA"
+            | "RGB_ALPHA"
+    )
+}
+
+fn standard_tuple(depth: usize, maxval: u16) -> &'static str {
+    match (depth, maxval) {
+        (1, 1) => "BLACKANDWHITE",
+        (1, _) => "GRAYSCALE",
+        (2, 1) => "BLACKANDWHITE_ALPHA",
+        (2, _) => "GRAYSCALE_ALPHA",
+        (3, _) => "RGB",
+        _ => "RGB_ALPHA",
+    }
+}
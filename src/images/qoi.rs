@@ -0,0 +1,413 @@
+use super::{Format, Image, Orientation, RGB, buffer::ImageBuffer};
+use crate::error::{ImageError, ImageResult};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+// Consts...
+const MAGIC: [u8; 4] = *b"qoif";
+const HEADER_SIZE: usize = 14;
+const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+const OP_INDEX: u8 = 0x00;
+const OP_DIFF: u8 = 0x40;
+const OP_LUMA: u8 = 0x80;
+const OP_RUN: u8 = 0xC0;
+const OP_RGB: u8 = 0xFE;
+const OP_RGBA: u8 = 0xFF;
+const OP_MASK: u8 = 0xC0;
+
+/// Maior sequencia guardada num unico QOI_OP_RUN
+const MAX_RUN: u8 = 62;
+
+// Enums...
+/// Enum que representa o campo `channels` do cabecalho
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QoiChannels {
+    Rgb = 3,
+    Rgba = 4,
+}
+
+/// Enum que representa o campo `colorspace` do cabecalho. E apenas informativo, os pixels nao
+/// sao convertidos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QoiColorSpace {
+    /// sRGB com alpha linear
+    Srgb = 0,
+    /// Todos os canais lineares
+    Linear = 1,
+}
+
+// Structs...
+/// Struct para representa uma imagem QOI
+pub struct Qoi {
+    width: usize,
+    height: usize,
+    channels: QoiChannels,
+    color_space: QoiColorSpace,
+    pixels: Vec<RGB>,
+    orientation: Orientation,
+}
+
+impl Qoi {
+    /// Cria uma imagem QOI sRGB a partir de pixels em ordem de linhas. Ela e salva com 4 canais
+    /// se algum pixel tiver alpha
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("qoi dimensions are zero"));
+        }
+        if width > u32::MAX as usize || height > u32::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(ImageError::InvalidData(
+                "pixel count does not match the dimensions",
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            channels: QoiChannels::Rgb,
+            color_space: QoiColorSpace::Srgb,
+            pixels,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    pub fn channels(&self) -> QoiChannels {
+        self.channels
+    }
+
+    /// Define os canais gravados. Com `Rgb` o alpha so e gravado se algum pixel tiver alpha
+    pub fn set_channels(&mut self, channels: QoiChannels) {
+        self.channels = channels;
+    }
+
+    pub fn color_space(&self) -> QoiColorSpace {
+        self.color_space
+    }
+
+    pub fn set_color_space(&mut self, color_space: QoiColorSpace) {
+        self.color_space = color_space;
+    }
+
+    fn read_qoi(data: &[u8]) -> ImageResult<Self> {
+        if data.len() < HEADER_SIZE {
+            return Err(ImageError::Truncated);
+        }
+        if data[0..4] != MAGIC {
+            return Err(ImageError::InvalidMagic);
+        }
+
+        let width = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let height = u32::from_be_bytes([data[8], data[9], data[10], data[11]]) as usize;
+        let channels = match data[12] {
+            3 => QoiChannels::Rgb,
+            4 => QoiChannels::Rgba,
+            _ => return Err(ImageError::InvalidData("qoi channels are invalid")),
+        };
+        let color_space = match data[13] {
+            0 => QoiColorSpace::Srgb,
+            1 => QoiColorSpace::Linear,
+            _ => return Err(ImageError::InvalidData("qoi colorspace is invalid")),
+        };
+
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("qoi dimensions are zero"));
+        }
+
+        let total = width
+            .checked_mul(height)
+            .ok_or(ImageError::DimensionOverflow)?;
+
+        // Cada byte gera no maximo MAX_RUN pixels, entao um cabecalho mentiroso para aqui
+        let body = &data[HEADER_SIZE..];
+        if total / MAX_RUN as usize > body.len() {
+            return Err(ImageError::Truncated);
+        }
+
+        let mut index = [[0_u8; 4]; 64];
+        let mut current = [0_u8, 0, 0, 255];
+        let mut pixels = Vec::with_capacity(total);
+        let mut offset = 0;
+
+        let mut next = || -> ImageResult<u8> {
+            let byte = *body.get(offset).ok_or(ImageError::Truncated)?;
+            offset += 1;
+            Ok(byte)
+        };
+
+        while pixels.len() < total {
+            let op = next()?;
+            let mut run = 1;
+
+            match op {
+                OP_RGB => {
+                    current[0] = next()?;
+                    current[1] = next()?;
+                    current[2] = next()?;
+                }
+                OP_RGBA => {
+                    current[0] = next()?;
+                    current[1] = next()?;
+                    current[2] = next()?;
+                    current[3] = next()?;
+                }
+                _ => match op & OP_MASK {
+                    OP_INDEX => current = index[op as usize],
+                    OP_DIFF => {
+                        current[0] = current[0].wrapping_add((op >> 4) & 0x03).wrapping_sub(2);
+                        current[1] = current[1].wrapping_add((op >> 2) & 0x03).wrapping_sub(2);
+                        current[2] = current[2].wrapping_add(op & 0x03).wrapping_sub(2);
+                    }
+                    OP_LUMA => {
+                        let green = (op & 0x3F).wrapping_sub(32);
+                        let second = next()?;
+                        let red = green.wrapping_add(second >> 4).wrapping_sub(8);
+                        let blue = green.wrapping_add(second & 0x0F).wrapping_sub(8);
+
+                        current[0] = current[0].wrapping_add(red);
+                        current[1] = current[1].wrapping_add(green);
+                        current[2] = current[2].wrapping_add(blue);
+                    }
+                    _ => run = (op & 0x3F) as usize + 1,
+                },
+            }
+
+            index[hash(&current)] = current;
+
+            let alpha = (channels == QoiChannels::Rgba).then_some(current[3]);
+            let pixel = RGB::new(current[0], current[1], current[2], alpha);
+            let run = run.min(total - pixels.len());
+            pixels.extend(std::iter::repeat_n(pixel, run));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            channels,
+            color_space,
+            pixels,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    fn write_qoi(&self) -> Vec<u8> {
+        let has_alpha = self.pixels.iter().any(|pixel| pixel.alpha().is_some());
+        let channels = if has_alpha {
+            QoiChannels::Rgba
+        } else {
+            self.channels
+        };
+
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.pixels.len() + END_MARKER.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&(self.width as u32).to_be_bytes());
+        bytes.extend_from_slice(&(self.height as u32).to_be_bytes());
+        bytes.push(channels as u8);
+        bytes.push(self.color_space as u8);
+
+        let mut index = [[0_u8; 4]; 64];
+        let mut previous = [0_u8, 0, 0, 255];
+        let mut run = 0_u8;
+
+        for pixel in &self.pixels {
+            let current = [
+                pixel.red(),
+                pixel.green(),
+                pixel.blue(),
+                pixel.alpha().unwrap_or(u8::MAX),
+            ];
+
+            if current == previous {
+                run += 1;
+                if run == MAX_RUN {
+                    bytes.push(OP_RUN | (run - 1));
+                    run = 0;
+                }
+                continue;
+            }
+
+            if run > 0 {
+                bytes.push(OP_RUN | (run - 1));
+                run = 0;
+            }
+
+            let position = hash(&current);
+            if index[position] == current {
+                bytes.push(OP_INDEX | position as u8);
+            } else {
+                index[position] = current;
+
+                if current[3] != previous[3] {
+                    bytes.extend_from_slice(&[OP_RGBA, current[0], current[1], current[2]]);
+                    bytes.push(current[3]);
+                } else {
+                    let red = current[0].wrapping_sub(previous[0]) as i8;
+                    let green = current[1].wrapping_sub(previous[1]) as i8;
+                    let blue = current[2].wrapping_sub(previous[2]) as i8;
+                    let red_green = red.wrapping_sub(green);
+                    let blue_green = blue.wrapping_sub(green);
+
+                    if (-2..=1).contains(&red)
+                        && (-2..=1).contains(&green)
+                        && (-2..=1).contains(&blue)
+                    {
+                        bytes.push(
+                            OP_DIFF
+                                | (((red + 2) as u8) << 4)
+                                | (((green + 2) as u8) << 2)
+                                | (blue + 2) as u8,
+                        );
+                    } else if (-32..=31).contains(&green)
+                        && (-8..=7).contains(&red_green)
+                        && (-8..=7).contains(&blue_green)
+                    {
+                        bytes.push(OP_LUMA | (green + 32) as u8);
+                        bytes.push((((red_green + 8) as u8) << 4) | (blue_green + 8) as u8);
+                    } else {
+                        bytes.extend_from_slice(&[OP_RGB, current[0], current[1], current[2]]);
+                    }
+                }
+            }
+
+            previous = current;
+        }
+
+        if run > 0 {
+            bytes.push(OP_RUN | (run - 1));
+        }
+        bytes.extend_from_slice(&END_MARKER);
+
+        bytes
+    }
+}
+
+impl From<Qoi> for ImageBuffer {
+    fn from(image: Qoi) -> Self {
+        ImageBuffer::from_raw(image.width, image.height, image.pixels)
+            .with_orientation(image.orientation)
+    }
+}
+
+impl TryFrom<ImageBuffer> for Qoi {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Qoi::from_pixels(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+impl Image for Qoi {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_qoi(&data)
+    }
+
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        writer.write_all(&self.write_qoi())?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        let image = Self::from_pixels(width, height, pixels)?;
+
+        self.width = image.width;
+        self.height = image.height;
+        self.pixels = image.pixels;
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        Format::QOI
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        self.channels as u16 * 8
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        &mut self.pixels
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        &self.pixels
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get_mut(y * self.width + x)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get(y * self.width + x)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        &mut self.pixels[range]
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        &self.pixels[range]
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (pixels, width, height) = self
+            .orientation
+            .apply(&self.pixels, self.width, self.height);
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+        self.orientation = Orientation::Normal;
+    }
+}
+
+// Utils Functions
+/// Posicao da cor na tabela de cores recentes
+fn hash(color: &[u8; 4]) -> usize {
+    (color[0] as usize * 3 + color[1] as usize * 5 + color[2] as usize * 7 + color[3] as usize * 11)
+        % 64
+}
@@ -0,0 +1,738 @@
+use super::{Format, Image, Orientation, RGB, buffer::ImageBuffer};
+use crate::error::{ImageError, ImageResult};
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+// Consts...
+const HEADER_SIZE: usize = 18;
+const FOOTER_SIZE: usize = 26;
+/// Assinatura no fim do rodape do TGA 2.0, usada tambem para detectar o formato
+pub(crate) const SIGNATURE: [u8; 18] = *b"TRUEVISION-XFILE.\0";
+/// Posicao do campo `Attributes Type` dentro da area de extensao (TGA 2.0)
+const ATTRIBUTES_TYPE_OFFSET: usize = 494;
+
+/// Bits do descritor: quantidade de bits de alpha e a origem dos pixels
+const DESCRIPTOR_ALPHA: u8 = 0x0F;
+const DESCRIPTOR_RIGHT: u8 = 0x10;
+const DESCRIPTOR_TOP: u8 = 0x20;
+
+/// Maior quantidade de pixels num pacote RLE (contador de 7 bits mais 1)
+const MAX_PACKET: usize = 128;
+const MAX_COLORS: usize = 256;
+
+// Enums...
+/// Enum que representa o tipo de cor do campo `Image Type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TgaColorType {
+    /// Indices para uma tabela de cores (tipos 1 e 9)
+    ColorMapped,
+    /// Cor direta em BGR(A) (tipos 2 e 10)
+    TrueColor,
+    /// Cinza, com alpha opcional (tipos 3 e 11)
+    Gray,
+}
+
+/// Enum que representa o canto da tela onde fica o primeiro pixel gravado
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TgaOrigin {
+    /// Padrao do formato: linhas de baixo para cima
+    #[default]
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+}
+
+impl TgaOrigin {
+    fn from_descriptor(descriptor: u8) -> Self {
+        match (
+            descriptor & DESCRIPTOR_TOP != 0,
+            descriptor & DESCRIPTOR_RIGHT != 0,
+        ) {
+            (false, false) => Self::BottomLeft,
+            (false, true) => Self::BottomRight,
+            (true, false) => Self::TopLeft,
+            (true, true) => Self::TopRight,
+        }
+    }
+
+    fn to_descriptor(self) -> u8 {
+        match self {
+            Self::BottomLeft => 0,
+            Self::BottomRight => DESCRIPTOR_RIGHT,
+            Self::TopLeft => DESCRIPTOR_TOP,
+            Self::TopRight => DESCRIPTOR_TOP | DESCRIPTOR_RIGHT,
+        }
+    }
+
+    fn is_top(self) -> bool {
+        matches!(self, Self::TopLeft | Self::TopRight)
+    }
+
+    fn is_right(self) -> bool {
+        matches!(self, Self::BottomRight | Self::TopRight)
+    }
+}
+
+// Structs...
+/// Struct para representa uma imagem TGA (Truevision), guardando o tipo original para a escrita
+pub struct Tga {
+    width: usize,
+    height: usize,
+    color_type: TgaColorType,
+    bits_per_pixel: u8,
+    rle: bool,
+    origin: TgaOrigin,
+    palette: Vec<RGB>,
+    pixels: Vec<RGB>,
+    orientation: Orientation,
+}
+
+/// Formato escolhido para a escrita
+struct Header {
+    color_type: TgaColorType,
+    /// Bits de cada pixel gravado (dos indices, no caso de tabela de cores)
+    bits_per_pixel: u8,
+    /// Bits de cada cor da tabela de cores
+    entry_bits: u8,
+    alpha_bits: u8,
+}
+
+impl Tga {
+    /// Cria um TGA de 24 bits sem compressao a partir de pixels em ordem de linhas. Imagens com
+    /// alpha sao salvas com 32 bits
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("tga dimensions are zero"));
+        }
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(ImageError::DimensionOverflow);
+        }
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(ImageError::InvalidData(
+                "pixel count does not match the dimensions",
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            color_type: TgaColorType::TrueColor,
+            bits_per_pixel: 24,
+            rle: false,
+            origin: TgaOrigin::BottomLeft,
+            palette: Vec::new(),
+            pixels,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    pub fn color_type(&self) -> TgaColorType {
+        self.color_type
+    }
+
+    /// Define o tipo usado ao salvar. Se os pixels nao couberem nele (cores num cinza, mais de
+    /// 256 cores numa tabela), a escrita usa cor direta
+    pub fn set_color_type(&mut self, color_type: TgaColorType) {
+        self.color_type = color_type;
+    }
+
+    /// Bits de cada pixel no arquivo (dos indices, nas imagens com tabela de cores)
+    pub fn bits_per_pixel(&self) -> u8 {
+        self.bits_per_pixel
+    }
+
+    /// Define os bits das imagens de cor direta: 15 e 16 so sao usados se os pixels couberem
+    /// em 5 bits por canal (e alpha de 1 bit), senao a escrita usa 24 ou 32
+    pub fn set_bits_per_pixel(&mut self, bits_per_pixel: u8) {
+        self.bits_per_pixel = bits_per_pixel;
+    }
+
+    pub fn rle(&self) -> bool {
+        self.rle
+    }
+
+    /// Define se os pixels sao gravados com compressao RLE
+    pub fn set_rle(&mut self, value: bool) {
+        self.rle = value;
+    }
+
+    /// Canto do primeiro pixel no arquivo. Os pixels em memoria sempre comecam no canto superior
+    /// esquerdo
+    pub fn origin(&self) -> TgaOrigin {
+        self.origin
+    }
+
+    pub fn set_origin(&mut self, origin: TgaOrigin) {
+        self.origin = origin;
+    }
+
+    /// Tabela de cores das imagens indexadas, vazia nas demais
+    pub fn palette(&self) -> &[RGB] {
+        &self.palette
+    }
+
+    fn read_tga(data: &[u8]) -> ImageResult<Self> {
+        if data.len() < HEADER_SIZE {
+            return Err(ImageError::Truncated);
+        }
+
+        let field = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]) as usize;
+        let id_length = data[0] as usize;
+        let color_map_type = data[1];
+        let image_type = data[2];
+        let (map_first, map_length, map_bits) = (field(3), field(5), data[7]);
+        let (width, height) = (field(12), field(14));
+        let bits_per_pixel = data[16];
+        let descriptor = data[17];
+
+        let (color_type, rle) = match image_type {
+            0 => return Err(ImageError::InvalidData("tga has no image data")),
+            1 => (TgaColorType::ColorMapped, false),
+            2 => (TgaColorType::TrueColor, false),
+            3 => (TgaColorType::Gray, false),
+            9 => (TgaColorType::ColorMapped, true),
+            10 => (TgaColorType::TrueColor, true),
+            11 => (TgaColorType::Gray, true),
+            other => return Err(ImageError::UnsupportedCompression(other as u32)),
+        };
+        if color_map_type > 1 {
+            return Err(ImageError::InvalidData("tga color map type is invalid"));
+        }
+
+        let supported = match color_type {
+            TgaColorType::ColorMapped => {
+                color_map_type == 1
+                    && matches!(bits_per_pixel, 8 | 16)
+                    && matches!(map_bits, 15 | 16 | 24 | 32)
+            }
+            TgaColorType::TrueColor => matches!(bits_per_pixel, 15 | 16 | 24 | 32),
+            TgaColorType::Gray => matches!(bits_per_pixel, 8 | 16),
+        };
+        if !supported {
+            return Err(ImageError::UnsupportedBitDepth(bits_per_pixel as u16));
+        }
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("tga dimensions are zero"));
+        }
+
+        // Alpha: 32 bits e cinza com 16 sempre tem; 16 bits so quando o descritor pede
+        let alpha_bits = descriptor & DESCRIPTOR_ALPHA;
+        let mut has_alpha = match color_type {
+            TgaColorType::ColorMapped => map_bits == 32 || (map_bits == 16 && alpha_bits > 0),
+            TgaColorType::TrueColor => {
+                bits_per_pixel == 32 || (bits_per_pixel == 16 && alpha_bits > 0)
+            }
+            TgaColorType::Gray => bits_per_pixel == 16,
+        };
+        // A area de extensao do TGA 2.0 pode dizer que o canal extra nao e alpha
+        if let Some(0..=2) = attributes_type(data) {
+            has_alpha = false;
+        }
+
+        let mut pos = HEADER_SIZE + id_length;
+        // Imagens de cor direta podem trazer uma tabela de cores, que so e pulada
+        let entry_size = (map_bits as usize).div_ceil(8);
+        let map_size = if color_map_type == 1 {
+            map_length * entry_size
+        } else {
+            0
+        };
+        let map = data.get(pos..pos + map_size).ok_or(ImageError::Truncated)?;
+        pos += map_size;
+
+        let palette = if color_type == TgaColorType::ColorMapped {
+            map.chunks_exact(entry_size)
+                .map(|entry| unpack_color(entry, TgaColorType::TrueColor))
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        let total = width
+            .checked_mul(height)
+            .ok_or(ImageError::DimensionOverflow)?;
+        let size = (bits_per_pixel as usize).div_ceil(8);
+        let body = data.get(pos..).unwrap_or_default();
+        // Um pacote RLE de 1 + size bytes gera no maximo MAX_PACKET pixels
+        if total / MAX_PACKET > body.len() {
+            return Err(ImageError::Truncated);
+        }
+
+        let stored = if rle {
+            read_rle(body, total, size)?
+        } else {
+            body.get(..total * size)
+                .ok_or(ImageError::Truncated)?
+                .to_vec()
+        };
+
+        let colors = stored
+            .chunks_exact(size)
+            .map(|bytes| match color_type {
+                TgaColorType::ColorMapped => {
+                    let index = match bytes {
+                        [low, high] => u16::from_le_bytes([*low, *high]) as usize,
+                        _ => bytes[0] as usize,
+                    };
+                    index
+                        .checked_sub(map_first)
+                        .and_then(|index| palette.get(index))
+                        .copied()
+                        .ok_or(ImageError::InvalidData("tga color index is out of range"))
+                }
+                _ => Ok(unpack_color(bytes, color_type)),
+            })
+            .collect::<ImageResult<Vec<_>>>()?;
+
+        // Arquivos que marcam alpha mas deixam tudo zerado sao tratados como opacos
+        if has_alpha && colors.iter().all(|color| color[3] == 0) {
+            has_alpha = false;
+        }
+
+        let origin = TgaOrigin::from_descriptor(descriptor);
+        let to_rgb =
+            |color: &[u8; 4]| RGB::new(color[0], color[1], color[2], has_alpha.then_some(color[3]));
+        let mut pixels = vec![RGB::default(); total];
+        for (index, color) in colors.iter().enumerate() {
+            let (x, y) = stored_position(index, width, height, origin);
+            pixels[y * width + x] = to_rgb(color);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            color_type,
+            bits_per_pixel,
+            rle,
+            origin,
+            palette: palette.iter().map(to_rgb).collect(),
+            pixels,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    fn write_tga(&self) -> Vec<u8> {
+        let (header, palette) = self.output_header();
+        let size = (header.bits_per_pixel as usize).div_ceil(8);
+
+        let image_type = match (header.color_type, self.rle) {
+            (TgaColorType::ColorMapped, false) => 1,
+            (TgaColorType::TrueColor, false) => 2,
+            (TgaColorType::Gray, false) => 3,
+            (TgaColorType::ColorMapped, true) => 9,
+            (TgaColorType::TrueColor, true) => 10,
+            (TgaColorType::Gray, true) => 11,
+        };
+
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.pixels.len() * size + FOOTER_SIZE);
+        bytes.extend_from_slice(&[0, !palette.is_empty() as u8, image_type, 0, 0]);
+        bytes.extend_from_slice(&(palette.len() as u16).to_le_bytes());
+        bytes.push(if palette.is_empty() {
+            0
+        } else {
+            header.entry_bits
+        });
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&(self.width as u16).to_le_bytes());
+        bytes.extend_from_slice(&(self.height as u16).to_le_bytes());
+        bytes.push(header.bits_per_pixel);
+        bytes.push(header.alpha_bits | self.origin.to_descriptor());
+
+        for color in &palette {
+            bytes.extend_from_slice(&pack_color(
+                color,
+                TgaColorType::TrueColor,
+                header.entry_bits,
+            ));
+        }
+
+        let indices = palette
+            .iter()
+            .enumerate()
+            .map(|(index, color)| (*color, index as u8))
+            .collect::<HashMap<_, _>>();
+
+        let mut stored = Vec::with_capacity(self.pixels.len() * size);
+        for index in 0..self.pixels.len() {
+            let (x, y) = stored_position(index, self.width, self.height, self.origin);
+            let color = rgba(&self.pixels[y * self.width + x]);
+
+            match header.color_type {
+                TgaColorType::ColorMapped => stored.push(indices[&color]),
+                color_type => {
+                    stored.extend_from_slice(&pack_color(&color, color_type, header.bits_per_pixel))
+                }
+            }
+        }
+
+        if self.rle {
+            for row in stored.chunks(self.width * size) {
+                write_rle(&mut bytes, row, size);
+            }
+        } else {
+            bytes.extend_from_slice(&stored);
+        }
+
+        // Rodape do TGA 2.0 sem area de extensao nem de desenvolvedor
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&SIGNATURE);
+
+        bytes
+    }
+
+    /// Escolhe o formato de saida mais proximo do original que ainda represente os pixels sem perdas
+    fn output_header(&self) -> (Header, Vec<[u8; 4]>) {
+        let has_alpha = self.pixels.iter().any(|c| c.alpha().is_some());
+        let is_gray = self
+            .pixels
+            .iter()
+            .all(|c| c.red() == c.green() && c.green() == c.blue());
+
+        let mut color_type = self.color_type;
+        let mut palette = Vec::new();
+
+        if color_type == TgaColorType::ColorMapped {
+            // Sem espaco para as cores originais, tenta so com as usadas
+            let colors = build_palette(&self.palette, &self.pixels)
+                .or_else(|| build_palette(&[], &self.pixels));
+            match colors {
+                Some(colors) => palette = colors,
+                None => color_type = TgaColorType::TrueColor,
+            }
+        }
+
+        if color_type == TgaColorType::Gray && !is_gray {
+            color_type = TgaColorType::TrueColor;
+        }
+
+        let (bits_per_pixel, entry_bits, alpha_bits) = match color_type {
+            TgaColorType::ColorMapped if has_alpha => (8, 32, 8),
+            TgaColorType::ColorMapped => (8, 24, 0),
+            TgaColorType::Gray if has_alpha => (16, 0, 8),
+            TgaColorType::Gray => (8, 0, 0),
+            TgaColorType::TrueColor => {
+                // 5 bits por canal so sem perdas quando os valores vieram de 5 bits
+                let fits = self.pixels.iter().all(|c| {
+                    [c.red(), c.green(), c.blue()]
+                        .iter()
+                        .all(|value| expand_5(reduce_5(*value) as u8) == *value)
+                });
+                let binary_alpha = self
+                    .pixels
+                    .iter()
+                    .all(|c| matches!(c.alpha(), None | Some(0) | Some(u8::MAX)));
+
+                match self.bits_per_pixel {
+                    15 | 16 if fits && !has_alpha => (self.bits_per_pixel, 0, 0),
+                    16 if fits && binary_alpha => (16, 0, 1),
+                    _ if has_alpha => (32, 0, 8),
+                    _ => (24, 0, 0),
+                }
+            }
+        };
+
+        let header = Header {
+            color_type,
+            bits_per_pixel,
+            entry_bits,
+            alpha_bits,
+        };
+
+        (header, palette)
+    }
+}
+
+impl From<Tga> for ImageBuffer {
+    fn from(image: Tga) -> Self {
+        ImageBuffer::from_raw(image.width, image.height, image.pixels)
+            .with_orientation(image.orientation)
+    }
+}
+
+impl TryFrom<ImageBuffer> for Tga {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Tga::from_pixels(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+impl Image for Tga {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_tga(&data)
+    }
+
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        writer.write_all(&self.write_tga())?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        let image = Self::from_pixels(width, height, pixels)?;
+
+        self.width = image.width;
+        self.height = image.height;
+        self.pixels = image.pixels;
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        Format::TGA
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        self.bits_per_pixel as u16
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        &mut self.pixels
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        &self.pixels
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get_mut(y * self.width + x)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get(y * self.width + x)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        &mut self.pixels[range]
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        &self.pixels[range]
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (pixels, width, height) = self
+            .orientation
+            .apply(&self.pixels, self.width, self.height);
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+        self.orientation = Orientation::Normal;
+    }
+}
+
+// Utils Functions
+/// `Attributes Type` da area de extensao, se o arquivo tiver o rodape do TGA 2.0
+fn attributes_type(data: &[u8]) -> Option<u8> {
+    let footer = data.len().checked_sub(FOOTER_SIZE)?;
+    if data[footer + 8..] != SIGNATURE {
+        return None;
+    }
+
+    let offset = u32::from_le_bytes(data[footer..footer + 4].try_into().ok()?) as usize;
+    if offset == 0 {
+        return None;
+    }
+    data.get(offset.checked_add(ATTRIBUTES_TYPE_OFFSET)?)
+        .copied()
+}
+
+/// Posicao (x, y) na tela do pixel gravado na posicao `index`
+fn stored_position(index: usize, width: usize, height: usize, origin: TgaOrigin) -> (usize, usize) {
+    let (column, row) = (index % width, index / width);
+    let x = if origin.is_right() {
+        width - 1 - column
+    } else {
+        column
+    };
+    let y = if origin.is_top() {
+        row
+    } else {
+        height - 1 - row
+    };
+    (x, y)
+}
+
+/// Expande os pacotes RLE ate `total` pixels de `size` bytes. Pacotes podem cruzar linhas
+fn read_rle(body: &[u8], total: usize, size: usize) -> ImageResult<Vec<u8>> {
+    let mut output = Vec::with_capacity(total * size);
+    let mut pos = 0;
+
+    while output.len() < total * size {
+        let packet = *body.get(pos).ok_or(ImageError::Truncated)?;
+        pos += 1;
+        let count = ((packet & 0x7F) as usize + 1).min(total - output.len() / size);
+
+        if packet & 0x80 != 0 {
+            let pixel = body.get(pos..pos + size).ok_or(ImageError::Truncated)?;
+            pos += size;
+            for _ in 0..count {
+                output.extend_from_slice(pixel);
+            }
+        } else {
+            let raw = ((packet & 0x7F) as usize + 1) * size;
+            let pixels = body.get(pos..pos + raw).ok_or(ImageError::Truncated)?;
+            pos += raw;
+            output.extend_from_slice(&pixels[..count * size]);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Grava uma linha em pacotes RLE: repeticoes viram um pacote de repeticao e o resto vai em
+/// pacotes crus
+fn write_rle(bytes: &mut Vec<u8>, row: &[u8], size: usize) {
+    let pixels = row.chunks_exact(size).collect::<Vec<_>>();
+    let mut start = 0;
+
+    while start < pixels.len() {
+        let run = pixels[start..]
+            .iter()
+            .take(MAX_PACKET)
+            .take_while(|pixel| **pixel == pixels[start])
+            .count();
+
+        if run > 1 {
+            bytes.push(0x80 | (run - 1) as u8);
+            bytes.extend_from_slice(pixels[start]);
+            start += run;
+            continue;
+        }
+
+        // Junta pixels crus ate comecar uma repeticao
+        let mut end = start + 1;
+        while end < pixels.len() && end - start < MAX_PACKET {
+            if end + 1 < pixels.len() && pixels[end] == pixels[end + 1] {
+                break;
+            }
+            end += 1;
+        }
+
+        bytes.push((end - start - 1) as u8);
+        for pixel in &pixels[start..end] {
+            bytes.extend_from_slice(pixel);
+        }
+        start = end;
+    }
+}
+
+/// Le uma cor gravada: cinza (com alpha em 16 bits), 5-5-5-1, BGR ou BGRA
+fn unpack_color(bytes: &[u8], color_type: TgaColorType) -> [u8; 4] {
+    match (color_type, bytes) {
+        (TgaColorType::Gray, [gray]) => [*gray, *gray, *gray, u8::MAX],
+        (TgaColorType::Gray, [gray, alpha, ..]) => [*gray, *gray, *gray, *alpha],
+        (_, [low, high]) => {
+            let value = u16::from_le_bytes([*low, *high]);
+            [
+                expand_5((value >> 10) as u8 & 0x1F),
+                expand_5((value >> 5) as u8 & 0x1F),
+                expand_5(value as u8 & 0x1F),
+                if value & 0x8000 != 0 { u8::MAX } else { 0 },
+            ]
+        }
+        (_, [blue, green, red]) => [*red, *green, *blue, u8::MAX],
+        (_, [blue, green, red, alpha, ..]) => [*red, *green, *blue, *alpha],
+        _ => [0, 0, 0, u8::MAX],
+    }
+}
+
+fn pack_color(color: &[u8; 4], color_type: TgaColorType, bits: u8) -> Vec<u8> {
+    let [red, green, blue, alpha] = *color;
+
+    match (color_type, bits) {
+        (TgaColorType::Gray, 8) => vec![red],
+        (TgaColorType::Gray, _) => vec![red, alpha],
+        (_, 15 | 16) => {
+            let mut value = (reduce_5(red) << 10) | (reduce_5(green) << 5) | reduce_5(blue);
+            if bits == 16 && alpha >= 128 {
+                value |= 0x8000;
+            }
+            value.to_le_bytes().to_vec()
+        }
+        (_, 24) => vec![blue, green, red],
+        _ => vec![blue, green, red, alpha],
+    }
+}
+
+/// Converte um canal de 5 bits para 8, arredondando `value * 255 / 31`
+fn expand_5(value: u8) -> u8 {
+    ((value as u16 * 255 + 15) / 31) as u8
+}
+
+/// Valor de 5 bits mais proximo de um canal de 8 bits
+fn reduce_5(value: u8) -> u16 {
+    (value as u16 * 31 + 127) / 255
+}
+
+fn rgba(color: &RGB) -> [u8; 4] {
+    [
+        color.red(),
+        color.green(),
+        color.blue(),
+        color.alpha().unwrap_or(u8::MAX),
+    ]
+}
+
+/// Tabela de ate 256 cores com as cores originais primeiro, ou `None` se nao couber
+fn build_palette(original: &[RGB], pixels: &[RGB]) -> Option<Vec<[u8; 4]>> {
+    let mut palette = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+
+    for color in original.iter().chain(pixels).map(rgba) {
+        if !seen.insert(color) {
+            continue;
+        }
+        if palette.len() == MAX_COLORS {
+            return None;
+        }
+        palette.push(color);
+    }
+
+    Some(palette)
+}
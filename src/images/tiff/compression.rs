@@ -0,0 +1,296 @@
+use crate::error::{ImageError, ImageResult};
+use std::collections::HashMap;
+
+// Consts...
+const LZW_CLEAR: u16 = 256;
+const LZW_END: u16 = 257;
+const LZW_FIRST: usize = 258;
+const LZW_MIN_BITS: u32 = 9;
+const LZW_MAX_BITS: u32 = 12;
+const LZW_MAX_CODES: usize = 1 << LZW_MAX_BITS;
+/// O escritor limpa a tabela antes dela encher, como a libtiff, para nao passar de 12 bits
+const LZW_LAST_CODE: usize = LZW_MAX_CODES - 2;
+
+/// Maior quantidade de bytes num pacote PackBits
+const PACKBITS_MAX: usize = 128;
+
+// Functions...
+/// Descompacta um strip ou tile LZW (bits mais significativos primeiro, com o aumento do tamanho
+/// do codigo um codigo antes, como no TIFF 6.0), parando em `limit` bytes
+pub(super) fn lzw_decode(data: &[u8], limit: usize) -> ImageResult<Vec<u8>> {
+    // LZW antigo (antes do TIFF 6.0) grava os bits na ordem inversa e comeca sem o codigo clear
+    if data.len() >= 2 && data[0] == 0 && data[1] & 0x01 != 0 {
+        return Err(ImageError::Unsupported("old-style tiff lzw"));
+    }
+
+    let mut prefixes = vec![0_u16; LZW_MAX_CODES];
+    let mut suffixes = vec![0_u8; LZW_MAX_CODES];
+    let mut firsts = vec![0_u8; LZW_MAX_CODES];
+    let mut lengths = vec![0_usize; LZW_MAX_CODES];
+    for code in 0..256 {
+        suffixes[code] = code as u8;
+        firsts[code] = code as u8;
+        lengths[code] = 1;
+    }
+
+    let mut output = Vec::with_capacity(limit);
+    let mut size = LZW_MIN_BITS;
+    let mut next = LZW_FIRST;
+    let mut previous: Option<usize> = None;
+
+    let mut buffer = 0_u32;
+    let mut count = 0_u32;
+    let mut bytes = data.iter();
+
+    while output.len() < limit {
+        while count < size {
+            let Some(byte) = bytes.next() else {
+                return Ok(output);
+            };
+            buffer = (buffer << 8) | *byte as u32;
+            count += 8;
+        }
+        let code = ((buffer >> (count - size)) & ((1 << size) - 1)) as usize;
+        count -= size;
+
+        if code == LZW_CLEAR as usize {
+            size = LZW_MIN_BITS;
+            next = LZW_FIRST;
+            previous = None;
+            continue;
+        }
+        if code == LZW_END as usize {
+            break;
+        }
+
+        let Some(last) = previous else {
+            if code >= LZW_CLEAR as usize {
+                return Err(ImageError::InvalidData("tiff lzw code is invalid"));
+            }
+            output.push(code as u8);
+            previous = Some(code);
+            continue;
+        };
+
+        let first = if code < next {
+            firsts[code]
+        } else if code == next {
+            firsts[last]
+        } else {
+            return Err(ImageError::InvalidData("tiff lzw code is invalid"));
+        };
+
+        if next < LZW_MAX_CODES {
+            prefixes[next] = last as u16;
+            suffixes[next] = first;
+            firsts[next] = firsts[last];
+            lengths[next] = lengths[last] + 1;
+            next += 1;
+            if next == (1 << size) - 1 && size < LZW_MAX_BITS {
+                size += 1;
+            }
+        }
+
+        // Escreve a sequencia de tras para frente, seguindo os prefixos
+        let start = output.len();
+        output.resize(start + lengths[code], 0);
+        let mut current = code;
+        for slot in output[start..].iter_mut().rev() {
+            *slot = suffixes[current];
+            current = prefixes[current] as usize;
+        }
+        previous = Some(code);
+    }
+
+    output.truncate(limit);
+    Ok(output)
+}
+
+pub(super) fn lzw_encode(data: &[u8]) -> Vec<u8> {
+    let mut writer = CodeWriter::default();
+    let mut table = HashMap::new();
+    let mut size = LZW_MIN_BITS;
+    let mut next = LZW_FIRST as u16;
+    writer.write(LZW_CLEAR, size);
+
+    let Some((first, rest)) = data.split_first() else {
+        writer.write(LZW_END, size);
+        return writer.finish();
+    };
+
+    let mut prefix = *first as u16;
+    for byte in rest {
+        if let Some(code) = table.get(&(prefix, *byte)) {
+            prefix = *code;
+            continue;
+        }
+
+        writer.write(prefix, size);
+        table.insert((prefix, *byte), next);
+        next += 1;
+        if next as usize == 1 << size && size < LZW_MAX_BITS {
+            size += 1;
+        }
+
+        if next as usize == LZW_LAST_CODE {
+            writer.write(LZW_CLEAR, size);
+            table.clear();
+            size = LZW_MIN_BITS;
+            next = LZW_FIRST as u16;
+        }
+        prefix = *byte as u16;
+    }
+
+    writer.write(prefix, size);
+    // O leitor cria mais um codigo ao ler o ultimo, o que pode aumentar o tamanho do fim
+    if next as usize + 1 == 1 << size && size < LZW_MAX_BITS {
+        size += 1;
+    }
+    writer.write(LZW_END, size);
+
+    writer.finish()
+}
+
+/// Expande um strip ou tile PackBits, parando em `limit` bytes
+pub(super) fn packbits_decode(data: &[u8], limit: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(limit);
+    let mut pos = 0;
+
+    while output.len() < limit && pos < data.len() {
+        let header = data[pos] as i8;
+        pos += 1;
+
+        match header {
+            -128 => {}
+            0..=127 => {
+                let end = (pos + header as usize + 1).min(data.len());
+                output.extend_from_slice(&data[pos..end]);
+                pos = end;
+            }
+            _ => {
+                let Some(byte) = data.get(pos) else {
+                    break;
+                };
+                pos += 1;
+                output.extend(std::iter::repeat_n(*byte, (1 - header as isize) as usize));
+            }
+        }
+    }
+
+    output.truncate(limit);
+    output
+}
+
+/// Compacta com PackBits, uma linha por vez para que nenhum pacote cruze linhas
+pub(super) fn packbits_encode(data: &[u8], row_bytes: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len() + data.len() / PACKBITS_MAX + 1);
+
+    for row in data.chunks(row_bytes.max(1)) {
+        let mut start = 0;
+        while start < row.len() {
+            let run = row[start..]
+                .iter()
+                .take(PACKBITS_MAX)
+                .take_while(|byte| **byte == row[start])
+                .count();
+
+            if run > 1 {
+                output.push((1 - run as isize) as u8);
+                output.push(row[start]);
+                start += run;
+                continue;
+            }
+
+            // Junta bytes crus ate comecar uma repeticao
+            let mut end = start + 1;
+            while end < row.len() && end - start < PACKBITS_MAX {
+                if end + 1 < row.len() && row[end] == row[end + 1] {
+                    break;
+                }
+                end += 1;
+            }
+
+            output.push((end - start - 1) as u8);
+            output.extend_from_slice(&row[start..end]);
+            start = end;
+        }
+    }
+
+    output
+}
+
+/// Desfaz o preditor horizontal de uma linha: cada amostra guardava a diferenca para a amostra
+/// do mesmo canal no pixel anterior
+pub(super) fn undo_predictor(row: &mut [u8], channels: usize, bits: u16, big_endian: bool) {
+    match bits {
+        8 => {
+            for i in channels..row.len() {
+                row[i] = row[i].wrapping_add(row[i - channels]);
+            }
+        }
+        16 => {
+            let read = |b: &[u8]| match big_endian {
+                true => u16::from_be_bytes([b[0], b[1]]),
+                false => u16::from_le_bytes([b[0], b[1]]),
+            };
+            let stride = channels * 2;
+            for i in (stride..row.len().saturating_sub(1)).step_by(2) {
+                let value = read(&row[i..]).wrapping_add(read(&row[i - stride..]));
+                let bytes = match big_endian {
+                    true => value.to_be_bytes(),
+                    false => value.to_le_bytes(),
+                };
+                row[i..i + 2].copy_from_slice(&bytes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Aplica o preditor horizontal numa linha de amostras de 8 ou 16 bits (little endian)
+pub(super) fn apply_predictor(row: &mut [u8], channels: usize, bits: u16) {
+    match bits {
+        8 => {
+            for i in (channels..row.len()).rev() {
+                row[i] = row[i].wrapping_sub(row[i - channels]);
+            }
+        }
+        16 => {
+            let stride = channels * 2;
+            for i in (stride..row.len().saturating_sub(1)).step_by(2).rev() {
+                let value = u16::from_le_bytes([row[i], row[i + 1]])
+                    .wrapping_sub(u16::from_le_bytes([row[i - stride], row[i - stride + 1]]));
+                row[i..i + 2].copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        _ => {}
+    }
+}
+
+// Structs...
+/// Junta os codigos LZW em bytes, comecando pelo bit mais significativo
+#[derive(Default)]
+struct CodeWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer = (self.buffer << size) | code as u32;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push((self.buffer >> (self.count - 8)) as u8);
+            self.count -= 8;
+        }
+        self.buffer &= (1 << self.count) - 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push((self.buffer << (8 - self.count)) as u8);
+        }
+        self.bytes
+    }
+}
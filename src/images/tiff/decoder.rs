@@ -0,0 +1,549 @@
+use super::{
+    TiffColorType, TiffCompression,
+    compression::{lzw_decode, packbits_decode, undo_predictor},
+};
+use crate::error::{ImageError, ImageResult};
+use crate::images::{
+    Orientation, RGB,
+    buffer::ImageBuffer,
+    pixel::{Rgba, Samples16},
+    zlib,
+};
+use std::collections::{HashMap, HashSet};
+
+// Consts...
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+const TAG_BITS_PER_SAMPLE: u16 = 258;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_PHOTOMETRIC: u16 = 262;
+const TAG_FILL_ORDER: u16 = 266;
+const TAG_STRIP_OFFSETS: u16 = 273;
+const TAG_ORIENTATION: u16 = 274;
+const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+const TAG_ROWS_PER_STRIP: u16 = 278;
+const TAG_STRIP_BYTE_COUNTS: u16 = 279;
+const TAG_PLANAR_CONFIGURATION: u16 = 284;
+const TAG_PREDICTOR: u16 = 317;
+const TAG_COLOR_MAP: u16 = 320;
+const TAG_TILE_WIDTH: u16 = 322;
+const TAG_TILE_LENGTH: u16 = 323;
+const TAG_TILE_OFFSETS: u16 = 324;
+const TAG_TILE_BYTE_COUNTS: u16 = 325;
+const TAG_EXTRA_SAMPLES: u16 = 338;
+const TAG_SAMPLE_FORMAT: u16 = 339;
+
+/// Maior expansao dos dados comprimidos (um codigo LZW de 12 bits gera ate ~4 mil bytes)
+const MAX_EXPANSION: usize = 4096;
+
+// Structs...
+/// Resultado da leitura de um arquivo TIFF. O formato e o da primeira pagina
+pub(super) struct Decoded {
+    pub pages: Vec<ImageBuffer>,
+    /// Amostras de 16 bits de cada pagina, vazias nas de outras profundidades
+    pub pages16: Vec<Samples16>,
+    pub color_type: TiffColorType,
+    pub bits_per_sample: u16,
+    pub compression: TiffCompression,
+    pub predictor: bool,
+    pub tiled: bool,
+}
+
+/// Formato de uma pagina lida
+struct Page {
+    image: ImageBuffer,
+    samples16: Samples16,
+    color_type: TiffColorType,
+    bits_per_sample: u16,
+    compression: TiffCompression,
+    predictor: bool,
+    tiled: bool,
+}
+
+/// Entrada de um diretorio (IFD): tipo, quantidade e o valor ou a posicao dele
+#[derive(Clone, Copy)]
+struct Entry {
+    kind: u16,
+    count: usize,
+    value: [u8; 4],
+}
+
+/// Diretorio de uma pagina, com as tags lidas na ordem de bytes do arquivo
+struct Ifd<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    entries: HashMap<u16, Entry>,
+    next: usize,
+}
+
+/// Le todas as paginas (IFDs encadeados). Paginas depois da primeira que usam algo nao suportado
+/// sao puladas
+pub(super) fn decode(data: &[u8]) -> ImageResult<Decoded> {
+    let big_endian = match data.get(0..4) {
+        Some([b'I', b'I', 42, 0]) => false,
+        Some([b'M', b'M', 0, 42]) => true,
+        Some([b'I', b'I', 43, 0] | [b'M', b'M', 0, 43]) => {
+            return Err(ImageError::Unsupported("bigtiff"));
+        }
+        Some(_) => return Err(ImageError::InvalidMagic),
+        None => return Err(ImageError::Truncated),
+    };
+
+    let mut offset = read_u32(data, 4, big_endian).ok_or(ImageError::Truncated)? as usize;
+    let mut visited = HashSet::new();
+    let mut pages: Vec<Page> = Vec::new();
+
+    while offset != 0 && visited.insert(offset) {
+        let ifd = match Ifd::read(data, offset, big_endian) {
+            Ok(ifd) => ifd,
+            Err(error) if pages.is_empty() => return Err(error),
+            Err(_) => break,
+        };
+
+        match ifd.decode_page() {
+            Ok(page) => pages.push(page),
+            Err(error) if pages.is_empty() => return Err(error),
+            Err(_) => {}
+        }
+        offset = ifd.next;
+    }
+
+    let Some(first) = pages.first() else {
+        return Err(ImageError::InvalidData("tiff has no pages"));
+    };
+
+    Ok(Decoded {
+        color_type: first.color_type,
+        bits_per_sample: first.bits_per_sample,
+        compression: first.compression,
+        predictor: first.predictor,
+        tiled: first.tiled,
+        pages16: pages.iter().map(|page| page.samples16.clone()).collect(),
+        pages: pages.into_iter().map(|page| page.image).collect(),
+    })
+}
+
+impl<'a> Ifd<'a> {
+    fn read(data: &'a [u8], offset: usize, big_endian: bool) -> ImageResult<Self> {
+        let count = read_u16(data, offset, big_endian).ok_or(ImageError::Truncated)? as usize;
+        let mut entries = HashMap::new();
+
+        for index in 0..count {
+            let pos = offset + 2 + index * 12;
+            let bytes = data.get(pos..pos + 12).ok_or(ImageError::Truncated)?;
+            let tag = read_u16(bytes, 0, big_endian).unwrap_or_default();
+
+            entries.insert(
+                tag,
+                Entry {
+                    kind: read_u16(bytes, 2, big_endian).unwrap_or_default(),
+                    count: read_u32(bytes, 4, big_endian).unwrap_or_default() as usize,
+                    value: [bytes[8], bytes[9], bytes[10], bytes[11]],
+                },
+            );
+        }
+
+        let next = read_u32(data, offset + 2 + count * 12, big_endian).unwrap_or(0) as usize;
+
+        Ok(Self {
+            data,
+            big_endian,
+            entries,
+            next,
+        })
+    }
+
+    /// Valores inteiros de uma tag (BYTE, SHORT ou LONG), `None` se ela nao existir
+    fn values(&self, tag: u16) -> ImageResult<Option<Vec<u64>>> {
+        let Some(entry) = self.entries.get(&tag) else {
+            return Ok(None);
+        };
+
+        let size = match entry.kind {
+            1 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 => 4,
+            _ => return Err(ImageError::InvalidData("tiff tag type is invalid")),
+        };
+        let length = entry
+            .count
+            .checked_mul(size)
+            .ok_or(ImageError::InvalidData("tiff tag count is invalid"))?;
+
+        let bytes = if length <= 4 {
+            &entry.value[..length]
+        } else {
+            let offset = read_u32(&entry.value, 0, self.big_endian).unwrap_or_default() as usize;
+            offset
+                .checked_add(length)
+                .and_then(|end| self.data.get(offset..end))
+                .ok_or(ImageError::Truncated)?
+        };
+
+        let values = bytes
+            .chunks_exact(size)
+            .map(|chunk| match size {
+                1 => chunk[0] as u64,
+                2 => read_u16(chunk, 0, self.big_endian).unwrap_or_default() as u64,
+                _ => read_u32(chunk, 0, self.big_endian).unwrap_or_default() as u64,
+            })
+            .collect();
+
+        Ok(Some(values))
+    }
+
+    /// Primeiro valor de uma tag, ou `default` se ela nao existir
+    fn value(&self, tag: u16, default: u64) -> ImageResult<u64> {
+        Ok(self
+            .values(tag)?
+            .and_then(|values| values.first().copied())
+            .unwrap_or(default))
+    }
+
+    fn required(&self, tag: u16, error: &'static str) -> ImageResult<Vec<u64>> {
+        self.values(tag)?
+            .filter(|values| !values.is_empty())
+            .ok_or(ImageError::InvalidData(error))
+    }
+
+    fn decode_page(&self) -> ImageResult<Page> {
+        let width = self.required(TAG_IMAGE_WIDTH, "tiff is missing the image width")?[0] as usize;
+        let height =
+            self.required(TAG_IMAGE_LENGTH, "tiff is missing the image length")?[0] as usize;
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("tiff dimensions are zero"));
+        }
+
+        let samples = self.value(TAG_SAMPLES_PER_PIXEL, 1)? as usize;
+        let bits_list = self.values(TAG_BITS_PER_SAMPLE)?.unwrap_or(vec![1]);
+        let bits = bits_list.first().copied().unwrap_or(1) as u16;
+        if bits_list.iter().any(|b| *b != bits as u64) {
+            return Err(ImageError::Unsupported(
+                "tiff with different bits per sample",
+            ));
+        }
+        if !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+            return Err(ImageError::UnsupportedBitDepth(bits));
+        }
+        if let Some(formats) = self.values(TAG_SAMPLE_FORMAT)?
+            && formats.iter().any(|format| *format != 1)
+        {
+            return Err(ImageError::Unsupported(
+                "tiff signed or floating point samples",
+            ));
+        }
+
+        let compression_value = self.value(TAG_COMPRESSION, 1)? as u16;
+        let compression = TiffCompression::from_u16(compression_value)
+            .ok_or(ImageError::UnsupportedCompression(compression_value as u32))?;
+
+        let predictor = match self.value(TAG_PREDICTOR, 1)? {
+            2 if matches!(bits, 8 | 16) => true,
+            3 => return Err(ImageError::Unsupported("tiff floating point predictor")),
+            _ => false,
+        };
+
+        // ExtraSamples 1 e alpha pre-multiplicado, 2 e alpha normal, 0 nao e alpha. Sem a tag, a
+        // amostra extra e tratada como alpha normal, como a maioria dos programas grava
+        let extra = self.value(TAG_EXTRA_SAMPLES, 2)?;
+        let photometric = self.value(TAG_PHOTOMETRIC, if samples >= 3 { 2 } else { 1 })?;
+        let color_type = match photometric {
+            0 | 1 if samples >= 2 && matches!(extra, 1 | 2) => TiffColorType::GrayAlpha,
+            0 | 1 if samples >= 1 => TiffColorType::Gray,
+            2 if samples >= 4 && matches!(extra, 1 | 2) => TiffColorType::Rgba,
+            2 if samples >= 3 => TiffColorType::Rgb,
+            3 if samples >= 1 => TiffColorType::Palette,
+            0..=3 => {
+                return Err(ImageError::InvalidData(
+                    "tiff samples per pixel are invalid",
+                ));
+            }
+            _ => return Err(ImageError::Unsupported("tiff photometric interpretation")),
+        };
+
+        let color_map = if color_type == TiffColorType::Palette {
+            let map = self.required(TAG_COLOR_MAP, "tiff palette is missing the color map")?;
+            if map.len() < 3 << bits {
+                return Err(ImageError::InvalidData("tiff color map is too short"));
+            }
+            map
+        } else {
+            Vec::new()
+        };
+
+        let separate = self.value(TAG_PLANAR_CONFIGURATION, 1)? == 2 && samples > 1;
+        let tiled = self.entries.contains_key(&TAG_TILE_WIDTH);
+        let (chunk_width, chunk_height, offsets, counts) = if tiled {
+            let tile_width = self.value(TAG_TILE_WIDTH, 0)? as usize;
+            let tile_height = self.value(TAG_TILE_LENGTH, 0)? as usize;
+            if tile_width == 0 || tile_height == 0 {
+                return Err(ImageError::InvalidData("tiff tile size is zero"));
+            }
+            (
+                tile_width,
+                tile_height,
+                self.required(TAG_TILE_OFFSETS, "tiff is missing the tile offsets")?,
+                self.values(TAG_TILE_BYTE_COUNTS)?,
+            )
+        } else {
+            let rows = (self.value(TAG_ROWS_PER_STRIP, u32::MAX as u64)? as usize).clamp(1, height);
+            (
+                width,
+                rows,
+                self.required(TAG_STRIP_OFFSETS, "tiff is missing the strip offsets")?,
+                self.values(TAG_STRIP_BYTE_COUNTS)?,
+            )
+        };
+
+        let total = width
+            .checked_mul(height)
+            .and_then(|size| size.checked_mul(samples))
+            .ok_or(ImageError::DimensionOverflow)?;
+        if total.saturating_mul(bits as usize) / 8 / MAX_EXPANSION > self.data.len() {
+            return Err(ImageError::Truncated);
+        }
+
+        let channels = if separate { 1 } else { samples };
+        let planes = if separate { samples } else { 1 };
+        let across = width.div_ceil(chunk_width);
+        let down = height.div_ceil(chunk_height);
+        let row_bytes = chunk_width
+            .checked_mul(channels * bits as usize)
+            .ok_or(ImageError::DimensionOverflow)?
+            .div_ceil(8);
+        // Tiles podem ser bem maiores que a imagem; o total descomprimido tambem precisa caber
+        let chunk_bytes = row_bytes
+            .checked_mul(chunk_height)
+            .ok_or(ImageError::DimensionOverflow)?;
+        if chunk_bytes.saturating_mul(across * down * planes) / MAX_EXPANSION > self.data.len() {
+            return Err(ImageError::Truncated);
+        }
+        let reversed = self.value(TAG_FILL_ORDER, 1)? == 2;
+
+        let mut values = vec![0_u16; total];
+        for plane in 0..planes {
+            for row in 0..down {
+                for column in 0..across {
+                    let index = (plane * down + row) * across + column;
+                    let offset = *offsets
+                        .get(index)
+                        .ok_or(ImageError::InvalidData("tiff has too few chunks"))?
+                        as usize;
+
+                    // Strips do fim podem ter menos linhas; tiles sempre tem o tamanho inteiro
+                    let rows = if tiled {
+                        chunk_height
+                    } else {
+                        chunk_height.min(height - row * chunk_height)
+                    };
+                    let expected = row_bytes * rows;
+                    let count = match &counts {
+                        Some(counts) => *counts
+                            .get(index)
+                            .ok_or(ImageError::InvalidData("tiff has too few chunks"))?
+                            as usize,
+                        None if compression == TiffCompression::None => expected,
+                        None => return Err(ImageError::InvalidData("tiff is missing byte counts")),
+                    };
+
+                    let end = offset.saturating_add(count).min(self.data.len());
+                    let raw = self.data.get(offset..end).ok_or(ImageError::Truncated)?;
+                    let mut chunk = self.decompress(raw, compression, expected, reversed)?;
+                    chunk.resize(expected, 0);
+
+                    for (y, line) in chunk.chunks_exact_mut(row_bytes).enumerate() {
+                        if predictor {
+                            undo_predictor(line, channels, bits, self.big_endian);
+                        }
+
+                        let image_y = row * chunk_height + y;
+                        if image_y >= height {
+                            break;
+                        }
+                        for x in 0..chunk_width.min(width - column * chunk_width) {
+                            let image_x = column * chunk_width + x;
+                            for channel in 0..channels {
+                                let sample = x * channels + channel;
+                                let target = (image_y * width + image_x) * samples
+                                    + if separate { plane } else { channel };
+                                values[target] = read_sample(line, sample, bits, self.big_endian);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut samples16 = Samples16::default();
+        let pixels = if bits == 16 && color_type != TiffColorType::Palette {
+            samples16 = Samples16::new(
+                values
+                    .chunks_exact(samples)
+                    .map(|sample| to_rgba16(sample, color_type, photometric, extra))
+                    .collect(),
+            );
+            samples16.narrow(matches!(
+                color_type,
+                TiffColorType::GrayAlpha | TiffColorType::Rgba
+            ))
+        } else {
+            values
+                .chunks_exact(samples)
+                .map(|sample| to_rgb(sample, color_type, bits, photometric, extra, &color_map))
+                .collect()
+        };
+
+        let orientation =
+            Orientation::from_exif(self.value(TAG_ORIENTATION, 1)? as u16).unwrap_or_default();
+
+        Ok(Page {
+            image: ImageBuffer::from_raw(width, height, pixels).with_orientation(orientation),
+            samples16,
+            color_type,
+            bits_per_sample: bits,
+            compression,
+            predictor,
+            tiled,
+        })
+    }
+
+    fn decompress(
+        &self,
+        raw: &[u8],
+        compression: TiffCompression,
+        expected: usize,
+        reversed: bool,
+    ) -> ImageResult<Vec<u8>> {
+        let reversed_bytes;
+        let raw = if reversed {
+            reversed_bytes = raw
+                .iter()
+                .map(|byte| byte.reverse_bits())
+                .collect::<Vec<_>>();
+            &reversed_bytes
+        } else {
+            raw
+        };
+
+        match compression {
+            TiffCompression::None => Ok(raw[..expected.min(raw.len())].to_vec()),
+            TiffCompression::PackBits => Ok(packbits_decode(raw, expected)),
+            TiffCompression::Lzw => lzw_decode(raw, expected),
+            TiffCompression::Deflate => zlib::decompress(raw),
+        }
+    }
+}
+
+// Utils Functions
+fn read_u16(data: &[u8], pos: usize, big_endian: bool) -> Option<u16> {
+    let bytes = [*data.get(pos)?, *data.get(pos + 1)?];
+    Some(match big_endian {
+        true => u16::from_be_bytes(bytes),
+        false => u16::from_le_bytes(bytes),
+    })
+}
+
+fn read_u32(data: &[u8], pos: usize, big_endian: bool) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(pos..pos.checked_add(4)?)?.try_into().ok()?;
+    Some(match big_endian {
+        true => u32::from_be_bytes(bytes),
+        false => u32::from_le_bytes(bytes),
+    })
+}
+
+/// Le a amostra `index` de uma linha; amostras menores que um byte comecam no bit mais alto
+fn read_sample(line: &[u8], index: usize, bits: u16, big_endian: bool) -> u16 {
+    match bits {
+        16 => read_u16(line, index * 2, big_endian).unwrap_or_default(),
+        8 => line[index] as u16,
+        _ => {
+            let bit = index * bits as usize;
+            let shift = 8 - bits as usize - bit % 8;
+            ((line[bit / 8] >> shift) & ((1 << bits) - 1)) as u16
+        }
+    }
+}
+
+fn scale(value: u16, bits: u16) -> u8 {
+    match bits {
+        16 => ((value as u32 * 255 + 32767) / 65535) as u8,
+        8 => value as u8,
+        bits => (value as u32 * 255 / ((1 << bits) - 1)) as u8,
+    }
+}
+
+fn to_rgb(
+    sample: &[u16],
+    color_type: TiffColorType,
+    bits: u16,
+    photometric: u64,
+    extra: u64,
+    color_map: &[u64],
+) -> RGB {
+    let (mut red, mut green, mut blue, alpha) = match color_type {
+        TiffColorType::Gray | TiffColorType::GrayAlpha => {
+            let mut gray = scale(sample[0], bits);
+            if photometric == 0 {
+                gray = u8::MAX - gray;
+            }
+            let alpha = (color_type == TiffColorType::GrayAlpha).then(|| scale(sample[1], bits));
+            (gray, gray, gray, alpha)
+        }
+        TiffColorType::Rgb | TiffColorType::Rgba => (
+            scale(sample[0], bits),
+            scale(sample[1], bits),
+            scale(sample[2], bits),
+            (color_type == TiffColorType::Rgba).then(|| scale(sample[3], bits)),
+        ),
+        TiffColorType::Palette => {
+            let size = 1_usize << bits;
+            let color =
+                |channel: usize| scale(color_map[channel * size + sample[0] as usize] as u16, 16);
+            (color(0), color(1), color(2), None)
+        }
+    };
+
+    // Alpha pre-multiplicado: divide as cores de volta
+    if let Some(alpha) = alpha
+        && extra == 1
+        && alpha > 0
+    {
+        let restore =
+            |value: u8| ((value as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8;
+        red = restore(red);
+        green = restore(green);
+        blue = restore(blue);
+    }
+
+    RGB::new(red, green, blue, alpha)
+}
+
+/// Cor de 16 bits de uma amostra (tipos sem paleta), com o alpha pre-multiplicado desfeito
+fn to_rgba16(sample: &[u16], color_type: TiffColorType, photometric: u64, extra: u64) -> Rgba<u16> {
+    let (mut color, alpha) = match color_type {
+        TiffColorType::Gray | TiffColorType::GrayAlpha => {
+            let gray = match photometric {
+                0 => u16::MAX - sample[0],
+                _ => sample[0],
+            };
+            let alpha = (color_type == TiffColorType::GrayAlpha).then(|| sample[1]);
+            ([gray; 3], alpha)
+        }
+        _ => (
+            [sample[0], sample[1], sample[2]],
+            (color_type == TiffColorType::Rgba).then(|| sample[3]),
+        ),
+    };
+
+    if let Some(alpha) = alpha
+        && extra == 1
+        && alpha > 0
+    {
+        color = color.map(|value| {
+            ((value as u32 * 65535 + alpha as u32 / 2) / alpha as u32).min(65535) as u16
+        });
+    }
+
+    let [red, green, blue] = color;
+    Rgba([red, green, blue, alpha.unwrap_or(u16::MAX)])
+}
@@ -0,0 +1,273 @@
+use super::{
+    TiffColorType, TiffCompression,
+    compression::{apply_predictor, lzw_encode, packbits_encode},
+};
+use crate::error::{ImageError, ImageResult};
+use crate::images::{
+    Image, Orientation, RGB,
+    buffer::ImageBuffer,
+    pixel::{Rgba, Samples16},
+    zlib,
+};
+
+// Consts...
+/// Tamanho aproximado de cada strip, como a libtiff faz (8 KiB)
+const STRIP_SIZE: usize = 8192;
+
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_RATIONAL: u16 = 5;
+
+// Structs...
+/// Entrada do diretorio ja serializada (little endian)
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    bytes: Vec<u8>,
+}
+
+/// Formato usado para gravar uma pagina
+struct Layout {
+    channels: usize,
+    bits: u16,
+    alpha: bool,
+    gray: bool,
+}
+
+/// Grava todas as paginas num arquivo little endian: os strips de cada pagina e em seguida o
+/// diretorio dela, que aponta para o diretorio da proxima. Em 16 bits usa as amostras de
+/// `pages16` que ainda correspondem aos pixels
+pub(super) fn encode(
+    pages: &[ImageBuffer],
+    pages16: &[Samples16],
+    color_type: TiffColorType,
+    bits_per_sample: u16,
+    compression: TiffCompression,
+    predictor: bool,
+) -> ImageResult<Vec<u8>> {
+    let mut output = b"II*\0".to_vec();
+    let mut link = output.len();
+    output.extend_from_slice(&[0; 4]);
+
+    let empty = Samples16::default();
+    for (index, page) in pages.iter().enumerate() {
+        let samples16 = pages16.get(index).unwrap_or(&empty);
+        let layout = output_layout(page, samples16, color_type, bits_per_sample);
+        let width = page.widht();
+        let height = page.height();
+        let row_bytes = (width * layout.channels * layout.bits as usize).div_ceil(8);
+        let rows_per_strip = (STRIP_SIZE / row_bytes.max(1)).clamp(1, height);
+        let predictor = predictor
+            && matches!(compression, TiffCompression::Lzw | TiffCompression::Deflate)
+            && layout.bits >= 8;
+
+        let pixels = page.get_pixels();
+        let wide = match layout.bits {
+            16 => samples16.widen(pixels),
+            _ => Vec::new(),
+        };
+
+        let mut offsets = Vec::new();
+        let mut counts = Vec::new();
+        for first in (0..height).step_by(rows_per_strip) {
+            let mut strip = Vec::with_capacity(row_bytes * rows_per_strip);
+            for y in first..(first + rows_per_strip).min(height) {
+                let start = strip.len();
+                let row = y * width..(y + 1) * width;
+                match layout.bits {
+                    16 => write_row16(&mut strip, &wide[row], &layout),
+                    _ => write_row(&mut strip, &pixels[row], &layout),
+                }
+                if predictor {
+                    apply_predictor(&mut strip[start..], layout.channels, layout.bits);
+                }
+            }
+
+            let data = match compression {
+                TiffCompression::None => strip,
+                TiffCompression::PackBits => packbits_encode(&strip, row_bytes),
+                TiffCompression::Lzw => lzw_encode(&strip),
+                TiffCompression::Deflate => zlib::compress(&strip),
+            };
+
+            offsets.push(to_u32(output.len())?);
+            counts.push(to_u32(data.len())?);
+            output.extend_from_slice(&data);
+            if output.len() % 2 == 1 {
+                output.push(0);
+            }
+        }
+
+        let mut entries = vec![
+            long(256, &[to_u32(width)?]),
+            long(257, &[to_u32(height)?]),
+            short(258, &vec![layout.bits; layout.channels]),
+            short(259, &[compression.to_u16()]),
+            short(262, &[if layout.gray { 1 } else { 2 }]),
+            long(273, &offsets),
+            short(277, &[layout.channels as u16]),
+            long(278, &[rows_per_strip as u32]),
+            long(279, &counts),
+            rational(282, 72, 1),
+            rational(283, 72, 1),
+            short(284, &[1]),
+            short(296, &[2]),
+        ];
+        if page.orientation() != Orientation::Normal {
+            entries.push(short(274, &[page.orientation().to_exif()]));
+        }
+        if predictor {
+            entries.push(short(317, &[2]));
+        }
+        if layout.alpha {
+            entries.push(short(338, &[2]));
+        }
+        entries.sort_by_key(|entry| entry.tag);
+
+        // Diretorio: quantidade, entradas de 12 bytes, proximo diretorio e os valores grandes
+        let ifd = output.len();
+        output[link..link + 4].copy_from_slice(&to_u32(ifd)?.to_le_bytes());
+        let mut extra = ifd + 2 + entries.len() * 12 + 4;
+        let mut values = Vec::new();
+
+        output.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for entry in &entries {
+            output.extend_from_slice(&entry.tag.to_le_bytes());
+            output.extend_from_slice(&entry.kind.to_le_bytes());
+            output.extend_from_slice(&entry.count.to_le_bytes());
+            if entry.bytes.len() <= 4 {
+                let mut inline = [0; 4];
+                inline[..entry.bytes.len()].copy_from_slice(&entry.bytes);
+                output.extend_from_slice(&inline);
+            } else {
+                output.extend_from_slice(&to_u32(extra)?.to_le_bytes());
+                values.extend_from_slice(&entry.bytes);
+                if entry.bytes.len() % 2 == 1 {
+                    values.push(0);
+                }
+                extra = ifd + 2 + entries.len() * 12 + 4 + values.len();
+            }
+        }
+
+        link = output.len();
+        output.extend_from_slice(&[0; 4]);
+        output.extend_from_slice(&values);
+    }
+
+    to_u32(output.len())?;
+    Ok(output)
+}
+
+// Utils Functions
+/// Escolhe o formato mais proximo do pedido que ainda represente os pixels sem perdas
+fn output_layout(
+    page: &ImageBuffer,
+    samples16: &Samples16,
+    color_type: TiffColorType,
+    bits: u16,
+) -> Layout {
+    let pixels = page.get_pixels();
+    let alpha = pixels.iter().any(|c| c.alpha().is_some());
+    // Em 16 bits, cores diferentes podem ficar iguais quando reduzidas para 8
+    let is_gray = match samples16.get(pixels) {
+        Some(samples) if bits == 16 => samples.iter().all(|c| c.0[0] == c.0[1] && c.0[1] == c.0[2]),
+        _ => pixels
+            .iter()
+            .all(|c| c.red() == c.green() && c.green() == c.blue()),
+    };
+    let gray = is_gray && matches!(color_type, TiffColorType::Gray | TiffColorType::GrayAlpha);
+
+    let bits = match bits {
+        1 if gray && !alpha && pixels.iter().all(|c| matches!(c.red(), 0 | 255)) => 1,
+        16 => 16,
+        _ => 8,
+    };
+    let channels = match gray {
+        true => 1,
+        false => 3,
+    } + alpha as usize;
+
+    Layout {
+        channels,
+        bits,
+        alpha,
+        gray,
+    }
+}
+
+fn write_row(output: &mut Vec<u8>, row: &[RGB], layout: &Layout) {
+    if layout.bits == 1 {
+        for byte in row.chunks(8) {
+            let packed = byte
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.red() == u8::MAX)
+                .fold(0_u8, |acc, (i, _)| acc | (0x80 >> i));
+            output.push(packed);
+        }
+        return;
+    }
+
+    for c in row {
+        let samples = [c.red(), c.green(), c.blue()];
+        let color = match layout.gray {
+            true => &samples[..1],
+            false => &samples[..],
+        };
+        let alpha = layout.alpha.then(|| c.alpha().unwrap_or(u8::MAX));
+
+        output.extend(color.iter().copied().chain(alpha));
+    }
+}
+
+fn write_row16(output: &mut Vec<u8>, row: &[Rgba<u16>], layout: &Layout) {
+    for c in row {
+        let [red, green, blue, alpha] = c.0;
+        let samples = [red, green, blue];
+        let color = match layout.gray {
+            true => &samples[..1],
+            false => &samples[..],
+        };
+        let alpha = layout.alpha.then_some(alpha);
+
+        for sample in color.iter().copied().chain(alpha) {
+            output.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+}
+
+fn short(tag: u16, values: &[u16]) -> Entry {
+    Entry {
+        tag,
+        kind: TYPE_SHORT,
+        count: values.len() as u32,
+        bytes: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    }
+}
+
+fn long(tag: u16, values: &[u32]) -> Entry {
+    Entry {
+        tag,
+        kind: TYPE_LONG,
+        count: values.len() as u32,
+        bytes: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    }
+}
+
+fn rational(tag: u16, numerator: u32, denominator: u32) -> Entry {
+    Entry {
+        tag,
+        kind: TYPE_RATIONAL,
+        count: 1,
+        bytes: [numerator, denominator]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect(),
+    }
+}
+
+/// Offsets e tamanhos do TIFF classico tem 32 bits
+fn to_u32(value: usize) -> ImageResult<u32> {
+    u32::try_from(value).map_err(|_| ImageError::Unsupported("tiff larger than 4 GiB"))
+}
@@ -0,0 +1,373 @@
+use super::{
+    Format, Image, Orientation, RGB,
+    buffer::ImageBuffer,
+    pixel::{Rgba, Samples16},
+};
+use crate::error::{ImageError, ImageResult};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+mod compression;
+mod decoder;
+mod encoder;
+
+// Enums...
+/// Enum que representa a compressao dos strips e tiles (tag `Compression`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TiffCompression {
+    None,
+    /// RLE da Apple, bom para imagens com areas de cor unica
+    PackBits,
+    Lzw,
+    /// zlib, como no PNG
+    Deflate,
+}
+
+impl TiffCompression {
+    fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(Self::None),
+            5 => Some(Self::Lzw),
+            8 | 32946 => Some(Self::Deflate),
+            32773 => Some(Self::PackBits),
+            _ => None,
+        }
+    }
+
+    fn to_u16(self) -> u16 {
+        match self {
+            Self::None => 1,
+            Self::Lzw => 5,
+            Self::Deflate => 8,
+            Self::PackBits => 32773,
+        }
+    }
+}
+
+/// Enum que representa os canais de uma pagina, vindos de `PhotometricInterpretation`,
+/// `SamplesPerPixel` e `ExtraSamples`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TiffColorType {
+    Gray,
+    GrayAlpha,
+    Rgb,
+    Rgba,
+    /// Indices para a tabela `ColorMap`
+    Palette,
+}
+
+// Structs...
+/// Struct para representa um arquivo TIFF com todas as paginas. Como `Image` ela expoe a primeira
+/// pagina; o tipo de cor, os bits e a compressao valem para a escrita de todas
+pub struct Tiff {
+    pages: Vec<ImageBuffer>,
+    /// Amostras de 16 bits de cada pagina, vazias quando a pagina nao tem 16 bits
+    pages16: Vec<Samples16>,
+    color_type: TiffColorType,
+    bits_per_sample: u16,
+    compression: TiffCompression,
+    predictor: bool,
+    tiled: bool,
+}
+
+impl Tiff {
+    /// Cria um TIFF RGB de 8 bits com LZW e preditor horizontal a partir de pixels em ordem de
+    /// linhas. Imagens com alpha sao salvas como RGBA
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<Self> {
+        Self::from_pages(vec![ImageBuffer::from_pixels(width, height, pixels)?])
+    }
+
+    /// Cria um TIFF de varias paginas, uma por imagem
+    pub fn from_pages(pages: Vec<ImageBuffer>) -> ImageResult<Self> {
+        if pages.is_empty() {
+            return Err(ImageError::InvalidData("tiff has no pages"));
+        }
+        for page in &pages {
+            validate_dimensions(page.widht(), page.height())?;
+        }
+
+        Ok(Self {
+            pages16: vec![Samples16::default(); pages.len()],
+            pages,
+            color_type: TiffColorType::Rgb,
+            bits_per_sample: 8,
+            compression: TiffCompression::Lzw,
+            predictor: true,
+            tiled: false,
+        })
+    }
+
+    /// Cria um TIFF RGB de 16 bits por canal. Imagens com algum pixel nao opaco sao salvas como
+    /// RGBA
+    pub fn from_rgba16(width: usize, height: usize, pixels: Vec<Rgba<u16>>) -> ImageResult<Self> {
+        let alpha = pixels.iter().any(|color| color.0[3] != u16::MAX);
+        let samples16 = Samples16::new(pixels);
+
+        let mut image = Self::from_pixels(width, height, samples16.narrow(alpha))?;
+        image.bits_per_sample = 16;
+        image.pages16[0] = samples16;
+
+        Ok(image)
+    }
+
+    /// Primeira pagina com 16 bits por canal (veja `page_rgba16`)
+    pub fn to_rgba16(&self) -> ImageBuffer<Rgba<u16>> {
+        widen_page(self.first(), &self.pages16[0])
+    }
+
+    /// Pagina com 16 bits por canal. Numa pagina de 16 bits sao as amostras do arquivo, ate o
+    /// primeiro acesso mutavel aos pixels dela (inclusive `pages_mut`); nas demais, os pixels de
+    /// 8 bits expandidos. Filtros aplicados neste buffer mantem os 16 bits ao voltar com
+    /// `Tiff::try_from`
+    pub fn page_rgba16(&self, index: usize) -> Option<ImageBuffer<Rgba<u16>>> {
+        Some(widen_page(self.pages.get(index)?, self.pages16.get(index)?))
+    }
+
+    pub fn pages(&self) -> &[ImageBuffer] {
+        &self.pages
+    }
+
+    /// Paginas para edicao. As amostras de 16 bits de todas elas deixam de valer
+    pub fn pages_mut(&mut self) -> &mut [ImageBuffer] {
+        self.pages16.iter_mut().for_each(Samples16::clear);
+        &mut self.pages
+    }
+
+    pub fn into_pages(self) -> Vec<ImageBuffer> {
+        self.pages
+    }
+
+    pub fn push_page(&mut self, page: ImageBuffer) -> ImageResult<()> {
+        validate_dimensions(page.widht(), page.height())?;
+        self.pages.push(page);
+        self.pages16.push(Samples16::default());
+
+        Ok(())
+    }
+
+    pub fn color_type(&self) -> TiffColorType {
+        self.color_type
+    }
+
+    /// Define o tipo usado ao salvar. Se os pixels nao couberem nele (cores num cinza), a
+    /// escrita usa o tipo mais proximo que os representa sem perdas. Paletas sao salvas em RGB
+    pub fn set_color_type(&mut self, color_type: TiffColorType) {
+        self.color_type = color_type;
+    }
+
+    pub fn bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+
+    /// Define os bits de cada amostra: 1 (so cinza em preto e branco), 8 ou 16
+    pub fn set_bits_per_sample(&mut self, bits_per_sample: u16) {
+        self.bits_per_sample = bits_per_sample;
+    }
+
+    pub fn compression(&self) -> TiffCompression {
+        self.compression
+    }
+
+    pub fn set_compression(&mut self, compression: TiffCompression) {
+        self.compression = compression;
+    }
+
+    pub fn predictor(&self) -> bool {
+        self.predictor
+    }
+
+    /// Define se o preditor horizontal e usado com LZW e Deflate (ignorado com 1 bit)
+    pub fn set_predictor(&mut self, value: bool) {
+        self.predictor = value;
+    }
+
+    /// Indica se o arquivo lido guardava a primeira pagina em tiles. A escrita sempre usa strips
+    pub fn tiled(&self) -> bool {
+        self.tiled
+    }
+
+    /// `from_pages` e a leitura nunca criam um TIFF sem paginas, e elas nao podem ser removidas
+    fn first(&self) -> &ImageBuffer {
+        &self.pages[0]
+    }
+
+    fn first_mut(&mut self) -> &mut ImageBuffer {
+        &mut self.pages[0]
+    }
+
+    fn read_tiff(data: &[u8]) -> ImageResult<Self> {
+        let decoded = decoder::decode(data)?;
+
+        Ok(Self {
+            pages: decoded.pages,
+            pages16: decoded.pages16,
+            color_type: decoded.color_type,
+            bits_per_sample: decoded.bits_per_sample,
+            compression: decoded.compression,
+            predictor: decoded.predictor,
+            tiled: decoded.tiled,
+        })
+    }
+}
+
+impl From<Tiff> for ImageBuffer {
+    fn from(image: Tiff) -> Self {
+        let mut pages = image.pages.into_iter();
+
+        match pages.next() {
+            Some(page) => page,
+            None => ImageBuffer::from_raw(0, 0, Vec::new()),
+        }
+    }
+}
+
+impl From<Tiff> for ImageBuffer<Rgba<u16>> {
+    fn from(image: Tiff) -> Self {
+        image.to_rgba16()
+    }
+}
+
+impl TryFrom<ImageBuffer<Rgba<u16>>> for Tiff {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer<Rgba<u16>>) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = Tiff::from_rgba16(width, height, buffer.into_pixels())?;
+        image.set_orientation(orientation);
+
+        Ok(image)
+    }
+}
+
+impl TryFrom<ImageBuffer> for Tiff {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        Tiff::from_pages(vec![buffer])
+    }
+}
+
+impl Image for Tiff {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_tiff(&data)
+    }
+
+    /// Grava todas as paginas em strips, com a orientacao de cada uma na tag `Orientation`
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        let bytes = encoder::encode(
+            &self.pages,
+            &self.pages16,
+            self.color_type,
+            self.bits_per_sample,
+            self.compression,
+            self.predictor,
+        )?;
+        writer.write_all(&bytes)?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    /// Troca so a primeira pagina, que e a exposta como `Image`
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        validate_dimensions(width, height)?;
+
+        self.pages16[0].clear();
+        self.first_mut().set_pixels(width, height, pixels)
+    }
+
+    fn widht(&self) -> usize {
+        self.first().widht()
+    }
+
+    fn height(&self) -> usize {
+        self.first().height()
+    }
+
+    fn format(&self) -> Format {
+        Format::TIFF
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        let channels = match self.color_type {
+            TiffColorType::Gray | TiffColorType::Palette => 1,
+            TiffColorType::GrayAlpha => 2,
+            TiffColorType::Rgb => 3,
+            TiffColorType::Rgba => 4,
+        };
+        channels * self.bits_per_sample
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        self.pages16[0].clear();
+        self.first_mut().pixels()
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        self.first().get_pixels()
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        self.pages16[0].clear();
+        self.first_mut().pixel(x, y)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        self.first().get_pixel(x, y)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        self.pages16[0].clear();
+        self.first_mut().slice_pixels(range)
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        self.first().get_slice_pixels(range)
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.first().orientation()
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.first_mut().set_orientation(orientation);
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (orientation, width, height) = (self.orientation(), self.widht(), self.height());
+        self.pages16[0].orient(orientation, width, height);
+        self.first_mut().normalize_orientation();
+    }
+}
+
+// Utils Functions
+fn validate_dimensions(width: usize, height: usize) -> ImageResult<()> {
+    if width == 0 || height == 0 {
+        return Err(ImageError::InvalidData("tiff dimensions are zero"));
+    }
+    if width > u32::MAX as usize || height > u32::MAX as usize {
+        return Err(ImageError::DimensionOverflow);
+    }
+
+    Ok(())
+}
+
+/// Pagina com as amostras de 16 bits, ou com os pixels de 8 bits expandidos quando nao tem
+fn widen_page(page: &ImageBuffer, samples16: &Samples16) -> ImageBuffer<Rgba<u16>> {
+    let pixels = samples16.widen(page.get_pixels());
+
+    ImageBuffer::from_raw(page.widht(), page.height(), pixels).with_orientation(page.orientation())
+}
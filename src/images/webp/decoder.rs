@@ -0,0 +1,474 @@
+use super::huffman::HuffmanCode;
+use super::{
+    CODE_LENGTH_PREFIXES, COLOR_CACHE_MULTIPLIER, DISTANCE_MAP, MAX_COLOR_CACHE_BITS, NUM_LITERALS,
+    VP8L_SIGNATURE, subsample,
+};
+use crate::error::{ImageError, ImageResult};
+use crate::images::zlib::BitReader;
+
+// Consts...
+/// Tamanho dos alfabetos vermelho, azul e alpha
+const CHANNEL_ALPHABET: usize = 256;
+/// Tamanho do alfabeto das distancias
+const DISTANCE_ALPHABET: usize = 40;
+
+// Enums...
+/// Transformacao lida do fluxo, com a largura em que ela foi aplicada
+enum Transform {
+    Predictor {
+        xsize: usize,
+        bits: u32,
+        modes: Vec<u32>,
+    },
+    Color {
+        xsize: usize,
+        bits: u32,
+        elements: Vec<u32>,
+    },
+    SubtractGreen,
+    ColorIndexing {
+        xsize: usize,
+        bits: u32,
+        palette: Vec<u32>,
+    },
+}
+
+// Structs...
+/// Imagem VP8L lida: dimensoes, pixels em ARGB e a dica de alpha do cabecalho
+pub(super) struct Decoded {
+    pub width: usize,
+    pub height: usize,
+    pub argb: Vec<u32>,
+    pub alpha: bool,
+}
+
+/// Os cinco codigos de um grupo: verde (com comprimentos e cache), vermelho, azul, alpha e
+/// distancia
+struct HuffmanGroup {
+    green: HuffmanCode,
+    red: HuffmanCode,
+    blue: HuffmanCode,
+    alpha: HuffmanCode,
+    distance: HuffmanCode,
+}
+
+/// Imagem de entropia: o grupo de codigos usado em cada bloco da imagem principal
+struct EntropyImage {
+    bits: u32,
+    xsize: usize,
+    groups: Vec<usize>,
+}
+
+/// Cache das cores usadas recentemente, indexado por um hash da cor
+pub(super) struct ColorCache {
+    colors: Vec<u32>,
+    shift: u32,
+}
+
+impl ColorCache {
+    pub(super) fn new(bits: u32) -> Self {
+        Self {
+            colors: vec![0; 1 << bits],
+            shift: 32 - bits,
+        }
+    }
+
+    pub(super) fn index(&self, argb: u32) -> usize {
+        (argb.wrapping_mul(COLOR_CACHE_MULTIPLIER) >> self.shift) as usize
+    }
+
+    pub(super) fn insert(&mut self, argb: u32) {
+        let index = self.index(argb);
+        self.colors[index] = argb;
+    }
+
+    pub(super) fn get(&self, index: usize) -> u32 {
+        self.colors[index]
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.colors.len()
+    }
+}
+
+/// Le o bitstream VP8L (o conteudo do chunk `VP8L`)
+pub(super) fn decode(data: &[u8]) -> ImageResult<Decoded> {
+    if data.len() < 5 {
+        return Err(ImageError::Truncated);
+    }
+    if data[0] != VP8L_SIGNATURE {
+        return Err(ImageError::InvalidMagic);
+    }
+
+    let mut reader = BitReader::new(&data[1..]);
+    let width = reader.read_bits(14)? as usize + 1;
+    let height = reader.read_bits(14)? as usize + 1;
+    let alpha = reader.read_bits(1)? == 1;
+    if reader.read_bits(3)? != 0 {
+        return Err(ImageError::InvalidData("webp VP8L version is not 0"));
+    }
+
+    // Transformacoes, cada tipo no maximo uma vez. A color indexing pode reduzir a largura
+    let mut transforms = Vec::new();
+    let mut seen = [false; 4];
+    let mut xsize = width;
+    while reader.read_bits(1)? == 1 {
+        let kind = reader.read_bits(2)? as usize;
+        if seen[kind] {
+            return Err(ImageError::InvalidData("webp transform is repeated"));
+        }
+        seen[kind] = true;
+
+        let transform = match kind {
+            0 | 1 => {
+                let bits = reader.read_bits(3)? + 2;
+                let data = decode_image_stream(
+                    &mut reader,
+                    subsample(xsize, bits),
+                    subsample(height, bits),
+                    false,
+                )?;
+                match kind {
+                    0 => Transform::Predictor {
+                        xsize,
+                        bits,
+                        modes: data,
+                    },
+                    _ => Transform::Color {
+                        xsize,
+                        bits,
+                        elements: data,
+                    },
+                }
+            }
+            2 => Transform::SubtractGreen,
+            _ => {
+                let count = reader.read_bits(8)? as usize + 1;
+                let bits = match count {
+                    0..=2 => 3,
+                    3..=4 => 2,
+                    5..=16 => 1,
+                    _ => 0,
+                };
+                let mut palette = decode_image_stream(&mut reader, count, 1, false)?;
+                for index in 1..palette.len() {
+                    palette[index] = add_pixels(palette[index], palette[index - 1]);
+                }
+
+                let transform = Transform::ColorIndexing {
+                    xsize,
+                    bits,
+                    palette,
+                };
+                xsize = subsample(xsize, bits);
+                transform
+            }
+        };
+        transforms.push(transform);
+    }
+
+    let mut argb = decode_image_stream(&mut reader, xsize, height, true)?;
+    for transform in transforms.iter().rev() {
+        argb = match transform {
+            Transform::Predictor { xsize, bits, modes } => {
+                inverse_predictor(&mut argb, *xsize, *bits, modes);
+                argb
+            }
+            Transform::Color {
+                xsize,
+                bits,
+                elements,
+            } => {
+                inverse_color(&mut argb, *xsize, *bits, elements);
+                argb
+            }
+            Transform::SubtractGreen => {
+                for pixel in argb.iter_mut() {
+                    let green = (*pixel >> 8) & 0xff;
+                    let red = (((*pixel >> 16) + green) & 0xff) << 16;
+                    let blue = ((*pixel & 0xff) + green) & 0xff;
+                    *pixel = (*pixel & 0xff00ff00) | red | blue;
+                }
+                argb
+            }
+            Transform::ColorIndexing {
+                xsize,
+                bits,
+                palette,
+            } => inverse_color_indexing(&argb, *xsize, height, *bits, palette),
+        };
+    }
+
+    Ok(Decoded {
+        width,
+        height,
+        argb,
+        alpha,
+    })
+}
+
+// Utils Functions
+/// Le uma imagem codificada por entropia. So a imagem principal (`main`) pode ter imagem de
+/// entropia com varios grupos de codigos
+fn decode_image_stream(
+    reader: &mut BitReader,
+    xsize: usize,
+    ysize: usize,
+    main: bool,
+) -> ImageResult<Vec<u32>> {
+    let mut cache = match reader.read_bits(1)? {
+        0 => None,
+        _ => {
+            let bits = reader.read_bits(4)?;
+            if !(1..=MAX_COLOR_CACHE_BITS).contains(&bits) {
+                return Err(ImageError::InvalidData("webp color cache size is invalid"));
+            }
+            Some(ColorCache::new(bits))
+        }
+    };
+
+    let entropy = match main && reader.read_bits(1)? == 1 {
+        true => {
+            let bits = reader.read_bits(3)? + 2;
+            let xsize = subsample(xsize, bits);
+            let groups = decode_image_stream(reader, xsize, subsample(ysize, bits), false)?
+                .into_iter()
+                .map(|pixel| ((pixel >> 8) & 0xffff) as usize)
+                .collect();
+            Some(EntropyImage {
+                bits,
+                xsize,
+                groups,
+            })
+        }
+        false => None,
+    };
+
+    let group_count = entropy
+        .as_ref()
+        .map_or(1, |entropy| entropy.groups.iter().max().unwrap_or(&0) + 1);
+    let cache_size = cache.as_ref().map_or(0, ColorCache::len);
+    let mut groups = Vec::with_capacity(group_count);
+    for _ in 0..group_count {
+        groups.push(HuffmanGroup {
+            green: HuffmanCode::read(reader, NUM_LITERALS + CODE_LENGTH_PREFIXES + cache_size)?,
+            red: HuffmanCode::read(reader, CHANNEL_ALPHABET)?,
+            blue: HuffmanCode::read(reader, CHANNEL_ALPHABET)?,
+            alpha: HuffmanCode::read(reader, CHANNEL_ALPHABET)?,
+            distance: HuffmanCode::read(reader, DISTANCE_ALPHABET)?,
+        });
+    }
+
+    let total = xsize * ysize;
+    let mut pixels: Vec<u32> = Vec::new();
+    while pixels.len() < total {
+        let position = pixels.len();
+        let group = match &entropy {
+            Some(entropy) => {
+                let (x, y) = (position % xsize, position / xsize);
+                let block = (y >> entropy.bits) * entropy.xsize + (x >> entropy.bits);
+                &groups[entropy.groups[block]]
+            }
+            None => &groups[0],
+        };
+
+        let green = group.green.decode(reader)? as usize;
+        if green < NUM_LITERALS {
+            let red = group.red.decode(reader)? as u32;
+            let blue = group.blue.decode(reader)? as u32;
+            let alpha = group.alpha.decode(reader)? as u32;
+            let argb = (alpha << 24) | (red << 16) | ((green as u32) << 8) | blue;
+
+            pixels.push(argb);
+            if let Some(cache) = &mut cache {
+                cache.insert(argb);
+            }
+        } else if green < NUM_LITERALS + CODE_LENGTH_PREFIXES {
+            let length = prefix_value(reader, green - NUM_LITERALS)?;
+            let prefix = group.distance.decode(reader)? as usize;
+            let code = prefix_value(reader, prefix)?;
+            let distance = plane_to_distance(xsize, code);
+
+            if distance > position || length > total - position {
+                return Err(ImageError::InvalidData(
+                    "webp backward reference is invalid",
+                ));
+            }
+            for index in position..position + length {
+                let argb = pixels[index - distance];
+                pixels.push(argb);
+                if let Some(cache) = &mut cache {
+                    cache.insert(argb);
+                }
+            }
+        } else {
+            let cache = cache
+                .as_mut()
+                .ok_or(ImageError::InvalidData("webp color cache is missing"))?;
+            let argb = cache.get(green - NUM_LITERALS - CODE_LENGTH_PREFIXES);
+
+            pixels.push(argb);
+            cache.insert(argb);
+        }
+    }
+
+    Ok(pixels)
+}
+
+/// Valor de um comprimento ou distancia: o prefixo escolhe a faixa e os bits extras a posicao
+fn prefix_value(reader: &mut BitReader, prefix: usize) -> ImageResult<usize> {
+    if prefix < 4 {
+        return Ok(prefix + 1);
+    }
+
+    let extra = (prefix as u32 - 2) >> 1;
+    let offset = (2 + (prefix & 1)) << extra;
+    Ok(offset + reader.read_bits(extra)? as usize + 1)
+}
+
+/// Converte o codigo de distancia em distancia linear. Os 120 primeiros codigos sao vizinhos
+/// proximos em 2D
+fn plane_to_distance(xsize: usize, code: usize) -> usize {
+    if code > DISTANCE_MAP.len() {
+        return code - DISTANCE_MAP.len();
+    }
+
+    let (dx, dy) = DISTANCE_MAP[code - 1];
+    (dx as isize + dy as isize * xsize as isize).max(1) as usize
+}
+
+fn inverse_predictor(pixels: &mut [u32], xsize: usize, bits: u32, modes: &[u32]) {
+    let blocks_per_row = subsample(xsize, bits);
+
+    for position in 0..pixels.len() {
+        let (x, y) = (position % xsize, position / xsize);
+        let prediction = match (x, y) {
+            (0, 0) => 0xff000000,
+            (_, 0) => pixels[position - 1],
+            (0, _) => pixels[position - xsize],
+            _ => {
+                let mode = (modes[(y >> bits) * blocks_per_row + (x >> bits)] >> 8) & 0xf;
+                predict(pixels, position, xsize, mode)
+            }
+        };
+        pixels[position] = add_pixels(pixels[position], prediction);
+    }
+}
+
+/// Predicao dos 14 modos. O pixel de cima a direita e lido em sequencia, entao na ultima coluna
+/// ele e o primeiro da linha atual
+pub(super) fn predict(pixels: &[u32], position: usize, xsize: usize, mode: u32) -> u32 {
+    let left = pixels[position - 1];
+    let top = pixels[position - xsize];
+    let top_left = pixels[position - xsize - 1];
+    let top_right = pixels[position - xsize + 1];
+
+    match mode {
+        1 => left,
+        2 => top,
+        3 => top_right,
+        4 => top_left,
+        5 => average(average(left, top_right), top),
+        6 => average(left, top_left),
+        7 => average(left, top),
+        8 => average(top_left, top),
+        9 => average(top, top_right),
+        10 => average(average(left, top_left), average(top, top_right)),
+        11 => select(left, top, top_left),
+        12 => per_channel(|c| {
+            (channel(left, c) + channel(top, c) - channel(top_left, c)).clamp(0, 255)
+        }),
+        13 => {
+            let avg = average(left, top);
+            per_channel(|c| {
+                let a = channel(avg, c);
+                (a + (a - channel(top_left, c)) / 2).clamp(0, 255)
+            })
+        }
+        _ => 0xff000000,
+    }
+}
+
+fn channel(argb: u32, index: u32) -> i32 {
+    ((argb >> (index * 8)) & 0xff) as i32
+}
+
+fn per_channel(f: impl Fn(u32) -> i32) -> u32 {
+    (0..4).fold(0, |acc, c| acc | ((f(c) as u32) << (c * 8)))
+}
+
+fn average(a: u32, b: u32) -> u32 {
+    per_channel(|c| (channel(a, c) + channel(b, c)) / 2)
+}
+
+fn select(left: u32, top: u32, top_left: u32) -> u32 {
+    let distance = |a: u32, b: u32| {
+        (0..4)
+            .map(|c| (channel(a, c) - channel(b, c)).abs())
+            .sum::<i32>()
+    };
+
+    // Estimativa L + T - TL: a distancia dela ate L e |T - TL|, ate T e |L - TL|
+    match distance(top, top_left) < distance(left, top_left) {
+        true => left,
+        false => top,
+    }
+}
+
+/// Soma canal a canal, modulo 256
+fn add_pixels(a: u32, b: u32) -> u32 {
+    let alpha_green = (a & 0xff00ff00).wrapping_add(b & 0xff00ff00) & 0xff00ff00;
+    let red_blue = (a & 0x00ff00ff).wrapping_add(b & 0x00ff00ff) & 0x00ff00ff;
+    alpha_green | red_blue
+}
+
+/// `(t * c) >> 5` com os dois valores como inteiros de 8 bits com sinal
+pub(super) fn color_delta(transform: u8, color: u8) -> u8 {
+    ((transform as i8 as i32 * color as i8 as i32) >> 5) as u8
+}
+
+fn inverse_color(pixels: &mut [u32], xsize: usize, bits: u32, elements: &[u32]) {
+    let blocks_per_row = subsample(xsize, bits);
+
+    for (position, pixel) in pixels.iter_mut().enumerate() {
+        let (x, y) = (position % xsize, position / xsize);
+        let element = elements[(y >> bits) * blocks_per_row + (x >> bits)];
+        let green_to_red = element as u8;
+        let green_to_blue = (element >> 8) as u8;
+        let red_to_blue = (element >> 16) as u8;
+
+        let green = (*pixel >> 8) as u8;
+        let red = ((*pixel >> 16) as u8).wrapping_add(color_delta(green_to_red, green));
+        let blue = (*pixel as u8)
+            .wrapping_add(color_delta(green_to_blue, green))
+            .wrapping_add(color_delta(red_to_blue, red));
+
+        *pixel = (*pixel & 0xff00ff00) | ((red as u32) << 16) | blue as u32;
+    }
+}
+
+/// Troca os indices (agrupados no verde quando a paleta e pequena) pelas cores. Indices fora da
+/// paleta viram preto transparente
+fn inverse_color_indexing(
+    pixels: &[u32],
+    xsize: usize,
+    ysize: usize,
+    bits: u32,
+    palette: &[u32],
+) -> Vec<u32> {
+    let packed_xsize = subsample(xsize, bits);
+    let index_bits = 8 >> bits;
+    let mask = (1 << index_bits) - 1;
+
+    let mut output = Vec::with_capacity(xsize * ysize);
+    for y in 0..ysize {
+        for x in 0..xsize {
+            let packed = (pixels[y * packed_xsize + (x >> bits)] >> 8) & 0xff;
+            let shift = (x & ((1 << bits) - 1)) as u32 * index_bits;
+            let index = (packed >> shift) & mask;
+            output.push(palette.get(index as usize).copied().unwrap_or(0));
+        }
+    }
+
+    output
+}
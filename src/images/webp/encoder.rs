@@ -0,0 +1,378 @@
+use super::decoder::{ColorCache, predict};
+use super::huffman::HuffmanEncoder;
+use super::{CODE_LENGTH_PREFIXES, DISTANCE_MAP, NUM_LITERALS, VP8L_SIGNATURE, subsample};
+use crate::images::zlib::BitWriter;
+use std::collections::{HashMap, HashSet};
+
+// Consts...
+/// Bits do cache de cores da imagem principal
+const COLOR_CACHE_BITS: u32 = 10;
+/// Blocos de 16x16 pixels no preditor
+const PREDICTOR_BITS: u32 = 4;
+const PREDICTOR_MODES: u32 = 14;
+
+const MIN_MATCH: usize = 3;
+/// Maior comprimento que os 24 prefixos representam
+const MAX_MATCH: usize = 4096;
+/// Maior distancia que os 40 prefixos representam, descontando os codigos 2D
+const MAX_DISTANCE: usize = (1 << 20) - 120;
+const HASH_BITS: u32 = 16;
+const MAX_CHAIN: usize = 64;
+
+const TRANSFORM_PREDICTOR: u32 = 0;
+const TRANSFORM_SUBTRACT_GREEN: u32 = 2;
+const TRANSFORM_COLOR_INDEXING: u32 = 3;
+
+// Enums...
+/// Simbolo da imagem depois do LZ77 e do cache
+#[derive(Clone, Copy)]
+enum Token {
+    Literal(u32),
+    Cache(usize),
+    /// Comprimento e codigo de distancia (ja com os codigos 2D)
+    Copy(usize, usize),
+}
+
+/// Grava o bitstream VP8L. Ate 256 cores usam paleta; as outras imagens usam subtract green e
+/// o preditor com o modo de menor residuo em cada bloco
+pub(super) fn encode(width: usize, height: usize, argb: &[u32], alpha: bool) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    writer.write_bits(VP8L_SIGNATURE as u32, 8);
+    writer.write_bits(width as u32 - 1, 14);
+    writer.write_bits(height as u32 - 1, 14);
+    writer.write_bits(alpha as u32, 1);
+    writer.write_bits(0, 3);
+
+    let (xsize, image) = match palette(argb) {
+        Some(palette) => {
+            let bits = match palette.len() {
+                0..=2 => 3,
+                3..=4 => 2,
+                5..=16 => 1,
+                _ => 0,
+            };
+
+            writer.write_bits(1, 1);
+            writer.write_bits(TRANSFORM_COLOR_INDEXING, 2);
+            writer.write_bits(palette.len() as u32 - 1, 8);
+            let deltas = (0..palette.len())
+                .map(|i| match i {
+                    0 => palette[0],
+                    _ => sub_pixels(palette[i], palette[i - 1]),
+                })
+                .collect::<Vec<_>>();
+            write_image_stream(&mut writer, &deltas, palette.len(), false);
+
+            let xsize = subsample(width, bits);
+            (xsize, pack_indices(argb, width, height, bits, &palette))
+        }
+        None => {
+            let mut image = argb.to_vec();
+            for pixel in image.iter_mut() {
+                let green = (*pixel >> 8) & 0xff;
+                let red = ((*pixel >> 16).wrapping_sub(green) & 0xff) << 16;
+                let blue = (*pixel).wrapping_sub(green) & 0xff;
+                *pixel = (*pixel & 0xff00ff00) | red | blue;
+            }
+            writer.write_bits(1, 1);
+            writer.write_bits(TRANSFORM_SUBTRACT_GREEN, 2);
+
+            let (modes, residuals) = predictor(&image, width, height);
+            writer.write_bits(1, 1);
+            writer.write_bits(TRANSFORM_PREDICTOR, 2);
+            writer.write_bits(PREDICTOR_BITS - 2, 3);
+            write_image_stream(&mut writer, &modes, subsample(width, PREDICTOR_BITS), false);
+
+            (width, residuals)
+        }
+    };
+    writer.write_bits(0, 1);
+
+    write_image_stream(&mut writer, &image, xsize, true);
+    writer.finish()
+}
+
+// Utils Functions
+/// Cores da imagem em ordem, se forem no maximo 256
+fn palette(argb: &[u32]) -> Option<Vec<u32>> {
+    let mut colors = Vec::new();
+    let mut seen = HashSet::new();
+
+    for pixel in argb {
+        if seen.insert(*pixel) {
+            if colors.len() == 256 {
+                return None;
+            }
+            colors.push(*pixel);
+        }
+    }
+
+    colors.sort_unstable();
+    Some(colors)
+}
+
+/// Troca as cores pelos indices, juntando `1 << bits` indices no verde de cada pixel
+fn pack_indices(argb: &[u32], width: usize, height: usize, bits: u32, palette: &[u32]) -> Vec<u32> {
+    let index = palette
+        .iter()
+        .enumerate()
+        .map(|(i, color)| (*color, i as u32))
+        .collect::<HashMap<_, _>>();
+    let index_bits = 8 >> bits;
+    let xsize = subsample(width, bits);
+
+    let mut packed = vec![0xff000000_u32; xsize * height];
+    for y in 0..height {
+        for x in 0..width {
+            let shift = (x & ((1 << bits) - 1)) as u32 * index_bits;
+            packed[y * xsize + (x >> bits)] |= index[&argb[y * width + x]] << (shift + 8);
+        }
+    }
+
+    packed
+}
+
+/// Escolhe o modo de cada bloco pela menor soma dos residuos e devolve a imagem de modos e os
+/// residuos
+fn predictor(image: &[u32], width: usize, height: usize) -> (Vec<u32>, Vec<u32>) {
+    let blocks_x = subsample(width, PREDICTOR_BITS);
+    let blocks_y = subsample(height, PREDICTOR_BITS);
+    let block = 1 << PREDICTOR_BITS;
+
+    let mut modes = vec![0_u32; blocks_x * blocks_y];
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let cost = |mode: u32| {
+                let mut cost = 0_u64;
+                for y in (by * block..(by + 1) * block).take_while(|y| *y < height) {
+                    for x in (bx * block..(bx + 1) * block).take_while(|x| *x < width) {
+                        let position = y * width + x;
+                        let prediction = prediction(image, position, width, mode);
+                        let residual = sub_pixels(image[position], prediction);
+                        cost += residual
+                            .to_le_bytes()
+                            .iter()
+                            .map(|c| (*c as i8).unsigned_abs() as u64)
+                            .sum::<u64>();
+                    }
+                }
+                cost
+            };
+
+            let mode = (0..PREDICTOR_MODES).min_by_key(|mode| cost(*mode)).unwrap();
+            modes[by * blocks_x + bx] = 0xff000000 | (mode << 8);
+        }
+    }
+
+    let residuals = (0..image.len())
+        .map(|position| {
+            let (x, y) = (position % width, position / width);
+            let mode = (modes[(y >> PREDICTOR_BITS) * blocks_x + (x >> PREDICTOR_BITS)] >> 8) & 0xf;
+            sub_pixels(image[position], prediction(image, position, width, mode))
+        })
+        .collect();
+
+    (modes, residuals)
+}
+
+/// Predicao com as regras da borda: o primeiro pixel usa preto, a primeira linha o da esquerda
+/// e a primeira coluna o de cima
+fn prediction(image: &[u32], position: usize, width: usize, mode: u32) -> u32 {
+    match (position % width, position / width) {
+        (0, 0) => 0xff000000,
+        (_, 0) => image[position - 1],
+        (0, _) => image[position - width],
+        _ => predict(image, position, width, mode),
+    }
+}
+
+/// Subtrai canal a canal, modulo 256
+fn sub_pixels(a: u32, b: u32) -> u32 {
+    let alpha_green = (a | 0x00ff00ff).wrapping_sub(b & 0xff00ff00) & 0xff00ff00;
+    let red_blue = (a | 0xff00ff00).wrapping_sub(b & 0x00ff00ff) & 0x00ff00ff;
+    alpha_green | red_blue
+}
+
+/// Grava uma imagem codificada por entropia com um unico grupo de codigos. Na imagem principal
+/// usa o cache de cores
+fn write_image_stream(writer: &mut BitWriter, image: &[u32], xsize: usize, main: bool) {
+    let cache_bits = main.then_some(COLOR_CACHE_BITS);
+    let tokens = tokenize(image, xsize, cache_bits);
+
+    match cache_bits {
+        Some(bits) => {
+            writer.write_bits(1, 1);
+            writer.write_bits(bits, 4);
+        }
+        None => writer.write_bits(0, 1),
+    }
+    if main {
+        // Sem imagem de entropia
+        writer.write_bits(0, 1);
+    }
+
+    let cache_size = cache_bits.map_or(0, |bits| 1 << bits);
+    let mut green = vec![0_u32; NUM_LITERALS + CODE_LENGTH_PREFIXES + cache_size];
+    let mut red = vec![0_u32; 256];
+    let mut blue = vec![0_u32; 256];
+    let mut alpha = vec![0_u32; 256];
+    let mut distance = vec![0_u32; 40];
+
+    for token in &tokens {
+        match *token {
+            Token::Literal(argb) => {
+                let [b, g, r, a] = argb.to_le_bytes();
+                green[g as usize] += 1;
+                red[r as usize] += 1;
+                blue[b as usize] += 1;
+                alpha[a as usize] += 1;
+            }
+            Token::Cache(index) => green[NUM_LITERALS + CODE_LENGTH_PREFIXES + index] += 1,
+            Token::Copy(length, code) => {
+                green[NUM_LITERALS + prefix_encode(length).0] += 1;
+                distance[prefix_encode(code).0] += 1;
+            }
+        }
+    }
+
+    let green = HuffmanEncoder::write(writer, &green);
+    let red = HuffmanEncoder::write(writer, &red);
+    let blue = HuffmanEncoder::write(writer, &blue);
+    let alpha = HuffmanEncoder::write(writer, &alpha);
+    let distance = HuffmanEncoder::write(writer, &distance);
+
+    for token in tokens {
+        match token {
+            Token::Literal(argb) => {
+                let [b, g, r, a] = argb.to_le_bytes();
+                green.encode(writer, g as usize);
+                red.encode(writer, r as usize);
+                blue.encode(writer, b as usize);
+                alpha.encode(writer, a as usize);
+            }
+            Token::Cache(index) => {
+                green.encode(writer, NUM_LITERALS + CODE_LENGTH_PREFIXES + index);
+            }
+            Token::Copy(length, code) => {
+                let (prefix, bits, extra) = prefix_encode(length);
+                green.encode(writer, NUM_LITERALS + prefix);
+                writer.write_bits(extra, bits);
+
+                let (prefix, bits, extra) = prefix_encode(code);
+                distance.encode(writer, prefix);
+                writer.write_bits(extra, bits);
+            }
+        }
+    }
+}
+
+/// LZ77 com cadeias de hash, testando antes as distancias de 1 pixel e de uma linha. Os pixels
+/// que sobram viram literais ou indices do cache
+fn tokenize(image: &[u32], xsize: usize, cache_bits: Option<u32>) -> Vec<Token> {
+    let mut cache = cache_bits.map(ColorCache::new);
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut previous = vec![usize::MAX; image.len()];
+    let mut tokens = Vec::new();
+
+    let hash = |position: usize| {
+        let pair = (image[position] as u64) << 32 | image[position + 1] as u64;
+        (pair.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> (64 - HASH_BITS)) as usize
+    };
+    let match_length = |position: usize, distance: usize| {
+        let limit = MAX_MATCH.min(image.len() - position);
+        (0..limit)
+            .take_while(|i| image[position + i] == image[position + i - distance])
+            .count()
+    };
+
+    let mut position = 0;
+    while position < image.len() {
+        let mut best = (0, 0);
+        for distance in [1, xsize] {
+            if distance <= position {
+                let length = match_length(position, distance);
+                if length > best.0 {
+                    best = (length, distance);
+                }
+            }
+        }
+
+        if position + 1 < image.len() {
+            let mut candidate = head[hash(position)];
+            let mut chain = 0;
+            while candidate != usize::MAX && chain < MAX_CHAIN && best.0 < MAX_MATCH {
+                let distance = position - candidate;
+                if distance > MAX_DISTANCE {
+                    break;
+                }
+
+                let length = match_length(position, distance);
+                if length > best.0 {
+                    best = (length, distance);
+                }
+                candidate = previous[candidate];
+                chain += 1;
+            }
+        }
+
+        let (length, distance) = best;
+        let step = match length >= MIN_MATCH {
+            true => {
+                tokens.push(Token::Copy(length, distance_to_code(xsize, distance)));
+                length
+            }
+            false => {
+                let argb = image[position];
+                let token = match &cache {
+                    Some(cache) if cache.get(cache.index(argb)) == argb => {
+                        Token::Cache(cache.index(argb))
+                    }
+                    _ => Token::Literal(argb),
+                };
+                tokens.push(token);
+                1
+            }
+        };
+
+        for index in position..position + step {
+            if let Some(cache) = &mut cache {
+                cache.insert(image[index]);
+            }
+            if index + 1 < image.len() {
+                let key = hash(index);
+                previous[index] = head[key];
+                head[key] = index;
+            }
+        }
+        position += step;
+    }
+
+    tokens
+}
+
+/// Usa o menor codigo 2D que leva a mesma distancia, ou a distancia somada a 120
+fn distance_to_code(xsize: usize, distance: usize) -> usize {
+    DISTANCE_MAP
+        .iter()
+        .position(|(dx, dy)| {
+            (*dx as isize + *dy as isize * xsize as isize).max(1) as usize == distance
+        })
+        .map_or(distance + DISTANCE_MAP.len(), |index| index + 1)
+}
+
+/// Prefixo, quantidade de bits extras e valor deles para um comprimento ou distancia
+fn prefix_encode(value: usize) -> (usize, u32, u32) {
+    let value = value - 1;
+    if value < 4 {
+        return (value, 0, 0);
+    }
+
+    let highest = usize::BITS - 1 - value.leading_zeros();
+    let second = (value >> (highest - 1)) & 1;
+    let extra = highest - 1;
+    (
+        2 * highest as usize + second,
+        extra,
+        (value & ((1 << extra) - 1)) as u32,
+    )
+}
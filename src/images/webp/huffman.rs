@@ -0,0 +1,342 @@
+use crate::error::{ImageError, ImageResult};
+use crate::images::zlib::{self, BitReader, BitWriter};
+
+// Consts...
+/// Maior comprimento de codigo permitido no VP8L
+pub(super) const MAX_CODE_LENGTH: usize = 15;
+/// Bits resolvidos pela tabela principal; codigos maiores usam uma tabela secundaria
+const ROOT_BITS: usize = 8;
+
+/// Ordem em que os comprimentos do codigo dos comprimentos sao gravados
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    17, 18, 0, 1, 2, 3, 4, 5, 16, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+];
+const CODE_LENGTH_CODES: usize = 19;
+/// Comprimento repetido pelo codigo 16 antes de aparecer algum comprimento diferente de zero
+const DEFAULT_CODE_LENGTH: u8 = 8;
+
+// Structs...
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    /// Simbolo, ou o inicio da tabela secundaria quando `bits` passa de `ROOT_BITS`
+    value: u16,
+    bits: u8,
+}
+
+/// Codigo de prefixo (Huffman) canonico com tabela de dois niveis
+pub(super) struct HuffmanCode {
+    entries: Vec<Entry>,
+    /// Codigo com um unico simbolo, que nao consome bits
+    single: Option<u16>,
+}
+
+impl HuffmanCode {
+    /// Monta o codigo a partir dos comprimentos de cada simbolo. Com mais de um simbolo a arvore
+    /// precisa estar completa
+    pub(super) fn new(lengths: &[u8]) -> ImageResult<Self> {
+        let used = lengths.iter().filter(|len| **len > 0).count();
+        match used {
+            0 => return Err(ImageError::InvalidData("webp prefix code is empty")),
+            1 => {
+                let symbol = lengths.iter().position(|len| *len > 0).unwrap();
+                return Ok(Self {
+                    entries: Vec::new(),
+                    single: Some(symbol as u16),
+                });
+            }
+            _ => {}
+        }
+
+        let kraft = lengths
+            .iter()
+            .filter(|len| **len > 0)
+            .map(|len| 1_u32 << (MAX_CODE_LENGTH - *len as usize))
+            .sum::<u32>();
+        if kraft != 1 << MAX_CODE_LENGTH {
+            return Err(ImageError::InvalidData("webp prefix code is incomplete"));
+        }
+
+        let codes = zlib::canonical_codes(lengths);
+        let reversed = |code: u16, len: usize| (code.reverse_bits() >> (16 - len)) as usize;
+
+        // Tamanho de cada tabela secundaria: o maior resto dos codigos com o mesmo prefixo
+        let mut sub_bits = [0_usize; 1 << ROOT_BITS];
+        for (len, code) in lengths.iter().zip(&codes) {
+            let len = *len as usize;
+            if len > ROOT_BITS {
+                let root = reversed(*code, len) & ((1 << ROOT_BITS) - 1);
+                sub_bits[root] = sub_bits[root].max(len - ROOT_BITS);
+            }
+        }
+
+        let mut entries = vec![Entry::default(); 1 << ROOT_BITS];
+        for (root, bits) in sub_bits.iter().enumerate().filter(|(_, bits)| **bits > 0) {
+            entries[root] = Entry {
+                value: entries.len() as u16,
+                bits: (ROOT_BITS + bits) as u8,
+            };
+            entries.resize(entries.len() + (1 << bits), Entry::default());
+        }
+
+        for (symbol, (len, code)) in lengths.iter().zip(&codes).enumerate() {
+            let len = *len as usize;
+            if len == 0 {
+                continue;
+            }
+
+            let code = reversed(*code, len);
+            let entry = Entry {
+                value: symbol as u16,
+                bits: len as u8,
+            };
+            if len <= ROOT_BITS {
+                for fill in (code..1 << ROOT_BITS).step_by(1 << len) {
+                    entries[fill] = entry;
+                }
+            } else {
+                let link = entries[code & ((1 << ROOT_BITS) - 1)];
+                let size = 1 << (link.bits as usize - ROOT_BITS);
+                let start = link.value as usize;
+                for fill in ((code >> ROOT_BITS)..size).step_by(1 << (len - ROOT_BITS)) {
+                    entries[start + fill] = entry;
+                }
+            }
+        }
+
+        Ok(Self {
+            entries,
+            single: None,
+        })
+    }
+
+    /// Le um codigo do fluxo: `simple_code` com ate dois simbolos ou os comprimentos codificados
+    pub(super) fn read(reader: &mut BitReader, alphabet_size: usize) -> ImageResult<Self> {
+        let mut lengths = vec![0_u8; alphabet_size];
+
+        if reader.read_bits(1)? == 1 {
+            let count = reader.read_bits(1)? + 1;
+            let first_bits = match reader.read_bits(1)? {
+                0 => 1,
+                _ => 8,
+            };
+
+            let mut symbols = vec![reader.read_bits(first_bits)? as usize];
+            if count == 2 {
+                symbols.push(reader.read_bits(8)? as usize);
+            }
+            for symbol in symbols {
+                *lengths.get_mut(symbol).ok_or(ImageError::InvalidData(
+                    "webp prefix code symbol is invalid",
+                ))? = 1;
+            }
+
+            // Dois simbolos iguais viram um codigo de simbolo unico
+            return Self::new(&lengths);
+        }
+
+        let mut code_length_lengths = [0_u8; CODE_LENGTH_CODES];
+        let count = reader.read_bits(4)? as usize + 4;
+        for index in CODE_LENGTH_ORDER.iter().take(count) {
+            code_length_lengths[*index] = reader.read_bits(3)? as u8;
+        }
+        let code_length_code = Self::new(&code_length_lengths)?;
+
+        let mut max_symbol = match reader.read_bits(1)? {
+            0 => alphabet_size,
+            _ => {
+                let bits = 2 + 2 * reader.read_bits(3)?;
+                let max_symbol = 2 + reader.read_bits(bits)? as usize;
+                if max_symbol > alphabet_size {
+                    return Err(ImageError::InvalidData("webp max_symbol is too large"));
+                }
+                max_symbol
+            }
+        };
+
+        let mut symbol = 0;
+        let mut previous = DEFAULT_CODE_LENGTH;
+        while symbol < alphabet_size && max_symbol > 0 {
+            max_symbol -= 1;
+
+            let code = code_length_code.decode(reader)?;
+            if code < 16 {
+                lengths[symbol] = code as u8;
+                symbol += 1;
+                if code != 0 {
+                    previous = code as u8;
+                }
+                continue;
+            }
+
+            let (extra, offset, length) = match code {
+                16 => (2, 3, previous),
+                17 => (3, 3, 0),
+                _ => (7, 11, 0),
+            };
+            let repeat = reader.read_bits(extra)? as usize + offset;
+            if symbol + repeat > alphabet_size {
+                return Err(ImageError::InvalidData("webp code lengths overflow"));
+            }
+            lengths[symbol..symbol + repeat].fill(length);
+            symbol += repeat;
+        }
+
+        Self::new(&lengths)
+    }
+
+    pub(super) fn decode(&self, reader: &mut BitReader) -> ImageResult<u16> {
+        if let Some(symbol) = self.single {
+            return Ok(symbol);
+        }
+
+        let bits = reader.peek_bits(MAX_CODE_LENGTH as u32) as usize;
+        let mut entry = self.entries[bits & ((1 << ROOT_BITS) - 1)];
+        if entry.bits as usize > ROOT_BITS {
+            let mask = (1 << (entry.bits as usize - ROOT_BITS)) - 1;
+            entry = self.entries[entry.value as usize + ((bits >> ROOT_BITS) & mask)];
+        }
+
+        reader.consume(entry.bits as u32)?;
+        Ok(entry.value)
+    }
+}
+
+/// Codigo usado na escrita: comprimento e codigo canonico de cada simbolo
+pub(super) struct HuffmanEncoder {
+    lengths: Vec<u8>,
+    codes: Vec<u16>,
+}
+
+impl HuffmanEncoder {
+    /// Monta o codigo para as frequencias e grava ele no fluxo
+    pub(super) fn write(writer: &mut BitWriter, freqs: &[u32]) -> Self {
+        let used = freqs
+            .iter()
+            .enumerate()
+            .filter(|(_, freq)| **freq > 0)
+            .map(|(symbol, _)| symbol)
+            .collect::<Vec<_>>();
+        let mut lengths = zlib::code_lengths(freqs, MAX_CODE_LENGTH);
+
+        // Ate dois simbolos pequenos cabem num `simple_code`
+        if used.len() <= 2 && used.iter().all(|symbol| *symbol < 256) {
+            let symbols = match used.is_empty() {
+                true => vec![0],
+                false => used,
+            };
+
+            writer.write_bits(1, 1);
+            writer.write_bits(symbols.len() as u32 - 1, 1);
+            match symbols[0] {
+                0 | 1 => writer.write_bits(0, 1),
+                _ => writer.write_bits(1, 1),
+            }
+            writer.write_bits(symbols[0] as u32, if symbols[0] < 2 { 1 } else { 8 });
+            if let Some(second) = symbols.get(1) {
+                writer.write_bits(*second as u32, 8);
+            }
+        } else {
+            writer.write_bits(0, 1);
+            write_code_lengths(writer, &lengths);
+        }
+
+        // Um unico simbolo nao gasta bits
+        if used_count(&lengths) == 1 {
+            lengths.fill(0);
+        }
+        let codes = zlib::canonical_codes(&lengths);
+
+        Self { lengths, codes }
+    }
+
+    pub(super) fn encode(&self, writer: &mut BitWriter, symbol: usize) {
+        if self.lengths[symbol] > 0 {
+            writer.write_code(self.codes[symbol], self.lengths[symbol]);
+        }
+    }
+}
+
+// Utils Functions
+fn used_count(lengths: &[u8]) -> usize {
+    lengths.iter().filter(|len| **len > 0).count()
+}
+
+/// Grava os comprimentos com o codigo dos comprimentos (repeticoes com 16, 17 e 18)
+fn write_code_lengths(writer: &mut BitWriter, lengths: &[u8]) {
+    let tokens = run_length_lengths(lengths);
+
+    let mut freqs = [0_u32; CODE_LENGTH_CODES];
+    for (code, _) in &tokens {
+        freqs[*code as usize] += 1;
+    }
+
+    let mut code_lengths = zlib::code_lengths(&freqs, 7);
+    // O decodificador so aceita um codigo de simbolo unico se ele tiver 0 bits
+    let single = used_count(&code_lengths) == 1;
+    let codes = zlib::canonical_codes(&code_lengths);
+
+    let count = CODE_LENGTH_ORDER
+        .iter()
+        .rposition(|index| code_lengths[*index] > 0)
+        .map_or(4, |last| (last + 1).max(4));
+    writer.write_bits(count as u32 - 4, 4);
+    for index in CODE_LENGTH_ORDER.iter().take(count) {
+        writer.write_bits(code_lengths[*index] as u32, 3);
+    }
+    if single {
+        code_lengths.fill(0);
+    }
+
+    // Sem max_symbol: todos os comprimentos sao gravados
+    writer.write_bits(0, 1);
+    for (code, extra) in tokens {
+        if code_lengths[code as usize] > 0 {
+            writer.write_code(codes[code as usize], code_lengths[code as usize]);
+        }
+        match code {
+            16 => writer.write_bits(extra as u32, 2),
+            17 => writer.write_bits(extra as u32, 3),
+            18 => writer.write_bits(extra as u32, 7),
+            _ => {}
+        }
+    }
+}
+
+/// Agrupa os comprimentos em (codigo, bits extras). O codigo 16 repete o ultimo comprimento
+/// diferente de zero, por isso so e usado logo depois dele
+fn run_length_lengths(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < lengths.len() {
+        let len = lengths[index];
+        let run = lengths[index..].iter().take_while(|l| **l == len).count();
+        index += run;
+
+        if len == 0 {
+            let mut left = run;
+            while left >= 11 {
+                let take = left.min(138);
+                tokens.push((18, (take - 11) as u8));
+                left -= take;
+            }
+            if left >= 3 {
+                tokens.push((17, (left - 3) as u8));
+                left = 0;
+            }
+            tokens.extend(std::iter::repeat_n((0, 0), left));
+            continue;
+        }
+
+        tokens.push((len, 0));
+        let mut left = run - 1;
+        while left >= 3 {
+            let take = left.min(6);
+            tokens.push((16, (take - 3) as u8));
+            left -= take;
+        }
+        tokens.extend(std::iter::repeat_n((len, 0), left));
+    }
+
+    tokens
+}
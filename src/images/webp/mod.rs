@@ -0,0 +1,333 @@
+use super::{Format, Image, Orientation, RGB, buffer::ImageBuffer};
+use crate::error::{ImageError, ImageResult};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+
+mod decoder;
+mod encoder;
+mod huffman;
+
+// Consts...
+const RIFF_HEADER_SIZE: usize = 12;
+const CHUNK_HEADER_SIZE: usize = 8;
+/// Primeiro byte do bitstream VP8L
+const VP8L_SIGNATURE: u8 = 0x2F;
+/// As dimensoes do VP8L tem 14 bits
+const MAX_DIMENSION: usize = 1 << 14;
+
+/// Flags do chunk `VP8X`
+const FLAG_ANIMATION: u8 = 0x02;
+
+const NUM_LITERALS: usize = 256;
+/// Prefixos dos comprimentos das referencias, logo depois dos literais no alfabeto verde
+const CODE_LENGTH_PREFIXES: usize = 24;
+const MAX_COLOR_CACHE_BITS: u32 = 11;
+const COLOR_CACHE_MULTIPLIER: u32 = 0x1E35_A7BD;
+
+/// Deslocamentos (x, y) dos 120 primeiros codigos de distancia, do mais proximo ao mais longe
+#[rustfmt::skip]
+const DISTANCE_MAP: [(i8, i8); 120] = [
+    (0, 1),  (1, 0),  (1, 1),  (-1, 1), (0, 2),  (2, 0),  (1, 2),  (-1, 2),
+    (2, 1),  (-2, 1), (2, 2),  (-2, 2), (0, 3),  (3, 0),  (1, 3),  (-1, 3),
+    (3, 1),  (-3, 1), (2, 3),  (-2, 3), (3, 2),  (-3, 2), (0, 4),  (4, 0),
+    (1, 4),  (-1, 4), (4, 1),  (-4, 1), (3, 3),  (-3, 3), (2, 4),  (-2, 4),
+    (4, 2),  (-4, 2), (0, 5),  (3, 4),  (-3, 4), (4, 3),  (-4, 3), (5, 0),
+    (1, 5),  (-1, 5), (5, 1),  (-5, 1), (2, 5),  (-2, 5), (5, 2),  (-5, 2),
+    (4, 4),  (-4, 4), (3, 5),  (-3, 5), (5, 3),  (-5, 3), (0, 6),  (6, 0),
+    (1, 6),  (-1, 6), (6, 1),  (-6, 1), (2, 6),  (-2, 6), (6, 2),  (-6, 2),
+    (4, 5),  (-4, 5), (5, 4),  (-5, 4), (3, 6),  (-3, 6), (6, 3),  (-6, 3),
+    (0, 7),  (7, 0),  (1, 7),  (-1, 7), (5, 5),  (-5, 5), (7, 1),  (-7, 1),
+    (4, 6),  (-4, 6), (6, 4),  (-6, 4), (2, 7),  (-2, 7), (7, 2),  (-7, 2),
+    (3, 7),  (-3, 7), (7, 3),  (-7, 3), (5, 6),  (-5, 6), (6, 5),  (-6, 5),
+    (8, 0),  (4, 7),  (-4, 7), (7, 4),  (-7, 4), (8, 1),  (8, 2),  (6, 6),
+    (-6, 6), (8, 3),  (5, 7),  (-5, 7), (7, 5),  (-7, 5), (8, 4),  (6, 7),
+    (-6, 7), (7, 6),  (-7, 6), (8, 5),  (7, 7),  (-7, 7), (8, 6),  (8, 7),
+];
+
+// Enums...
+/// Enum que representa o bitstream da imagem. So o VP8L (sem perdas) e suportado; o VP8 (com
+/// perdas) fica para um modo futuro
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebPMode {
+    Lossless,
+}
+
+// Structs...
+/// Struct para representa uma imagem WebP estatica
+pub struct WebP {
+    width: usize,
+    height: usize,
+    pixels: Vec<RGB>,
+    mode: WebPMode,
+    orientation: Orientation,
+}
+
+/// Chunk do RIFF: identificador e conteudo sem o byte de alinhamento
+struct Chunk<'a> {
+    kind: [u8; 4],
+    data: &'a [u8],
+}
+
+impl WebP {
+    /// Cria uma imagem WebP sem perdas a partir de pixels em ordem de linhas. O alpha so e
+    /// gravado se algum pixel tiver alpha
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidData("webp dimensions are zero"));
+        }
+        if width > MAX_DIMENSION || height > MAX_DIMENSION {
+            return Err(ImageError::DimensionOverflow);
+        }
+        if width * height != pixels.len() {
+            return Err(ImageError::InvalidData(
+                "pixel count does not match the dimensions",
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            mode: WebPMode::Lossless,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    pub fn mode(&self) -> WebPMode {
+        self.mode
+    }
+
+    fn read_webp(data: &[u8]) -> ImageResult<Self> {
+        if data.len() < RIFF_HEADER_SIZE {
+            return Err(ImageError::Truncated);
+        }
+        if &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+            return Err(ImageError::InvalidMagic);
+        }
+
+        // O tamanho do RIFF conta a partir do "WEBP"; bytes depois dele sao ignorados
+        let riff_size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        if riff_size < 4 {
+            return Err(ImageError::InvalidData("webp riff size is too small"));
+        }
+        let end = data.len().min(riff_size.saturating_add(8));
+        let chunks = read_chunks(&data[RIFF_HEADER_SIZE..end])?;
+
+        let first = chunks.first().ok_or(ImageError::Truncated)?;
+        let bitstream = match &first.kind {
+            b"VP8X" => {
+                let flags = *first.data.first().ok_or(ImageError::Truncated)?;
+                if flags & FLAG_ANIMATION != 0 {
+                    return Err(ImageError::Unsupported("webp animation"));
+                }
+
+                chunks[1..]
+                    .iter()
+                    .find(|chunk| matches!(&chunk.kind, b"VP8L" | b"VP8 " | b"ALPH"))
+                    .ok_or(ImageError::InvalidData("webp has no image chunk"))?
+            }
+            _ => first,
+        };
+
+        let decoded = match &bitstream.kind {
+            b"VP8L" => decoder::decode(bitstream.data)?,
+            b"VP8 " | b"ALPH" => return Err(ImageError::Unsupported("webp lossy (VP8)")),
+            _ => return Err(ImageError::InvalidData("webp has no image chunk")),
+        };
+
+        let pixels = decoded
+            .argb
+            .iter()
+            .map(|argb| {
+                let [blue, green, red, alpha] = argb.to_le_bytes();
+                RGB::new(red, green, blue, decoded.alpha.then_some(alpha))
+            })
+            .collect();
+
+        Ok(Self {
+            width: decoded.width,
+            height: decoded.height,
+            pixels,
+            mode: WebPMode::Lossless,
+            orientation: Orientation::Normal,
+        })
+    }
+
+    fn write_webp(&self) -> Vec<u8> {
+        let alpha = self.pixels.iter().any(|pixel| pixel.alpha().is_some());
+        let argb = self
+            .pixels
+            .iter()
+            .map(|pixel| {
+                let alpha = pixel.alpha().unwrap_or(u8::MAX);
+                u32::from_le_bytes([pixel.blue(), pixel.green(), pixel.red(), alpha])
+            })
+            .collect::<Vec<_>>();
+        let bitstream = encoder::encode(self.width, self.height, &argb, alpha);
+
+        let padded = bitstream.len() + bitstream.len() % 2;
+        let mut bytes = Vec::with_capacity(RIFF_HEADER_SIZE + CHUNK_HEADER_SIZE + padded);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&((4 + CHUNK_HEADER_SIZE + padded) as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WEBP");
+        bytes.extend_from_slice(b"VP8L");
+        bytes.extend_from_slice(&(bitstream.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&bitstream);
+        if bitstream.len() % 2 == 1 {
+            bytes.push(0);
+        }
+
+        bytes
+    }
+}
+
+impl From<WebP> for ImageBuffer {
+    fn from(image: WebP) -> Self {
+        ImageBuffer::from_raw(image.width, image.height, image.pixels)
+            .with_orientation(image.orientation)
+    }
+}
+
+impl TryFrom<ImageBuffer> for WebP {
+    type Error = ImageError;
+
+    fn try_from(buffer: ImageBuffer) -> ImageResult<Self> {
+        let (width, height) = (buffer.widht(), buffer.height());
+        let orientation = buffer.orientation();
+
+        let mut image = WebP::from_pixels(width, height, buffer.into_pixels())?;
+        image.orientation = orientation;
+
+        Ok(image)
+    }
+}
+
+impl Image for WebP {
+    type Pixel = RGB;
+
+    fn decode(mut reader: impl Read + Seek) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::read_webp(&data)
+    }
+
+    fn encode(&self, mut writer: impl Write) -> ImageResult<()> {
+        writer.write_all(&self.write_webp())?;
+
+        Ok(())
+    }
+
+    fn filter(
+        &mut self,
+        filter: impl crate::filters::Filter,
+    ) -> Result<(), crate::filters::FilterError> {
+        filter.apply(self)
+    }
+
+    fn set_pixels(&mut self, width: usize, height: usize, pixels: Vec<RGB>) -> ImageResult<()> {
+        let image = Self::from_pixels(width, height, pixels)?;
+
+        self.width = image.width;
+        self.height = image.height;
+        self.pixels = image.pixels;
+
+        Ok(())
+    }
+
+    fn widht(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        Format::WEBP
+    }
+
+    fn bytes_per_pixels(&self) -> u16 {
+        match self.pixels.iter().any(|pixel| pixel.alpha().is_some()) {
+            true => 32,
+            false => 24,
+        }
+    }
+
+    fn pixels(&mut self) -> &mut [RGB] {
+        &mut self.pixels
+    }
+
+    fn get_pixels(&self) -> &[RGB] {
+        &self.pixels
+    }
+
+    fn pixel(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get_mut(y * self.width + x)
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get(y * self.width + x)
+    }
+
+    fn slice_pixels(&mut self, range: Range<usize>) -> &mut [RGB] {
+        &mut self.pixels[range]
+    }
+
+    fn get_slice_pixels(&self, range: Range<usize>) -> &[RGB] {
+        &self.pixels[range]
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn normalize_orientation(&mut self) {
+        let (pixels, width, height) = self
+            .orientation
+            .apply(&self.pixels, self.width, self.height);
+
+        self.width = width;
+        self.height = height;
+        self.pixels = pixels;
+        self.orientation = Orientation::Normal;
+    }
+}
+
+// Utils Functions
+/// Separa os chunks do RIFF. Cada um e alinhado em 2 bytes
+fn read_chunks(mut data: &[u8]) -> ImageResult<Vec<Chunk<'_>>> {
+    let mut chunks = Vec::new();
+
+    while data.len() >= CHUNK_HEADER_SIZE {
+        let kind = [data[0], data[1], data[2], data[3]];
+        let size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let body = data
+            .get(CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE + size)
+            .ok_or(ImageError::Truncated)?;
+
+        chunks.push(Chunk { kind, data: body });
+        data = data
+            .get(CHUNK_HEADER_SIZE + size + size % 2..)
+            .unwrap_or_default();
+    }
+
+    Ok(chunks)
+}
+
+/// Divide o tamanho pelo tamanho do bloco (`1 << bits`), arredondando para cima
+fn subsample(size: usize, bits: u32) -> usize {
+    size.div_ceil(1 << bits)
+}
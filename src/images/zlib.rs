@@ -0,0 +1,738 @@
+use crate::error::{ImageError, ImageResult};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// Consts...
+const MAX_BITS: usize = 15;
+const WINDOW_SIZE: usize = 32768;
+const HASH_SIZE: usize = 1 << 15;
+const MAX_CHAIN: usize = 128;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const BLOCK_SYMBOLS: usize = 1 << 16;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const OUTPUT_TOO_LARGE: ImageError =
+    ImageError::InvalidData("deflate output is larger than expected");
+
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+// Functions...
+/// Descomprime um fluxo zlib (RFC 1950), validando o cabecalho e o checksum Adler-32
+pub fn decompress(data: &[u8]) -> ImageResult<Vec<u8>> {
+    decompress_limited(data, usize::MAX)
+}
+
+/// Igual a `decompress`, mas falha se a saida passar de `limit` bytes. Usado quando o tamanho
+/// esperado ja e conhecido, para um fluxo pequeno nao gerar gigabytes
+pub fn decompress_limited(data: &[u8], limit: usize) -> ImageResult<Vec<u8>> {
+    if data.len() < 6 {
+        return Err(ImageError::Truncated);
+    }
+
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 {
+        return Err(ImageError::UnsupportedCompression((cmf & 0x0F) as u32));
+    }
+    if !((cmf as u16) << 8 | flg as u16).is_multiple_of(31) {
+        return Err(ImageError::InvalidData("zlib header checksum mismatch"));
+    }
+    if flg & 0x20 != 0 {
+        return Err(ImageError::Unsupported("zlib preset dictionary"));
+    }
+
+    let (output, consumed) = inflate_with_size(&data[2..], limit)?;
+    let trailer = data
+        .get(2 + consumed..2 + consumed + 4)
+        .ok_or(ImageError::Truncated)?;
+    let expected = u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+
+    if adler32(&output) != expected {
+        return Err(ImageError::InvalidData("zlib adler32 mismatch"));
+    }
+
+    Ok(output)
+}
+
+/// Comprime os bytes em um fluxo zlib (RFC 1950)
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut output = vec![0x78, 0x9C];
+    output.append(&mut deflate(data));
+    output.extend_from_slice(&adler32(data).to_be_bytes());
+    output
+}
+
+/// Descomprime um fluxo deflate puro (RFC 1951)
+pub fn inflate(data: &[u8]) -> ImageResult<Vec<u8>> {
+    inflate_with_size(data, usize::MAX).map(|(output, _)| output)
+}
+
+/// Comprime os bytes em um fluxo deflate puro (RFC 1951) usando blocos com Huffman dinamico
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    let symbols = lz77(data);
+
+    if symbols.is_empty() {
+        writer.write_bits(1, 1);
+        writer.write_bits(1, 2);
+        writer.write_bits(0, 7);
+        return writer.finish();
+    }
+
+    let chunks = symbols.chunks(BLOCK_SYMBOLS).collect::<Vec<_>>();
+    for (index, chunk) in chunks.iter().enumerate() {
+        write_dynamic_block(&mut writer, chunk, index + 1 == chunks.len());
+    }
+
+    writer.finish()
+}
+
+/// Calcula o checksum Adler-32 dos bytes
+pub fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+
+    (b << 16) | a
+}
+
+/// Calcula comprimentos de codigo Huffman canonicos limitados a `max_len` bits
+pub(crate) fn code_lengths(freqs: &[u32], max_len: usize) -> Vec<u8> {
+    let mut lengths = vec![0_u8; freqs.len()];
+    let mut used = freqs
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| **f > 0)
+        .map(|(i, f)| (*f, i))
+        .collect::<Vec<_>>();
+
+    match used.len() {
+        0 => return lengths,
+        1 => {
+            lengths[used[0].1] = 1;
+            return lengths;
+        }
+        _ => {}
+    }
+
+    // Arvore de Huffman classica, guardando o pai de cada no
+    let mut parents = vec![0_usize; used.len() * 2];
+    let mut heap = BinaryHeap::new();
+    for (node, (freq, _)) in used.iter().enumerate() {
+        heap.push(Reverse((*freq as u64, node)));
+    }
+
+    let mut next = used.len();
+    while heap.len() > 1 {
+        let Reverse((fa, a)) = heap.pop().unwrap();
+        let Reverse((fb, b)) = heap.pop().unwrap();
+        parents[a] = next;
+        parents[b] = next;
+        heap.push(Reverse((fa + fb, next)));
+        next += 1;
+    }
+
+    let root = next - 1;
+    let mut depth = vec![0_usize; next];
+    for node in (0..root).rev() {
+        depth[node] = depth[parents[node]] + 1;
+    }
+
+    let mut counts = vec![0_usize; used.len().max(max_len) + 1];
+    for node in 0..used.len() {
+        counts[depth[node]] += 1;
+    }
+
+    // Limita o tamanho dos codigos (algoritmo do anexo K.3 do JPEG)
+    for len in (max_len + 1..counts.len()).rev() {
+        while counts[len] > 0 {
+            let mut j = len - 2;
+            while counts[j] == 0 {
+                j -= 1;
+            }
+            counts[len] -= 2;
+            counts[len - 1] += 1;
+            counts[j + 1] += 2;
+            counts[j] -= 1;
+        }
+    }
+
+    used.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut symbols = used.iter();
+    for (len, count) in counts.iter().enumerate().take(max_len + 1).skip(1) {
+        for _ in 0..*count {
+            let (_, symbol) = symbols.next().unwrap();
+            lengths[*symbol] = len as u8;
+        }
+    }
+
+    lengths
+}
+
+/// Gera os codigos canonicos (MSB primeiro) para os comprimentos dados
+pub(crate) fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+    let max = lengths.iter().copied().max().unwrap_or(0) as usize;
+    let mut counts = vec![0_u16; max + 1];
+    for len in lengths.iter().filter(|l| **l > 0) {
+        counts[*len as usize] += 1;
+    }
+
+    let mut next = vec![0_u16; max + 2];
+    let mut code = 0_u16;
+    for bits in 1..=max {
+        code = (code + counts[bits - 1]) << 1;
+        next[bits] = code;
+    }
+
+    lengths
+        .iter()
+        .map(|len| {
+            if *len == 0 {
+                return 0;
+            }
+            let code = next[*len as usize];
+            next[*len as usize] += 1;
+            code
+        })
+        .collect()
+}
+
+fn inflate_with_size(data: &[u8], limit: usize) -> ImageResult<(Vec<u8>, usize)> {
+    let mut reader = BitReader::new(data);
+    let mut output = Vec::with_capacity(data.len().saturating_mul(4).min(limit));
+
+    loop {
+        let last = reader.read_bits(1)? == 1;
+
+        match reader.read_bits(2)? {
+            0 => {
+                reader.align();
+                let len = reader.read_bits(16)? as usize;
+                let nlen = reader.read_bits(16)? as usize;
+                if len != !nlen & 0xFFFF {
+                    return Err(ImageError::InvalidData(
+                        "deflate stored block length mismatch",
+                    ));
+                }
+                reader.align();
+                if output.len() + len > limit {
+                    return Err(OUTPUT_TOO_LARGE);
+                }
+                output.extend_from_slice(reader.read_bytes(len)?);
+            }
+            1 => {
+                let (literals, distances) = fixed_tables();
+                inflate_block(&mut reader, &mut output, &literals, &distances, limit)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut output, &literals, &distances, limit)?;
+            }
+            _ => return Err(ImageError::InvalidData("deflate block type is invalid")),
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    reader.align();
+    Ok((output, reader.position))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    literals: &HuffmanTable,
+    distances: &HuffmanTable,
+    limit: usize,
+) -> ImageResult<()> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+
+        match symbol {
+            0..=255 if output.len() >= limit => return Err(OUTPUT_TOO_LARGE),
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol - 257;
+                let length = LENGTH_BASE[index] as usize
+                    + reader.read_bits(LENGTH_EXTRA[index] as u32)? as usize;
+
+                let index = distances.decode(reader)? as usize;
+                if index >= 30 {
+                    return Err(ImageError::InvalidData("deflate distance code is invalid"));
+                }
+                let distance = DIST_BASE[index] as usize
+                    + reader.read_bits(DIST_EXTRA[index] as u32)? as usize;
+
+                if distance > output.len() {
+                    return Err(ImageError::InvalidData("deflate distance is too far back"));
+                }
+                if output.len() + length > limit {
+                    return Err(OUTPUT_TOO_LARGE);
+                }
+
+                let start = output.len() - distance;
+                for i in 0..length {
+                    let byte = output[start + i];
+                    output.push(byte);
+                }
+            }
+            _ => return Err(ImageError::InvalidData("deflate literal code is invalid")),
+        }
+    }
+}
+
+fn fixed_tables() -> (HuffmanTable, HuffmanTable) {
+    let mut lengths = [0_u8; 288];
+    lengths[0..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..288].fill(8);
+
+    (
+        HuffmanTable::new(&lengths).unwrap(),
+        HuffmanTable::new(&[5; 30]).unwrap(),
+    )
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> ImageResult<(HuffmanTable, HuffmanTable)> {
+    let hlit = reader.read_bits(5)? as usize + 257;
+    let hdist = reader.read_bits(5)? as usize + 1;
+    let hclen = reader.read_bits(4)? as usize + 4;
+
+    let mut code_lengths = [0_u8; 19];
+    for index in CODE_LENGTH_ORDER.iter().take(hclen) {
+        code_lengths[*index] = reader.read_bits(3)? as u8;
+    }
+    let code_table = HuffmanTable::new(&code_lengths)?;
+
+    let mut lengths = Vec::with_capacity(hlit + hdist);
+    while lengths.len() < hlit + hdist {
+        let symbol = code_table.decode(reader)?;
+        match symbol {
+            0..=15 => lengths.push(symbol as u8),
+            16 => {
+                let previous = *lengths.last().ok_or(ImageError::InvalidData(
+                    "deflate repeat without previous length",
+                ))?;
+                let repeat = 3 + reader.read_bits(2)? as usize;
+                lengths.extend(std::iter::repeat_n(previous, repeat));
+            }
+            17 => {
+                let repeat = 3 + reader.read_bits(3)? as usize;
+                lengths.extend(std::iter::repeat_n(0, repeat));
+            }
+            _ => {
+                let repeat = 11 + reader.read_bits(7)? as usize;
+                lengths.extend(std::iter::repeat_n(0, repeat));
+            }
+        }
+    }
+
+    if lengths.len() > hlit + hdist {
+        return Err(ImageError::InvalidData("deflate code lengths overflow"));
+    }
+    if lengths[256] == 0 {
+        return Err(ImageError::InvalidData(
+            "deflate block has no end-of-block code",
+        ));
+    }
+
+    Ok((
+        HuffmanTable::new(&lengths[..hlit])?,
+        HuffmanTable::new(&lengths[hlit..])?,
+    ))
+}
+
+fn lz77(data: &[u8]) -> Vec<Symbol> {
+    let mut symbols = Vec::with_capacity(data.len() / 2);
+    let mut head = vec![usize::MAX; HASH_SIZE];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+
+    let mut index = 0;
+    while index < data.len() {
+        let (best_len, best_dist) = longest_match(data, index, &head, &prev);
+
+        let step = if best_len >= MIN_MATCH {
+            symbols.push(Symbol::Match(best_len as u16, best_dist as u16));
+            best_len
+        } else {
+            symbols.push(Symbol::Literal(data[index]));
+            1
+        };
+
+        for i in index..index + step {
+            if i + MIN_MATCH <= data.len() {
+                let h = hash(data, i);
+                prev[i % WINDOW_SIZE] = head[h];
+                head[h] = i;
+            }
+        }
+        index += step;
+    }
+
+    symbols
+}
+
+fn hash(data: &[u8], index: usize) -> usize {
+    let value =
+        (data[index] as usize) << 16 | (data[index + 1] as usize) << 8 | data[index + 2] as usize;
+    (value.wrapping_mul(2654435761) >> 8) & (HASH_SIZE - 1)
+}
+
+fn longest_match(data: &[u8], index: usize, head: &[usize], prev: &[usize]) -> (usize, usize) {
+    if index + MIN_MATCH > data.len() {
+        return (0, 0);
+    }
+
+    let max_len = MAX_MATCH.min(data.len() - index);
+    let mut best = (0, 0);
+    let mut candidate = head[hash(data, index)];
+
+    for _ in 0..MAX_CHAIN {
+        if candidate == usize::MAX || index - candidate > WINDOW_SIZE {
+            break;
+        }
+
+        let probe = best.0.min(max_len - 1);
+        if data[candidate + probe] == data[index + probe] {
+            let len = data[candidate..candidate + max_len]
+                .iter()
+                .zip(&data[index..index + max_len])
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if len > best.0 {
+                best = (len, index - candidate);
+                if len == max_len {
+                    break;
+                }
+            }
+        }
+
+        let previous = prev[candidate % WINDOW_SIZE];
+        if previous == usize::MAX || previous >= candidate {
+            break;
+        }
+        candidate = previous;
+    }
+
+    best
+}
+
+fn write_dynamic_block(writer: &mut BitWriter, symbols: &[Symbol], last: bool) {
+    let mut literal_freqs = [0_u32; 286];
+    let mut distance_freqs = [0_u32; 30];
+
+    for symbol in symbols {
+        match symbol {
+            Symbol::Literal(byte) => literal_freqs[*byte as usize] += 1,
+            Symbol::Match(len, dist) => {
+                literal_freqs[257 + length_code(*len)] += 1;
+                distance_freqs[distance_code(*dist)] += 1;
+            }
+        }
+    }
+    literal_freqs[256] = 1;
+
+    // Garante ao menos dois codigos de distancia, como os decodificadores esperam
+    for freq in distance_freqs.iter_mut().take(2) {
+        if *freq == 0 {
+            *freq = 1;
+        }
+    }
+
+    let literal_lengths = code_lengths(&literal_freqs, MAX_BITS);
+    let distance_lengths = code_lengths(&distance_freqs, MAX_BITS);
+    let literal_codes = canonical_codes(&literal_lengths);
+    let distance_codes = canonical_codes(&distance_lengths);
+
+    let hlit = 257.max(literal_lengths.iter().rposition(|l| *l > 0).unwrap_or(0) + 1);
+    let hdist = 1.max(distance_lengths.iter().rposition(|l| *l > 0).unwrap_or(0) + 1);
+
+    let mut all_lengths = literal_lengths[..hlit].to_vec();
+    all_lengths.extend_from_slice(&distance_lengths[..hdist]);
+    let runs = run_length_lengths(&all_lengths);
+
+    let mut code_freqs = [0_u32; 19];
+    for (symbol, _) in &runs {
+        code_freqs[*symbol as usize] += 1;
+    }
+    let code_length_lengths = code_lengths(&code_freqs, 7);
+    let code_length_codes = canonical_codes(&code_length_lengths);
+
+    let hclen = 4.max(
+        CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|i| code_length_lengths[*i] > 0)
+            .unwrap_or(0)
+            + 1,
+    );
+
+    writer.write_bits(last as u32, 1);
+    writer.write_bits(2, 2);
+    writer.write_bits((hlit - 257) as u32, 5);
+    writer.write_bits((hdist - 1) as u32, 5);
+    writer.write_bits((hclen - 4) as u32, 4);
+
+    for index in CODE_LENGTH_ORDER.iter().take(hclen) {
+        writer.write_bits(code_length_lengths[*index] as u32, 3);
+    }
+
+    for (symbol, extra) in &runs {
+        let symbol = *symbol as usize;
+        writer.write_code(code_length_codes[symbol], code_length_lengths[symbol]);
+        match symbol {
+            16 => writer.write_bits(*extra as u32, 2),
+            17 => writer.write_bits(*extra as u32, 3),
+            18 => writer.write_bits(*extra as u32, 7),
+            _ => {}
+        }
+    }
+
+    for symbol in symbols {
+        match symbol {
+            Symbol::Literal(byte) => {
+                let byte = *byte as usize;
+                writer.write_code(literal_codes[byte], literal_lengths[byte]);
+            }
+            Symbol::Match(len, dist) => {
+                let code = length_code(*len);
+                writer.write_code(literal_codes[257 + code], literal_lengths[257 + code]);
+                writer.write_bits((*len - LENGTH_BASE[code]) as u32, LENGTH_EXTRA[code] as u32);
+
+                let code = distance_code(*dist);
+                writer.write_code(distance_codes[code], distance_lengths[code]);
+                writer.write_bits((*dist - DIST_BASE[code]) as u32, DIST_EXTRA[code] as u32);
+            }
+        }
+    }
+
+    writer.write_code(literal_codes[256], literal_lengths[256]);
+}
+
+fn run_length_lengths(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut runs = Vec::new();
+    let mut index = 0;
+
+    while index < lengths.len() {
+        let value = lengths[index];
+        let run = lengths[index..].iter().take_while(|l| **l == value).count();
+
+        if value == 0 && run >= 3 {
+            let run = run.min(138);
+            if run >= 11 {
+                runs.push((18, (run - 11) as u8));
+            } else {
+                runs.push((17, (run - 3) as u8));
+            }
+            index += run;
+        } else if value != 0 && run >= 4 {
+            runs.push((value, 0));
+            let run = (run - 1).min(6);
+            runs.push((16, (run - 3) as u8));
+            index += run + 1;
+        } else {
+            runs.push((value, 0));
+            index += 1;
+        }
+    }
+
+    runs
+}
+
+fn length_code(len: u16) -> usize {
+    LENGTH_BASE.iter().rposition(|base| *base <= len).unwrap()
+}
+
+fn distance_code(dist: u16) -> usize {
+    DIST_BASE.iter().rposition(|base| *base <= dist).unwrap()
+}
+
+// Enums...
+enum Symbol {
+    Literal(u8),
+    Match(u16, u16),
+}
+
+// Structs...
+/// Tabela de decodificacao Huffman indexada pelos proximos `MAX_BITS` bits (LSB primeiro)
+struct HuffmanTable {
+    entries: Vec<(u16, u8)>,
+}
+
+impl HuffmanTable {
+    fn new(lengths: &[u8]) -> ImageResult<Self> {
+        let codes = canonical_codes(lengths);
+        let mut entries = vec![(0_u16, 0_u8); 1 << MAX_BITS];
+
+        for (symbol, (len, code)) in lengths.iter().zip(codes).enumerate() {
+            let len = *len as usize;
+            if len == 0 {
+                continue;
+            }
+            if len > MAX_BITS || code as usize >= 1 << len {
+                return Err(ImageError::InvalidData(
+                    "deflate huffman code is oversubscribed",
+                ));
+            }
+
+            let reversed = (code.reverse_bits() >> (16 - len)) as usize;
+            for fill in (reversed..1 << MAX_BITS).step_by(1 << len) {
+                entries[fill] = (symbol as u16, len as u8);
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> ImageResult<u16> {
+        let (symbol, len) = self.entries[reader.peek_bits(MAX_BITS as u32) as usize];
+        if len == 0 {
+            return Err(ImageError::InvalidData("deflate huffman code is invalid"));
+        }
+
+        reader.consume(len as u32)?;
+        Ok(symbol)
+    }
+}
+
+/// Le bits com o menos significativo primeiro, como no deflate e no VP8L
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        while self.count <= 56 {
+            let byte = self.data.get(self.position).copied().unwrap_or(0);
+            self.buffer |= (byte as u64) << self.count;
+            self.position += 1;
+            self.count += 8;
+        }
+    }
+
+    pub(crate) fn peek_bits(&mut self, count: u32) -> u32 {
+        if self.count < count {
+            self.refill();
+        }
+        (self.buffer & ((1 << count) - 1)) as u32
+    }
+
+    pub(crate) fn consume(&mut self, count: u32) -> ImageResult<()> {
+        if self.count < count {
+            self.refill();
+        }
+        self.buffer >>= count;
+        self.count -= count;
+
+        if self.position - (self.count as usize / 8) > self.data.len() {
+            return Err(ImageError::Truncated);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read_bits(&mut self, count: u32) -> ImageResult<u32> {
+        let value = self.peek_bits(count);
+        self.consume(count)?;
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        let skip = self.count % 8;
+        self.buffer >>= skip;
+        self.count -= skip;
+
+        // Devolve os bytes inteiros ainda nao consumidos
+        self.position -= self.count as usize / 8;
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    fn read_bytes(&mut self, len: usize) -> ImageResult<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + len)
+            .ok_or(ImageError::Truncated)?;
+        self.position += len;
+        Ok(bytes)
+    }
+}
+
+/// Grava bits com o menos significativo primeiro
+pub(crate) struct BitWriter {
+    output: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    pub(crate) fn new() -> Self {
+        Self {
+            output: Vec::new(),
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    pub(crate) fn write_bits(&mut self, value: u32, count: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += count;
+
+        while self.count >= 8 {
+            self.output.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    pub(crate) fn write_code(&mut self, code: u16, len: u8) {
+        let reversed = code.reverse_bits() >> (16 - len as u32);
+        self.write_bits(reversed as u32, len as u32);
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.output.push(self.buffer as u8);
+        }
+        self.output
+    }
+}
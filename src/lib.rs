@@ -1,3 +1,6 @@
+/// Modulo dos erros
+pub mod error;
+
 /// Modulo dos filtros
 pub mod filters;
 
@@ -0,0 +1,142 @@
+use std_image::error::ImageError;
+use std_image::filters::{FilterError, flip_h::FlipH};
+use std_image::images::{
+    Image, RGB,
+    animation::Animation,
+    buffer::ImageBuffer,
+    frames::{Disposal, Frame},
+    gif::Gif,
+};
+
+/// Pixel da tela vazia
+fn clear() -> RGB {
+    RGB::new(0, 0, 0, Some(0))
+}
+
+fn solid(color: RGB, width: usize, height: usize) -> ImageBuffer {
+    ImageBuffer::new(width, height, color).unwrap()
+}
+
+fn frame(image: ImageBuffer, left: usize, top: usize, disposal: Disposal) -> Frame {
+    let mut frame = Frame::new(image);
+    frame.set_offset(left, top);
+    frame.set_disposal(disposal);
+    frame
+}
+
+/// Tela 3x1: fundo vermelho, depois quadros de 1 pixel com cada tipo de descarte
+fn disposals() -> Animation {
+    let (red, green, blue) = (
+        RGB::new(255, 0, 0, None),
+        RGB::new(0, 255, 0, None),
+        RGB::new(0, 0, 255, None),
+    );
+
+    let mut animation = Animation::new(3, 1);
+    animation.push(frame(solid(red, 3, 1), 0, 0, Disposal::None));
+    animation.push(frame(solid(blue, 1, 1), 1, 0, Disposal::Background));
+    animation.push(frame(solid(green, 1, 1), 0, 0, Disposal::Previous));
+    animation.push(frame(solid(clear(), 1, 1), 2, 0, Disposal::None));
+    animation
+}
+
+#[test]
+fn disposal_controls_the_next_canvas() {
+    let red = RGB::new(255, 0, 0, None);
+    let canvases = disposals().composite().unwrap();
+    let pixels = canvases
+        .iter()
+        .map(|canvas| canvas.get_pixels().to_vec())
+        .collect::<Vec<_>>();
+
+    assert_eq!(pixels[0], vec![red.clone(); 3]);
+    assert_eq!(
+        pixels[1],
+        vec![red.clone(), RGB::new(0, 0, 255, None), red.clone()]
+    );
+    // O fundo limpa o pixel azul, e o verde some depois porque restaura a tela anterior
+    assert_eq!(
+        pixels[2],
+        vec![RGB::new(0, 255, 0, None), clear(), red.clone()]
+    );
+    // Um pixel transparente nao muda a tela
+    assert_eq!(pixels[3], vec![red.clone(), clear(), red]);
+}
+
+#[test]
+fn alpha_is_blended_over_the_canvas() {
+    let mut animation = Animation::new(2, 1);
+    animation.push(frame(
+        solid(RGB::new(0, 0, 200, None), 1, 1),
+        0,
+        0,
+        Disposal::None,
+    ));
+    animation.push(frame(
+        solid(RGB::new(255, 0, 0, Some(128)), 2, 1),
+        0,
+        0,
+        Disposal::None,
+    ));
+
+    let canvases = animation.composite().unwrap();
+    assert_eq!(
+        canvases[0].get_pixels(),
+        &[RGB::new(0, 0, 200, None), clear()]
+    );
+    // Sobre um pixel opaco o resultado e opaco; sobre a tela vazia fica a cor do quadro
+    assert_eq!(
+        canvases[1].get_pixels(),
+        &[
+            RGB::new(128, 0, 100, Some(255)),
+            RGB::new(255, 0, 0, Some(128))
+        ]
+    );
+}
+
+#[test]
+fn coalesce_keeps_full_canvases() {
+    let mut animation = disposals();
+    let canvases = animation.composite().unwrap();
+    animation.coalesce().unwrap();
+
+    for (frame, canvas) in animation.frames().iter().zip(&canvases) {
+        assert_eq!(frame.image(), canvas);
+        assert_eq!((frame.left(), frame.top()), (0, 0));
+        assert_eq!(frame.disposal(), Disposal::Background);
+    }
+    assert_eq!(animation.composite().unwrap(), canvases);
+
+    // Espelhar a tela inteira move tambem os quadros pequenos
+    let mut animation = disposals();
+    animation.filter_composited(FlipH::full()).unwrap();
+    let second = animation.frames().get(1).unwrap().image();
+    assert_eq!(second.get_pixels()[1], RGB::new(0, 0, 255, None));
+    assert_eq!(
+        animation.frames().get(2).unwrap().image().get_pixels()[2],
+        RGB::new(0, 255, 0, None)
+    );
+}
+
+#[test]
+fn offsets_past_usize_are_rejected() {
+    let mut animation = Animation::new(2, 2);
+    animation.push(Frame::new(solid(clear(), 2, 2)));
+    animation.push(frame(solid(clear(), 2, 1), usize::MAX, 0, Disposal::None));
+
+    assert!(matches!(
+        animation.composite(),
+        Err(ImageError::DimensionOverflow)
+    ));
+    assert!(matches!(
+        animation.clone().coalesce(),
+        Err(ImageError::DimensionOverflow)
+    ));
+    assert!(matches!(
+        animation.clone().filter_composited(FlipH::full()),
+        Err(FilterError::Image(ImageError::DimensionOverflow))
+    ));
+
+    let gif = Gif::try_from(animation).unwrap();
+    assert!(matches!(gif.to_bytes(), Err(ImageError::DimensionOverflow)));
+}
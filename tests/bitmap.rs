@@ -0,0 +1,475 @@
+mod common;
+
+use common::gradient;
+use std_image::error::ImageError;
+use std_image::images::{
+    Image, Orientation, RGB,
+    bitmap::{Bitmap, ColorSpace, Compression},
+};
+
+/// Imagem com poucas cores, que cabe na paleta do RLE4
+fn stripes(width: usize, height: usize) -> Vec<RGB> {
+    (0..width * height)
+        .map(|index| match (index % width) / 3 % 4 {
+            0 => RGB::new(255, 0, 0, None),
+            1 => RGB::new(0, 255, 0, None),
+            2 => RGB::new(0, 0, 255, None),
+            _ => RGB::new(255, 255, 255, None),
+        })
+        .collect()
+}
+
+/// Cabecalho BITMAPINFOHEADER de 40 bytes
+fn info_header(width: i32, height: i32, bits: u16, compression: u32, colors_used: u32) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&40_u32.to_le_bytes());
+    header.extend_from_slice(&width.to_le_bytes());
+    header.extend_from_slice(&height.to_le_bytes());
+    header.extend_from_slice(&1_u16.to_le_bytes());
+    header.extend_from_slice(&bits.to_le_bytes());
+    header.extend_from_slice(&compression.to_le_bytes());
+    header.extend_from_slice(&[0; 12]);
+    header.extend_from_slice(&colors_used.to_le_bytes());
+    header.extend_from_slice(&[0; 4]);
+    header
+}
+
+/// Arquivo BMP com o cabecalho DIB informado, seguido das mascaras/paleta e dos pixels. O
+/// tamanho dos pixels no cabecalho DIB e preenchido aqui
+fn bmp(dib: &[u8], table: &[u8], data: &[u8]) -> Vec<u8> {
+    let start = (14 + dib.len() + table.len()) as u32;
+    let mut bytes = b"BM".to_vec();
+    bytes.extend_from_slice(&(start + data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&start.to_le_bytes());
+    bytes.extend_from_slice(&dib[..20]);
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&dib[24..]);
+    bytes.extend_from_slice(table);
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// Linhas de indices empacotados na profundidade informada, com o alinhamento de 4 bytes
+fn packed_rows(rows: &[Vec<usize>], bits: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for row in rows {
+        let mut line = vec![0_u8; (row.len() * bits).div_ceil(32) * 4];
+        for (x, index) in row.iter().enumerate() {
+            let shift = 8 - bits - (x * bits) % 8;
+            line[x * bits / 8] |= (*index << shift) as u8;
+        }
+        bytes.extend(line);
+    }
+    bytes
+}
+
+/// Cabecalho V4 (108 bytes) ou V5 (124 bytes) de 32 bits com mascaras e espaco de cor
+fn color_space_header(size: u32, masks: [u32; 4], color_space: u32) -> Vec<u8> {
+    let mut header = info_header(2, 2, 32, 3, 0);
+    header[0..4].copy_from_slice(&size.to_le_bytes());
+    header.resize(size as usize, 0);
+    for (i, mask) in masks.iter().enumerate() {
+        header[40 + i * 4..44 + i * 4].copy_from_slice(&mask.to_le_bytes());
+    }
+    header[56..60].copy_from_slice(&color_space.to_le_bytes());
+    header
+}
+
+fn rle_bytes(compression: Compression) -> Vec<u8> {
+    let mut bitmap = Bitmap::from_pixels(13, 6, stripes(13, 6), 24).unwrap();
+    bitmap.set_compression(compression);
+    bitmap.to_bytes().unwrap()
+}
+
+#[test]
+fn round_trip() {
+    for bits in [24, 32] {
+        let alpha = bits == 32;
+        let bitmap = Bitmap::from_pixels(9, 4, gradient(9, 4, alpha), bits).unwrap();
+        let decoded = Bitmap::from_bytes(&bitmap.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(decoded.get_pixels(), gradient(9, 4, alpha).as_slice());
+    }
+}
+
+#[test]
+fn rle_round_trip() {
+    for compression in [Compression::Rle8, Compression::Rle4] {
+        let decoded = Bitmap::from_bytes(&rle_bytes(compression)).unwrap();
+
+        assert_eq!(decoded.compression(), compression);
+        assert_eq!(decoded.get_pixels(), stripes(13, 6).as_slice());
+    }
+}
+
+#[test]
+fn rle_with_huge_header_is_rejected() {
+    for compression in [Compression::Rle8, Compression::Rle4] {
+        let mut bytes = rle_bytes(compression);
+        bytes[18..22].copy_from_slice(&0x7FFF_0000_i32.to_le_bytes());
+        bytes[22..26].copy_from_slice(&0x7FFF_0000_i32.to_le_bytes());
+
+        assert!(matches!(
+            Bitmap::from_bytes(&bytes),
+            Err(ImageError::Truncated)
+        ));
+    }
+}
+
+#[test]
+fn rle_with_wide_header_and_skips_is_rejected() {
+    // 174 bytes que pedem 4 linhas de 2^30 pixels e so pulam linhas e colunas
+    let mut data = [0, 2, 255, 0].repeat(27);
+    data.extend_from_slice(&[0, 0, 0, 1]);
+    let bytes = bmp(&info_header(0x4000_0000, 4, 8, 1, 2), &[0; 8], &data);
+    assert_eq!(bytes.len(), 174);
+
+    assert!(matches!(
+        Bitmap::from_bytes(&bytes),
+        Err(ImageError::Truncated)
+    ));
+}
+
+#[test]
+fn truncated_rle_is_an_error() {
+    let bytes = rle_bytes(Compression::Rle8);
+
+    for len in [60, bytes.len() / 2, bytes.len() - 3] {
+        assert!(Bitmap::from_bytes(&bytes[..len]).is_err());
+    }
+}
+
+#[test]
+fn bitfields_start_with_default_masks() {
+    let mut bitmap = Bitmap::from_pixels(9, 4, gradient(9, 4, true), 32).unwrap();
+    bitmap.set_compression(Compression::AlphaBitfields);
+    assert_eq!(
+        bitmap.channel_masks(),
+        [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000]
+    );
+
+    let decoded = Bitmap::from_bytes(&bitmap.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.compression(), Compression::AlphaBitfields);
+    assert_eq!(decoded.get_pixels(), gradient(9, 4, true).as_slice());
+
+    let mut bitmap = Bitmap::from_pixels(9, 4, gradient(9, 4, false), 32).unwrap();
+    bitmap.set_compression(Compression::Bitfields);
+    let decoded = Bitmap::from_bytes(&bitmap.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.compression(), Compression::Bitfields);
+    assert_eq!(decoded.get_pixels(), gradient(9, 4, false).as_slice());
+}
+
+#[test]
+fn bitfields_are_ignored_at_24_bits() {
+    let mut bitmap = Bitmap::from_pixels(9, 4, gradient(9, 4, false), 24).unwrap();
+    bitmap.set_compression(Compression::Bitfields);
+
+    assert_eq!(bitmap.compression(), Compression::Rgb);
+    let decoded = Bitmap::from_bytes(&bitmap.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.get_pixels(), gradient(9, 4, false).as_slice());
+}
+
+#[test]
+fn indexed_depths_decode_and_write_back() {
+    let (width, height) = (11, 3);
+
+    for (bits, colors) in [(1, 2), (4, 16), (8, 40)] {
+        let palette: Vec<RGB> = (0..colors)
+            .map(|k| RGB::new((k * 6) as u8, 255 - (k * 5) as u8, (k * 3 + 1) as u8, None))
+            .collect();
+        let table: Vec<u8> = palette
+            .iter()
+            .flat_map(|c| [c.blue(), c.green(), c.red(), 0])
+            .collect();
+        // Linhas de cima para baixo; o arquivo as grava de baixo para cima
+        let rows: Vec<Vec<usize>> = (0..height)
+            .map(|y| (0..width).map(|x| (x * 7 + y * 3) % colors).collect())
+            .collect();
+        let file_rows: Vec<Vec<usize>> = rows.iter().rev().cloned().collect();
+        let bytes = bmp(
+            &info_header(width as i32, height as i32, bits, 0, colors as u32),
+            &table,
+            &packed_rows(&file_rows, bits as usize),
+        );
+        let expected: Vec<RGB> = rows.concat().iter().map(|i| palette[*i].clone()).collect();
+
+        let mut bitmap = Bitmap::from_bytes(&bytes).unwrap();
+        assert_eq!(bitmap.palette(), palette.as_slice(), "{bits} bits");
+        assert_eq!(bitmap.get_pixels(), expected.as_slice(), "{bits} bits");
+
+        // Sem mudancas as cores cabem na paleta e a profundidade indexada e mantida
+        let saved = bitmap.to_bytes().unwrap();
+        assert_eq!(u16::from_le_bytes([saved[28], saved[29]]), bits);
+        assert_eq!(saved.len(), bytes.len());
+        let decoded = Bitmap::from_bytes(&saved).unwrap();
+        assert_eq!(decoded.palette(), palette.as_slice());
+        assert_eq!(decoded.get_pixels(), expected.as_slice());
+
+        // Uma cor fora da paleta obriga a gravar em 24 bits
+        *bitmap.pixel(0, 0).unwrap() = RGB::new(1, 2, 3, None);
+        let saved = bitmap.to_bytes().unwrap();
+        assert_eq!(u16::from_le_bytes([saved[28], saved[29]]), 24);
+        let decoded = Bitmap::from_bytes(&saved).unwrap();
+        assert!(decoded.palette().is_empty());
+        assert_eq!(decoded.get_pixel(0, 0), Some(&RGB::new(1, 2, 3, None)));
+        assert_eq!(decoded.get_pixels()[1..], expected[1..]);
+    }
+}
+
+#[test]
+fn sixteen_bits_565_and_555() {
+    let row =
+        |values: [u16; 4]| -> Vec<u8> { values.iter().flat_map(|v| v.to_le_bytes()).collect() };
+    let expected = [
+        RGB::new(255, 0, 0, None),
+        RGB::new(0, 255, 0, None),
+        RGB::new(0, 0, 255, None),
+        RGB::new(132, 130, 132, None),
+    ];
+    let masks_565: Vec<u8> = [0xF800_u32, 0x07E0, 0x001F]
+        .iter()
+        .flat_map(|m| m.to_le_bytes())
+        .collect();
+    let rgb_565 = bmp(
+        &info_header(4, 1, 16, 3, 0),
+        &masks_565,
+        &row([0xF800, 0x07E0, 0x001F, 0x8410]),
+    );
+    // Sem BI_BITFIELDS os 16 bits sao 555
+    let rgb_555 = bmp(
+        &info_header(4, 1, 16, 0, 0),
+        &[],
+        &row([0x7C00, 0x03E0, 0x001F, 0x4210]),
+    );
+
+    let bitmap = Bitmap::from_bytes(&rgb_565).unwrap();
+    assert_eq!(bitmap.compression(), Compression::Bitfields);
+    assert_eq!(bitmap.channel_masks(), [0xF800, 0x07E0, 0x001F, 0]);
+    assert_eq!(bitmap.get_pixels(), expected.as_slice());
+    assert_eq!(bitmap.to_bytes().unwrap(), rgb_565);
+
+    let expected_555 = [&expected[..3], &[RGB::new(132, 132, 132, None)]].concat();
+    let bitmap = Bitmap::from_bytes(&rgb_555).unwrap();
+    assert_eq!(bitmap.compression(), Compression::Rgb);
+    assert_eq!(bitmap.channel_masks(), [0x7C00, 0x03E0, 0x001F, 0]);
+    assert_eq!(bitmap.get_pixels(), expected_555.as_slice());
+    assert_eq!(bitmap.to_bytes().unwrap(), rgb_555);
+}
+
+#[test]
+fn v4_header_with_custom_masks() {
+    // Canais na ordem ABGR, ao contrario do padrao
+    let masks = [0x0000_00FF, 0x0000_FF00, 0x00FF_0000, 0xFF00_0000];
+    let data = [
+        [10, 20, 30, 40],
+        [50, 60, 70, 80],
+        [1, 2, 3, 255],
+        [4, 5, 6, 0],
+    ]
+    .concat();
+    let bytes = bmp(&color_space_header(108, masks, 0x7352_4742), &[], &data);
+
+    let bitmap = Bitmap::from_bytes(&bytes).unwrap();
+    assert_eq!(bitmap.color_space(), Some(ColorSpace::Srgb));
+    assert_eq!(bitmap.channel_masks(), masks);
+    assert!(bitmap.icc_profile().is_none());
+    // Linhas de baixo para cima no arquivo
+    assert_eq!(
+        bitmap.get_pixels(),
+        [
+            RGB::new(1, 2, 3, Some(255)),
+            RGB::new(4, 5, 6, Some(0)),
+            RGB::new(10, 20, 30, Some(40)),
+            RGB::new(50, 60, 70, Some(80)),
+        ]
+    );
+    assert_eq!(bitmap.to_bytes().unwrap(), bytes);
+}
+
+#[test]
+fn v5_embedded_profile_is_kept_apart_from_pixels() {
+    let masks = [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000];
+    let profile = b"fake icc profile".to_vec();
+    let data = [
+        [1, 2, 3, 4],
+        [5, 6, 7, 8],
+        [9, 10, 11, 12],
+        [13, 14, 15, 16],
+    ]
+    .concat();
+    let expected = [
+        RGB::new(11, 10, 9, Some(12)),
+        RGB::new(15, 14, 13, Some(16)),
+        RGB::new(3, 2, 1, Some(4)),
+        RGB::new(7, 6, 5, Some(8)),
+    ];
+    let mut header = color_space_header(124, masks, 0x4D42_4544);
+    header[116..120].copy_from_slice(&(profile.len() as u32).to_le_bytes());
+
+    // Perfil depois dos pixels, como o Windows grava
+    let mut after = header.clone();
+    after[112..116].copy_from_slice(&(124 + data.len() as u32).to_le_bytes());
+    let after = [bmp(&after, &[], &data), profile.clone()].concat();
+
+    // Perfil entre o cabecalho e os pixels, pulado pelo offset dos pixels
+    let mut before = header;
+    before[112..116].copy_from_slice(&124_u32.to_le_bytes());
+    let before = bmp(&before, &profile, &data);
+
+    for bytes in [after, before] {
+        let bitmap = Bitmap::from_bytes(&bytes).unwrap();
+        assert_eq!(bitmap.color_space(), Some(ColorSpace::Embedded));
+        assert_eq!(bitmap.icc_profile(), Some(profile.as_slice()));
+        assert_eq!(bitmap.get_pixels(), expected);
+
+        let decoded = Bitmap::from_bytes(&bitmap.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.icc_profile(), Some(profile.as_slice()));
+        assert_eq!(decoded.channel_masks(), masks);
+        assert_eq!(decoded.get_pixels(), expected);
+    }
+}
+
+/// Pixels 3x2 marcados com a letra da posicao: "abc" em cima e "def" embaixo
+fn letters(text: &str) -> Vec<RGB> {
+    text.bytes()
+        .map(|letter| RGB::new(letter, 0, 0, None))
+        .collect()
+}
+
+fn rows_24(pixels: &[RGB], width: usize) -> Vec<Vec<u8>> {
+    pixels
+        .chunks(width)
+        .map(|row| {
+            let mut line: Vec<u8> = row
+                .iter()
+                .flat_map(|p| [p.blue(), p.green(), p.red()])
+                .collect();
+            line.resize(line.len().next_multiple_of(4), 0);
+            line
+        })
+        .collect()
+}
+
+#[test]
+fn top_down_and_bottom_up_rows() {
+    let pixels = letters("abcdef");
+    let rows = rows_24(&pixels, 3);
+    let bottom_up = bmp(
+        &info_header(3, 2, 24, 0, 0),
+        &[],
+        &[&rows[1][..], &rows[0]].concat(),
+    );
+    let top_down = bmp(&info_header(3, -2, 24, 0, 0), &[], &rows.concat());
+
+    for bytes in [bottom_up, top_down] {
+        let bitmap = Bitmap::from_bytes(&bytes).unwrap();
+        assert_eq!((bitmap.widht(), bitmap.height()), (3, 2));
+        assert_eq!(bitmap.get_pixels(), pixels.as_slice());
+        // Origem no canto superior esquerdo, sem espelhar o x
+        assert_eq!(bitmap.get_pixel(2, 0), Some(&letters("c")[0]));
+        assert_eq!(bitmap.get_pixel(0, 1), Some(&letters("d")[0]));
+
+        // O sentido das linhas do arquivo e mantido ao salvar
+        let saved = bitmap.to_bytes().unwrap();
+        assert_eq!(saved, bytes);
+    }
+}
+
+#[test]
+fn orientation_is_applied_on_normalize() {
+    let cases = [
+        (Orientation::Normal, 3, "abcdef"),
+        (Orientation::FlipHorizontal, 3, "cbafed"),
+        (Orientation::Rotate180, 3, "fedcba"),
+        (Orientation::FlipVertical, 3, "defabc"),
+        (Orientation::Transpose, 2, "adbecf"),
+        (Orientation::Rotate90, 2, "daebfc"),
+        (Orientation::Transverse, 2, "fcebda"),
+        (Orientation::Rotate270, 2, "cfbead"),
+    ];
+
+    for (orientation, width, expected) in cases {
+        assert_eq!(
+            Orientation::from_exif(orientation.to_exif()),
+            Some(orientation)
+        );
+
+        let mut bitmap = Bitmap::from_pixels(3, 2, letters("abcdef"), 24).unwrap();
+        bitmap.set_orientation(orientation);
+        bitmap.normalize_orientation();
+
+        assert_eq!(bitmap.orientation(), Orientation::Normal, "{orientation:?}");
+        assert_eq!((bitmap.widht(), bitmap.height()), (width, 6 / width));
+        assert_eq!(bitmap.get_pixels(), letters(expected), "{orientation:?}");
+
+        let decoded = Bitmap::from_bytes(&bitmap.to_bytes().unwrap()).unwrap();
+        assert_eq!((decoded.widht(), decoded.height()), (width, 6 / width));
+        assert_eq!(decoded.get_pixels(), letters(expected), "{orientation:?}");
+    }
+}
+
+#[test]
+fn new_computes_headers_and_padding() {
+    let fill = RGB::new(10, 20, 30, Some(40));
+
+    // 5 pixels de 24 bits ocupam 15 bytes, alinhados em 16
+    for (bits, stride) in [(24, 16), (32, 20)] {
+        let bitmap = Bitmap::new(5, 3, fill.clone(), bits).unwrap();
+        let bytes = bitmap.to_bytes().unwrap();
+
+        assert_eq!(bitmap.identify(), "BM");
+        assert_eq!(bitmap.size_in_bytes() as usize, 54 + stride * 3);
+        assert_eq!(bytes.len(), 54 + stride * 3);
+        assert_eq!(
+            u32::from_le_bytes(bytes[2..6].try_into().unwrap()),
+            bitmap.size_in_bytes()
+        );
+        assert_eq!(u32::from_le_bytes(bytes[10..14].try_into().unwrap()), 54);
+        assert_eq!(u16::from_le_bytes([bytes[28], bytes[29]]), bits);
+        assert_eq!(
+            u32::from_le_bytes(bytes[34..38].try_into().unwrap()) as usize,
+            stride * 3
+        );
+        if bits == 24 {
+            assert_eq!(
+                bytes[54..54 + 16],
+                [
+                    30, 20, 10, 30, 20, 10, 30, 20, 10, 30, 20, 10, 30, 20, 10, 0
+                ]
+            );
+        }
+
+        let decoded = Bitmap::from_bytes(&bytes).unwrap();
+        let alpha = (bits == 32).then_some(40);
+        assert_eq!(decoded.get_pixels(), vec![RGB::new(10, 20, 30, alpha); 15]);
+    }
+}
+
+#[test]
+fn invalid_dimensions_and_depths_are_rejected() {
+    let fill = RGB::new(0, 0, 0, None);
+
+    for bits in [1, 8, 16] {
+        assert!(matches!(
+            Bitmap::new(2, 2, fill.clone(), bits),
+            Err(ImageError::UnsupportedBitDepth(b)) if b == bits
+        ));
+    }
+    assert!(matches!(
+        Bitmap::new(0, 2, fill.clone(), 24),
+        Err(ImageError::InvalidData(_))
+    ));
+    assert!(matches!(
+        Bitmap::from_pixels(2, 2, vec![fill.clone(); 3], 24),
+        Err(ImageError::InvalidData(_))
+    ));
+    assert!(matches!(
+        Bitmap::new(usize::MAX, 2, fill.clone(), 24),
+        Err(ImageError::DimensionOverflow)
+    ));
+    assert!(matches!(
+        Bitmap::from_pixels(i32::MAX as usize + 1, 1, Vec::new(), 24),
+        Err(ImageError::DimensionOverflow)
+    ));
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::gradient;
+use std_image::error::ImageError;
+use std_image::filters::flip_h::FlipH;
+use std_image::images::{
+    Format, Image, Orientation, RGB, bitmap::Bitmap, buffer::ImageBuffer, png::Png,
+};
+
+#[test]
+fn bitmap_conversions_are_lossless() {
+    for alpha in [false, true] {
+        let pixels = gradient(7, 4, alpha);
+        let mut bitmap = Bitmap::from_pixels(7, 4, pixels.clone(), 32).unwrap();
+        bitmap.set_orientation(Orientation::Rotate90);
+
+        let buffer = ImageBuffer::from(bitmap);
+        assert_eq!((buffer.widht(), buffer.height()), (7, 4));
+        assert_eq!(buffer.orientation(), Orientation::Rotate90);
+        assert_eq!(buffer.get_pixels(), pixels.as_slice());
+
+        // O bitmap gerado so tem 32 bits quando algum pixel tem alpha
+        let bitmap = Bitmap::try_from(buffer).unwrap();
+        let bytes = bitmap.to_bytes().unwrap();
+        assert_eq!(
+            u16::from_le_bytes([bytes[28], bytes[29]]),
+            if alpha { 32 } else { 24 }
+        );
+        assert_eq!(bitmap.orientation(), Orientation::Rotate90);
+        assert_eq!(bitmap.get_pixels(), pixels.as_slice());
+    }
+}
+
+#[test]
+fn codecs_convert_through_the_buffer() {
+    let pixels = gradient(6, 5, true);
+    let bitmap = Bitmap::from_pixels(6, 5, pixels.clone(), 32).unwrap();
+
+    let png = Png::try_from(ImageBuffer::from(bitmap)).unwrap();
+    let decoded = Png::from_bytes(&png.to_bytes().unwrap()).unwrap();
+    let bitmap = Bitmap::try_from(ImageBuffer::from(decoded)).unwrap();
+
+    assert_eq!(bitmap.get_pixels(), pixels.as_slice());
+}
+
+#[test]
+fn buffer_is_a_working_image() {
+    let mut buffer = ImageBuffer::from_pixels(3, 1, gradient(3, 1, false)).unwrap();
+    buffer.filter(FlipH::full()).unwrap();
+
+    let mut expected = gradient(3, 1, false);
+    expected.reverse();
+    assert_eq!(buffer.format(), Format::PNG);
+    assert_eq!(buffer.get_pixels(), expected.as_slice());
+
+    let filled = ImageBuffer::new(2, 3, RGB::new(1, 2, 3, None)).unwrap();
+    assert_eq!(filled.get_pixels(), vec![RGB::new(1, 2, 3, None); 6]);
+}
+
+#[test]
+fn invalid_sizes_are_rejected() {
+    let fill = RGB::new(0, 0, 0, None);
+
+    assert!(matches!(
+        ImageBuffer::from_pixels(2, 2, vec![fill.clone(); 5]),
+        Err(ImageError::InvalidData(_))
+    ));
+    assert!(matches!(
+        ImageBuffer::new(usize::MAX, 2, fill.clone()),
+        Err(ImageError::DimensionOverflow)
+    ));
+    // Um buffer vazio existe, mas nenhum codec aceita gravar zero pixels
+    let empty = ImageBuffer::from_pixels(0, 0, Vec::<RGB>::new()).unwrap();
+    assert!(Bitmap::try_from(empty.clone()).is_err());
+    assert!(Png::try_from(empty).is_err());
+}
@@ -0,0 +1,53 @@
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std_image::images::{RGB, pixel::Rgba};
+
+/// Gradiente com cores diferentes em cada pixel, com alpha opcional
+pub fn gradient(width: usize, height: usize, alpha: bool) -> Vec<RGB> {
+    (0..width * height)
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            RGB::new(
+                (x * 255 / width.max(2).saturating_sub(1)) as u8,
+                (y * 255 / height.max(2).saturating_sub(1)) as u8,
+                ((x + y) * 37 % 256) as u8,
+                alpha.then_some(((x * 7 + y * 13) % 256) as u8),
+            )
+        })
+        .collect()
+}
+
+/// Mesmo gradiente em tons de cinza
+pub fn gray(width: usize, height: usize) -> Vec<RGB> {
+    (0..width * height)
+        .map(|index| {
+            let value = ((index * 255) / (width * height).max(2).saturating_sub(1)) as u8;
+            RGB::new(value, value, value, None)
+        })
+        .collect()
+}
+
+/// Pixels de 16 bits cujos bits baixos mudam entre vizinhos, para notar reducoes para 8 bits
+pub fn gradient16(width: usize, height: usize, alpha: bool, gray: bool) -> Vec<Rgba<u16>> {
+    (0..width * height)
+        .map(|index| {
+            let value = (index as u32 * 40503 % 65536) as u16;
+            let alpha = match alpha {
+                true => (index as u32 * 7919 % 65536) as u16,
+                false => u16::MAX,
+            };
+            match gray {
+                true => Rgba([value, value, value, alpha]),
+                false => Rgba([value, value ^ 0x00FF, value.wrapping_add(1), alpha]),
+            }
+        })
+        .collect()
+}
+
+/// Caminho unico no diretorio temporario, removido se ja existir
+pub fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("std-image-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
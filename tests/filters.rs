@@ -0,0 +1,46 @@
+use std::error::Error;
+use std_image::error::ImageError;
+use std_image::filters::{FilterError, box_blur::BoxBlur, grayscale::GrayScale};
+use std_image::images::{Image, RGB, buffer::ImageBuffer, pixel::Rgba};
+
+#[test]
+fn filter_errors_describe_themselves() {
+    assert_eq!(
+        FilterError::InvalidFormat.to_string(),
+        "filter is not supported for this image format"
+    );
+    assert!(FilterError::InvalidDimensions.source().is_none());
+
+    let error = FilterError::from(ImageError::Truncated);
+    assert_eq!(error.to_string(), "image data is truncated");
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn gray_scale_truncates_rgb_and_rounds_wider_pixels() {
+    let mut buffer = ImageBuffer::from_pixels(
+        2,
+        1,
+        vec![RGB::new(0, 0, 2, None), RGB::new(10, 20, 31, Some(7))],
+    )
+    .unwrap();
+    buffer.filter(GrayScale).unwrap();
+    assert_eq!(
+        buffer.get_pixels(),
+        [RGB::new(0, 0, 0, None), RGB::new(20, 20, 20, Some(7))]
+    );
+
+    let mut buffer = ImageBuffer::from_pixels(1, 1, vec![Rgba([0_u16, 0, 2, 9])]).unwrap();
+    buffer.filter(GrayScale).unwrap();
+    assert_eq!(buffer.get_pixels(), [Rgba([1, 1, 1, 9])]);
+}
+
+#[test]
+fn box_blur_truncates_rgb_means() {
+    let red = |value| RGB::new(value, 0, 0, Some(50));
+    let mut buffer = ImageBuffer::from_pixels(3, 1, vec![red(0), red(1), red(1)]).unwrap();
+    buffer.filter(BoxBlur(1)).unwrap();
+
+    // O ultimo pixel da linha nao tem vizinhos completos e fica como esta
+    assert_eq!(buffer.get_pixels(), [red(0), red(0), red(1)]);
+}
@@ -0,0 +1,49 @@
+use std_image::images::Format;
+
+/// Cabecalho de um ICO com uma entrada de 16x16 em 32 bits
+const ICO_HEADER: [u8; 16] = [
+    0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x10, 0x10, 0x00, 0x00, 0x01, 0x00, 0x20, 0x00, 0x00, 0x00,
+];
+
+/// Cabecalho de um TGA true-color sem color map, 2x2 em 24 bits
+const TGA_HEADER: [u8; 16] = [
+    0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00,
+];
+
+#[test]
+fn detects_icon_directory() {
+    assert_eq!(Format::from_magic(&ICO_HEADER), Some(Format::ICO));
+
+    // CUR com hotspot (3, 5) dentro da imagem
+    let mut cursor = ICO_HEADER;
+    cursor[2] = 0x02;
+    cursor[10..14].copy_from_slice(&[0x03, 0x00, 0x05, 0x00]);
+    assert_eq!(Format::from_magic(&cursor), Some(Format::ICO));
+}
+
+#[test]
+fn uncompressed_tga_is_not_an_icon() {
+    assert_eq!(Format::from_magic(&TGA_HEADER), None);
+}
+
+#[test]
+fn rejects_implausible_icon_directory() {
+    let mut empty = ICO_HEADER;
+    empty[4] = 0x00;
+    assert_eq!(Format::from_magic(&empty), None);
+
+    let mut reserved = ICO_HEADER;
+    reserved[9] = 0xFF;
+    assert_eq!(Format::from_magic(&reserved), None);
+
+    let mut bit_count = ICO_HEADER;
+    bit_count[12] = 0x07;
+    assert_eq!(Format::from_magic(&bit_count), None);
+
+    let mut hotspot = ICO_HEADER;
+    hotspot[2] = 0x02;
+    hotspot[10..14].copy_from_slice(&[0x10, 0x00, 0x00, 0x00]);
+    assert_eq!(Format::from_magic(&hotspot), None);
+
+    assert_eq!(Format::from_magic(&ICO_HEADER[..8]), None);
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use common::{gradient, temp_path};
+use std::time::Duration;
+use std_image::error::ImageError;
+use std_image::filters::flip_h::FlipH;
+use std_image::images::{
+    Image, RGB,
+    animation::Animation,
+    buffer::ImageBuffer,
+    frames::{Disposal, Frame, Frames},
+    gif::Gif,
+};
+
+/// Pixels opacos ou totalmente transparentes, que o GIF guarda sem perdas
+fn transparent(width: usize, height: usize) -> Vec<RGB> {
+    gradient(width, height, false)
+        .into_iter()
+        .enumerate()
+        .map(|(index, c)| match index % 5 {
+            0 => RGB::new(0, 0, 0, Some(0)),
+            _ => RGB::new(c.red(), c.green(), c.blue(), None),
+        })
+        .collect()
+}
+
+fn frame(image: ImageBuffer, left: usize, top: usize, disposal: Disposal) -> Frame {
+    let mut frame = Frame::new(image);
+    frame.set_offset(left, top);
+    frame.set_delay(Duration::from_millis(120));
+    frame.set_disposal(disposal);
+    frame
+}
+
+fn animation() -> Frames {
+    let mut frames = Frames::new(8, 6);
+    frames.set_loop_count(3);
+    let full = ImageBuffer::from_pixels(8, 6, transparent(8, 6)).unwrap();
+    let small = ImageBuffer::from_pixels(3, 2, transparent(3, 2)).unwrap();
+    frames.push(frame(full, 0, 0, Disposal::None));
+    frames.push(frame(small.clone(), 4, 3, Disposal::Background));
+    frames.push(frame(small, 1, 1, Disposal::Previous));
+    frames
+}
+
+/// Arquivo de um quadro sem entrelacamento e a posicao do separador da imagem (0x2C). O
+/// codificador grava a tela, a paleta global e a Graphic Control Extension antes dele
+fn single_frame(width: usize, height: usize) -> (Vec<u8>, usize) {
+    let bytes = Gif::from_pixels(width, height, gradient(width, height, false))
+        .unwrap()
+        .to_bytes()
+        .unwrap();
+    let image = 13 + (3 << ((bytes[10] & 0x07) + 1)) + 8;
+    assert_eq!(bytes[image], 0x2C);
+    (bytes, image)
+}
+
+#[test]
+fn round_trip() {
+    for interlaced in [false, true] {
+        let mut gif = Gif::from_pixels(13, 6, gradient(13, 6, false)).unwrap();
+        gif.set_interlaced(interlaced);
+        let decoded = Gif::from_bytes(&gif.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(decoded.interlaced(), interlaced);
+        assert_eq!(decoded.get_pixels(), gradient(13, 6, false).as_slice());
+    }
+}
+
+#[test]
+fn round_trip_transparency() {
+    let gif = Gif::from_pixels(7, 4, transparent(7, 4)).unwrap();
+    let decoded = Gif::from_bytes(&gif.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(decoded.get_pixels(), transparent(7, 4).as_slice());
+}
+
+#[test]
+fn round_trip_animation() {
+    let gif = Gif::from_frames(animation()).unwrap();
+    let decoded = Gif::from_bytes(&gif.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(decoded.frames(), &animation());
+}
+
+#[test]
+fn animation_filters_every_frame() {
+    let path = temp_path("animation.gif");
+    let mut gif = Gif::try_from(Animation::from_frames(animation())).unwrap();
+    gif.save(path.to_str().unwrap()).unwrap();
+
+    let mut animation = Animation::open(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    animation.filter(FlipH::full()).unwrap();
+
+    assert_eq!(animation.len(), 3);
+    assert_eq!(animation.loop_count(), 3);
+    let second = animation.frames().get(1).unwrap().image();
+    assert_eq!(second.get_pixel(0, 0), transparent(3, 2).get(2));
+    assert_eq!(animation.composite().unwrap().len(), 3);
+}
+
+#[test]
+fn frames_can_be_edited_but_not_removed() {
+    let mut gif = Gif::from_pixels(4, 3, gradient(4, 3, false)).unwrap();
+    let small = ImageBuffer::from_pixels(2, 2, transparent(2, 2)).unwrap();
+
+    gif.frames_mut()[0].set_delay(Duration::from_millis(50));
+    gif.push_frame(frame(small, 1, 1, Disposal::Background));
+    gif.set_loop_count(0);
+    assert_eq!(gif.frames_mut().len(), 2);
+
+    let decoded = Gif::from_bytes(&gif.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.frames().len(), 2);
+    assert_eq!(decoded.frames().loop_count(), 0);
+    assert_eq!(
+        decoded.frames().get(0).unwrap().delay(),
+        Duration::from_millis(50)
+    );
+    assert_eq!((decoded.widht(), decoded.height()), (4, 3));
+    assert_eq!(decoded.get_pixels(), gradient(4, 3, false).as_slice());
+
+    assert!(matches!(
+        Gif::from_frames(Frames::new(4, 3)),
+        Err(ImageError::InvalidData(_))
+    ));
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    let (bytes, image) = single_frame(5, 4);
+
+    let mut magic = bytes.clone();
+    magic[..6].copy_from_slice(b"GIF90a");
+    assert!(matches!(
+        Gif::from_bytes(&magic),
+        Err(ImageError::InvalidMagic)
+    ));
+    assert!(matches!(
+        Gif::from_bytes(&bytes[..12]),
+        Err(ImageError::Truncated)
+    ));
+
+    // O tamanho minimo dos codigos LZW precisa ficar abaixo do limite de 12 bits
+    for size in [0, 12, 13] {
+        let mut code_size = bytes.clone();
+        code_size[image + 10] = size;
+        assert!(
+            matches!(
+                Gif::from_bytes(&code_size),
+                Err(ImageError::InvalidData("gif lzw code size is invalid"))
+            ),
+            "code size {size}"
+        );
+    }
+
+    // Sem paleta global nem local nao ha cores para os indices
+    let mut palette = bytes[..13].to_vec();
+    palette[10] &= 0x7F;
+    palette.extend_from_slice(&bytes[image..]);
+    assert!(matches!(
+        Gif::from_bytes(&palette),
+        Err(ImageError::InvalidData("gif has no palette"))
+    ));
+
+    // Bloco desconhecido antes do primeiro quadro, e arquivo que termina sem nenhum quadro
+    let mut unknown = bytes[..image].to_vec();
+    unknown.push(0x00);
+    assert!(matches!(
+        Gif::from_bytes(&unknown),
+        Err(ImageError::InvalidData("gif block is unknown"))
+    ));
+    let mut empty = bytes[..image].to_vec();
+    empty.push(0x3B);
+    assert!(matches!(
+        Gif::from_bytes(&empty),
+        Err(ImageError::InvalidData("gif has no frames"))
+    ));
+}
+
+#[test]
+fn missing_image_data_is_truncated() {
+    let (bytes, image) = single_frame(5, 4);
+
+    // Separador sem descritor completo, e descritor sem o tamanho dos codigos
+    for len in [image, image + 1, image + 9, image + 10] {
+        assert!(
+            matches!(Gif::from_bytes(&bytes[..len]), Err(ImageError::Truncated)),
+            "prefix of {len} bytes"
+        );
+    }
+
+    // Um quadro enorme com poucos dados nao pode ser alocado
+    let mut huge = bytes.clone();
+    for offset in [5, 7] {
+        huge[image + offset..image + offset + 2].copy_from_slice(&u16::MAX.to_le_bytes());
+    }
+    assert!(matches!(Gif::from_bytes(&huge), Err(ImageError::Truncated)));
+}
+
+#[test]
+fn short_data_leaves_the_rest_transparent() {
+    let (bytes, _) = single_frame(16, 16);
+    let pixels = gradient(16, 16, false);
+
+    // O trailer e opcional
+    let decoded = Gif::from_bytes(&bytes[..bytes.len() - 1]).unwrap();
+    assert_eq!(decoded.get_pixels(), pixels.as_slice());
+
+    // Com os dados cortados a tela continua inteira, e o que falta fica transparente
+    let decoded = Gif::from_bytes(&bytes[..bytes.len() - 40]).unwrap();
+    assert_eq!((decoded.widht(), decoded.height()), (16, 16));
+    assert_eq!(decoded.get_pixel(0, 0), pixels.first());
+    assert_eq!(decoded.get_pixel(15, 15), Some(&RGB::new(0, 0, 0, Some(0))));
+
+    let decoded_pixels = decoded.get_pixels();
+    let cut = decoded_pixels
+        .iter()
+        .position(|pixel| pixel.alpha() == Some(0))
+        .unwrap();
+    assert!(cut > 0);
+    assert_eq!(&decoded_pixels[..cut], &pixels[..cut]);
+    assert!(decoded_pixels[cut..].iter().all(|p| p.alpha() == Some(0)));
+}
@@ -0,0 +1,229 @@
+mod common;
+
+use common::temp_path;
+use std_image::error::ImageError;
+use std_image::images::{
+    self, Format, Image, RGB,
+    buffer::ImageBuffer,
+    dynamic::DynamicImage,
+    hdr::Hdr,
+    pixel::{Pixel, Rgb},
+};
+
+fn pixels(width: usize, height: usize) -> Vec<Rgb<f32>> {
+    (0..width * height)
+        .map(|index| Rgb([index as f32 * 0.25, 1.0, 0.5]))
+        .collect()
+}
+
+/// Cabecalho RGBE com a linha de resolucao dada, seguido dos bytes das scanlines
+fn hdr_file(resolution: &str, body: &[u8]) -> Vec<u8> {
+    let mut bytes = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{resolution}\n").into_bytes();
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+fn decode_error(bytes: &[u8]) -> ImageError {
+    Hdr::from_bytes(bytes).err().expect("decoding should fail")
+}
+
+#[test]
+fn open_reduces_hdr_to_8_bits() {
+    let bytes = Hdr::from_pixels(4, 2, pixels(4, 2))
+        .unwrap()
+        .to_bytes()
+        .unwrap();
+    let path = temp_path("open.hdr");
+    std::fs::write(&path, &bytes).unwrap();
+
+    // Valores acima de 1.0 ficam em 255
+    let expected: Vec<RGB> = pixels(4, 2).iter().map(Pixel::convert).collect();
+    assert_eq!(expected[1], RGB::new(64, 255, 128, None));
+    assert_eq!(expected[7], RGB::new(255, 255, 128, None));
+
+    let image = images::open(path.to_str().unwrap()).unwrap();
+    assert!(matches!(image, DynamicImage::Hdr(_)));
+    assert_eq!(image.format(), Format::HDR);
+    assert_eq!((image.widht(), image.height()), (4, 2));
+    assert_eq!(image.get_pixels(), expected.as_slice());
+    assert!(matches!(
+        DynamicImage::from_bytes(&bytes).unwrap(),
+        DynamicImage::Hdr(_)
+    ));
+
+    // `Hdr` e `ImageBuffer<Rgb<f32>>` mantem os valores em f32
+    let hdr = Hdr::open(path.to_str().unwrap()).unwrap();
+    let buffer = ImageBuffer::<Rgb<f32>>::open(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(hdr.get_pixels(), pixels(4, 2).as_slice());
+    assert_eq!(buffer.get_pixels(), pixels(4, 2).as_slice());
+}
+
+#[test]
+fn dynamic_image_saves_hdr() {
+    let colors = vec![
+        RGB::new(0, 128, 255, None),
+        RGB::new(10, 20, 30, Some(0)),
+        RGB::new(255, 255, 255, None),
+    ];
+    let buffer = ImageBuffer::from_pixels(3, 1, colors.clone()).unwrap();
+    let image = DynamicImage::from_buffer(buffer, Format::HDR).unwrap();
+    assert_eq!(image.format(), Format::HDR);
+
+    // O alpha nao e gravado
+    let decoded = DynamicImage::from_bytes(&image.to_bytes().unwrap()).unwrap();
+    let opaque: Vec<RGB> = colors
+        .iter()
+        .map(|color| RGB::new(color.red(), color.green(), color.blue(), None))
+        .collect();
+    assert_eq!(decoded.get_pixels(), opaque.as_slice());
+
+    let empty = ImageBuffer::from_pixels(0, 0, Vec::<RGB>::new()).unwrap();
+    assert!(DynamicImage::from_buffer(empty, Format::HDR).is_err());
+}
+
+#[test]
+fn round_trip_flat_and_rle_scanlines() {
+    // Menos de 8 pixels por linha nao usa RLE
+    for width in [3, 8, 40] {
+        let pixels: Vec<Rgb<f32>> = (0..width * 4)
+            .map(|index| {
+                let value = (index % 7) as f32 * 0.37 + 0.01;
+                Rgb([value, value * 12.5, 1.0 / (index as f32 + 1.0)])
+            })
+            .collect();
+        let hdr = Hdr::from_pixels(width, 4, pixels.clone()).unwrap();
+        let decoded = Hdr::from_bytes(&hdr.to_bytes().unwrap()).unwrap();
+
+        // O RGBE guarda 8 bits de mantissa para o maior canal
+        for (got, expected) in decoded.get_pixels().iter().zip(&pixels) {
+            let max = expected.0.iter().copied().fold(0.0, f32::max);
+            for (got, expected) in got.0.iter().zip(expected.0) {
+                assert!((got - expected).abs() <= max / 128.0, "{got} {expected}");
+            }
+        }
+    }
+}
+
+#[test]
+fn reads_other_scanline_orders() {
+    let mut body = Vec::new();
+    for mantissa in 1..=6_u8 {
+        body.extend_from_slice(&[mantissa * 16, 0, 0, 129]);
+    }
+    let red = |mantissa: u8| (mantissa * 16) as f32 / 128.0;
+    let reds = |resolution: &str| -> Vec<f32> {
+        let hdr = Hdr::from_bytes(&hdr_file(resolution, &body)).unwrap();
+        hdr.get_pixels().iter().map(|pixel| pixel.0[0]).collect()
+    };
+
+    // Linhas de baixo para cima e pixels da direita para a esquerda
+    assert_eq!(reds("+Y 2 -X 3"), [6, 5, 4, 3, 2, 1].map(red));
+    // Scanlines em colunas: cada uma tem 2 pixels de cima para baixo
+    assert_eq!(reds("+X 3 -Y 2"), [1, 3, 5, 2, 4, 6].map(red));
+}
+
+#[test]
+fn old_rle_repeats_the_previous_pixel() {
+    // (1, 1, 1, n) repete o pixel anterior n vezes; seguidos, o n anda 8 bits
+    let body = [[128, 0, 0, 129], [1, 1, 1, 2], [1, 1, 1, 0]].concat();
+    let hdr = Hdr::from_bytes(&hdr_file("-Y 1 +X 3", &body)).unwrap();
+    assert_eq!(hdr.get_pixels(), &[Rgb([1.0, 0.0, 0.0]); 3]);
+
+    assert!(matches!(
+        decode_error(&hdr_file("-Y 1 +X 2", &[[1, 1, 1, 1], [0; 4]].concat())),
+        ImageError::InvalidData("hdr repeat has no previous pixel")
+    ));
+    assert!(matches!(
+        decode_error(&hdr_file(
+            "-Y 1 +X 2",
+            &[[128, 0, 0, 129], [1, 1, 1, 5]].concat()
+        )),
+        ImageError::InvalidData("hdr run overflows the scanline")
+    ));
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    let valid = hdr_file("-Y 1 +X 1", &[128, 0, 0, 129]);
+    assert!(Hdr::from_bytes(&valid).is_ok());
+
+    let mut magic = valid.clone();
+    magic[1] = b'!';
+    assert!(matches!(decode_error(&magic), ImageError::InvalidMagic));
+
+    let format = |value: &str| {
+        let text = format!("#?RADIANCE\nFORMAT={value}\n\n-Y 1 +X 1\n");
+        decode_error(&[text.as_bytes(), &[128, 0, 0, 129]].concat())
+    };
+    assert!(matches!(
+        format("32-bit_rle_xyze"),
+        ImageError::Unsupported("hdr XYZE color space")
+    ));
+    assert!(matches!(
+        format("16-bit"),
+        ImageError::InvalidData("hdr format is unknown")
+    ));
+
+    for resolution in ["-Y 1 +Z 1", "-Y 1 -Y 1", "-Y one +X 1", "-Y 1"] {
+        assert!(
+            matches!(
+                decode_error(&hdr_file(resolution, &[0; 4])),
+                ImageError::InvalidData("hdr resolution line is invalid")
+            ),
+            "{resolution}"
+        );
+    }
+    assert!(matches!(
+        decode_error(&hdr_file("-Y 0 +X 1", &[0; 4])),
+        ImageError::InvalidData("hdr dimensions are zero")
+    ));
+
+    // Sem a linha vazia o cabecalho nao termina, e sem dados nao ha pixels
+    assert!(matches!(
+        decode_error(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n"),
+        ImageError::Truncated
+    ));
+    assert!(matches!(
+        decode_error(&valid[..valid.len() - 1]),
+        ImageError::Truncated
+    ));
+    assert!(matches!(
+        decode_error(&hdr_file("-Y 60000 +X 60000", &[0; 4])),
+        ImageError::Truncated
+    ));
+}
+
+#[test]
+fn invalid_rle_scanlines_are_rejected() {
+    // Scanline de 8 pixels: cabecalho (2, 2, largura) e uma repeticao de 8 por canal
+    let run = [136, 128];
+    let channels = [run, run, run, [136, 129]].concat();
+    let valid = hdr_file("-Y 1 +X 8", &[&[2, 2, 0, 8], channels.as_slice()].concat());
+    assert_eq!(
+        Hdr::from_bytes(&valid).unwrap().get_pixels(),
+        &[Rgb([1.0, 1.0, 1.0]); 8]
+    );
+
+    let scanline = |header: [u8; 4], channels: &[u8]| {
+        decode_error(&hdr_file("-Y 1 +X 8", &[&header, channels].concat()))
+    };
+    assert!(matches!(
+        scanline([2, 2, 0, 9], &channels),
+        ImageError::InvalidData("hdr scanline length mismatch")
+    ));
+    // Repeticao de 9, bytes crus com contador 0 e bytes crus demais
+    for channel in [[137, 64, 0], [0, 64, 0], [9, 64, 0]] {
+        let mut bad = channels.clone();
+        bad.splice(0..2, channel);
+        assert!(matches!(
+            scanline([2, 2, 0, 8], &bad),
+            ImageError::InvalidData("hdr run overflows the scanline")
+        ));
+    }
+    assert!(matches!(
+        scanline([2, 2, 0, 8], &channels[..7]),
+        ImageError::Truncated
+    ));
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use common::gradient;
+use std_image::error::ImageError;
+use std_image::images::{
+    Image,
+    buffer::ImageBuffer,
+    ico::{Ico, IconEntry, IconKind},
+};
+
+fn entry(size: usize, alpha: bool, png: bool) -> IconEntry {
+    let image = ImageBuffer::from_pixels(size, size, gradient(size, size, alpha)).unwrap();
+    let mut entry = IconEntry::new(image).unwrap();
+    entry.set_png(png);
+    entry
+}
+
+/// Icone com uma imagem DIB de 8x8 com alpha e um PNG de 4x4
+fn two_entries() -> Vec<u8> {
+    let entries = vec![entry(8, true, false), entry(4, false, true)];
+    Ico::from_entries(IconKind::Icon, entries)
+        .unwrap()
+        .to_bytes()
+        .unwrap()
+}
+
+/// Posicao e tamanho dos dados da entrada `index` no diretorio
+fn entry_data(bytes: &[u8], index: usize) -> (usize, usize) {
+    let field = |pos: usize| u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+    let pos = 6 + index * 16;
+    (field(pos + 12), field(pos + 8))
+}
+
+fn decode_error(bytes: &[u8]) -> ImageError {
+    Ico::from_bytes(bytes).expect_err("decoding should fail")
+}
+
+#[test]
+fn round_trip_icon_sizes_and_depths() {
+    let entries = vec![
+        entry(16, false, false),
+        entry(32, true, false),
+        entry(48, true, true),
+        entry(256, true, true),
+    ];
+    let ico = Ico::from_entries(IconKind::Icon, entries).unwrap();
+    let decoded = Ico::from_bytes(&ico.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(decoded.kind(), IconKind::Icon);
+    let expected = [
+        (16, 24, false),
+        (32, 32, false),
+        (48, 32, true),
+        (256, 32, true),
+    ];
+    for (entry, (size, bits, png)) in decoded.entries().iter().zip(expected) {
+        let alpha = size != 16;
+        assert_eq!((entry.bits_per_pixel(), entry.png()), (bits, png), "{size}");
+        assert_eq!(
+            entry.image().get_pixels(),
+            gradient(size, size, alpha).as_slice()
+        );
+    }
+    assert_eq!((decoded.widht(), decoded.height()), (256, 256));
+}
+
+#[test]
+fn round_trip_cursor_hotspot() {
+    let mut cursor = entry(32, true, false);
+    cursor.set_hotspot(5, 27);
+    let ico = Ico::from_entries(IconKind::Cursor, vec![cursor]).unwrap();
+    let bytes = ico.to_bytes().unwrap();
+
+    // Nos cursores os campos de planos e bits guardam o hotspot
+    assert_eq!(&bytes[2..4], &[2, 0]);
+    assert_eq!(&bytes[10..14], &[5, 0, 27, 0]);
+    let decoded = Ico::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.kind(), IconKind::Cursor);
+    assert_eq!(decoded.entries()[0].hotspot(), (5, 27));
+}
+
+#[test]
+fn entries_can_be_edited_but_not_removed() {
+    let mut ico = Ico::from_entries(IconKind::Icon, vec![entry(8, false, false)]).unwrap();
+    ico.entries_mut()[0].set_png(true);
+    ico.push(entry(16, true, false)).unwrap();
+
+    let decoded = Ico::from_bytes(&ico.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.entries().len(), 2);
+    assert!(decoded.entries()[0].png());
+    // A maior imagem e a que aparece como `Image`
+    assert_eq!((decoded.widht(), decoded.height()), (16, 16));
+    assert_eq!(
+        ImageBuffer::from(decoded).get_pixels(),
+        gradient(16, 16, true).as_slice()
+    );
+
+    assert!(matches!(
+        Ico::from_entries(IconKind::Icon, Vec::new()),
+        Err(ImageError::InvalidData("ico has no images"))
+    ));
+    let large = ImageBuffer::from_pixels(257, 1, gradient(257, 1, false)).unwrap();
+    assert!(matches!(
+        IconEntry::new(large),
+        Err(ImageError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn invalid_directories_are_rejected() {
+    let bytes = two_entries();
+    assert!(Ico::from_bytes(&bytes).is_ok());
+
+    let mut magic = bytes.clone();
+    magic[2] = 3;
+    assert!(matches!(decode_error(&magic), ImageError::InvalidMagic));
+    assert!(matches!(decode_error(&bytes[..5]), ImageError::Truncated));
+    // O diretorio tem 2 entradas de 16 bytes
+    assert!(matches!(decode_error(&bytes[..30]), ImageError::Truncated));
+
+    let mut empty = bytes.clone();
+    empty[4] = 0;
+    assert!(matches!(
+        decode_error(&empty),
+        ImageError::InvalidData("ico has no images")
+    ));
+
+    // Dados fora do arquivo, ou com offset + tamanho passando de usize no u32
+    let (offset, size) = entry_data(&bytes, 1);
+    assert!(matches!(
+        decode_error(&bytes[..offset + size - 1]),
+        ImageError::Truncated
+    ));
+    let mut outside = bytes.clone();
+    outside[6 + 12..6 + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(decode_error(&outside), ImageError::Truncated));
+
+    // As dimensoes do diretorio sao ignoradas: valem as da imagem
+    let mut directory = bytes.clone();
+    directory[6] = 99;
+    directory[7] = 3;
+    let decoded = Ico::from_bytes(&directory).unwrap();
+    assert_eq!(decoded.entries()[0].image().widht(), 8);
+    assert_eq!(decoded.entries()[0].image().height(), 8);
+}
+
+#[test]
+fn invalid_images_are_rejected() {
+    let bytes = two_entries();
+    let (dib, _) = entry_data(&bytes, 0);
+    let (png, _) = entry_data(&bytes, 1);
+
+    // A altura do DIB conta as duas mascaras (cor e AND); 1 nao deixa nenhuma linha
+    let mut height = bytes.clone();
+    height[dib + 8..dib + 12].copy_from_slice(&1_i32.to_le_bytes());
+    assert!(matches!(
+        decode_error(&height),
+        ImageError::InvalidData("ico dimensions are invalid")
+    ));
+
+    // Icones nao aceitam RLE
+    let mut rle = bytes.clone();
+    rle[dib + 16] = 1;
+    assert!(matches!(
+        decode_error(&rle),
+        ImageError::UnsupportedCompression(1)
+    ));
+
+    // O PNG embutido e verificado como um PNG normal (aqui o CRC do IHDR)
+    let mut crc = bytes.clone();
+    crc[png + 29] ^= 0xFF;
+    assert!(Ico::from_bytes(&crc).is_err());
+
+    // Um PNG cortado dentro da entrada
+    let mut short = bytes.clone();
+    let size = (bytes.len() - png - 20) as u32;
+    short[6 + 16 + 8..6 + 16 + 12].copy_from_slice(&size.to_le_bytes());
+    assert!(Ico::from_bytes(&short).is_err());
+}
@@ -0,0 +1,450 @@
+mod common;
+
+use common::gray;
+use std::ops::Range;
+use std_image::error::ImageError;
+use std_image::images::{
+    Image, Orientation, RGB,
+    jpeg::{Jpeg, JpegColorSpace, JpegOptions, JpegSubsampling},
+};
+
+// Fixtures geradas por outro encoder (jpeg-encoder, qualidade 95) a partir de `source` em 33x25,
+// tamanho que deixa MCUs incompletos nas bordas
+const FIXTURE_WIDTH: usize = 33;
+const FIXTURE_HEIGHT: usize = 25;
+
+fn source(x: usize, y: usize) -> [u8; 3] {
+    let (width, height) = (FIXTURE_WIDTH, FIXTURE_HEIGHT);
+    [
+        (x * 255 / (width - 1)) as u8,
+        (y * 255 / (height - 1)) as u8,
+        (255 - (x + y) * 255 / (width + height - 2)) as u8,
+    ]
+}
+
+/// Cor esperada das fixtures CMYK, gravadas com C, M e Y invertidos de `source` e o preto
+/// crescendo com a linha
+fn cmyk_source(x: usize, y: usize) -> [u8; 3] {
+    let black = (y * 96 / FIXTURE_HEIGHT) as u32;
+    source(x, y).map(|value| (value as u32 * (255 - black) / 255) as u8)
+}
+
+/// Media da diferenca absoluta entre os canais
+fn mean_error(a: &[RGB], b: &[RGB]) -> f64 {
+    let total: u32 = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| {
+            a.red().abs_diff(b.red()) as u32
+                + a.green().abs_diff(b.green()) as u32
+                + a.blue().abs_diff(b.blue()) as u32
+        })
+        .sum();
+    total as f64 / (a.len() * 3) as f64
+}
+
+fn fixture_path(name: &str) -> String {
+    format!(
+        "{}/tests/fixtures/jpeg/{name}.jpg",
+        env!("CARGO_MANIFEST_DIR")
+    )
+}
+
+fn fixture_bytes(name: &str) -> Vec<u8> {
+    std::fs::read(fixture_path(name)).unwrap()
+}
+
+fn fixture(name: &str) -> Jpeg {
+    Jpeg::open(fixture_path(name)).unwrap()
+}
+
+/// Marcador e posicao de cada segmento ate o cabecalho do primeiro scan (incluso)
+fn segments(bytes: &[u8]) -> Vec<(u8, Range<usize>)> {
+    let mut found = Vec::new();
+    let mut pos = 2;
+    loop {
+        let marker = bytes[pos + 1];
+        let length = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        found.push((marker, pos..pos + 2 + length));
+        pos += 2 + length;
+        if marker == 0xDA {
+            return found;
+        }
+    }
+}
+
+/// Posicao (marcador incluso) do primeiro segmento com o marcador
+fn segment(bytes: &[u8], marker: u8) -> Range<usize> {
+    segments(bytes)
+        .into_iter()
+        .find(|(found, _)| *found == marker)
+        .unwrap()
+        .1
+}
+
+/// Fixture baseline com uma mudanca aplicada no primeiro segmento com o marcador. O indice
+/// passado para `change` conta a partir do primeiro byte depois do tamanho
+fn baseline_with(marker: u8, change: impl Fn(&mut [u8])) -> Vec<u8> {
+    let mut bytes = fixture_bytes("baseline");
+    let range = segment(&bytes, marker);
+    change(&mut bytes[range.start + 4..range.end]);
+    bytes
+}
+
+/// Fixture baseline sem os segmentos com o marcador
+fn baseline_without(marker: u8) -> Vec<u8> {
+    let bytes = fixture_bytes("baseline");
+    let mut output = bytes[..2].to_vec();
+    let mut last = 2;
+    for (found, range) in segments(&bytes) {
+        if found == marker {
+            output.extend_from_slice(&bytes[last..range.start]);
+            last = range.end;
+        }
+    }
+    output.extend_from_slice(&bytes[last..]);
+    output
+}
+
+/// Fixture baseline com um APP1 EXIF (cabecalho TIFF informado) logo depois do SOI
+fn baseline_with_exif(tiff: &[u8]) -> Vec<u8> {
+    let bytes = fixture_bytes("baseline");
+    let mut output = vec![0xFF, 0xD8, 0xFF, 0xE1];
+    output.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+    output.extend_from_slice(b"Exif\0\0");
+    output.extend_from_slice(tiff);
+    output.extend_from_slice(&bytes[2..]);
+    output
+}
+
+fn decode_error(bytes: &[u8]) -> ImageError {
+    match Jpeg::from_bytes(bytes) {
+        Ok(_) => panic!("jpeg decoded"),
+        Err(error) => error,
+    }
+}
+
+/// Compara com a cor esperada de cada pixel: diferenca media e maxima por canal
+fn assert_close(jpeg: &Jpeg, expected: impl Fn(usize, usize) -> [u8; 3], mean: f64, max: u8) {
+    assert_eq!(
+        (jpeg.widht(), jpeg.height()),
+        (FIXTURE_WIDTH, FIXTURE_HEIGHT)
+    );
+    let expected: Vec<RGB> = (0..FIXTURE_WIDTH * FIXTURE_HEIGHT)
+        .map(|index| {
+            let [red, green, blue] = expected(index % FIXTURE_WIDTH, index / FIXTURE_WIDTH);
+            RGB::new(red, green, blue, None)
+        })
+        .collect();
+
+    let worst = jpeg
+        .get_pixels()
+        .iter()
+        .zip(&expected)
+        .map(|(a, b)| {
+            a.red()
+                .abs_diff(b.red())
+                .max(a.green().abs_diff(b.green()))
+                .max(a.blue().abs_diff(b.blue()))
+        })
+        .max()
+        .unwrap();
+    let error = mean_error(jpeg.get_pixels(), &expected);
+
+    assert!(error <= mean, "mean error {error}");
+    assert!(worst <= max, "max error {worst}");
+}
+
+#[test]
+fn decodes_baseline_fixture() {
+    let jpeg = fixture("baseline");
+
+    assert_eq!(jpeg.color_space(), JpegColorSpace::YCbCr);
+    assert!(!jpeg.progressive());
+    assert_close(&jpeg, source, 1.0, 6);
+}
+
+#[test]
+fn decodes_progressive_fixture() {
+    let jpeg = fixture("progressive");
+
+    assert!(jpeg.progressive());
+    assert_close(&jpeg, source, 1.0, 6);
+}
+
+#[test]
+fn decodes_restart_markers() {
+    // Intervalo de 2 MCUs e cores com metade da largura
+    let jpeg = fixture("restart");
+
+    assert_close(&jpeg, source, 3.0, 10);
+}
+
+#[test]
+fn decodes_cmyk_and_ycck_fixtures() {
+    let cmyk = fixture("cmyk");
+    assert_eq!(cmyk.color_space(), JpegColorSpace::Cmyk);
+    assert_close(&cmyk, cmyk_source, 1.0, 4);
+
+    let ycck = fixture("ycck");
+    assert_eq!(ycck.color_space(), JpegColorSpace::Ycck);
+    assert!(ycck.progressive());
+    assert_close(&ycck, cmyk_source, 1.0, 5);
+}
+
+#[test]
+fn reads_exif_orientation() {
+    let mut jpeg = fixture("orientation");
+
+    assert_eq!(jpeg.orientation(), Orientation::Rotate90);
+    assert_close(&jpeg, source, 1.0, 6);
+
+    // Girando 90 graus no sentido horario, a primeira linha vira a ultima coluna
+    jpeg.normalize_orientation();
+    assert_eq!(jpeg.orientation(), Orientation::Normal);
+    assert_eq!(
+        (jpeg.widht(), jpeg.height()),
+        (FIXTURE_HEIGHT, FIXTURE_WIDTH)
+    );
+    let top_right = jpeg.get_pixel(FIXTURE_HEIGHT - 1, 0).unwrap();
+    let [red, green, blue] = source(0, 0);
+    assert!(top_right.red().abs_diff(red) <= 6);
+    assert!(top_right.green().abs_diff(green) <= 6);
+    assert!(top_right.blue().abs_diff(blue) <= 6);
+}
+
+#[test]
+fn frame_header_errors() {
+    let mut soi = fixture_bytes("baseline");
+    soi[1] = 0xD9;
+    assert!(matches!(decode_error(&soi), ImageError::InvalidMagic));
+
+    let precision = baseline_with(0xC0, |sof| sof[0] = 12);
+    assert!(matches!(
+        decode_error(&precision),
+        ImageError::UnsupportedBitDepth(12)
+    ));
+
+    // Altura zero pede o marcador DNL
+    let height = baseline_with(0xC0, |sof| sof[1..3].fill(0));
+    assert!(matches!(
+        decode_error(&height),
+        ImageError::Unsupported("jpeg DNL marker")
+    ));
+
+    let count = baseline_with(0xC0, |sof| sof[5] = 2);
+    assert!(matches!(
+        decode_error(&count),
+        ImageError::Unsupported("jpeg component count")
+    ));
+
+    // Fator de amostragem horizontal zero no primeiro componente
+    let sampling = baseline_with(0xC0, |sof| sof[7] &= 0x0F);
+    assert!(matches!(
+        decode_error(&sampling),
+        ImageError::InvalidData("jpeg component is invalid")
+    ));
+}
+
+#[test]
+fn unsupported_processes_are_reported() {
+    for (marker, message) in [(0xC3, "lossless jpeg"), (0xC9, "arithmetic coded jpeg")] {
+        let mut bytes = fixture_bytes("baseline");
+        let sof = segment(&bytes, 0xC0);
+        bytes[sof.start + 1] = marker;
+
+        assert!(
+            matches!(decode_error(&bytes), ImageError::Unsupported(m) if m == message),
+            "{message}"
+        );
+    }
+}
+
+#[test]
+fn missing_tables_and_headers_are_rejected() {
+    assert!(matches!(
+        decode_error(&baseline_without(0xC4)),
+        ImageError::InvalidData("jpeg huffman table is missing")
+    ));
+    assert!(matches!(
+        decode_error(&baseline_without(0xC0)),
+        ImageError::InvalidData("jpeg scan before the frame header")
+    ));
+
+    // O scan cita um componente que o frame nao tem
+    let unknown = baseline_with(0xDA, |sos| sos[1] = 0x7F);
+    assert!(matches!(
+        decode_error(&unknown),
+        ImageError::InvalidData("jpeg scan component is unknown")
+    ));
+
+    let mut length = fixture_bytes("baseline");
+    let dqt = segment(&length, 0xDB);
+    length[dqt.start + 2..dqt.start + 4].copy_from_slice(&[0, 1]);
+    assert!(matches!(
+        decode_error(&length),
+        ImageError::InvalidData("jpeg segment length is invalid")
+    ));
+}
+
+#[test]
+fn files_cut_before_the_scan_are_truncated() {
+    let bytes = fixture_bytes("baseline");
+    let sos = segment(&bytes, 0xDA);
+
+    // Um scan cortado e completado, como na libjpeg, mas sem scan nao ha imagem
+    for len in [3, sos.start / 2, sos.start + 4] {
+        assert!(
+            matches!(decode_error(&bytes[..len]), ImageError::Truncated),
+            "{len}"
+        );
+    }
+    assert!(Jpeg::from_bytes(&bytes[..bytes.len() - 40]).is_ok());
+}
+
+#[test]
+fn corrupted_exif_is_ignored() {
+    // Tag de orientacao 6 num IFD0 de uma entrada, em little endian
+    let mut tiff = b"II*\0\x08\0\0\0\x01\0".to_vec();
+    tiff.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0]);
+    let jpeg = Jpeg::from_bytes(&baseline_with_exif(&tiff)).unwrap();
+    assert_eq!(jpeg.orientation(), Orientation::Rotate90);
+
+    let mut byte_order = tiff.clone();
+    byte_order[0..2].copy_from_slice(b"XX");
+    let mut offset = tiff.clone();
+    offset[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    // Muitas entradas, sem a de orientacao nas que existem
+    let mut entries = tiff.clone();
+    entries[8..10].copy_from_slice(&u16::MAX.to_le_bytes());
+    entries[10] = 0x13;
+    let mut value = tiff.clone();
+    value[18] = 9;
+
+    for broken in [byte_order, offset, entries, value, tiff[..14].to_vec()] {
+        let jpeg = Jpeg::from_bytes(&baseline_with_exif(&broken)).unwrap();
+        assert_eq!(jpeg.orientation(), Orientation::Normal);
+    }
+}
+
+/// Gradiente suave, sem as bordas que o JPEG borra
+fn smooth(width: usize, height: usize) -> Vec<RGB> {
+    (0..width * height)
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            RGB::new(
+                (x * 255 / (width - 1)) as u8,
+                (y * 255 / (height - 1)) as u8,
+                ((x + y) * 255 / (width + height - 2)) as u8,
+                None,
+            )
+        })
+        .collect()
+}
+
+fn encode(width: usize, height: usize, pixels: Vec<RGB>, options: JpegOptions) -> Vec<u8> {
+    let mut jpeg = Jpeg::from_pixels(width, height, pixels).unwrap();
+    jpeg.set_options(options);
+    jpeg.to_bytes().unwrap()
+}
+
+#[test]
+fn round_trip_every_option() {
+    let (width, height) = (37, 21);
+    let pixels = smooth(width, height);
+    let subsamplings = [JpegSubsampling::Chroma444, JpegSubsampling::Chroma420];
+
+    for (quality, tolerance) in [(50, 4.0), (90, 2.0), (100, 1.0)] {
+        for subsampling in subsamplings {
+            for (optimize_huffman, progressive) in [(false, false), (true, false), (true, true)] {
+                let options = JpegOptions {
+                    quality,
+                    subsampling,
+                    optimize_huffman,
+                    progressive,
+                };
+                let bytes = encode(width, height, pixels.clone(), options);
+                let decoded = Jpeg::from_bytes(&bytes).unwrap();
+                let error = mean_error(decoded.get_pixels(), &pixels);
+
+                assert_eq!(decoded.progressive(), progressive, "{options:?}");
+                assert_eq!(decoded.color_space(), JpegColorSpace::YCbCr, "{options:?}");
+                assert!(error <= tolerance, "{options:?}: {error}");
+            }
+        }
+    }
+}
+
+#[test]
+fn frame_header_follows_the_options() {
+    let pixels = smooth(20, 12);
+    let sof = |options: JpegOptions| {
+        let bytes = encode(20, 12, pixels.clone(), options);
+        let marker = if options.progressive { 0xC2 } else { 0xC0 };
+        let range = segment(&bytes, marker);
+        bytes[range.start + 4..range.end].to_vec()
+    };
+
+    // Precisao, altura, largura, componentes e a amostragem do brilho e das cores
+    let full = sof(JpegOptions {
+        subsampling: JpegSubsampling::Chroma444,
+        ..JpegOptions::default()
+    });
+    assert_eq!(full[..6], [8, 0, 12, 0, 20, 3]);
+    assert_eq!([full[7], full[10], full[13]], [0x11, 0x11, 0x11]);
+
+    let half = sof(JpegOptions {
+        progressive: true,
+        ..JpegOptions::default()
+    });
+    assert_eq!([half[7], half[10], half[13]], [0x22, 0x11, 0x11]);
+}
+
+#[test]
+fn quality_and_huffman_change_the_size() {
+    let pixels = smooth(48, 32);
+    let size = |quality, optimize_huffman| {
+        let options = JpegOptions {
+            quality,
+            optimize_huffman,
+            ..JpegOptions::default()
+        };
+        encode(48, 32, pixels.clone(), options).len()
+    };
+
+    assert!(size(20, true) < size(60, true));
+    assert!(size(60, true) < size(95, true));
+    assert!(size(60, true) < size(60, false));
+    // Fora de 1..=100 a qualidade e limitada
+    assert_eq!(size(0, true), size(1, true));
+    assert_eq!(size(255, true), size(100, true));
+}
+
+#[test]
+fn gray_images_keep_one_component() {
+    let jpeg = Jpeg::from_pixels(16, 9, gray(16, 9)).unwrap();
+    let decoded = Jpeg::from_bytes(&jpeg.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(decoded.color_space(), JpegColorSpace::Grayscale);
+    assert!(mean_error(decoded.get_pixels(), &gray(16, 9)) <= 2.0);
+}
+
+#[test]
+fn alpha_is_dropped_and_orientation_is_written() {
+    let opaque = smooth(9, 5);
+    let translucent: Vec<RGB> = opaque
+        .iter()
+        .map(|p| RGB::new(p.red(), p.green(), p.blue(), Some(10)))
+        .collect();
+    let bytes = |pixels: Vec<RGB>| {
+        let mut jpeg = Jpeg::from_pixels(9, 5, pixels).unwrap();
+        jpeg.set_orientation(Orientation::Rotate270);
+        jpeg.to_bytes().unwrap()
+    };
+
+    let saved = bytes(translucent);
+    assert_eq!(saved, bytes(opaque));
+    let decoded = Jpeg::from_bytes(&saved).unwrap();
+    assert_eq!(decoded.orientation(), Orientation::Rotate270);
+    assert!(decoded.get_pixels().iter().all(|p| p.alpha().is_none()));
+}
@@ -0,0 +1,402 @@
+mod common;
+
+use common::{gradient, gradient16};
+use std_image::error::ImageError;
+use std_image::filters::flip_h::FlipH;
+use std_image::images::{
+    Image, RGB,
+    buffer::ImageBuffer,
+    pixel::Rgba,
+    png::{ColorType, Png},
+    zlib,
+};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+fn chunk(bytes: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = bytes.len();
+    bytes.extend_from_slice(kind);
+    bytes.extend_from_slice(data);
+    let crc = crc32(&bytes[start..]);
+    bytes.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// PNG com o IHDR informado (8 bits) e um unico IDAT
+fn png(width: u32, height: u32, color_type: u8, idat: &[u8]) -> Vec<u8> {
+    png_with_depth(width, height, 8, color_type, &[], idat)
+}
+
+/// PNG com a profundidade informada e os chunks extras (PLTE, tRNS) antes do IDAT
+fn png_with_depth(
+    width: u32,
+    height: u32,
+    depth: u8,
+    color_type: u8,
+    extra: &[(&[u8; 4], Vec<u8>)],
+    idat: &[u8],
+) -> Vec<u8> {
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[depth, color_type, 0, 0, 0]);
+
+    let mut bytes = SIGNATURE.to_vec();
+    chunk(&mut bytes, b"IHDR", &ihdr);
+    for (kind, data) in extra {
+        chunk(&mut bytes, kind, data);
+    }
+    chunk(&mut bytes, b"IDAT", idat);
+    chunk(&mut bytes, b"IEND", &[]);
+    bytes
+}
+
+/// Amostras de cada linha empacotadas como no PNG, com o filtro 0 no inicio
+fn scanlines(rows: &[Vec<u16>], depth: u8) -> Vec<u8> {
+    let mut raw = Vec::new();
+    for row in rows {
+        raw.push(0);
+        match depth {
+            16 => raw.extend(row.iter().flat_map(|sample| sample.to_be_bytes())),
+            8 => raw.extend(row.iter().map(|sample| *sample as u8)),
+            _ => {
+                let per_byte = 8 / depth as usize;
+                for group in row.chunks(per_byte) {
+                    let byte = group.iter().enumerate().fold(0_u8, |acc, (i, sample)| {
+                        acc | (*sample as u8) << (8 - depth as usize * (i + 1))
+                    });
+                    raw.push(byte);
+                }
+            }
+        }
+    }
+    raw
+}
+
+#[test]
+fn huge_header_with_little_data_is_truncated() {
+    let bytes = png(60_000, 60_000, 6, &zlib::compress(&[0; 64]));
+
+    assert!(matches!(
+        Png::from_bytes(&bytes),
+        Err(ImageError::Truncated)
+    ));
+}
+
+#[test]
+fn inflate_stops_at_expected_size() {
+    // 1x1 em cinza precisa de 2 bytes, o fluxo gera 1 MiB
+    let bytes = png(1, 1, 0, &zlib::compress(&vec![0; 1 << 20]));
+
+    assert!(Png::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn decodes_hand_written_png() {
+    let bytes = png(2, 1, 2, &zlib::compress(&[0, 255, 0, 0, 0, 0, 255]));
+    let image = Png::from_bytes(&bytes).unwrap();
+    let pixels = image.get_pixels();
+
+    assert_eq!((pixels[0].red(), pixels[0].blue()), (255, 0));
+    assert_eq!((pixels[1].red(), pixels[1].blue()), (0, 255));
+}
+
+#[test]
+fn round_trip() {
+    let png = Png::from_pixels(7, 5, gradient(7, 5, true)).unwrap();
+    let decoded = Png::from_bytes(&png.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(decoded.get_pixels(), gradient(7, 5, true).as_slice());
+}
+
+#[test]
+fn round_trip_16_bits() {
+    for (alpha, gray) in [(false, false), (true, false), (false, true), (true, true)] {
+        let pixels = gradient16(9, 4, alpha, gray);
+        let png = Png::from_rgba16(9, 4, pixels.clone()).unwrap();
+        let decoded = Png::from_bytes(&png.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(decoded.bit_depth(), 16);
+        assert_eq!(decoded.to_rgba16().get_pixels(), pixels.as_slice());
+    }
+}
+
+#[test]
+fn buffer_16_bits_round_trip() {
+    let pixels = gradient16(6, 6, true, false);
+    let buffer = ImageBuffer::from_pixels(6, 6, pixels.clone()).unwrap();
+    let decoded = ImageBuffer::<Rgba<u16>>::from_bytes(&buffer.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(decoded.get_pixels(), pixels.as_slice());
+}
+
+#[test]
+fn edited_16_bits_pixels_are_saved() {
+    let mut png = Png::from_rgba16(3, 1, gradient16(3, 1, false, false)).unwrap();
+    *png.pixel(1, 0).unwrap() = RGB::new(255, 0, 0, None);
+    let decoded = Png::from_bytes(&png.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(
+        decoded.to_rgba16().get_pixels()[1],
+        Rgba([u16::MAX, 0, 0, u16::MAX])
+    );
+}
+
+#[test]
+fn filters_drop_the_16_bits_copy() {
+    let pixels = vec![Rgba([0x1200, 0x1200, 0x1200, u16::MAX]), Rgba([0x1201; 4])];
+    let mut png = Png::from_rgba16(2, 1, pixels.clone()).unwrap();
+    png.filter(FlipH::full()).unwrap();
+
+    // Os dois pixels viram 0x12 em 8 bits; a copia antiga (sem o flip) nao e reaproveitada
+    let decoded = Png::from_bytes(&png.to_bytes().unwrap()).unwrap();
+    assert_eq!(
+        decoded.to_rgba16().get_pixels(),
+        [Rgba([0x1212; 4]), Rgba([0x1212, 0x1212, 0x1212, u16::MAX])]
+    );
+
+    // Os filtros aplicados no buffer de 16 bits mantem todas as amostras
+    let mut buffer = Png::from_rgba16(2, 1, pixels.clone()).unwrap().to_rgba16();
+    buffer.filter(FlipH::full()).unwrap();
+    let png = Png::try_from(buffer).unwrap();
+    let decoded = Png::from_bytes(&png.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.to_rgba16().get_pixels(), [pixels[1], pixels[0]]);
+}
+
+#[test]
+fn mutable_access_drops_the_16_bits_copy() {
+    let pixels = gradient16(3, 1, false, false);
+
+    let mut png = Png::from_rgba16(3, 1, pixels.clone()).unwrap();
+    png.pixels();
+    assert_ne!(png.to_rgba16().get_pixels(), pixels.as_slice());
+
+    let mut png = Png::from_rgba16(3, 1, pixels.clone()).unwrap();
+    png.slice_pixels(0..1);
+    assert_ne!(png.to_rgba16().get_pixels(), pixels.as_slice());
+
+    // Leituras mantem a copia
+    let png = Png::from_rgba16(3, 1, pixels.clone()).unwrap();
+    assert_eq!(png.get_pixels().len(), 3);
+    assert_eq!(png.to_rgba16().get_pixels(), pixels.as_slice());
+}
+
+#[test]
+fn round_trip_every_color_type_and_depth() {
+    let (width, height) = (5, 3);
+    let modes: [(ColorType, &[u8]); 5] = [
+        (ColorType::Grayscale, &[1, 2, 4, 8, 16]),
+        (ColorType::Rgb, &[8, 16]),
+        (ColorType::Indexed, &[1, 2, 4, 8]),
+        (ColorType::GrayscaleAlpha, &[8, 16]),
+        (ColorType::Rgba, &[8, 16]),
+    ];
+
+    for (color_type, depths) in modes {
+        for &depth in depths {
+            let max = ((1_u32 << depth) - 1) as u16;
+            let channels = color_type.channels();
+            let palette: Vec<[u8; 3]> = (0..=max.min(255))
+                .map(|k| [(k * 13) as u8, (k * 29) as u8, (k * 7) as u8])
+                .collect();
+
+            let mut rows = Vec::new();
+            let mut expected = Vec::new();
+            for y in 0..height {
+                let mut row = Vec::new();
+                for x in 0..width {
+                    let index = y * width + x;
+                    let samples: Vec<u16> = (0..channels as u32)
+                        .map(|c| ((index * 4099 + c * 1031) % (max as u32 + 1)) as u16)
+                        .collect();
+                    let wide: Vec<u16> = samples.iter().map(|s| s * (u16::MAX / max)).collect();
+                    expected.push(match color_type {
+                        ColorType::Grayscale => Rgba([wide[0], wide[0], wide[0], u16::MAX]),
+                        ColorType::GrayscaleAlpha => Rgba([wide[0], wide[0], wide[0], wide[1]]),
+                        ColorType::Rgb => Rgba([wide[0], wide[1], wide[2], u16::MAX]),
+                        ColorType::Rgba => Rgba([wide[0], wide[1], wide[2], wide[3]]),
+                        ColorType::Indexed => {
+                            let [r, g, b] = palette[samples[0] as usize].map(|v| v as u16 * 257);
+                            Rgba([r, g, b, u16::MAX])
+                        }
+                    });
+                    row.extend(samples);
+                }
+                rows.push(row);
+            }
+
+            let extra = match color_type {
+                ColorType::Indexed => vec![(b"PLTE", palette.concat())],
+                _ => Vec::new(),
+            };
+            let idat = zlib::compress(&scanlines(&rows, depth));
+            let bytes = png_with_depth(width, height, depth, color_type as u8, &extra, &idat);
+            let mode = format!("{color_type:?} {depth}");
+
+            let decoded = Png::from_bytes(&bytes).expect(&mode);
+            assert_eq!(
+                decoded.to_rgba16().get_pixels(),
+                expected.as_slice(),
+                "{mode}"
+            );
+
+            let again = Png::from_bytes(&decoded.to_bytes().unwrap()).expect(&mode);
+            assert_eq!(
+                (again.color_type(), again.bit_depth()),
+                (color_type, depth),
+                "{mode}"
+            );
+            assert_eq!(
+                again.to_rgba16().get_pixels(),
+                expected.as_slice(),
+                "{mode}"
+            );
+        }
+    }
+}
+
+#[test]
+fn transparency_chunk_is_applied() {
+    let palette = vec![(b"PLTE", vec![255, 0, 0, 0, 0, 255]), (b"tRNS", vec![0])];
+    let bytes = png_with_depth(2, 1, 8, 3, &palette, &zlib::compress(&[0, 0, 1]));
+    let pixels = Png::from_bytes(&bytes).unwrap().get_pixels().to_vec();
+
+    assert_eq!(pixels[0].alpha(), Some(0));
+    assert_eq!(pixels[1].alpha().unwrap_or(u8::MAX), u8::MAX);
+
+    let key = vec![(b"tRNS", vec![0, 1, 0, 2, 0, 3])];
+    let bytes = png_with_depth(2, 1, 8, 2, &key, &zlib::compress(&[0, 0, 0, 255, 1, 2, 3]));
+    let pixels = Png::from_bytes(&bytes).unwrap().get_pixels().to_vec();
+
+    assert_eq!(pixels[0].alpha().unwrap_or(u8::MAX), u8::MAX);
+    assert_eq!(pixels[1].alpha(), Some(0));
+}
+
+#[test]
+fn interlaced_round_trip() {
+    let mut png = Png::from_pixels(9, 7, gradient(9, 7, true)).unwrap();
+    png.set_interlaced(true);
+    let decoded = Png::from_bytes(&png.to_bytes().unwrap()).unwrap();
+
+    assert!(decoded.interlaced());
+    assert_eq!(decoded.get_pixels(), gradient(9, 7, true).as_slice());
+}
+
+#[test]
+fn bad_signature_is_rejected() {
+    let mut bytes = png(1, 1, 0, &zlib::compress(&[0, 0]));
+    bytes[1] = b'p';
+
+    assert!(matches!(
+        Png::from_bytes(&bytes),
+        Err(ImageError::InvalidMagic)
+    ));
+}
+
+#[test]
+fn chunk_crc_is_checked() {
+    let mut bytes = png(1, 1, 0, &zlib::compress(&[0, 0]));
+    // Primeiro byte da largura no IHDR
+    bytes[16] ^= 0x01;
+
+    assert!(matches!(
+        Png::from_bytes(&bytes),
+        Err(ImageError::InvalidData("png chunk crc mismatch"))
+    ));
+}
+
+#[test]
+fn missing_iend_is_truncated() {
+    let bytes = png(1, 1, 0, &zlib::compress(&[0, 0]));
+
+    assert!(matches!(
+        Png::from_bytes(&bytes[..bytes.len() - 12]),
+        Err(ImageError::Truncated)
+    ));
+}
+
+#[test]
+fn missing_ihdr_is_rejected() {
+    let mut bytes = SIGNATURE.to_vec();
+    chunk(&mut bytes, b"IDAT", &zlib::compress(&[0, 0]));
+    chunk(&mut bytes, b"IEND", &[]);
+
+    assert!(matches!(
+        Png::from_bytes(&bytes),
+        Err(ImageError::InvalidData("png is missing IHDR"))
+    ));
+}
+
+#[test]
+fn indexed_without_palette_is_rejected() {
+    let bytes = png(1, 1, 3, &zlib::compress(&[0, 0]));
+
+    assert!(matches!(
+        Png::from_bytes(&bytes),
+        Err(ImageError::InvalidData("png indexed image is missing PLTE"))
+    ));
+}
+
+#[test]
+fn unknown_critical_chunk_is_rejected() {
+    let idat = zlib::compress(&[0, 0]);
+    let critical = png_with_depth(1, 1, 8, 0, &[(b"ABCD", vec![1, 2])], &idat);
+    // Chunks auxiliares desconhecidos sao ignorados
+    let ancillary = png_with_depth(1, 1, 8, 0, &[(b"abCD", vec![1, 2])], &idat);
+
+    assert!(matches!(
+        Png::from_bytes(&critical),
+        Err(ImageError::InvalidData("png has an unknown critical chunk"))
+    ));
+    assert!(Png::from_bytes(&ancillary).is_ok());
+}
+
+#[test]
+fn invalid_depth_for_color_type_is_rejected() {
+    let idat = zlib::compress(&[0, 0, 0]);
+
+    for (color_type, depth) in [(2, 4), (3, 16), (4, 1), (6, 2), (0, 3)] {
+        assert!(
+            matches!(
+                Png::from_bytes(&png_with_depth(1, 1, depth, color_type, &[], &idat)),
+                Err(ImageError::UnsupportedBitDepth(d)) if d == depth as u16
+            ),
+            "color type {color_type}, depth {depth}"
+        );
+    }
+}
+
+#[test]
+fn invalid_filter_type_is_rejected() {
+    let bytes = png(1, 1, 0, &zlib::compress(&[5, 0]));
+
+    assert!(matches!(
+        Png::from_bytes(&bytes),
+        Err(ImageError::InvalidData("png filter type is invalid"))
+    ));
+}
+
+#[test]
+fn short_image_data_is_truncated() {
+    // Dois pixels cinza pedem 2 linhas de 2 bytes, o IDAT tem so uma
+    let bytes = png(1, 2, 0, &zlib::compress(&[0, 0]));
+
+    assert!(matches!(
+        Png::from_bytes(&bytes),
+        Err(ImageError::Truncated)
+    ));
+}
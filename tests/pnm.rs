@@ -0,0 +1,176 @@
+mod common;
+
+use common::{gradient, gradient16, gray};
+use std_image::error::ImageError;
+use std_image::filters::{flip_h::FlipH, flip_v::FlipV};
+use std_image::images::{
+    Image, RGB,
+    pnm::{Pnm, PnmEncoding, PnmKind},
+};
+
+#[test]
+fn round_trip_16_bits() {
+    for alpha in [false, true] {
+        let pixels = gradient16(5, 7, alpha, false);
+        let pnm = Pnm::from_rgba16(5, 7, pixels.clone()).unwrap();
+        let decoded = Pnm::from_bytes(&pnm.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(decoded.maxval(), u16::MAX);
+        assert_eq!(decoded.to_rgba16().get_pixels(), pixels.as_slice());
+    }
+}
+
+#[test]
+fn keeps_samples_of_uncommon_maxval() {
+    let samples: Vec<u16> = (0..12).map(|index| index * 93 % 1024).collect();
+    let text: Vec<String> = samples.iter().map(u16::to_string).collect();
+    let bytes = format!("P2\n4 3\n1023\n{}\n", text.join(" "));
+
+    let pnm = Pnm::from_bytes(bytes.as_bytes()).unwrap();
+    let decoded = Pnm::from_bytes(&pnm.to_bytes().unwrap()).unwrap();
+    let output = decoded.to_bytes().unwrap();
+    let values: Vec<u16> = String::from_utf8_lossy(&output)
+        .split_whitespace()
+        .skip(4)
+        .map(|value| value.parse().unwrap())
+        .collect();
+
+    assert_eq!(decoded.maxval(), 1023);
+    assert_eq!(values, samples);
+}
+
+#[test]
+fn flips_pixmap() {
+    let pixels = gradient(4, 3, false);
+    let mut pnm = Pnm::from_pixels(4, 3, pixels.clone()).unwrap();
+
+    pnm.filter(FlipH::full()).unwrap();
+    assert_eq!(pnm.get_pixel(0, 1), pixels.get(7));
+    pnm.filter(FlipV::full()).unwrap();
+    assert_eq!(pnm.get_pixel(0, 0), pixels.get(11));
+}
+
+/// Pixels em preto e branco, que cabem num PBM
+fn black_and_white(width: usize, height: usize) -> Vec<RGB> {
+    (0..width * height)
+        .map(|index| match (index * 7 / 3) % 2 {
+            0 => RGB::new(0, 0, 0, None),
+            _ => RGB::new(255, 255, 255, None),
+        })
+        .collect()
+}
+
+#[test]
+fn round_trip_every_kind_and_encoding() {
+    let (width, height) = (11, 4);
+    let cases = [
+        (PnmKind::Bitmap, black_and_white(width, height)),
+        (PnmKind::Graymap, gray(width, height)),
+        (PnmKind::Pixmap, gradient(width, height, false)),
+        (PnmKind::ArbitraryMap, gradient(width, height, true)),
+    ];
+
+    for (kind, pixels) in cases {
+        for encoding in [PnmEncoding::Ascii, PnmEncoding::Binary] {
+            let mut pnm = Pnm::from_pixels(width, height, pixels.clone()).unwrap();
+            pnm.set_kind(kind);
+            pnm.set_encoding(encoding);
+            let decoded = Pnm::from_bytes(&pnm.to_bytes().unwrap()).unwrap();
+
+            assert_eq!(decoded.kind(), kind, "{kind:?} {encoding:?}");
+            assert_eq!(
+                decoded.get_pixels(),
+                pixels.as_slice(),
+                "{kind:?} {encoding:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn header_comments_and_packed_bits() {
+    // Comentarios em qualquer ponto do cabecalho e bits do P1 sem espaco entre eles
+    let pbm = Pnm::from_bytes(b"P1 # comentario\n3 # largura\n2\n101\n010").unwrap();
+    let white = RGB::new(255, 255, 255, None);
+    let black = RGB::new(0, 0, 0, None);
+
+    assert_eq!(
+        pbm.get_pixels(),
+        [
+            black.clone(),
+            white.clone(),
+            black.clone(),
+            white.clone(),
+            black,
+            white
+        ]
+    );
+
+    // Amostras ASCII acima do maxval sao limitadas a ele
+    let pgm = Pnm::from_bytes(b"P2\n2 1\n# fim\n100\n50 300").unwrap();
+    assert_eq!(pgm.maxval(), 100);
+    assert_eq!(
+        pgm.get_pixels(),
+        [RGB::new(128, 128, 128, None), RGB::new(255, 255, 255, None)]
+    );
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    let cases: [(&[u8], &str); 6] = [
+        (b"P5\n0 2\n255\n", "pnm dimensions are zero"),
+        (b"P5\n1 1\n0\n\0", "pnm maxval is invalid"),
+        (b"P5\n1 1\n65536\n\0\0", "pnm maxval is invalid"),
+        (b"P6\n2x 1\n255\n", "pnm header number is invalid"),
+        (b"P1\n2 1\n0 2", "pbm sample is invalid"),
+        (
+            b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nENDHDR\n\0",
+            "pam header is missing a field",
+        ),
+    ];
+
+    for (bytes, message) in cases {
+        assert!(
+            matches!(Pnm::from_bytes(bytes), Err(ImageError::InvalidData(m)) if m == message),
+            "{message}"
+        );
+    }
+    assert!(matches!(
+        Pnm::from_bytes(b"P8\n1 1\n255\n\0"),
+        Err(ImageError::InvalidMagic)
+    ));
+}
+
+#[test]
+fn pam_depth_and_keys_are_checked() {
+    let pam =
+        |fields: &str| format!("P7\nWIDTH 1\nHEIGHT 1\nMAXVAL 255\n{fields}\nENDHDR\n\0\0\0\0\0");
+
+    assert!(matches!(
+        Pnm::from_bytes(pam("DEPTH 0").as_bytes()),
+        Err(ImageError::InvalidData("pam depth is zero"))
+    ));
+    assert!(matches!(
+        Pnm::from_bytes(pam("DEPTH 5").as_bytes()),
+        Err(ImageError::Unsupported("pam depth greater than 4"))
+    ));
+    assert!(matches!(
+        Pnm::from_bytes(pam("DEPTH 1\nCOLORS 3").as_bytes()),
+        Err(ImageError::InvalidData("pam header has an unknown key"))
+    ));
+    assert!(Pnm::from_bytes(pam("DEPTH 1\nTUPLTYPE GRAYSCALE").as_bytes()).is_ok());
+}
+
+#[test]
+fn short_rasters_are_truncated() {
+    let cases: [&[u8]; 4] = [
+        b"P4\n9 2\n\xFF\x80\xFF",
+        b"P5\n2 2\n255\n\0\0\0",
+        b"P6\n1 1\n65535\n\0\0\0\0\0",
+        b"P3\n1 1\n255\n1 2",
+    ];
+
+    for bytes in cases {
+        assert!(matches!(Pnm::from_bytes(bytes), Err(ImageError::Truncated)));
+    }
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use common::gradient;
+use std_image::error::ImageError;
+use std_image::filters::{flip_h::FlipH, flip_v::FlipV};
+use std_image::images::{
+    Image, RGB,
+    qoi::{Qoi, QoiChannels, QoiColorSpace},
+};
+
+#[test]
+fn flips_without_format_checks() {
+    let pixels = gradient(5, 2, true);
+    let mut qoi = Qoi::from_pixels(5, 2, pixels.clone()).unwrap();
+
+    qoi.filter(FlipH::rect((0, 0), (5, 1))).unwrap();
+    assert_eq!(qoi.get_pixel(0, 0), pixels.get(4));
+    assert_eq!(qoi.get_pixel(0, 1), pixels.get(5));
+
+    qoi.filter(FlipV::full()).unwrap();
+    assert_eq!(qoi.get_pixel(0, 1), pixels.get(4));
+}
+
+const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+/// Arquivo QOI com o cabecalho informado e os bytes dos chunks
+fn qoi(width: u32, height: u32, channels: u8, color_space: u8, chunks: &[u8]) -> Vec<u8> {
+    let mut bytes = b"qoif".to_vec();
+    bytes.extend_from_slice(&width.to_be_bytes());
+    bytes.extend_from_slice(&height.to_be_bytes());
+    bytes.extend_from_slice(&[channels, color_space]);
+    bytes.extend_from_slice(chunks);
+    bytes
+}
+
+#[test]
+fn round_trip_channels_and_color_space() {
+    let cases = [
+        (QoiChannels::Rgb, gradient(13, 6, false)),
+        (QoiChannels::Rgba, gradient(13, 6, true)),
+    ];
+
+    for (channels, pixels) in cases {
+        for color_space in [QoiColorSpace::Srgb, QoiColorSpace::Linear] {
+            let mut qoi = Qoi::from_pixels(13, 6, pixels.clone()).unwrap();
+            qoi.set_color_space(color_space);
+            let decoded = Qoi::from_bytes(&qoi.to_bytes().unwrap()).unwrap();
+
+            assert_eq!(decoded.channels(), channels);
+            assert_eq!(decoded.color_space(), color_space);
+            assert_eq!(decoded.get_pixels(), pixels.as_slice());
+        }
+    }
+}
+
+#[test]
+fn decodes_every_chunk_type() {
+    let chunks = [
+        &[0xFE, 10, 20, 30][..], // QOI_OP_RGB
+        &[0x76],                 // QOI_OP_DIFF: +1, -1, 0
+        &[0xA5, 0x6B],           // QOI_OP_LUMA: verde +5, vermelho +3, azul +8
+        &[0xC1],                 // QOI_OP_RUN de 2
+        &[0xFF, 1, 2, 3, 4],     // QOI_OP_RGBA
+        &[0x09],                 // QOI_OP_INDEX de (10, 20, 30, 255)
+        &[0x6A],                 // QOI_OP_DIFF sem diferenca
+        &END_MARKER,
+    ]
+    .concat();
+    let image = Qoi::from_bytes(&qoi(8, 1, 4, 1, &chunks)).unwrap();
+
+    let first = RGB::new(10, 20, 30, Some(255));
+    let luma = RGB::new(14, 24, 38, Some(255));
+    assert_eq!(image.color_space(), QoiColorSpace::Linear);
+    assert_eq!(
+        image.get_pixels(),
+        [
+            first.clone(),
+            RGB::new(11, 19, 30, Some(255)),
+            luma.clone(),
+            luma.clone(),
+            luma,
+            RGB::new(1, 2, 3, Some(4)),
+            first.clone(),
+            first,
+        ]
+    );
+}
+
+#[test]
+fn runs_and_index_hits_round_trip() {
+    let mut pixels = vec![RGB::new(9, 9, 9, None); 100];
+    pixels.extend(gradient(10, 3, false));
+    pixels.extend(gradient(10, 3, false));
+    let qoi = Qoi::from_pixels(10, 16, pixels.clone()).unwrap();
+    let bytes = qoi.to_bytes().unwrap();
+
+    assert!(bytes.len() < 14 + 160 * 4);
+    assert_eq!(
+        Qoi::from_bytes(&bytes).unwrap().get_pixels(),
+        pixels.as_slice()
+    );
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    let chunks = [&[0xFE, 1, 2, 3][..], &END_MARKER].concat();
+    let mut magic = qoi(1, 1, 3, 0, &chunks);
+    magic[3] = b'g';
+
+    assert!(matches!(
+        Qoi::from_bytes(&magic),
+        Err(ImageError::InvalidMagic)
+    ));
+    assert!(matches!(
+        Qoi::from_bytes(&qoi(1, 1, 2, 0, &chunks)),
+        Err(ImageError::InvalidData("qoi channels are invalid"))
+    ));
+    assert!(matches!(
+        Qoi::from_bytes(&qoi(1, 1, 3, 2, &chunks)),
+        Err(ImageError::InvalidData("qoi colorspace is invalid"))
+    ));
+    assert!(matches!(
+        Qoi::from_bytes(&qoi(0, 1, 3, 0, &chunks)),
+        Err(ImageError::InvalidData("qoi dimensions are zero"))
+    ));
+    assert!(matches!(
+        Qoi::from_bytes(&magic[..13]),
+        Err(ImageError::Truncated)
+    ));
+}
+
+#[test]
+fn missing_chunks_are_truncated() {
+    // Um QOI_OP_RGB cortado, e pixels que faltam depois do ultimo chunk
+    for (height, chunks) in [(1, &[0xFE, 1, 2][..]), (3, &[0xFE, 1, 2, 3, 0xC0][..])] {
+        assert!(matches!(
+            Qoi::from_bytes(&qoi(1, height, 3, 0, chunks)),
+            Err(ImageError::Truncated)
+        ));
+    }
+    // Um cabecalho enorme para antes de alocar os pixels
+    assert!(matches!(
+        Qoi::from_bytes(&qoi(u32::MAX, u32::MAX, 3, 0, &END_MARKER)),
+        Err(ImageError::Truncated)
+    ));
+
+    // O marcador final e opcional, como no decodificador de referencia
+    let image = Qoi::from_bytes(&qoi(1, 1, 3, 0, &[0xFE, 1, 2, 3])).unwrap();
+    assert_eq!(image.get_pixels(), [RGB::new(1, 2, 3, None)]);
+}
@@ -0,0 +1,231 @@
+mod common;
+
+use common::gradient;
+use std_image::error::ImageError;
+use std_image::filters::resize::{Interpolation, Resize};
+use std_image::images::{Image, Orientation, RGB, buffer::ImageBuffer};
+
+const FILTERS: [Interpolation; 4] = [
+    Interpolation::Nearest,
+    Interpolation::Bilinear,
+    Interpolation::Bicubic,
+    Interpolation::Lanczos3,
+];
+
+/// Linha em tons de cinza com os valores dados
+fn line(values: &[u8]) -> ImageBuffer {
+    let pixels = values
+        .iter()
+        .map(|&value| RGB::new(value, value, value, None))
+        .collect();
+    ImageBuffer::from_pixels(values.len(), 1, pixels).unwrap()
+}
+
+/// Redimensiona a linha so na horizontal e retorna o canal vermelho
+fn resized(values: &[u8], width: usize, filter: Interpolation) -> Vec<u8> {
+    let image = Resize::new(width, 1, filter).apply(&line(values)).unwrap();
+    assert_eq!((image.widht(), image.height()), (width, 1));
+    image.get_pixels().iter().map(|pixel| pixel.red()).collect()
+}
+
+fn size(image: &ImageBuffer) -> (usize, usize) {
+    (image.widht(), image.height())
+}
+
+#[test]
+fn nearest_repeats_and_skips_pixels() {
+    let filter = Interpolation::Nearest;
+
+    assert_eq!(resized(&[10, 20], 4, filter), [10, 10, 20, 20]);
+    // Um pixel a mais ou a menos
+    assert_eq!(resized(&[10, 20, 30], 4, filter), [10, 20, 20, 30]);
+    assert_eq!(resized(&[10, 20, 30, 40], 3, filter), [10, 30, 40]);
+    assert_eq!(resized(&[10, 20, 30, 40], 1, filter), [30]);
+}
+
+#[test]
+fn bilinear_interpolates_between_neighbours() {
+    let filter = Interpolation::Bilinear;
+
+    // As bordas repetem o primeiro e o ultimo pixel
+    assert_eq!(resized(&[0, 255], 4, filter), [0, 64, 191, 255]);
+    // Ao reduzir o raio cresce junto com a escala (1.5 pixels aqui)
+    assert_eq!(resized(&[0, 120, 240], 2, filter), [45, 195]);
+    assert_eq!(resized(&[0, 100, 200, 250], 3, filter), [30, 150, 235]);
+}
+
+#[test]
+fn bicubic_and_lanczos_overshoot_and_are_clamped() {
+    // Perto da borda o peso negativo do vizinho puxa o valor para baixo de 0
+    assert_eq!(
+        resized(&[0, 255], 4, Interpolation::Bicubic),
+        [0, 53, 202, 255]
+    );
+    assert_eq!(
+        resized(&[0, 255], 4, Interpolation::Lanczos3),
+        [0, 59, 196, 255]
+    );
+
+    // Reduzindo em 1 pixel os vizinhos de fora entram com peso negativo
+    assert_eq!(
+        resized(&[0, 100, 200, 250], 3, Interpolation::Bicubic),
+        [19, 152, 243]
+    );
+    assert_eq!(
+        resized(&[0, 100, 200, 250], 3, Interpolation::Lanczos3),
+        [15, 153, 246]
+    );
+
+    // Um degrau fica mais nitido que no bilinear
+    let step = [0, 0, 255, 255];
+    let bilinear = resized(&step, 8, Interpolation::Bilinear);
+    for filter in [Interpolation::Bicubic, Interpolation::Lanczos3] {
+        let sharp = resized(&step, 8, filter);
+        assert!(
+            sharp[3] < bilinear[3] && sharp[4] > bilinear[4],
+            "{filter:?}"
+        );
+    }
+}
+
+#[test]
+fn every_filter_keeps_solid_colors() {
+    let color = RGB::new(30, 140, 220, Some(90));
+    let image = ImageBuffer::new(7, 5, color.clone()).unwrap();
+
+    for filter in FILTERS {
+        // Aumentando, reduzindo e mudando em 1 pixel em cada eixo
+        for (width, height) in [(15, 11), (3, 2), (8, 4), (6, 6), (1, 1)] {
+            let resized = Resize::new(width, height, filter).apply(&image).unwrap();
+            assert_eq!(size(&resized), (width, height));
+            assert!(
+                resized.get_pixels().iter().all(|pixel| *pixel == color),
+                "{filter:?} {width}x{height}"
+            );
+        }
+    }
+}
+
+#[test]
+fn same_size_is_a_copy() {
+    let image = ImageBuffer::from_pixels(6, 4, gradient(6, 4, true)).unwrap();
+
+    for filter in FILTERS {
+        let resized = Resize::new(6, 4, filter).apply(&image).unwrap();
+        assert_eq!(resized, image);
+    }
+}
+
+#[test]
+fn transparent_pixels_do_not_bleed() {
+    let pixels = vec![RGB::new(255, 0, 0, Some(255)), RGB::new(0, 0, 255, Some(0))];
+    let image = ImageBuffer::from_pixels(2, 1, pixels).unwrap();
+    let resized = Resize::new(4, 1, Interpolation::Bilinear)
+        .apply(&image)
+        .unwrap();
+
+    // O azul transparente nao muda a cor, so o alpha
+    let pixels = resized.get_pixels();
+    assert_eq!(pixels[1], RGB::new(255, 0, 0, Some(191)));
+    assert_eq!(pixels[2], RGB::new(255, 0, 0, Some(64)));
+    assert_eq!(pixels[3], RGB::new(0, 0, 0, Some(0)));
+}
+
+#[test]
+fn fit_fill_and_thumbnail_sizes() {
+    let image = ImageBuffer::from_pixels(40, 20, gradient(40, 20, false)).unwrap();
+    let resize = |width, height| Resize::new(width, height, Interpolation::Bilinear);
+
+    assert_eq!(size(&resize(10, 10).fit(&image).unwrap()), (10, 5));
+    assert_eq!(size(&resize(100, 100).fit(&image).unwrap()), (100, 50));
+    assert_eq!(size(&resize(39, 100).fit(&image).unwrap()), (39, 20));
+    // Uma linha muito fina ainda tem 1 pixel
+    assert_eq!(size(&resize(1, 100).fit(&image).unwrap()), (1, 1));
+
+    assert_eq!(size(&resize(10, 10).fill(&image).unwrap()), (10, 10));
+    assert_eq!(size(&resize(41, 19).fill(&image).unwrap()), (41, 19));
+
+    // O thumbnail nunca aumenta a imagem
+    assert_eq!(size(&resize(10, 10).thumbnail(&image).unwrap()), (10, 5));
+    assert_eq!(resize(100, 100).thumbnail(&image).unwrap(), image);
+    assert_eq!(size(&resize(39, 100).thumbnail(&image).unwrap()), (39, 20));
+}
+
+#[test]
+fn fill_crops_the_center() {
+    // Metade esquerda preta e direita branca, com uma coluna cinza de cada lado
+    let mut values = vec![100];
+    values.extend([0; 4]);
+    values.extend([255; 4]);
+    values.push(100);
+    let image = line(&values);
+
+    let filled = Resize::new(2, 1, Interpolation::Nearest)
+        .fill(&image)
+        .unwrap();
+    assert_eq!(
+        filled.get_pixels().iter().map(RGB::red).collect::<Vec<_>>(),
+        [0, 255]
+    );
+
+    // Com a mesma proporcao nada e cortado
+    let mut pixels = image.get_pixels().to_vec();
+    pixels.extend_from_slice(image.get_pixels());
+    let image = ImageBuffer::from_pixels(10, 2, pixels).unwrap();
+    let filled = Resize::new(5, 1, Interpolation::Nearest)
+        .fill(&image)
+        .unwrap();
+    assert_eq!(
+        filled.get_pixels().iter().map(RGB::red).collect::<Vec<_>>(),
+        [0, 0, 255, 255, 100]
+    );
+}
+
+#[test]
+fn sizes_follow_the_displayed_orientation() {
+    let mut image = ImageBuffer::from_pixels(40, 20, gradient(40, 20, false)).unwrap();
+    image.set_orientation(Orientation::Rotate90);
+
+    // Exibida com 20x40, entao guarda 40x20 com os eixos trocados
+    let resized = Resize::new(10, 30, Interpolation::Bilinear)
+        .apply(&image)
+        .unwrap();
+    assert_eq!(size(&resized), (30, 10));
+    assert_eq!(resized.orientation(), Orientation::Rotate90);
+
+    let fitted = Resize::new(10, 10, Interpolation::Bilinear)
+        .fit(&image)
+        .unwrap();
+    assert_eq!(size(&fitted), (10, 5));
+}
+
+#[test]
+fn empty_sizes_are_rejected() {
+    let image = ImageBuffer::from_pixels(4, 4, gradient(4, 4, false)).unwrap();
+
+    for resize in [
+        Resize::new(0, 4, Interpolation::Bilinear),
+        Resize::new(4, 0, Interpolation::Nearest),
+    ] {
+        for result in [
+            resize.apply(&image),
+            resize.fit(&image),
+            resize.fill(&image),
+        ] {
+            assert!(matches!(
+                result,
+                Err(ImageError::InvalidData("resize dimensions are zero"))
+            ));
+        }
+    }
+
+    let empty = ImageBuffer::from_pixels(0, 0, Vec::<RGB>::new()).unwrap();
+    assert!(matches!(
+        Resize::new(4, 4, Interpolation::Bilinear).apply(&empty),
+        Err(ImageError::InvalidData("image is empty"))
+    ));
+    assert!(matches!(
+        Resize::new(usize::MAX, 2, Interpolation::Nearest).apply(&image),
+        Err(ImageError::DimensionOverflow)
+    ));
+}
@@ -0,0 +1,187 @@
+mod common;
+
+use common::gradient;
+use std_image::filters::{
+    FilterError, Transform,
+    resize::Interpolation,
+    rotate::{Rotate, Rotate90, Rotate180, Rotate270},
+};
+use std_image::images::{Image, RGB, buffer::ImageBuffer};
+
+const FILTERS: [Interpolation; 4] = [
+    Interpolation::Nearest,
+    Interpolation::Bilinear,
+    Interpolation::Bicubic,
+    Interpolation::Lanczos3,
+];
+
+fn background() -> RGB {
+    RGB::new(0, 0, 0, None)
+}
+
+fn rotate(image: &ImageBuffer, degrees: f32, filter: Interpolation, expand: bool) -> ImageBuffer {
+    Rotate::new(degrees, filter, expand, background())
+        .apply(image)
+        .unwrap()
+}
+
+fn size(image: &ImageBuffer) -> (usize, usize) {
+    (image.widht(), image.height())
+}
+
+#[test]
+fn right_angles_match_the_exact_rotations() {
+    let image = ImageBuffer::from_pixels(7, 4, gradient(7, 4, true)).unwrap();
+    let square = ImageBuffer::from_pixels(5, 5, gradient(5, 5, false)).unwrap();
+
+    for filter in FILTERS {
+        for (degrees, exact) in [
+            (90.0, Rotate90.apply(&image).unwrap()),
+            (-270.0, Rotate90.apply(&image).unwrap()),
+            (180.0, Rotate180.apply(&image).unwrap()),
+            (270.0, Rotate270.apply(&image).unwrap()),
+            (-90.0, Rotate270.apply(&image).unwrap()),
+            (360.0, image.clone()),
+            (0.0, image.clone()),
+        ] {
+            assert_eq!(
+                rotate(&image, degrees, filter, true),
+                exact,
+                "{filter:?} {degrees}"
+            );
+        }
+
+        // Numa imagem quadrada o tamanho nao muda, entao `expand` nao faz diferenca
+        assert_eq!(
+            rotate(&square, 90.0, filter, false),
+            Rotate90.apply(&square).unwrap()
+        );
+        assert_eq!(
+            rotate(&square, 180.0, filter, false),
+            Rotate180.apply(&square).unwrap()
+        );
+    }
+}
+
+#[test]
+fn rotation_is_clockwise() {
+    let colors = vec![
+        RGB::new(255, 0, 0, None),
+        RGB::new(0, 255, 0, None),
+        RGB::new(0, 0, 255, None),
+    ];
+    let line = ImageBuffer::from_pixels(3, 1, colors.clone()).unwrap();
+
+    // O pixel da esquerda vai para cima
+    let rotated = rotate(&line, 90.0, Interpolation::Nearest, true);
+    assert_eq!(size(&rotated), (1, 3));
+    assert_eq!(rotated.get_pixels(), colors.as_slice());
+}
+
+#[test]
+fn arbitrary_angles_expand_the_canvas() {
+    let color = RGB::new(200, 100, 50, None);
+    let image = ImageBuffer::new(10, 10, color.clone()).unwrap();
+    let wide = ImageBuffer::new(16, 8, color.clone()).unwrap();
+
+    // A cor e conferida longe das bordas, onde o bicubico e o Lanczos passam do valor
+    for filter in FILTERS {
+        // 10 * (cos 45 + sin 45) = 14.14
+        let rotated = rotate(&image, 45.0, filter, true);
+        assert_eq!(size(&rotated), (15, 15), "{filter:?}");
+        assert_eq!(rotated.get_pixel(0, 0), Some(&background()));
+        assert_eq!(rotated.get_pixel(14, 14), Some(&background()));
+        assert_eq!(rotated.get_pixel(7, 7), Some(&color));
+        assert_eq!(rotated.get_pixel(7, 5), Some(&color));
+
+        // 16x8 a 30 graus: 16 * 0.87 + 8 * 0.5 = 17.86 e 16 * 0.5 + 8 * 0.87 = 14.93
+        let rotated = rotate(&wide, 30.0, filter, true);
+        assert_eq!(size(&rotated), (18, 15), "{filter:?}");
+        assert_eq!(rotated.get_pixel(9, 7), Some(&color));
+        assert_eq!(rotated.get_pixel(0, 14), Some(&background()));
+
+        // Sem expandir os cantos sao cortados e o tamanho fica igual
+        let rotated = rotate(&image, 45.0, filter, false);
+        assert_eq!(size(&rotated), (10, 10), "{filter:?}");
+        assert_eq!(rotated.get_pixel(0, 0), Some(&background()));
+        assert_eq!(rotated.get_pixel(5, 5), Some(&color));
+        assert_eq!(rotated.get_pixel(5, 3), Some(&color));
+    }
+}
+
+#[test]
+fn interpolation_blends_the_edges() {
+    let color = RGB::new(255, 255, 255, None);
+    let image = ImageBuffer::new(10, 10, color.clone()).unwrap();
+    let blended = |image: &ImageBuffer| {
+        image
+            .get_pixels()
+            .iter()
+            .filter(|pixel| **pixel != color && **pixel != background())
+            .count()
+    };
+
+    // O mais proximo so copia pixels; os outros misturam a borda com o fundo
+    assert_eq!(
+        blended(&rotate(&image, 30.0, Interpolation::Nearest, true)),
+        0
+    );
+    for filter in &FILTERS[1..] {
+        assert!(
+            blended(&rotate(&image, 30.0, *filter, true)) > 0,
+            "{filter:?}"
+        );
+    }
+
+    // A 90 graus as amostras caem nos centros dos pixels, entao a borda continua nitida
+    let mut edge = vec![background(); 4];
+    edge.extend(vec![color.clone(); 4]);
+    let edge = ImageBuffer::from_pixels(4, 2, edge).unwrap();
+    let rotated = rotate(&edge, 90.0, Interpolation::Bilinear, true);
+    assert_eq!(
+        rotated.get_pixels(),
+        Rotate90.apply(&edge).unwrap().get_pixels()
+    );
+}
+
+#[test]
+fn uncovered_areas_use_the_background() {
+    let image = ImageBuffer::from_pixels(8, 8, gradient(8, 8, false)).unwrap();
+    let clear = RGB::new(0, 0, 0, Some(0));
+
+    for filter in FILTERS {
+        let rotated = Rotate::new(45.0, filter, true, clear.clone())
+            .apply(&image)
+            .unwrap();
+        assert_eq!(rotated.get_pixel(0, 0), Some(&clear), "{filter:?}");
+
+        // Perto da borda o alpha fica entre 0 e 255, sem escurecer a cor
+        if filter != Interpolation::Nearest {
+            assert!(
+                rotated
+                    .get_pixels()
+                    .iter()
+                    .any(|pixel| matches!(pixel.alpha(), Some(1..=254)))
+            );
+        }
+    }
+}
+
+#[test]
+fn invalid_angles_are_rejected() {
+    let image = ImageBuffer::from_pixels(2, 2, gradient(2, 2, false)).unwrap();
+
+    for degrees in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        assert!(matches!(
+            Rotate::new(degrees, Interpolation::Bilinear, true, background()).apply(&image),
+            Err(FilterError::InvalidDimensions)
+        ));
+    }
+
+    // Uma imagem vazia continua vazia, ou com 1 pixel de fundo ao expandir
+    let empty = ImageBuffer::from_pixels(0, 0, Vec::<RGB>::new()).unwrap();
+    let rotated = rotate(&empty, 30.0, Interpolation::Bilinear, false);
+    assert_eq!(size(&rotated), (0, 0));
+    let rotated = rotate(&empty, 30.0, Interpolation::Bilinear, true);
+    assert_eq!(rotated.get_pixels(), &[background()]);
+}
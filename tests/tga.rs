@@ -0,0 +1,280 @@
+mod common;
+
+use common::{gradient, gray, temp_path};
+use std_image::error::ImageError;
+use std_image::images::{
+    self, Format, Image, RGB,
+    dynamic::DynamicImage,
+    tga::{Tga, TgaColorType, TgaOrigin},
+};
+
+const TOP_LEFT: u8 = 0x20;
+
+/// Cabecalho de 18 bytes sem campo de ID. A tabela de cores comeca no indice `map_first`
+fn header(
+    image_type: u8,
+    map: (u8, u16, u16, u8),
+    size: (u16, u16),
+    bits: u8,
+    descriptor: u8,
+) -> Vec<u8> {
+    let (map_type, map_first, map_length, map_bits) = map;
+    let mut bytes = vec![0, map_type, image_type];
+    bytes.extend_from_slice(&map_first.to_le_bytes());
+    bytes.extend_from_slice(&map_length.to_le_bytes());
+    bytes.push(map_bits);
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&size.0.to_le_bytes());
+    bytes.extend_from_slice(&size.1.to_le_bytes());
+    bytes.extend_from_slice(&[bits, descriptor]);
+    bytes
+}
+
+/// Cor direta de 24 bits, sem tabela de cores
+fn true_color(image_type: u8, width: u16, height: u16) -> Vec<u8> {
+    header(image_type, (0, 0, 0, 0), (width, height), 24, TOP_LEFT)
+}
+
+fn decode_error(bytes: &[u8]) -> ImageError {
+    Tga::from_bytes(bytes).err().expect("decoding should fail")
+}
+
+fn opaque(red: u8, green: u8, blue: u8) -> RGB {
+    RGB::new(red, green, blue, None)
+}
+
+#[test]
+fn detects_tga_by_footer() {
+    let tga = Tga::from_pixels(4, 3, gradient(4, 3, false)).unwrap();
+    let bytes = tga.to_bytes().unwrap();
+
+    assert_eq!(Format::from_magic(&bytes), None);
+    let image = DynamicImage::from_bytes(&bytes).unwrap();
+    assert!(matches!(image, DynamicImage::Tga(_)));
+    assert_eq!(image.get_pixels(), gradient(4, 3, false).as_slice());
+}
+
+#[test]
+fn opens_tga_from_path() {
+    let path = temp_path("open.tga");
+    let mut tga = Tga::from_pixels(5, 2, gradient(5, 2, true)).unwrap();
+    tga.save(path.to_str().unwrap()).unwrap();
+
+    let image = images::open(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(image, DynamicImage::Tga(_)));
+    assert_eq!((image.widht(), image.height()), (5, 2));
+    assert_eq!(image.get_pixels(), gradient(5, 2, true).as_slice());
+}
+
+#[test]
+fn opens_rle_tga_from_path() {
+    let path = temp_path("rle.tga");
+    let mut tga = Tga::from_pixels(8, 4, gradient(8, 4, false)).unwrap();
+    tga.set_rle(true);
+    tga.save(path.to_str().unwrap()).unwrap();
+
+    let image = images::open(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(image, DynamicImage::Tga(_)));
+    assert_eq!(image.get_pixels(), gradient(8, 4, false).as_slice());
+}
+
+/// Cores que cabem em 5 bits por canal, com alpha de 1 bit opcional
+fn five_bits(width: usize, height: usize, alpha: bool) -> Vec<RGB> {
+    let expand = |value: usize| ((value % 32 * 255 + 15) / 31) as u8;
+    (0..width * height)
+        .map(|index| {
+            let alpha = alpha.then_some(if index % 3 == 0 { 0 } else { u8::MAX });
+            RGB::new(expand(index), expand(index * 7), expand(index * 13), alpha)
+        })
+        .collect()
+}
+
+/// Cinza com alpha
+fn gray_alpha(width: usize, height: usize) -> Vec<RGB> {
+    gray(width, height)
+        .into_iter()
+        .enumerate()
+        .map(|(index, c)| RGB::new(c.red(), c.red(), c.red(), Some((index * 41 % 256) as u8)))
+        .collect()
+}
+
+#[test]
+fn round_trip_every_color_type_and_depth() {
+    let (width, height) = (13, 6);
+    let cases = [
+        (TgaColorType::TrueColor, 24, gradient(width, height, false)),
+        (TgaColorType::TrueColor, 32, gradient(width, height, true)),
+        (TgaColorType::TrueColor, 15, five_bits(width, height, false)),
+        (TgaColorType::TrueColor, 16, five_bits(width, height, true)),
+        (TgaColorType::Gray, 8, gray(width, height)),
+        (TgaColorType::Gray, 16, gray_alpha(width, height)),
+        (TgaColorType::ColorMapped, 8, gradient(width, height, false)),
+        (TgaColorType::ColorMapped, 8, gradient(width, height, true)),
+    ];
+    let origins = [
+        TgaOrigin::BottomLeft,
+        TgaOrigin::BottomRight,
+        TgaOrigin::TopLeft,
+        TgaOrigin::TopRight,
+    ];
+
+    for (color_type, bits, pixels) in cases {
+        for rle in [false, true] {
+            for origin in origins {
+                let mode = format!("{color_type:?} {bits} rle {rle} {origin:?}");
+                let mut tga = Tga::from_pixels(width, height, pixels.clone()).unwrap();
+                tga.set_color_type(color_type);
+                tga.set_bits_per_pixel(bits);
+                tga.set_rle(rle);
+                tga.set_origin(origin);
+                let decoded = Tga::from_bytes(&tga.to_bytes().unwrap()).expect(&mode);
+
+                assert_eq!(decoded.color_type(), color_type, "{mode}");
+                assert_eq!(decoded.bits_per_pixel(), bits, "{mode}");
+                assert_eq!((decoded.rle(), decoded.origin()), (rle, origin), "{mode}");
+                assert_eq!(decoded.get_pixels(), pixels.as_slice(), "{mode}");
+            }
+        }
+    }
+}
+
+#[test]
+fn rle_packets_can_cross_rows() {
+    // 3x2: repeticao de 4 pixels (passa para a segunda linha) e dois pixels crus
+    let mut bytes = true_color(10, 3, 2);
+    bytes.extend_from_slice(&[0x83, 30, 20, 10]);
+    bytes.extend_from_slice(&[0x01, 3, 2, 1, 6, 5, 4]);
+    let decoded = Tga::from_bytes(&bytes).unwrap();
+
+    let run = opaque(10, 20, 30);
+    assert!(decoded.rle());
+    assert_eq!(
+        decoded.get_pixels(),
+        &[
+            run.clone(),
+            run.clone(),
+            run.clone(),
+            run,
+            opaque(1, 2, 3),
+            opaque(4, 5, 6)
+        ]
+    );
+
+    // Pacotes alem do fim da imagem sao cortados, e pacotes incompletos faltam dados
+    let mut long = true_color(10, 2, 1);
+    long.extend_from_slice(&[0xFF, 1, 2, 3]);
+    assert_eq!(
+        Tga::from_bytes(&long).unwrap().get_pixels(),
+        vec![opaque(3, 2, 1); 2]
+    );
+    for cut in [&bytes[..21], &bytes[..23], &bytes[..26]] {
+        assert!(matches!(decode_error(cut), ImageError::Truncated));
+    }
+}
+
+#[test]
+fn color_map_starts_at_its_first_index() {
+    // Tabela de 2 cores de 24 bits comecando no indice 5
+    let mut bytes = header(1, (1, 5, 2, 24), (2, 1), 8, TOP_LEFT);
+    bytes.extend_from_slice(&[0, 0, 255, 255, 0, 0]);
+    let mut valid = bytes.clone();
+    valid.extend_from_slice(&[6, 5]);
+
+    let decoded = Tga::from_bytes(&valid).unwrap();
+    assert_eq!(decoded.color_type(), TgaColorType::ColorMapped);
+    assert_eq!(decoded.palette(), &[opaque(255, 0, 0), opaque(0, 0, 255)]);
+    assert_eq!(
+        decoded.get_pixels(),
+        &[opaque(0, 0, 255), opaque(255, 0, 0)]
+    );
+
+    for index in [4, 7] {
+        let mut outside = bytes.clone();
+        outside.extend_from_slice(&[5, index]);
+        assert!(matches!(
+            decode_error(&outside),
+            ImageError::InvalidData("tga color index is out of range")
+        ));
+    }
+    assert!(matches!(decode_error(&bytes[..21]), ImageError::Truncated));
+}
+
+#[test]
+fn alpha_follows_the_descriptor_and_the_data() {
+    // 16 bits so tem alpha quando o descritor tem bits de alpha (vermelho transparente e verde)
+    let mut bytes = header(2, (0, 0, 0, 0), (2, 1), 16, TOP_LEFT);
+    bytes.extend_from_slice(&0x7C00_u16.to_le_bytes());
+    bytes.extend_from_slice(&0x83E0_u16.to_le_bytes());
+    assert_eq!(
+        Tga::from_bytes(&bytes).unwrap().get_pixels(),
+        &[opaque(255, 0, 0), opaque(0, 255, 0)]
+    );
+    bytes[17] |= 1;
+    assert_eq!(
+        Tga::from_bytes(&bytes).unwrap().get_pixels(),
+        &[RGB::new(255, 0, 0, Some(0)), RGB::new(0, 255, 0, Some(255))]
+    );
+
+    // Alpha zerado em todos os pixels e tratado como opaco
+    let mut bytes = header(2, (0, 0, 0, 0), (2, 1), 32, TOP_LEFT | 8);
+    bytes.extend_from_slice(&[1, 2, 3, 0, 4, 5, 6, 0]);
+    let decoded = Tga::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.get_pixels(), &[opaque(3, 2, 1), opaque(6, 5, 4)]);
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    let mut valid = true_color(2, 1, 1);
+    valid.extend_from_slice(&[1, 2, 3]);
+    assert!(Tga::from_bytes(&valid).is_ok());
+    assert!(matches!(decode_error(&valid[..17]), ImageError::Truncated));
+    assert!(matches!(decode_error(&valid[..20]), ImageError::Truncated));
+
+    let edit = |index: usize, value: u8| {
+        let mut bytes = valid.clone();
+        bytes[index] = value;
+        decode_error(&bytes)
+    };
+    assert!(matches!(
+        edit(2, 0),
+        ImageError::InvalidData("tga has no image data")
+    ));
+    assert!(matches!(
+        edit(2, 32),
+        ImageError::UnsupportedCompression(32)
+    ));
+    assert!(matches!(
+        edit(1, 2),
+        ImageError::InvalidData("tga color map type is invalid")
+    ));
+    assert!(matches!(edit(16, 12), ImageError::UnsupportedBitDepth(12)));
+    // Cinza so tem 8 ou 16 bits, e imagens indexadas precisam da tabela de cores
+    assert!(matches!(edit(2, 3), ImageError::UnsupportedBitDepth(24)));
+    assert!(matches!(
+        decode_error(&header(1, (0, 0, 0, 0), (1, 1), 8, 0)),
+        ImageError::UnsupportedBitDepth(8)
+    ));
+    assert!(matches!(
+        edit(12, 0),
+        ImageError::InvalidData("tga dimensions are zero")
+    ));
+
+    // Sem dados para cobrir a imagem nada e alocado
+    let huge = true_color(10, u16::MAX, u16::MAX);
+    assert!(matches!(decode_error(&huge), ImageError::Truncated));
+}
+
+#[test]
+fn footer_is_optional() {
+    let tga = Tga::from_pixels(6, 4, gradient(6, 4, true)).unwrap();
+    let bytes = tga.to_bytes().unwrap();
+
+    let footer = bytes.len() - 26;
+    assert_eq!(&bytes[footer + 8..], b"TRUEVISION-XFILE.\0");
+    let decoded = Tga::from_bytes(&bytes[..footer]).unwrap();
+    assert_eq!(decoded.get_pixels(), gradient(6, 4, true).as_slice());
+}
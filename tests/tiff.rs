@@ -0,0 +1,515 @@
+mod common;
+
+use common::{gradient, gradient16, gray};
+use std_image::error::ImageError;
+use std_image::images::{
+    Image, RGB,
+    buffer::ImageBuffer,
+    pixel::Rgba,
+    tiff::{Tiff, TiffColorType, TiffCompression},
+    zlib,
+};
+
+#[test]
+fn round_trip_16_bits() {
+    let compressions = [
+        TiffCompression::None,
+        TiffCompression::PackBits,
+        TiffCompression::Lzw,
+        TiffCompression::Deflate,
+    ];
+    for compression in compressions {
+        for (alpha, gray) in [(false, false), (true, false), (false, true), (true, true)] {
+            let pixels = gradient16(11, 6, alpha, gray);
+            let mut tiff = Tiff::from_rgba16(11, 6, pixels.clone()).unwrap();
+            tiff.set_compression(compression);
+            if gray {
+                tiff.set_color_type(TiffColorType::Gray);
+            }
+            let decoded = Tiff::from_bytes(&tiff.to_bytes().unwrap()).unwrap();
+
+            assert_eq!(decoded.bits_per_sample(), 16);
+            assert_eq!(decoded.to_rgba16().get_pixels(), pixels.as_slice());
+        }
+    }
+}
+
+#[test]
+fn editing_pages_drops_the_16_bits_copy() {
+    let pixels = gradient16(4, 2, false, false);
+    let mut tiff = Tiff::from_rgba16(4, 2, pixels.clone()).unwrap();
+    tiff.pages_mut()[0].pixels()[0] = RGB::new(1, 2, 3, None);
+
+    let decoded = Tiff::from_bytes(&tiff.to_bytes().unwrap()).unwrap();
+    let saved = decoded.to_rgba16();
+    assert_eq!(saved.get_pixels()[0], Rgba([257, 514, 771, u16::MAX]));
+    assert_ne!(saved.get_pixels()[1], pixels[1]);
+}
+
+/// Entrada de um diretorio montado a mao: tag, tipo (3 SHORT, 4 LONG) e valores
+struct Tag(u16, u16, Vec<u32>);
+
+/// TIFF de uma pagina montado a mao, com os blocos (strips ou tiles) logo depois do cabecalho.
+/// As tags de offsets (273 ou 324) sao preenchidas com a posicao de cada bloco
+fn tiff_file(big_endian: bool, tags: Vec<Tag>, offsets_tag: u16, blocks: &[Vec<u8>]) -> Vec<u8> {
+    let u16_bytes = |value: u16| match big_endian {
+        true => value.to_be_bytes(),
+        false => value.to_le_bytes(),
+    };
+    let u32_bytes = |value: u32| match big_endian {
+        true => value.to_be_bytes(),
+        false => value.to_le_bytes(),
+    };
+
+    let mut bytes = match big_endian {
+        true => b"MM\0*".to_vec(),
+        false => b"II*\0".to_vec(),
+    };
+    bytes.extend_from_slice(&[0; 4]);
+
+    let mut offsets = Vec::new();
+    for block in blocks {
+        offsets.push(bytes.len() as u32);
+        bytes.extend_from_slice(block);
+    }
+    let mut tags = tags;
+    tags.push(Tag(offsets_tag, 4, offsets));
+    tags.sort_by_key(|tag| tag.0);
+
+    let ifd = bytes.len() as u32;
+    bytes[4..8].copy_from_slice(&u32_bytes(ifd));
+    let mut extra = ifd as usize + 2 + tags.len() * 12 + 4;
+    let mut values = Vec::new();
+
+    bytes.extend_from_slice(&u16_bytes(tags.len() as u16));
+    for Tag(tag, kind, list) in &tags {
+        let data: Vec<u8> = list
+            .iter()
+            .flat_map(|value| match kind {
+                3 => u16_bytes(*value as u16).to_vec(),
+                _ => u32_bytes(*value).to_vec(),
+            })
+            .collect();
+        bytes.extend_from_slice(&u16_bytes(*tag));
+        bytes.extend_from_slice(&u16_bytes(*kind));
+        bytes.extend_from_slice(&u32_bytes(list.len() as u32));
+        if data.len() <= 4 {
+            let mut inline = [0; 4];
+            inline[..data.len()].copy_from_slice(&data);
+            bytes.extend_from_slice(&inline);
+        } else {
+            bytes.extend_from_slice(&u32_bytes(extra as u32));
+            extra += data.len();
+            values.extend(data);
+        }
+    }
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend(values);
+    bytes
+}
+
+/// Amostra `channel` do pixel (x, y) nas imagens montadas a mao
+fn sample(x: usize, y: usize, channel: usize) -> u16 {
+    ((x * 3001 + y * 7919 + channel * 1237) % 65536) as u16
+}
+
+/// Bytes das linhas `rows` com as colunas `columns` (preenchendo com zero o que passar da
+/// imagem), em 8 ou 16 bits e RGB, opcionalmente com o preditor horizontal
+fn block(
+    width: usize,
+    height: usize,
+    columns: std::ops::Range<usize>,
+    rows: std::ops::Range<usize>,
+    bits: u16,
+    big_endian: bool,
+    predictor: bool,
+) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for y in rows {
+        let mut previous = [0_u16; 3];
+        for x in columns.clone() {
+            for (channel, last) in previous.iter_mut().enumerate() {
+                let value = match x < width && y < height {
+                    true => sample(x, y, channel),
+                    false => 0,
+                };
+                let value = match bits {
+                    16 => value,
+                    _ => value >> 8,
+                };
+                let stored = match predictor {
+                    true => value.wrapping_sub(*last) & if bits == 16 { 0xFFFF } else { 0xFF },
+                    false => value,
+                };
+                *last = value;
+                match (bits, big_endian) {
+                    (16, true) => bytes.extend_from_slice(&stored.to_be_bytes()),
+                    (16, false) => bytes.extend_from_slice(&stored.to_le_bytes()),
+                    _ => bytes.push(stored as u8),
+                }
+            }
+        }
+    }
+    bytes
+}
+
+fn expected(width: usize, height: usize, bits: u16) -> Vec<Rgba<u16>> {
+    (0..width * height)
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            let [red, green, blue] = [0, 1, 2].map(|channel| match bits {
+                16 => sample(x, y, channel),
+                _ => (sample(x, y, channel) >> 8) * 257,
+            });
+            Rgba([red, green, blue, u16::MAX])
+        })
+        .collect()
+}
+
+fn common_tags(width: usize, height: usize, bits: u16, compression: u16) -> Vec<Tag> {
+    vec![
+        Tag(256, 4, vec![width as u32]),
+        Tag(257, 4, vec![height as u32]),
+        Tag(258, 3, vec![bits as u32; 3]),
+        Tag(259, 3, vec![compression as u32]),
+        Tag(262, 3, vec![2]),
+        Tag(277, 3, vec![3]),
+    ]
+}
+
+/// Preto e branco, para 1 bit
+fn black_and_white(width: usize, height: usize) -> Vec<RGB> {
+    (0..width * height)
+        .map(|index| match (index * 5 / 3) % 2 {
+            0 => RGB::new(0, 0, 0, None),
+            _ => RGB::new(255, 255, 255, None),
+        })
+        .collect()
+}
+
+const COMPRESSIONS: [TiffCompression; 4] = [
+    TiffCompression::None,
+    TiffCompression::PackBits,
+    TiffCompression::Lzw,
+    TiffCompression::Deflate,
+];
+
+#[test]
+fn round_trip_every_compression_and_depth() {
+    type Pixels = fn(usize, usize) -> Vec<RGB>;
+    let images: [(u16, TiffColorType, Pixels); 4] = [
+        (1, TiffColorType::Gray, black_and_white),
+        (8, TiffColorType::Gray, gray),
+        (8, TiffColorType::Rgb, |width, height| {
+            gradient(width, height, false)
+        }),
+        (8, TiffColorType::Rgba, |width, height| {
+            gradient(width, height, true)
+        }),
+    ];
+
+    for compression in COMPRESSIONS {
+        let predicted = matches!(compression, TiffCompression::Lzw | TiffCompression::Deflate);
+        for predictor in [false, true] {
+            // A imagem larga tem varios strips
+            for (width, height) in [(37, 29), (300, 40)] {
+                for (bits, color_type, pixels) in images {
+                    let pixels = pixels(width, height);
+                    let mode =
+                        format!("{compression:?} {bits} {color_type:?} predictor {predictor}");
+                    let mut tiff = Tiff::from_pixels(width, height, pixels.clone()).unwrap();
+                    tiff.set_color_type(color_type);
+                    tiff.set_bits_per_sample(bits);
+                    tiff.set_compression(compression);
+                    tiff.set_predictor(predictor);
+                    let decoded = Tiff::from_bytes(&tiff.to_bytes().unwrap()).expect(&mode);
+
+                    let uses_predictor = predictor && predicted && bits == 8;
+                    assert_eq!(decoded.compression(), compression, "{mode}");
+                    assert_eq!(decoded.bits_per_sample(), bits, "{mode}");
+                    assert_eq!(decoded.color_type(), color_type, "{mode}");
+                    assert_eq!(decoded.predictor(), uses_predictor, "{mode}");
+                    assert_eq!(decoded.get_pixels(), pixels.as_slice(), "{mode}");
+                }
+
+                let pixels = gradient16(width, height, true, false);
+                let mut tiff = Tiff::from_rgba16(width, height, pixels.clone()).unwrap();
+                tiff.set_compression(compression);
+                tiff.set_predictor(predictor);
+                let decoded = Tiff::from_bytes(&tiff.to_bytes().unwrap()).unwrap();
+
+                assert_eq!(decoded.bits_per_sample(), 16);
+                assert_eq!(decoded.predictor(), predictor && predicted);
+                assert_eq!(decoded.to_rgba16().get_pixels(), pixels.as_slice());
+            }
+        }
+    }
+}
+
+#[test]
+fn decodes_big_endian_strips() {
+    let (width, height) = (13, 9);
+    for (bits, predictor) in [(8, false), (16, false), (16, true)] {
+        let strips: Vec<Vec<u8>> = [0..5, 5..9]
+            .into_iter()
+            .map(|rows| block(width, height, 0..width, rows, bits, true, predictor))
+            .collect();
+        let (compression, strips) = match predictor {
+            true => (8, strips.iter().map(|s| zlib::compress(s)).collect()),
+            false => (1, strips),
+        };
+        let mut tags = common_tags(width, height, bits, compression);
+        tags.push(Tag(278, 3, vec![5]));
+        tags.push(Tag(279, 4, strips.iter().map(|s| s.len() as u32).collect()));
+        if predictor {
+            tags.push(Tag(317, 3, vec![2]));
+        }
+        let bytes = tiff_file(true, tags, 273, &strips);
+        let decoded = Tiff::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.bits_per_sample(), bits);
+        assert_eq!(decoded.predictor(), predictor);
+        assert_eq!(
+            decoded.to_rgba16().get_pixels(),
+            expected(width, height, bits).as_slice()
+        );
+    }
+}
+
+#[test]
+fn decodes_tiles() {
+    // 20x18 com tiles de 16x16: os da borda direita e de baixo passam da imagem
+    let (width, height) = (20, 18);
+    for (big_endian, compression) in [(false, 1), (true, 8), (false, 32946)] {
+        let mut tiles = Vec::new();
+        for top in [0, 16] {
+            for left in [0, 16] {
+                let tile = block(
+                    width,
+                    height,
+                    left..left + 16,
+                    top..top + 16,
+                    8,
+                    big_endian,
+                    false,
+                );
+                tiles.push(match compression {
+                    1 => tile,
+                    _ => zlib::compress(&tile),
+                });
+            }
+        }
+        let mut tags = common_tags(width, height, 8, compression);
+        tags.push(Tag(322, 3, vec![16]));
+        tags.push(Tag(323, 3, vec![16]));
+        tags.push(Tag(325, 4, tiles.iter().map(|t| t.len() as u32).collect()));
+        let bytes = tiff_file(big_endian, tags, 324, &tiles);
+        let decoded = Tiff::from_bytes(&bytes).unwrap();
+
+        assert!(decoded.tiled());
+        assert_eq!((decoded.widht(), decoded.height()), (width, height));
+        assert_eq!(
+            decoded.to_rgba16().get_pixels(),
+            expected(width, height, 8).as_slice()
+        );
+    }
+}
+
+#[test]
+fn round_trip_multipage() {
+    let pages = vec![
+        ImageBuffer::from_pixels(7, 5, gradient(7, 5, false)).unwrap(),
+        ImageBuffer::from_pixels(3, 9, gradient(3, 9, true)).unwrap(),
+        ImageBuffer::from_pixels(4, 4, gray(4, 4)).unwrap(),
+    ];
+    let mut tiff = Tiff::from_pages(pages.clone()).unwrap();
+    tiff.set_compression(TiffCompression::Deflate);
+    let decoded = Tiff::from_bytes(&tiff.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(decoded.pages(), pages.as_slice());
+    assert_eq!((decoded.widht(), decoded.height()), (7, 5));
+    assert!(decoded.page_rgba16(2).is_some());
+    assert!(decoded.page_rgba16(3).is_none());
+}
+
+/// TIFF 2x2 RGB de 8 bits sem compressao, com as tags trocadas por `edit`
+fn small_tiff(edit: impl FnOnce(&mut Vec<Tag>)) -> Vec<u8> {
+    let strip = block(2, 2, 0..2, 0..2, 8, false, false);
+    let mut tags = common_tags(2, 2, 8, 1);
+    tags.push(Tag(279, 4, vec![strip.len() as u32]));
+    edit(&mut tags);
+    tiff_file(false, tags, 273, &[strip])
+}
+
+/// Mudanca nas tags de `small_tiff`
+type TagEdit = Box<dyn FnOnce(&mut Vec<Tag>)>;
+
+/// Troca os valores de uma tag, ou adiciona a tag se ela nao existir
+fn set_tag(tags: &mut Vec<Tag>, tag: u16, kind: u16, values: Vec<u32>) {
+    tags.retain(|entry| entry.0 != tag);
+    tags.push(Tag(tag, kind, values));
+}
+
+fn decode_error(bytes: &[u8]) -> ImageError {
+    Tiff::from_bytes(bytes).err().expect("decoding should fail")
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    let valid = small_tiff(|_| {});
+    assert_eq!(
+        Tiff::from_bytes(&valid).unwrap().to_rgba16().get_pixels(),
+        expected(2, 2, 8).as_slice()
+    );
+
+    let mut magic = valid.clone();
+    magic[2] = 43;
+    assert!(matches!(
+        decode_error(&magic),
+        ImageError::Unsupported("bigtiff")
+    ));
+    magic[..2].copy_from_slice(b"XX");
+    assert!(matches!(decode_error(&magic), ImageError::InvalidMagic));
+    assert!(matches!(decode_error(&valid[..3]), ImageError::Truncated));
+    assert!(matches!(decode_error(&valid[..6]), ImageError::Truncated));
+
+    // O diretorio fica depois da strip de 12 bytes; sem ele nao ha paginas
+    let mut no_ifd = valid.clone();
+    no_ifd[4..8].copy_from_slice(&0_u32.to_le_bytes());
+    assert!(matches!(
+        decode_error(&no_ifd),
+        ImageError::InvalidData("tiff has no pages")
+    ));
+    assert!(matches!(decode_error(&valid[..22]), ImageError::Truncated));
+}
+
+#[test]
+fn invalid_tags_are_rejected() {
+    let cases: Vec<(TagEdit, ImageError)> = vec![
+        (
+            Box::new(|tags| tags.retain(|tag| tag.0 != 256)),
+            ImageError::InvalidData("tiff is missing the image width"),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 257, 4, vec![0])),
+            ImageError::InvalidData("tiff dimensions are zero"),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 259, 2, vec![1])),
+            ImageError::InvalidData("tiff tag type is invalid"),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 258, 3, vec![12; 3])),
+            ImageError::UnsupportedBitDepth(12),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 258, 3, vec![8, 8, 16])),
+            ImageError::Unsupported("tiff with different bits per sample"),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 339, 3, vec![3; 3])),
+            ImageError::Unsupported("tiff signed or floating point samples"),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 259, 3, vec![7])),
+            ImageError::UnsupportedCompression(7),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 317, 3, vec![3])),
+            ImageError::Unsupported("tiff floating point predictor"),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 262, 3, vec![5])),
+            ImageError::Unsupported("tiff photometric interpretation"),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 277, 3, vec![2])),
+            ImageError::InvalidData("tiff samples per pixel are invalid"),
+        ),
+        (
+            Box::new(|tags| {
+                set_tag(tags, 262, 3, vec![3]);
+                set_tag(tags, 277, 3, vec![1]);
+                set_tag(tags, 258, 3, vec![8]);
+            }),
+            ImageError::InvalidData("tiff palette is missing the color map"),
+        ),
+        (
+            Box::new(|tags| {
+                set_tag(tags, 262, 3, vec![3]);
+                set_tag(tags, 277, 3, vec![1]);
+                set_tag(tags, 258, 3, vec![8]);
+                set_tag(tags, 320, 3, vec![0; 3 * 255]);
+            }),
+            ImageError::InvalidData("tiff color map is too short"),
+        ),
+        (
+            Box::new(|tags| set_tag(tags, 278, 3, vec![1])),
+            ImageError::InvalidData("tiff has too few chunks"),
+        ),
+        (
+            Box::new(|tags| {
+                set_tag(tags, 259, 3, vec![8]);
+                tags.retain(|tag| tag.0 != 279);
+            }),
+            ImageError::InvalidData("tiff is missing byte counts"),
+        ),
+        (
+            Box::new(|tags| {
+                set_tag(tags, 322, 3, vec![0]);
+                set_tag(tags, 323, 3, vec![16]);
+            }),
+            ImageError::InvalidData("tiff tile size is zero"),
+        ),
+    ];
+
+    for (index, (edit, expected)) in cases.into_iter().enumerate() {
+        let error = Tiff::from_bytes(&small_tiff(edit)).err();
+        assert_eq!(
+            format!("{error:?}"),
+            format!("{:?}", Some(expected)),
+            "case {index}"
+        );
+    }
+
+    // Sem os offsets da tag certa (tiles pedem 324) nao ha onde ler os dados
+    let tiled = small_tiff(|tags| {
+        set_tag(tags, 322, 3, vec![16]);
+        set_tag(tags, 323, 3, vec![16]);
+    });
+    assert!(matches!(
+        decode_error(&tiled),
+        ImageError::InvalidData("tiff is missing the tile offsets")
+    ));
+}
+
+#[test]
+fn broken_pages_after_the_first_are_skipped() {
+    let pages = vec![
+        ImageBuffer::from_pixels(3, 2, gradient(3, 2, false)).unwrap(),
+        ImageBuffer::from_pixels(2, 2, gradient(2, 2, false)).unwrap(),
+    ];
+    let mut tiff = Tiff::from_pages(pages.clone()).unwrap();
+    tiff.set_compression(TiffCompression::None);
+    let mut bytes = tiff.to_bytes().unwrap();
+
+    // Troca a compressao da segunda pagina por uma desconhecida
+    let first = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    let count = u16::from_le_bytes([bytes[first], bytes[first + 1]]) as usize;
+    let next_at = first + 2 + count * 12;
+    let second = u32::from_le_bytes(bytes[next_at..next_at + 4].try_into().unwrap()) as usize;
+    let entries = second + 2..second + 2 + 12 * bytes[second] as usize;
+    let compression = bytes[entries]
+        .chunks_exact(12)
+        .position(|entry| entry[..2] == 259_u16.to_le_bytes())
+        .unwrap();
+    let value = second + 2 + compression * 12 + 8;
+    bytes[value..value + 2].copy_from_slice(&7_u16.to_le_bytes());
+
+    let decoded = Tiff::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.pages(), &pages[..1]);
+
+    // Um ciclo de diretorios nao trava a leitura
+    bytes[next_at..next_at + 4].copy_from_slice(&(first as u32).to_le_bytes());
+    assert_eq!(Tiff::from_bytes(&bytes).unwrap().pages(), &pages[..1]);
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use common::gradient;
+use std_image::filters::{
+    FilterError, Transform, crop::Crop, pad::Pad, rotate::Rotate90, transpose::Transpose,
+};
+use std_image::images::{Image, RGB, bitmap::Bitmap, buffer::ImageBuffer, png::Png, tga::Tga};
+
+fn u16_le(bytes: &[u8], pos: usize) -> usize {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]]) as usize
+}
+
+fn u32_le(bytes: &[u8], pos: usize) -> usize {
+    u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize
+}
+
+fn u32_be(bytes: &[u8], pos: usize) -> usize {
+    u32::from_be_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize
+}
+
+/// Pixels esperados, aplicando a operacao num `ImageBuffer`
+fn expected(pixels: Vec<RGB>, width: usize, height: usize, transform: impl Transform) -> Vec<RGB> {
+    let buffer = ImageBuffer::from_pixels(width, height, pixels).unwrap();
+    transform.apply(&buffer).unwrap().into_pixels()
+}
+
+#[test]
+fn bitmap_headers_follow_crop_and_pad() {
+    // Linhas de 24 bits alinhadas em 4 bytes: 5 pixels ocupam 16 bytes, 7 ocupam 24
+    for (bits, cropped_stride, padded_stride) in [(24, 16, 24), (32, 20, 28)] {
+        let alpha = bits == 32;
+        let white = RGB::new(255, 255, 255, None);
+        let bitmap = Bitmap::from_pixels(7, 4, gradient(7, 4, alpha), bits).unwrap();
+        let mut bitmap = Bitmap::from_bytes(&bitmap.to_bytes().unwrap()).unwrap();
+
+        bitmap.transform(Crop::new(1, 1, 5, 3)).unwrap();
+        let bytes = bitmap.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 54 + cropped_stride * 3, "{bits}");
+        assert_eq!(u32_le(&bytes, 2), bytes.len());
+        assert_eq!(bitmap.size_in_bytes() as usize, bytes.len());
+        assert_eq!((u32_le(&bytes, 18), u32_le(&bytes, 22)), (5, 3));
+        assert_eq!(u32_le(&bytes, 34), cropped_stride * 3);
+
+        let cropped = expected(gradient(7, 4, alpha), 7, 4, Crop::new(1, 1, 5, 3));
+        let mut decoded = Bitmap::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.get_pixels(), cropped.as_slice());
+
+        decoded.transform(Pad::uniform(1, white.clone())).unwrap();
+        let bytes = decoded.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 54 + padded_stride * 5, "{bits}");
+        assert_eq!(u32_le(&bytes, 2), bytes.len());
+        assert_eq!((u32_le(&bytes, 18), u32_le(&bytes, 22)), (7, 5));
+        assert_eq!(u32_le(&bytes, 34), padded_stride * 5);
+
+        // Com 32 bits o alpha e gravado, entao a borda volta opaca com alpha
+        let padded: Vec<RGB> = expected(cropped, 5, 3, Pad::uniform(1, white.clone()))
+            .into_iter()
+            .map(|pixel| match (alpha, pixel.alpha()) {
+                (true, None) => RGB::new(255, 255, 255, Some(255)),
+                _ => pixel,
+            })
+            .collect();
+        let decoded = Bitmap::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.get_pixels(), padded.as_slice());
+    }
+}
+
+#[test]
+fn tga_header_follows_rotation_and_crop() {
+    let tga = Tga::from_pixels(6, 3, gradient(6, 3, true)).unwrap();
+    let mut tga = Tga::from_bytes(&tga.to_bytes().unwrap()).unwrap();
+
+    tga.transform(Rotate90).unwrap();
+    let bytes = tga.to_bytes().unwrap();
+    assert_eq!((u16_le(&bytes, 12), u16_le(&bytes, 14)), (3, 6));
+
+    let rotated = expected(gradient(6, 3, true), 6, 3, Rotate90);
+    let mut decoded = Tga::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.get_pixels(), rotated.as_slice());
+
+    decoded.transform(Crop::new(0, 2, 2, 1)).unwrap();
+    let bytes = decoded.to_bytes().unwrap();
+    assert_eq!((u16_le(&bytes, 12), u16_le(&bytes, 14)), (2, 1));
+    assert_eq!(
+        Tga::from_bytes(&bytes).unwrap().get_pixels(),
+        expected(rotated, 3, 6, Crop::new(0, 2, 2, 1)).as_slice()
+    );
+}
+
+#[test]
+fn png_header_follows_transpose_and_pad() {
+    let black = RGB::new(0, 0, 0, None);
+    let png = Png::from_pixels(5, 2, gradient(5, 2, false)).unwrap();
+    let mut png = Png::from_bytes(&png.to_bytes().unwrap()).unwrap();
+
+    png.transform(Transpose).unwrap();
+    png.transform(Pad::new(0, 3, 1, 0, black.clone())).unwrap();
+
+    // O IHDR vem logo depois da assinatura e do tamanho e tipo do chunk
+    let bytes = png.to_bytes().unwrap();
+    assert_eq!((u32_be(&bytes, 16), u32_be(&bytes, 20)), (5, 6));
+
+    let transposed = expected(gradient(5, 2, false), 5, 2, Transpose);
+    let padded = expected(transposed, 2, 5, Pad::new(0, 3, 1, 0, black));
+    assert_eq!(
+        Png::from_bytes(&bytes).unwrap().get_pixels(),
+        padded.as_slice()
+    );
+}
+
+#[test]
+fn invalid_transforms_keep_the_image() {
+    let mut bitmap = Bitmap::from_pixels(4, 3, gradient(4, 3, false), 24).unwrap();
+    let bytes = bitmap.to_bytes().unwrap();
+
+    for crop in [
+        Crop::new(0, 0, 0, 3),
+        Crop::new(1, 0, 4, 3),
+        Crop::new(0, usize::MAX, 4, 2),
+    ] {
+        assert!(matches!(
+            bitmap.transform(crop),
+            Err(FilterError::InvalidDimensions)
+        ));
+    }
+    assert!(matches!(
+        bitmap.transform(Pad::new(0, usize::MAX, 0, 1, RGB::new(0, 0, 0, None))),
+        Err(FilterError::InvalidDimensions)
+    ));
+    assert_eq!(bitmap.to_bytes().unwrap(), bytes);
+}
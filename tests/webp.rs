@@ -0,0 +1,249 @@
+mod common;
+
+use common::gradient;
+use std_image::error::ImageError;
+use std_image::images::{
+    Image, RGB,
+    webp::{WebP, WebPMode},
+};
+
+fn encoded() -> Vec<u8> {
+    WebP::from_pixels(6, 5, gradient(6, 5, true))
+        .unwrap()
+        .to_bytes()
+        .unwrap()
+}
+
+/// Junta bits comecando pelo menos significativo, como o VP8L
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    count: usize,
+}
+
+impl Bits {
+    fn push(&mut self, value: u32, bits: usize) -> &mut Self {
+        for bit in 0..bits {
+            if self.count.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if value >> bit & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 1 << (self.count % 8);
+            }
+            self.count += 1;
+        }
+        self
+    }
+
+    /// `simple_code` com um simbolo de 8 bits, que nao gasta bits por pixel
+    fn single(&mut self, symbol: u32) -> &mut Self {
+        self.push(1, 1).push(0, 1).push(1, 1).push(symbol, 8)
+    }
+}
+
+/// Bitstream VP8L com assinatura, tamanho, bit de alpha e versao 0. `header` escreve o resto
+fn vp8l(width: u32, height: u32, alpha: bool, header: impl FnOnce(&mut Bits)) -> Vec<u8> {
+    let mut bits = Bits::default();
+    bits.push(0x2F, 8)
+        .push(width - 1, 14)
+        .push(height - 1, 14)
+        .push(alpha as u32, 1)
+        .push(0, 3);
+    header(&mut bits);
+    bits.bytes
+}
+
+/// Imagem sem transformacoes, cache nem grupos de codigos, com todos os pixels na cor
+/// `[r, g, b, a]`: cada canal tem um codigo de simbolo unico
+fn solid_codes(bits: &mut Bits, color: [u32; 4], distance: u32) {
+    let [red, green, blue, alpha] = color;
+    bits.push(0, 1).push(0, 1).push(0, 1);
+    bits.single(green)
+        .single(red)
+        .single(blue)
+        .single(alpha)
+        .single(distance);
+}
+
+/// Arquivo RIFF com os chunks dados, cada um alinhado em 2 bytes
+fn riff(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut body = b"WEBP".to_vec();
+    for (kind, data) in chunks {
+        body.extend_from_slice(*kind);
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            body.push(0);
+        }
+    }
+
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    bytes.extend(body);
+    bytes
+}
+
+fn decode_error(bytes: &[u8]) -> ImageError {
+    WebP::from_bytes(bytes).err().expect("decoding should fail")
+}
+
+#[test]
+fn round_trip() {
+    let decoded = WebP::from_bytes(&encoded()).unwrap();
+
+    assert_eq!(decoded.get_pixels(), gradient(6, 5, true).as_slice());
+}
+
+#[test]
+fn round_trip_without_alpha_and_repeated_colors() {
+    let mut pixels = gradient(40, 20, false);
+    pixels.extend(gradient(40, 20, false));
+    pixels.extend(vec![pixels[3].clone(); 400]);
+    let webp = WebP::from_pixels(40, 50, pixels.clone()).unwrap();
+    let decoded = WebP::from_bytes(&webp.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(decoded.mode(), WebPMode::Lossless);
+    assert_eq!(decoded.get_pixels(), pixels.as_slice());
+}
+
+#[test]
+fn tiny_riff_size_is_an_error() {
+    for size in 0..4_u32 {
+        let mut bytes = encoded();
+        bytes[4..8].copy_from_slice(&size.to_le_bytes());
+
+        assert!(matches!(
+            decode_error(&bytes),
+            ImageError::InvalidData("webp riff size is too small")
+        ));
+    }
+}
+
+#[test]
+fn truncated_file_is_an_error() {
+    let bytes = encoded();
+    // Sem contar o byte de alinhamento do chunk, que pode faltar no fim do arquivo
+    let padding = u32::from_le_bytes([bytes[16], bytes[17], bytes[18], bytes[19]]) as usize % 2;
+
+    for len in 0..bytes.len() - padding {
+        assert!(WebP::from_bytes(&bytes[..len]).is_err());
+    }
+}
+
+#[test]
+fn decodes_a_hand_built_bitstream() {
+    let solid = |alpha| vp8l(3, 2, alpha, |bits| solid_codes(bits, [10, 20, 30, 40], 0));
+
+    let decoded = WebP::from_bytes(&riff(&[(b"VP8L", solid(true))])).unwrap();
+    assert_eq!((decoded.widht(), decoded.height()), (3, 2));
+    assert_eq!(
+        decoded.get_pixels(),
+        vec![RGB::new(10, 20, 30, Some(40)); 6]
+    );
+
+    // Sem o bit de alpha o canal e ignorado
+    let decoded = WebP::from_bytes(&riff(&[(b"VP8L", solid(false))])).unwrap();
+    assert_eq!(decoded.get_pixels(), vec![RGB::new(10, 20, 30, None); 6]);
+
+    // Formato estendido: VP8X sem animacao e chunks desconhecidos antes da imagem
+    let extended = riff(&[
+        (b"VP8X", vec![0; 10]),
+        (b"ICCP", vec![1, 2, 3]),
+        (b"VP8L", solid(true)),
+    ]);
+    let decoded = WebP::from_bytes(&extended).unwrap();
+    assert_eq!(
+        decoded.get_pixels(),
+        vec![RGB::new(10, 20, 30, Some(40)); 6]
+    );
+
+    // Bytes depois do tamanho do RIFF nao fazem parte do arquivo
+    let mut trailing = riff(&[(b"VP8L", solid(true))]);
+    trailing.extend_from_slice(b"VP8 \x04\0\0\0junk");
+    assert!(WebP::from_bytes(&trailing).is_ok());
+}
+
+#[test]
+fn invalid_containers_are_rejected() {
+    let image = vp8l(1, 1, false, |bits| solid_codes(bits, [0, 0, 0, 0], 0));
+
+    let mut magic = riff(&[(b"VP8L", image.clone())]);
+    magic[8..12].copy_from_slice(b"WEBQ");
+    assert!(matches!(decode_error(&magic), ImageError::InvalidMagic));
+    assert!(matches!(decode_error(&magic[..11]), ImageError::Truncated));
+
+    // Chunk maior que o arquivo
+    let mut chunk = riff(&[(b"VP8L", image.clone())]);
+    chunk[16..20].copy_from_slice(&1000_u32.to_le_bytes());
+    assert!(matches!(decode_error(&chunk), ImageError::Truncated));
+
+    let mut animated = vec![0; 10];
+    animated[0] = 0x02;
+    assert!(matches!(
+        decode_error(&riff(&[(b"VP8X", animated), (b"ANIM", vec![0; 6])])),
+        ImageError::Unsupported("webp animation")
+    ));
+    assert!(matches!(
+        decode_error(&riff(&[(b"VP8X", vec![0; 10]), (b"EXIF", vec![0; 4])])),
+        ImageError::InvalidData("webp has no image chunk")
+    ));
+    assert!(matches!(
+        decode_error(&riff(&[(b"VP8 ", vec![0; 10])])),
+        ImageError::Unsupported("webp lossy (VP8)")
+    ));
+    assert!(matches!(
+        decode_error(&riff(&[(b"ICCP", vec![0; 4]), (b"VP8L", image)])),
+        ImageError::InvalidData("webp has no image chunk")
+    ));
+}
+
+#[test]
+fn invalid_bitstreams_are_rejected() {
+    let mut signature = vp8l(1, 1, false, |bits| solid_codes(bits, [0, 0, 0, 0], 0));
+    signature[0] = 0x2E;
+    assert!(matches!(
+        decode_error(&riff(&[(b"VP8L", signature)])),
+        ImageError::InvalidMagic
+    ));
+
+    let mut version = vp8l(1, 1, false, |bits| solid_codes(bits, [0, 0, 0, 0], 0));
+    version[4] |= 0x40;
+    assert!(matches!(
+        decode_error(&riff(&[(b"VP8L", version)])),
+        ImageError::InvalidData("webp VP8L version is not 0")
+    ));
+
+    // Subtract green (tipo 2) duas vezes
+    let repeated = vp8l(1, 1, false, |bits| {
+        bits.push(1, 1).push(2, 2).push(1, 1).push(2, 2);
+    });
+    assert!(matches!(
+        decode_error(&riff(&[(b"VP8L", repeated)])),
+        ImageError::InvalidData("webp transform is repeated")
+    ));
+
+    for cache_bits in [0, 12] {
+        let cache = vp8l(1, 1, false, |bits| {
+            bits.push(0, 1).push(1, 1).push(cache_bits, 4);
+        });
+        assert!(matches!(
+            decode_error(&riff(&[(b"VP8L", cache)])),
+            ImageError::InvalidData("webp color cache size is invalid")
+        ));
+    }
+
+    // O alfabeto das distancias tem 40 simbolos
+    let distance = vp8l(1, 1, false, |bits| solid_codes(bits, [0, 0, 0, 0], 200));
+    assert!(matches!(
+        decode_error(&riff(&[(b"VP8L", distance)])),
+        ImageError::InvalidData("webp prefix code symbol is invalid")
+    ));
+
+    // Os codigos acabam antes do ultimo canal
+    let mut short = vp8l(1, 1, false, |bits| solid_codes(bits, [0, 0, 0, 0], 0));
+    short.truncate(10);
+    assert!(matches!(
+        decode_error(&riff(&[(b"VP8L", short)])),
+        ImageError::Truncated
+    ));
+}